@@ -0,0 +1,51 @@
+// Bake build metadata into the binary for GET /api/version and the X-Service-Version header:
+// GIT_SHA, GIT_SHORT_SHA and BUILD_TIMESTAMP (epoch seconds).
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    // Docker builds have no .git (see .dockerignore), so the SHA can be passed in: GIT_SHA
+    // explicitly, or Railway's RAILWAY_GIT_COMMIT_SHA build variable
+    let sha = ["GIT_SHA", "RAILWAY_GIT_COMMIT_SHA"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty()))
+        .or_else(git_head)
+        .unwrap_or_else(|| "unknown".to_string());
+    let short = sha.get(..12).unwrap_or(&sha);
+
+    // SOURCE_DATE_EPOCH pins the timestamp for reproducible builds
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+
+    println!("cargo:rustc-env=GIT_SHA={sha}");
+    println!("cargo:rustc-env=GIT_SHORT_SHA={short}");
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={timestamp}");
+
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    println!("cargo:rerun-if-env-changed=RAILWAY_GIT_COMMIT_SHA");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    // A new commit or checkout moves HEAD (or the branch it points at); a source edit
+    // refreshes the timestamp
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-changed=src");
+}
+
+fn git_head() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let sha = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!sha.is_empty()).then_some(sha)
+}
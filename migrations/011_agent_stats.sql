@@ -0,0 +1,31 @@
+-- Denormalized per-agent feedback aggregates, maintained by insert_feedback / revoke_feedback.
+-- Replaces the per-request LEFT JOIN + GROUP BY over feedbacks in agent list/detail/leaderboard.
+CREATE TABLE IF NOT EXISTS agent_stats (
+    agent_id BIGINT NOT NULL,
+    chain_id INT NOT NULL,
+    feedback_count BIGINT NOT NULL DEFAULT 0,
+    score_sum FLOAT8,
+    avg_score FLOAT8,
+    positive_count BIGINT NOT NULL DEFAULT 0,
+    negative_count BIGINT NOT NULL DEFAULT 0,
+    last_feedback_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    PRIMARY KEY (agent_id, chain_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_agent_stats_avg_score ON agent_stats(chain_id, avg_score DESC NULLS LAST);
+
+-- One-time backfill from existing feedbacks
+INSERT INTO agent_stats (agent_id, chain_id, feedback_count, score_sum, avg_score, positive_count, negative_count, last_feedback_at)
+SELECT
+    f.agent_id,
+    f.chain_id,
+    COUNT(CASE WHEN f.revoked = false THEN 1 ELSE NULL END),
+    SUM(CASE WHEN f.revoked = false THEN f.value / POWER(10, COALESCE(f.value_decimals, 0)) ELSE NULL END)::FLOAT8,
+    AVG(CASE WHEN f.revoked = false THEN f.value / POWER(10, COALESCE(f.value_decimals, 0)) ELSE NULL END)::FLOAT8,
+    COUNT(CASE WHEN f.revoked = false AND f.value / POWER(10, COALESCE(f.value_decimals, 0)) >= 3 THEN 1 ELSE NULL END),
+    COUNT(CASE WHEN f.revoked = false AND f.value / POWER(10, COALESCE(f.value_decimals, 0)) < 3 THEN 1 ELSE NULL END),
+    MAX(CASE WHEN f.revoked = false THEN f.created_at ELSE NULL END)
+FROM feedbacks f
+GROUP BY f.agent_id, f.chain_id
+ON CONFLICT (agent_id, chain_id) DO NOTHING;
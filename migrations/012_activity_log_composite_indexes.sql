@@ -0,0 +1,12 @@
+-- /api/agents/:id/activity filters on (agent_id, chain_id) and pages newest block first;
+-- this serves both without a sort. It leads with idx_activity_agent's columns, so that
+-- index is dropped.
+CREATE INDEX IF NOT EXISTS idx_activity_log_agent_block
+    ON activity_log(agent_id, chain_id, block_number DESC, log_index DESC);
+DROP INDEX IF EXISTS idx_activity_agent;
+
+-- /api/activity?chain_id= walks the feed order (event time, then id) within one chain.
+-- block_timestamp is NULL until the timestamp backfill reaches a row, so the order keeps
+-- the COALESCE.
+CREATE INDEX IF NOT EXISTS idx_activity_log_chain_feed
+    ON activity_log(chain_id, (COALESCE(block_timestamp, created_at)) DESC, id DESC);
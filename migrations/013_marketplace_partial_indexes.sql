@@ -0,0 +1,17 @@
+-- Partial indexes for the common `status = 'Active'` browse queries. Sold/cancelled rows
+-- accumulate over time and would otherwise dominate the plain status indexes.
+-- The list queries use `($n IS NULL OR status = $n)`, so these are only picked with custom
+-- plans (Postgres' default plan_cache_mode). tests/explain_tests.rs checks the plans.
+
+-- Listings: collection browse sorted by price, global price sorts, and the default "recent" sort
+CREATE INDEX IF NOT EXISTS idx_ml_active_chain_nft_price ON marketplace_listings(chain_id, nft_contract, price) WHERE status = 'Active';
+CREATE INDEX IF NOT EXISTS idx_ml_active_price ON marketplace_listings(price) WHERE status = 'Active';
+CREATE INDEX IF NOT EXISTS idx_ml_active_block ON marketplace_listings(block_number DESC) WHERE status = 'Active';
+
+-- Auctions: "ending soon" and status + end_time lookups
+CREATE INDEX IF NOT EXISTS idx_ma_status_end_time ON marketplace_auctions(status, end_time);
+CREATE INDEX IF NOT EXISTS idx_ma_active_end_time ON marketplace_auctions(end_time) WHERE status = 'Active';
+
+-- Offers on a specific token, and collection-wide offers
+CREATE INDEX IF NOT EXISTS idx_mo_active_nft_token ON marketplace_offers(nft_contract, token_id) WHERE status = 'Active';
+CREATE INDEX IF NOT EXISTS idx_mco_active_nft ON marketplace_collection_offers(nft_contract) WHERE status = 'Active';
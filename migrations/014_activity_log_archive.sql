@@ -0,0 +1,18 @@
+-- Archive for activity_log rows older than ACTIVITY_RETENTION_DAYS. Same columns as
+-- activity_log; ids are carried over, so no sequence of its own.
+CREATE TABLE IF NOT EXISTS activity_log_archive (
+    id INT PRIMARY KEY,
+    agent_id BIGINT NOT NULL,
+    chain_id INT NOT NULL,
+    event_type TEXT NOT NULL,
+    event_data JSONB DEFAULT '{}',
+    block_number BIGINT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    log_index INT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    block_timestamp TIMESTAMPTZ
+);
+CREATE INDEX IF NOT EXISTS idx_activity_archive_agent ON activity_log_archive(agent_id, chain_id);
+
+-- The retention task scans for rows older than the cutoff
+CREATE INDEX IF NOT EXISTS idx_activity_log_event_time ON activity_log((COALESCE(block_timestamp, created_at)));
@@ -0,0 +1,8 @@
+-- Result of checking feedback_uri content against the on-chain feedback_hash
+-- (VERIFY_FEEDBACK_CONTENT). NULL = not checked yet, or the content couldn't be fetched.
+ALTER TABLE feedbacks ADD COLUMN IF NOT EXISTS content_verified BOOLEAN;
+ALTER TABLE feedbacks ADD COLUMN IF NOT EXISTS content_checked_at TIMESTAMPTZ;
+
+-- Rows still waiting on a verdict
+CREATE INDEX IF NOT EXISTS idx_feedbacks_content_pending ON feedbacks(id)
+    WHERE content_verified IS NULL AND feedback_uri IS NOT NULL AND feedback_hash IS NOT NULL;
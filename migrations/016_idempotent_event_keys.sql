@@ -0,0 +1,50 @@
+-- Natural keys for event-sourced rows, so the batch inserts can use ON CONFLICT DO NOTHING
+-- and replaying a block range (e.g. after a failed batch) is a no-op.
+-- Earlier replays left duplicates; keep the first-indexed row of each before adding the keys.
+
+DELETE FROM activity_log a
+USING activity_log b
+WHERE a.id > b.id
+  AND a.chain_id = b.chain_id
+  AND a.tx_hash = b.tx_hash
+  AND a.log_index = b.log_index
+  AND a.agent_id = b.agent_id
+  AND a.event_type = b.event_type;
+
+-- agent_id/event_type are part of the key: one bundle log yields an activity per agent item
+CREATE UNIQUE INDEX IF NOT EXISTS uq_activity_log_event
+    ON activity_log(chain_id, tx_hash, log_index, agent_id, event_type);
+
+DELETE FROM feedbacks a
+USING feedbacks b
+WHERE a.id > b.id
+  AND a.agent_id = b.agent_id
+  AND a.chain_id = b.chain_id
+  AND a.client_address = b.client_address
+  AND a.feedback_index = b.feedback_index;
+
+CREATE UNIQUE INDEX IF NOT EXISTS uq_feedbacks_client_index
+    ON feedbacks(agent_id, chain_id, client_address, feedback_index);
+
+-- Removing duplicate feedbacks changes the aggregates; recompute agent_stats
+INSERT INTO agent_stats (agent_id, chain_id, feedback_count, score_sum, avg_score, positive_count, negative_count, last_feedback_at, updated_at)
+SELECT
+    f.agent_id,
+    f.chain_id,
+    COUNT(CASE WHEN f.revoked = false THEN 1 ELSE NULL END),
+    SUM(CASE WHEN f.revoked = false THEN f.value / POWER(10, COALESCE(f.value_decimals, 0)) ELSE NULL END)::FLOAT8,
+    AVG(CASE WHEN f.revoked = false THEN f.value / POWER(10, COALESCE(f.value_decimals, 0)) ELSE NULL END)::FLOAT8,
+    COUNT(CASE WHEN f.revoked = false AND f.value / POWER(10, COALESCE(f.value_decimals, 0)) >= 3 THEN 1 ELSE NULL END),
+    COUNT(CASE WHEN f.revoked = false AND f.value / POWER(10, COALESCE(f.value_decimals, 0)) < 3 THEN 1 ELSE NULL END),
+    MAX(CASE WHEN f.revoked = false THEN f.created_at ELSE NULL END),
+    NOW()
+FROM feedbacks f
+GROUP BY f.agent_id, f.chain_id
+ON CONFLICT (agent_id, chain_id) DO UPDATE SET
+    feedback_count = EXCLUDED.feedback_count,
+    score_sum = EXCLUDED.score_sum,
+    avg_score = EXCLUDED.avg_score,
+    positive_count = EXCLUDED.positive_count,
+    negative_count = EXCLUDED.negative_count,
+    last_feedback_at = EXCLUDED.last_feedback_at,
+    updated_at = NOW();
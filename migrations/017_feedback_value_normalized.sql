@@ -0,0 +1,14 @@
+-- value / 10^value_decimals, stored once per feedback instead of recomputed by every aggregate.
+-- Same expression the aggregates used (it evaluates in double precision), so scores are unchanged.
+-- value and value_decimals stay as the on-chain source of truth.
+ALTER TABLE feedbacks ADD COLUMN IF NOT EXISTS value_normalized DOUBLE PRECISION;
+
+UPDATE feedbacks
+SET value_normalized = value / POWER(10, COALESCE(value_decimals, 0))
+WHERE value_normalized IS NULL;
+
+ALTER TABLE feedbacks ALTER COLUMN value_normalized SET NOT NULL;
+
+-- Per-agent score aggregates (agent_stats refresh, legacy aggregation) can read the index alone
+CREATE INDEX IF NOT EXISTS idx_feedbacks_agent_value ON feedbacks(agent_id, chain_id, value_normalized)
+    WHERE revoked = false;
@@ -0,0 +1,87 @@
+-- One row per completed sale across every sale path, so stats and feeds read a single table
+-- instead of reconciling sold_price / settled_price / amount across five tables.
+-- sale_type: listing | offer | collection_offer | auction | dutch_auction | bundle
+-- (auction covers both AuctionSettled and AuctionBuyNow). entity_id is the listing/offer/
+-- auction/bundle id in its own table. Bundles have no single NFT, so nft_contract and
+-- token_id are NULL for them.
+CREATE TABLE IF NOT EXISTS marketplace_sales (
+    id BIGSERIAL PRIMARY KEY,
+    chain_id INT NOT NULL,
+    sale_type TEXT NOT NULL,
+    entity_id BIGINT NOT NULL,
+    nft_contract TEXT,
+    token_id NUMERIC,
+    seller TEXT,
+    buyer TEXT,
+    payment_token TEXT,
+    price NUMERIC NOT NULL,
+    block_number BIGINT,
+    block_timestamp TIMESTAMPTZ,
+    tx_hash TEXT,
+    log_index INT,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    -- Replaying a block range is a no-op
+    CONSTRAINT uq_marketplace_sales_event UNIQUE (chain_id, tx_hash, log_index),
+    -- Each listing/offer/auction/bundle sells at most once; also keeps the backfill idempotent
+    CONSTRAINT uq_marketplace_sales_entity UNIQUE (chain_id, sale_type, entity_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_msales_time ON marketplace_sales(chain_id, block_timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_msales_nft ON marketplace_sales(nft_contract, token_id);
+
+-- One-time backfill from the per-type tables. Only agent-NFT listing and bundle sales left
+-- an activity_log row with the sale's tx; other backfilled rows have no tx_hash/log_index/
+-- block_number, and block_timestamp falls back to when the row was marked sold.
+WITH sale_activity AS (
+    SELECT DISTINCT ON (chain_id, event_type, entity_id)
+        chain_id, event_type, entity_id, block_number, block_timestamp, tx_hash, log_index
+    FROM (
+        SELECT chain_id, event_type, event_data, block_number, block_timestamp, tx_hash, log_index,
+               COALESCE(event_data->>'listing_id', event_data->>'bundle_id')::BIGINT AS entity_id
+        FROM activity_log
+        WHERE event_type IN ('marketplace:Bought', 'marketplace:BundleBought')
+        UNION ALL
+        SELECT chain_id, event_type, event_data, block_number, block_timestamp, tx_hash, log_index,
+               COALESCE(event_data->>'listing_id', event_data->>'bundle_id')::BIGINT AS entity_id
+        FROM activity_log_archive
+        WHERE event_type IN ('marketplace:Bought', 'marketplace:BundleBought')
+    ) a
+    ORDER BY chain_id, event_type, entity_id, block_number
+)
+INSERT INTO marketplace_sales
+    (chain_id, sale_type, entity_id, nft_contract, token_id, seller, buyer, payment_token, price,
+     block_number, block_timestamp, tx_hash, log_index)
+SELECT l.chain_id, 'listing', l.listing_id, l.nft_contract, l.token_id, l.seller, l.buyer, l.payment_token,
+       COALESCE(l.sold_price, l.price), sa.block_number, COALESCE(sa.block_timestamp, l.updated_at), sa.tx_hash, sa.log_index
+FROM marketplace_listings l
+LEFT JOIN sale_activity sa
+    ON sa.chain_id = l.chain_id AND sa.event_type = 'marketplace:Bought' AND sa.entity_id = l.listing_id
+WHERE l.status = 'Sold'
+UNION ALL
+SELECT o.chain_id, 'offer', o.offer_id, o.nft_contract, o.token_id, o.accepted_by, o.offerer, o.payment_token,
+       o.amount, NULL, o.updated_at, NULL, NULL
+FROM marketplace_offers o
+WHERE o.status = 'Accepted'
+UNION ALL
+SELECT co.chain_id, 'collection_offer', co.offer_id, co.nft_contract, co.accepted_token_id, co.accepted_by, co.offerer,
+       co.payment_token, co.amount, NULL, co.updated_at, NULL, NULL
+FROM marketplace_collection_offers co
+WHERE co.status = 'Accepted'
+UNION ALL
+SELECT a.chain_id, 'auction', a.auction_id, a.nft_contract, a.token_id, a.seller, a.winner, a.payment_token,
+       a.settled_price, NULL, a.updated_at, NULL, NULL
+FROM marketplace_auctions a
+WHERE a.status = 'Ended' AND a.winner IS NOT NULL AND a.settled_price IS NOT NULL
+UNION ALL
+SELECT d.chain_id, 'dutch_auction', d.auction_id, d.nft_contract, d.token_id, d.seller, d.buyer, d.payment_token,
+       d.sold_price, NULL, d.updated_at, NULL, NULL
+FROM marketplace_dutch_auctions d
+WHERE d.status = 'Sold' AND d.sold_price IS NOT NULL
+UNION ALL
+SELECT b.chain_id, 'bundle', b.bundle_id, NULL, NULL, b.seller, b.buyer, b.payment_token,
+       COALESCE(b.sold_price, b.price), sa.block_number, COALESCE(sa.block_timestamp, b.updated_at), sa.tx_hash, sa.log_index
+FROM marketplace_bundles b
+LEFT JOIN sale_activity sa
+    ON sa.chain_id = b.chain_id AND sa.event_type = 'marketplace:BundleBought' AND sa.entity_id = b.bundle_id
+WHERE b.status = 'Sold'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,25 @@
+-- Bids get the same natural key as other event rows, so re-indexing a range doesn't
+-- double-record them, and bid_count is derived from the stored bids instead of a blind +1.
+ALTER TABLE marketplace_auction_bids ADD COLUMN IF NOT EXISTS log_index INT;
+
+-- Existing rows predate log_index. A replayed BidPlaced repeats the tx, auction, bidder and
+-- amount; keep the first-indexed copy.
+DELETE FROM marketplace_auction_bids a
+USING marketplace_auction_bids b
+WHERE a.id > b.id
+  AND a.chain_id = b.chain_id
+  AND a.tx_hash = b.tx_hash
+  AND a.auction_id = b.auction_id
+  AND a.bidder = b.bidder
+  AND a.amount = b.amount;
+
+-- Legacy rows keep a NULL log_index, which the unique constraint treats as distinct
+ALTER TABLE marketplace_auction_bids
+    ADD CONSTRAINT uq_marketplace_auction_bids_event UNIQUE (chain_id, tx_hash, log_index);
+
+-- Replays also inflated bid_count; recompute it from the deduplicated bids
+UPDATE marketplace_auctions a
+SET bid_count = (
+    SELECT COUNT(*) FROM marketplace_auction_bids b
+    WHERE b.auction_id = a.auction_id AND b.chain_id = a.chain_id
+);
@@ -0,0 +1,15 @@
+-- Ranked full-text search for GET /api/agents?search_mode=rank. Name matches weigh most,
+-- then categories, then description.
+-- array_to_string is only STABLE (it goes through the element type's output function),
+-- which generated columns reject; for TEXT[] the result can't change, so wrap it.
+CREATE OR REPLACE FUNCTION agent_categories_text(categories TEXT[]) RETURNS TEXT
+    LANGUAGE sql IMMUTABLE PARALLEL SAFE
+    AS $$ SELECT array_to_string(categories, ' ') $$;
+
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS search_tsv TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', COALESCE(name, '')), 'A') ||
+    setweight(to_tsvector('english', COALESCE(agent_categories_text(categories), '')), 'B') ||
+    setweight(to_tsvector('english', COALESCE(description, '')), 'C')
+) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_agents_search_tsv ON agents USING GIN (search_tsv);
@@ -0,0 +1,16 @@
+-- Distinct clients with non-revoked feedback, so agent lists can sort by breadth of review
+-- (sort=clients) without a COUNT(DISTINCT) over feedbacks per request.
+ALTER TABLE agent_stats ADD COLUMN IF NOT EXISTS unique_clients BIGINT NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_agent_stats_unique_clients ON agent_stats(chain_id, unique_clients DESC);
+
+-- One-time backfill from existing feedbacks
+UPDATE agent_stats s
+SET unique_clients = c.unique_clients
+FROM (
+    SELECT agent_id, chain_id, COUNT(DISTINCT client_address) AS unique_clients
+    FROM feedbacks
+    WHERE revoked = false
+    GROUP BY agent_id, chain_id
+) c
+WHERE s.agent_id = c.agent_id AND s.chain_id = c.chain_id;
@@ -0,0 +1,4 @@
+-- Serves the global recent-feedback feed (GET /api/feedbacks/recent), which orders
+-- non-revoked feedbacks by event time across all agents.
+CREATE INDEX IF NOT EXISTS idx_feedbacks_recent ON feedbacks((COALESCE(block_timestamp, created_at)) DESC, id DESC)
+    WHERE revoked = false;
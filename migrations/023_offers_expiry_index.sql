@@ -0,0 +1,4 @@
+-- Serves the offer expiry window (expiring_after / expiring_before) and sort=expiring_soon,
+-- which puts Active offers first, soonest expiry first.
+CREATE INDEX IF NOT EXISTS idx_mo_status_expiry ON marketplace_offers(status, expiry);
+CREATE INDEX IF NOT EXISTS idx_mco_status_expiry ON marketplace_collection_offers(status, expiry);
@@ -0,0 +1,5 @@
+-- value_decimals is nullable (INT DEFAULT 0), but the indexer always writes it and the API
+-- models it as a plain integer. Store 0 for any row that was written without it, matching
+-- the COALESCE(value_decimals, 0) every normalization site uses. value_normalized already
+-- used that fallback, so it is unchanged.
+UPDATE feedbacks SET value_decimals = 0 WHERE value_decimals IS NULL;
@@ -0,0 +1,35 @@
+-- Maintain updated_at in the database instead of in every UPDATE / ON CONFLICT DO UPDATE:
+-- a BEFORE UPDATE trigger stamps NOW() on each changed row, so no write path can forget it.
+-- Inserts keep the column's DEFAULT NOW(); created_at is untouched.
+CREATE OR REPLACE FUNCTION set_updated_at() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- feedbacks had no updated_at (revocation left no trace of when it happened). Existing rows
+-- start at their created_at; the backfill runs before the trigger exists.
+ALTER TABLE feedbacks ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ;
+UPDATE feedbacks SET updated_at = created_at WHERE updated_at IS NULL;
+ALTER TABLE feedbacks ALTER COLUMN updated_at SET DEFAULT NOW();
+
+DO $$
+DECLARE
+    t TEXT;
+BEGIN
+    FOREACH t IN ARRAY ARRAY[
+        'agents', 'feedbacks', 'agent_stats', 'indexer_state',
+        'marketplace_listings', 'marketplace_offers', 'marketplace_collection_offers',
+        'marketplace_auctions', 'marketplace_dutch_auctions', 'marketplace_bundles',
+        'marketplace_config', 'marketplace_payment_tokens'
+    ]
+    LOOP
+        EXECUTE format('DROP TRIGGER IF EXISTS trg_%s_updated_at ON %I', t, t);
+        EXECUTE format(
+            'CREATE TRIGGER trg_%s_updated_at BEFORE UPDATE ON %I FOR EACH ROW EXECUTE FUNCTION set_updated_at()',
+            t, t
+        );
+    END LOOP;
+END;
+$$;
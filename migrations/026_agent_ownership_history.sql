@@ -0,0 +1,37 @@
+-- Ownership history of agent NFTs: one row per identity registry Transfer, the mint
+-- (from the zero address) included. Keyed like activity_log so replaying a range is a no-op.
+CREATE TABLE IF NOT EXISTS agent_ownership_history (
+    id SERIAL PRIMARY KEY,
+    agent_id BIGINT NOT NULL,
+    chain_id INT NOT NULL,
+    from_address TEXT NOT NULL,
+    to_address TEXT NOT NULL,
+    block_number BIGINT NOT NULL,
+    block_timestamp TIMESTAMPTZ,
+    tx_hash TEXT NOT NULL,
+    log_index INT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS uq_agent_ownership_history_event
+    ON agent_ownership_history(chain_id, tx_hash, log_index);
+CREATE INDEX IF NOT EXISTS idx_agent_ownership_history_agent
+    ON agent_ownership_history(agent_id, chain_id, block_number, log_index);
+
+-- One-time backfill from already-indexed events (live and archived). Mint Transfers were never
+-- logged, so each agent's mint is taken from its Registered activity (same tx, its log_index).
+INSERT INTO agent_ownership_history (agent_id, chain_id, from_address, to_address, block_number, block_timestamp, tx_hash, log_index)
+SELECT agent_id, chain_id,
+       CASE WHEN event_type = 'Registered' THEN '0x0000000000000000000000000000000000000000' ELSE event_data->>'from' END,
+       CASE WHEN event_type = 'Registered' THEN event_data->>'owner' ELSE event_data->>'to' END,
+       block_number, block_timestamp, tx_hash, log_index
+FROM (
+    SELECT agent_id, chain_id, event_type, event_data, block_number, block_timestamp, tx_hash, log_index
+    FROM activity_log WHERE event_type IN ('Registered', 'Transfer')
+    UNION ALL
+    SELECT agent_id, chain_id, event_type, event_data, block_number, block_timestamp, tx_hash, log_index
+    FROM activity_log_archive WHERE event_type IN ('Registered', 'Transfer')
+) e
+WHERE (e.event_type = 'Registered' AND e.event_data->>'owner' IS NOT NULL)
+   OR (e.event_type = 'Transfer' AND e.event_data->>'from' IS NOT NULL AND e.event_data->>'to' IS NOT NULL)
+ON CONFLICT (chain_id, tx_hash, log_index) DO NOTHING;
@@ -0,0 +1,68 @@
+-- Marketplace ids (listing_id, offer_id, ...) are counters inside the contract, so a redeployed
+-- marketplace on the same chain starts again at 0. Rows are now keyed by the emitting contract
+-- too: marketplace_address is the lowercase 0x address the indexer read the event from.
+ALTER TABLE marketplace_listings ADD COLUMN IF NOT EXISTS marketplace_address TEXT NOT NULL DEFAULT '';
+ALTER TABLE marketplace_offers ADD COLUMN IF NOT EXISTS marketplace_address TEXT NOT NULL DEFAULT '';
+ALTER TABLE marketplace_collection_offers ADD COLUMN IF NOT EXISTS marketplace_address TEXT NOT NULL DEFAULT '';
+ALTER TABLE marketplace_auctions ADD COLUMN IF NOT EXISTS marketplace_address TEXT NOT NULL DEFAULT '';
+ALTER TABLE marketplace_auction_bids ADD COLUMN IF NOT EXISTS marketplace_address TEXT NOT NULL DEFAULT '';
+ALTER TABLE marketplace_dutch_auctions ADD COLUMN IF NOT EXISTS marketplace_address TEXT NOT NULL DEFAULT '';
+ALTER TABLE marketplace_bundles ADD COLUMN IF NOT EXISTS marketplace_address TEXT NOT NULL DEFAULT '';
+ALTER TABLE marketplace_sales ADD COLUMN IF NOT EXISTS marketplace_address TEXT NOT NULL DEFAULT '';
+
+-- Existing rows all came from the one marketplace each chain has indexed so far: take its
+-- address from the indexer cursor. Chains with no (or several) marketplace cursors keep ''.
+CREATE TEMP TABLE single_marketplace ON COMMIT DROP AS
+SELECT chain_id, MIN(LOWER(contract_address)) AS marketplace_address
+FROM indexer_state
+WHERE contract_name = 'MoltMarketplace'
+GROUP BY chain_id
+HAVING COUNT(*) = 1;
+
+UPDATE marketplace_listings t SET marketplace_address = m.marketplace_address
+FROM single_marketplace m WHERE t.chain_id = m.chain_id AND t.marketplace_address = '';
+UPDATE marketplace_offers t SET marketplace_address = m.marketplace_address
+FROM single_marketplace m WHERE t.chain_id = m.chain_id AND t.marketplace_address = '';
+UPDATE marketplace_collection_offers t SET marketplace_address = m.marketplace_address
+FROM single_marketplace m WHERE t.chain_id = m.chain_id AND t.marketplace_address = '';
+UPDATE marketplace_auctions t SET marketplace_address = m.marketplace_address
+FROM single_marketplace m WHERE t.chain_id = m.chain_id AND t.marketplace_address = '';
+UPDATE marketplace_auction_bids t SET marketplace_address = m.marketplace_address
+FROM single_marketplace m WHERE t.chain_id = m.chain_id AND t.marketplace_address = '';
+UPDATE marketplace_dutch_auctions t SET marketplace_address = m.marketplace_address
+FROM single_marketplace m WHERE t.chain_id = m.chain_id AND t.marketplace_address = '';
+UPDATE marketplace_bundles t SET marketplace_address = m.marketplace_address
+FROM single_marketplace m WHERE t.chain_id = m.chain_id AND t.marketplace_address = '';
+UPDATE marketplace_sales t SET marketplace_address = m.marketplace_address
+FROM single_marketplace m WHERE t.chain_id = m.chain_id AND t.marketplace_address = '';
+
+-- Swap the per-chain id keys for per-contract ones. Bids and sales keep their event key
+-- (chain_id, tx_hash, log_index), which already tells contracts apart.
+ALTER TABLE marketplace_listings DROP CONSTRAINT IF EXISTS marketplace_listings_listing_id_chain_id_key;
+ALTER TABLE marketplace_listings ADD CONSTRAINT uq_marketplace_listings_contract_id
+    UNIQUE (listing_id, chain_id, marketplace_address);
+
+ALTER TABLE marketplace_offers DROP CONSTRAINT IF EXISTS marketplace_offers_offer_id_chain_id_key;
+ALTER TABLE marketplace_offers ADD CONSTRAINT uq_marketplace_offers_contract_id
+    UNIQUE (offer_id, chain_id, marketplace_address);
+
+ALTER TABLE marketplace_collection_offers DROP CONSTRAINT IF EXISTS marketplace_collection_offers_offer_id_chain_id_key;
+ALTER TABLE marketplace_collection_offers ADD CONSTRAINT uq_marketplace_collection_offers_contract_id
+    UNIQUE (offer_id, chain_id, marketplace_address);
+
+ALTER TABLE marketplace_auctions DROP CONSTRAINT IF EXISTS marketplace_auctions_auction_id_chain_id_key;
+ALTER TABLE marketplace_auctions ADD CONSTRAINT uq_marketplace_auctions_contract_id
+    UNIQUE (auction_id, chain_id, marketplace_address);
+
+ALTER TABLE marketplace_dutch_auctions DROP CONSTRAINT IF EXISTS marketplace_dutch_auctions_auction_id_chain_id_key;
+ALTER TABLE marketplace_dutch_auctions ADD CONSTRAINT uq_marketplace_dutch_auctions_contract_id
+    UNIQUE (auction_id, chain_id, marketplace_address);
+
+ALTER TABLE marketplace_bundles DROP CONSTRAINT IF EXISTS marketplace_bundles_bundle_id_chain_id_key;
+ALTER TABLE marketplace_bundles ADD CONSTRAINT uq_marketplace_bundles_contract_id
+    UNIQUE (bundle_id, chain_id, marketplace_address);
+
+-- One sale per sold entity, now per contract
+ALTER TABLE marketplace_sales DROP CONSTRAINT IF EXISTS uq_marketplace_sales_entity;
+ALTER TABLE marketplace_sales ADD CONSTRAINT uq_marketplace_sales_entity
+    UNIQUE (chain_id, marketplace_address, sale_type, entity_id);
@@ -0,0 +1,3 @@
+-- /api/agents/:id/responses lists an agent's responses newest first
+CREATE INDEX IF NOT EXISTS idx_feedback_responses_agent
+    ON feedback_responses(agent_id, chain_id, block_number DESC);
@@ -0,0 +1,7 @@
+-- Auctions whose end_time is not after start_time (bad contract input or a decode error).
+-- They break the ending_soon sort and current-price math, so the lists can leave them out.
+ALTER TABLE marketplace_auctions ADD COLUMN IF NOT EXISTS malformed BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE marketplace_dutch_auctions ADD COLUMN IF NOT EXISTS malformed BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE marketplace_auctions SET malformed = TRUE WHERE end_time <= start_time;
+UPDATE marketplace_dutch_auctions SET malformed = TRUE WHERE end_time <= start_time;
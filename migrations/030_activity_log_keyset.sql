@@ -0,0 +1,3 @@
+-- /api/activity?before=<cursor> seeks on the feed order (event time, then id), newest first
+CREATE INDEX IF NOT EXISTS idx_activity_log_feed_keyset
+    ON activity_log((COALESCE(block_timestamp, created_at)) DESC, id DESC);
@@ -0,0 +1,25 @@
+-- The activity feed orders on the event time. Until now that was COALESCE(block_timestamp,
+-- created_at), because block_timestamp stays NULL until the timestamp backfill reaches a row,
+-- and every index on it had to be an expression index. Fill the column instead: a row without
+-- its block's timestamp gets the insert time (what the COALESCE fell back to) and is flagged
+-- estimated, so the backfill still replaces it with the real one.
+ALTER TABLE activity_log ADD COLUMN IF NOT EXISTS block_timestamp_estimated BOOLEAN NOT NULL DEFAULT false;
+UPDATE activity_log SET block_timestamp = created_at, block_timestamp_estimated = true WHERE block_timestamp IS NULL;
+ALTER TABLE activity_log ALTER COLUMN block_timestamp SET DEFAULT NOW();
+ALTER TABLE activity_log ALTER COLUMN block_timestamp SET NOT NULL;
+
+-- The backfill looks for estimated rows
+CREATE INDEX IF NOT EXISTS idx_activity_log_estimated_time
+    ON activity_log(chain_id, block_number) WHERE block_timestamp_estimated;
+
+-- /api/activity?chain_id= walks (chain_id, block_timestamp DESC); id breaks ties in the same
+-- second so the feed order needs no sort.
+CREATE INDEX IF NOT EXISTS idx_activity_log_chain_time
+    ON activity_log(chain_id, block_timestamp DESC, id DESC);
+DROP INDEX IF EXISTS idx_activity_log_chain_feed;
+
+-- The COALESCE expression indexes from 014 (retention) and 030 (keyset) on the plain column
+CREATE INDEX IF NOT EXISTS idx_activity_log_feed_time
+    ON activity_log(block_timestamp DESC, id DESC);
+DROP INDEX IF EXISTS idx_activity_log_feed_keyset;
+DROP INDEX IF EXISTS idx_activity_log_event_time;
@@ -0,0 +1,18 @@
+-- Feedback responses get the same natural key as other event rows, so a replayed block range
+-- (a failed batch is retried as a whole) doesn't record a ResponseAppended twice.
+ALTER TABLE feedback_responses ADD COLUMN IF NOT EXISTS log_index INT;
+
+-- Existing rows predate log_index. A replayed ResponseAppended repeats the tx, agent, feedback
+-- and URI; keep the first-indexed copy.
+DELETE FROM feedback_responses a
+USING feedback_responses b
+WHERE a.id > b.id
+  AND a.chain_id = b.chain_id
+  AND a.tx_hash = b.tx_hash
+  AND a.agent_id = b.agent_id
+  AND a.feedback_id = b.feedback_id
+  AND a.response_uri IS NOT DISTINCT FROM b.response_uri;
+
+-- Legacy rows keep a NULL log_index, which the unique constraint treats as distinct
+ALTER TABLE feedback_responses
+    ADD CONSTRAINT uq_feedback_responses_event UNIQUE (chain_id, tx_hash, log_index);
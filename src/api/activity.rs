@@ -1,13 +1,9 @@
-use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    response::IntoResponse,
-    routing::get,
-    Json, Router,
-};
+use axum::{extract::State, routing::get, Json, Router};
 
+use crate::api::error::ApiError;
+use crate::api::extract::ValidatedQuery;
 use crate::db;
-use crate::types::{ActivityParams, ErrorResponse, GlobalActivityResponse};
+use crate::types::{ActivityCursor, ActivityParams, GlobalActivityResponse, Pagination};
 use crate::AppState;
 
 pub fn router() -> Router<AppState> {
@@ -17,32 +13,34 @@ pub fn router() -> Router<AppState> {
 /// GET /api/activity — global activity feed across all agents
 async fn get_global_activity(
     State(state): State<AppState>,
-    Query(params): Query<ActivityParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let (activities, total) = db::activity::get_global_activities(
-        &state.pool,
-        params.event_type.as_deref(),
-        params.chain_id,
-        params.offset(),
-        params.limit(),
-    )
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to get global activities: {:?}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Internal Server Error".to_string(),
-                message: "Failed to fetch global activities".to_string(),
-                status: 500,
-            }),
+    ValidatedQuery(params): ValidatedQuery<ActivityParams>,
+) -> Result<Json<GlobalActivityResponse>, ApiError> {
+    let (mut activities, count) = state.db.read(|pool| {
+        db::activity::get_global_activities(
+            pool,
+            params.event_type.as_deref(),
+            params.chain_id,
+            params.before,
+            params.from,
+            params.to,
+            params.count_mode,
+            params.offset(),
+            params.limit() + 1,
         )
-    })?;
+    })
+    .await
+    .map_err(|e| ApiError::internal(e, "Failed to fetch global activities"))?;
+
+    let pagination = Pagination::from_rows(&mut activities, count, params.page(), params.limit());
+    let next_cursor = activities
+        .last()
+        .filter(|_| pagination.has_next)
+        .and_then(|last| ActivityCursor::after(last.block_timestamp, last.id))
+        .map(|cursor| cursor.encode());
 
     Ok(Json(GlobalActivityResponse {
         activities,
-        total,
-        page: params.page(),
-        limit: params.limit(),
+        pagination,
+        next_cursor,
     }))
 }
@@ -0,0 +1,218 @@
+use std::sync::atomic::Ordering;
+
+use axum::{
+    extract::{rejection::JsonRejection, MatchedPath, Path, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+
+use crate::api::agents::parse_agent_id;
+use crate::api::limits;
+use crate::api::error::ApiError;
+use crate::api::request_id;
+use crate::config::mask_rpc_url;
+use crate::db;
+use crate::indexer::metadata::MetadataJob;
+use crate::indexer::{BLOCK_BATCH_SIZE, PARALLEL_BATCHES, POLL_INTERVAL_MS};
+use crate::types::{
+    AdminChainConfig, AdminConfigResponse, AdminFeatureFlags, AdminIndexerSettings, AdminSkippedChain, IndexerPauseResponse,
+    IndexerToggleResponse, RefetchMetadataRequest, RefetchMetadataResponse,
+};
+use crate::AppState;
+
+/// Routes under /api/admin, all behind `require_admin_key`.
+pub fn router(state: &AppState) -> Router<AppState> {
+    let router = Router::new()
+        .route("/config", get(get_config))
+        .route("/indexer/{chain_id}/enable", post(enable_indexer))
+        .route("/indexer/{chain_id}/disable", post(disable_indexer))
+        .route("/indexer/pause", post(pause_indexer))
+        .route("/indexer/resume", post(resume_indexer))
+        .route("/agents/refetch-metadata", post(refetch_metadata))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_admin_key));
+    limits::with_json_body_limit(router)
+}
+
+/// Let the request through only with the admin key as `Authorization: Bearer <key>` (or the
+/// older `x-api-key` header), compared in constant time. 503 when no key is configured, 401
+/// otherwise. Every call is logged with its request id and action (the route template).
+async fn require_admin_key(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let action = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| format!("{} {}", req.method(), path.as_str()))
+        .unwrap_or_else(|| req.method().to_string());
+    let request_id = request_id::current().unwrap_or_default();
+
+    let Some(expected) = state.admin_api_key.as_deref() else {
+        tracing::warn!(request_id = %request_id, action = %action, "Admin call refused: ADMIN_API_KEY not set");
+        return ApiError::Unavailable("Admin API is disabled (ADMIN_API_KEY not set)".to_string()).into_response();
+    };
+    match provided_key(req.headers()) {
+        Some(key) if constant_time_eq(key.as_bytes(), expected.as_bytes()) => {}
+        _ => {
+            tracing::warn!(request_id = %request_id, action = %action, "Admin call refused: missing or invalid API key");
+            return ApiError::Unauthorized("Missing or invalid API key".to_string()).into_response();
+        }
+    }
+
+    tracing::info!(request_id = %request_id, action = %action, path = %req.uri().path(), "Admin call");
+    next.run(req).await
+}
+
+fn provided_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get("x-api-key").and_then(|v| v.to_str().ok()))
+}
+
+/// GET /api/admin/config — the loaded chain list, indexer batch settings and feature flags,
+/// straight from the in-memory config, for debugging a chain that isn't indexing
+async fn get_config(State(state): State<AppState>) -> Json<AdminConfigResponse> {
+    let config = &state.config;
+    let chains = config
+        .chains
+        .iter()
+        .map(|chain| AdminChainConfig {
+            chain_id: chain.chain_id,
+            rpc_url: mask_rpc_url(&chain.rpc_url),
+            identity_address: format!("{:#x}", chain.identity_address),
+            reputation_address: format!("{:#x}", chain.reputation_address),
+            marketplace_address: chain.marketplace_address.map(|a| format!("{:#x}", a)),
+            start_block: chain.start_block,
+            reputation_start_block: chain.reputation_start_block,
+            marketplace_start_block: chain.marketplace_start_block,
+            dry_run: chain.dry_run,
+            skip_block_timestamps: chain.skip_block_timestamps,
+            confirmations: chain.confirmations,
+            indexing_enabled: state
+                .indexer_flags
+                .get(&chain.chain_id)
+                .is_some_and(|flag| flag.load(Ordering::Acquire)),
+        })
+        .collect();
+
+    let skipped_chains = config
+        .skipped_chains
+        .iter()
+        .map(|skipped| AdminSkippedChain { chain_id: skipped.chain_id, error: skipped.error.clone() })
+        .collect();
+
+    Json(AdminConfigResponse {
+        chains,
+        skipped_chains,
+        indexer: AdminIndexerSettings {
+            block_batch_size: BLOCK_BATCH_SIZE,
+            parallel_batches: PARALLEL_BATCHES,
+            poll_interval_ms: POLL_INTERVAL_MS,
+            marketplace_config_sync_interval_secs: config.marketplace_config_sync_interval.as_secs(),
+        },
+        features: AdminFeatureFlags {
+            enable_indexer: config.enable_indexer,
+            indexer_paused: state.indexer_paused.load(Ordering::Acquire),
+            rebuild_agent_stats: config.rebuild_agent_stats,
+            verify_feedback_content: config.verify_feedback_content,
+            activity_retention_days: config.activity_retention_days,
+            strict_query_params: config.limits.strict_query_params,
+            agent_stats_legacy_aggregation: config.limits.agent_stats_legacy_aggregation,
+        },
+    })
+}
+
+/// POST /api/admin/indexer/:chain_id/enable — resume indexing a chain
+async fn enable_indexer(
+    State(state): State<AppState>,
+    Path(chain_id): Path<i32>,
+) -> Result<Json<IndexerToggleResponse>, ApiError> {
+    set_indexer_enabled(&state, chain_id, true)
+}
+
+/// POST /api/admin/indexer/:chain_id/disable — pause indexing a chain until re-enabled
+async fn disable_indexer(
+    State(state): State<AppState>,
+    Path(chain_id): Path<i32>,
+) -> Result<Json<IndexerToggleResponse>, ApiError> {
+    set_indexer_enabled(&state, chain_id, false)
+}
+
+fn set_indexer_enabled(
+    state: &AppState,
+    chain_id: i32,
+    enabled: bool,
+) -> Result<Json<IndexerToggleResponse>, ApiError> {
+    let flag = state.indexer_flags.get(&chain_id).ok_or_else(|| {
+        ApiError::NotFound(format!("Chain {} is not configured for indexing", chain_id))
+    })?;
+    flag.store(enabled, Ordering::Release);
+    tracing::info!(
+        chain_id = chain_id,
+        "Admin {} indexing for chain {}",
+        if enabled { "enabled" } else { "disabled" },
+        chain_id
+    );
+
+    Ok(Json(IndexerToggleResponse { chain_id, enabled }))
+}
+
+/// POST /api/admin/indexer/pause — stop indexing every chain once its current cycle is done
+async fn pause_indexer(State(state): State<AppState>) -> Json<IndexerPauseResponse> {
+    set_indexer_paused(&state, true)
+}
+
+/// POST /api/admin/indexer/resume — undo /pause; per-chain flags still apply
+async fn resume_indexer(State(state): State<AppState>) -> Json<IndexerPauseResponse> {
+    set_indexer_paused(&state, false)
+}
+
+fn set_indexer_paused(state: &AppState, paused: bool) -> Json<IndexerPauseResponse> {
+    state.indexer_paused.store(paused, Ordering::Release);
+    tracing::info!("Admin {} the indexer", if paused { "paused" } else { "resumed" });
+    Json(IndexerPauseResponse { paused })
+}
+
+/// POST /api/admin/agents/refetch-metadata — queue metadata fetches for the agents given by
+/// `ids` or matching `chain_id` / `missing_name`; progress shows on /api/indexer/status
+async fn refetch_metadata(
+    State(state): State<AppState>,
+    body: Result<Json<RefetchMetadataRequest>, JsonRejection>,
+) -> Result<Json<RefetchMetadataResponse>, ApiError> {
+    let Json(request) = body.map_err(|e| match e.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => ApiError::PayloadTooLarge(e.body_text()),
+        _ => ApiError::BadRequest(e.body_text()),
+    })?;
+    if request.ids.is_none() && request.chain_id.is_none() && !request.missing_name {
+        return Err(ApiError::BadRequest(
+            "Give either ids or a filter (chain_id, missing_name)".to_string(),
+        ));
+    }
+    let ids = request
+        .ids
+        .as_deref()
+        .map(|ids| ids.iter().map(|id| parse_agent_id(id)).collect::<Result<Vec<_>, _>>())
+        .transpose()?;
+
+    let agents = db::agents::get_agent_uris(state.db.primary(), ids.as_deref(), request.chain_id, request.missing_name)
+        .await
+        .map_err(|e| ApiError::internal(e, "Failed to select agents for refetch"))?;
+
+    let mut queued = 0;
+    for (chain_id, agent_id, uri) in &agents {
+        let job = MetadataJob { agent_id: *agent_id, chain_id: *chain_id, uri: uri.clone() };
+        if state.metadata_queue.try_enqueue(job) {
+            queued += 1;
+        }
+    }
+    tracing::info!(matched = agents.len(), queued, "Admin queued metadata refetch");
+
+    Ok(Json(RefetchMetadataResponse { matched: agents.len(), queued }))
+}
+
+/// Compare without short-circuiting on the first differing byte.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
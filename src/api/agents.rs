@@ -1,15 +1,21 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, State},
+    response::Response,
     routing::get,
     Json, Router,
 };
 
+use bigdecimal::BigDecimal;
+
+use crate::api::error::ApiError;
+use crate::api::extract::ValidatedQuery;
+use crate::api::image_proxy;
+use crate::api::validate::known_chain;
 use crate::db;
 use crate::types::{
     ActivityParams, ActivityResponse, AgentDetailResponse, AgentListParams, AgentListResponse,
-    ErrorResponse, PaginationParams, ReputationParams, ReputationResponse,
+    AgentOwnersResponse, CapabilityListResponse, CapabilityParams, FeedbackVelocityResponse, FeedbackResponseListResponse, FeedbackResponseParams, Pagination,
+    sync_checkpoint, PaginationParams, ReputationParams, ReputationResponse, SyncCursor, TotalCount,
 };
 use crate::AppState;
 
@@ -18,45 +24,32 @@ pub fn router() -> Router<AppState> {
         .route("/agents", get(list_agents))
         .route("/agents/{id}", get(get_agent))
         .route("/agents/{id}/reputation", get(get_agent_reputation))
+        .route("/agents/{id}/feedback-velocity", get(get_agent_feedback_velocity))
+        .route("/agents/{id}/responses", get(get_agent_responses))
         .route("/agents/{id}/activity", get(get_agent_activity))
+        .route("/agents/{id}/owners", get(get_agent_owners))
         .route("/agents/{id}/marketplace", get(get_agent_marketplace))
+        .route("/agents/{id}/image", get(get_agent_image))
+        .route("/capabilities", get(list_capabilities))
 }
 
 /// Parse an agent path ID in the format "chainId-agentId" (e.g., "143-1")
-fn parse_agent_id(id: &str) -> Result<(i32, i64), (StatusCode, Json<ErrorResponse>)> {
+pub(crate) fn parse_agent_id(id: &str) -> Result<(i32, i64), ApiError> {
     let parts: Vec<&str> = id.splitn(2, '-').collect();
     if parts.len() != 2 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Bad Request".to_string(),
-                message: format!("Invalid agent id format '{}'. Expected 'chainId-agentId'.", id),
-                status: 400,
-            }),
-        ));
+        return Err(ApiError::BadRequest(format!(
+            "Invalid agent id format '{}'. Expected 'chainId-agentId'.",
+            id
+        )));
     }
 
-    let chain_id: i32 = parts[0].parse().map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Bad Request".to_string(),
-                message: format!("Invalid chain_id in '{}'", id),
-                status: 400,
-            }),
-        )
-    })?;
-
-    let agent_id: i64 = parts[1].parse().map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Bad Request".to_string(),
-                message: format!("Invalid agent_id in '{}'", id),
-                status: 400,
-            }),
-        )
-    })?;
+    let chain_id: i32 = parts[0]
+        .parse()
+        .map_err(|_| ApiError::BadRequest(format!("Invalid chain_id in '{}'", id)))?;
+
+    let agent_id: i64 = parts[1]
+        .parse()
+        .map_err(|_| ApiError::BadRequest(format!("Invalid agent_id in '{}'", id)))?;
 
     Ok((chain_id, agent_id))
 }
@@ -64,78 +57,107 @@ fn parse_agent_id(id: &str) -> Result<(i32, i64), (StatusCode, Json<ErrorRespons
 /// GET /api/agents — list agents with optional filters and pagination
 async fn list_agents(
     State(state): State<AppState>,
-    Query(params): Query<AgentListParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let (agents, total) = db::agents::get_agents(
-        &state.pool,
-        params.chain_id,
-        params.search.as_deref(),
-        params.category.as_deref(),
-        params.owner.as_deref(),
-        params.sort(),
-        params.offset(),
-        params.limit(),
-    )
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to get agents: {:?}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Internal Server Error".to_string(),
-                message: "Failed to fetch agents".to_string(),
-                status: 500,
-            }),
+    ValidatedQuery(params): ValidatedQuery<AgentListParams>,
+) -> Result<Json<AgentListResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let sync = SyncCursor::start(params.updated_since, params.sync_cursor);
+    let (mut agents, count) = state.db.read(|pool| {
+        db::agents::get_agents(
+            pool,
+            params.chain_id,
+            params.search.as_deref(),
+            params.category.as_deref(),
+            params.owner.as_deref(),
+            params.protocol.as_deref(),
+            params.capability.as_deref(),
+            sync,
+            params.sort(),
+            params.search_mode(),
+            params.count_mode,
+            params.offset(),
+            params.limit() + 1,
         )
-    })?;
+    })
+    .await
+    .map_err(|e| ApiError::internal(e, "Failed to fetch agents"))?;
+
+    let pagination = Pagination::from_rows(&mut agents, count, params.page(), params.limit());
+    if params.include_top_tag {
+        let keys: Vec<(i32, i64)> = agents.iter().map(|a| (a.chain_id, a.agent_id)).collect();
+        let mut top_tags = state.db.read(|pool| db::agents::get_top_tags(pool, &keys))
+            .await
+            .map_err(|e| ApiError::internal(e, "Failed to fetch agent tag scores"))?;
+        for agent in &mut agents {
+            agent.top_tag = top_tags.remove(&(agent.chain_id, agent.agent_id));
+        }
+    }
+    let sync_cursor = sync_checkpoint(
+        sync,
+        &agents,
+        pagination.has_next,
+        state.config.sync_settled_before(),
+        |a| SyncCursor::after(a.updated_at, a.id),
+    )
+    .map(|cursor| cursor.encode());
 
     Ok(Json(AgentListResponse {
         agents,
-        total,
-        page: params.page(),
-        limit: params.limit(),
+        pagination,
+        sync_cursor,
     }))
 }
 
+/// GET /api/capabilities — distinct `metadata.capabilities` values with agent counts, for
+/// building `/api/agents?capability=` filters
+async fn list_capabilities(
+    State(state): State<AppState>,
+    ValidatedQuery(params): ValidatedQuery<CapabilityParams>,
+) -> Result<Json<CapabilityListResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let capabilities = state
+        .db
+        .read(|pool| db::agents::get_capabilities(pool, params.chain_id))
+        .await
+        .map_err(|e| ApiError::internal(e, "Failed to fetch capabilities"))?;
+
+    Ok(Json(CapabilityListResponse { capabilities }))
+}
+
 /// GET /api/agents/:id — get single agent detail
 async fn get_agent(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<AgentDetailResponse>, ApiError> {
     let (chain_id, agent_id) = parse_agent_id(&id)?;
 
-    let agent = db::agents::get_agent_by_id(&state.pool, agent_id, chain_id)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to get agent: {:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Internal Server Error".to_string(),
-                    message: "Failed to fetch agent".to_string(),
-                    status: 500,
-                }),
-            )
-        })?;
+    let (agent, activity) = tokio::join!(
+        state.db.read(|pool| db::agents::get_agent_by_id(pool, agent_id, chain_id)),
+        state.db.read(|pool| db::activity::get_activity_span(pool, agent_id, chain_id)),
+    );
+    let agent = agent.map_err(|e| ApiError::internal(e, "Failed to fetch agent"))?;
 
     match agent {
         Some(a) => {
-            let scores = db::agents::get_scores_by_tag(&state.pool, agent_id, chain_id)
+            let activity = activity.map_err(|e| ApiError::internal(e, "Failed to fetch agent activity"))?;
+            let scores = state.db.read(|pool| db::agents::get_scores_by_tag(pool, agent_id, chain_id))
                 .await
                 .unwrap_or_default();
-            Ok(Json(AgentDetailResponse {
-                agent: a,
-                scores,
-            }))
+            // The agent's NFT is its token on the chain's identity registry
+            let last_sale = match state.config.chains.iter().find(|c| c.chain_id == chain_id) {
+                Some(chain) => {
+                    let identity = format!("{:#x}", chain.identity_address);
+                    let token_id = BigDecimal::from(agent_id);
+                    state
+                        .db
+                        .read(|pool| db::marketplace::get_last_sale_for_token(pool, chain_id, &identity, &token_id))
+                        .await
+                        .map_err(|e| ApiError::internal(e, "Failed to fetch last sale"))?
+                }
+                None => None,
+            };
+            Ok(Json(AgentDetailResponse::new(a, activity, scores, last_sale)))
         }
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Not found".to_string(),
-                message: format!("Agent with id {} not found", id),
-                status: 404,
-            }),
-        )),
+        None => Err(ApiError::NotFound(format!("Agent with id {} not found", id))),
     }
 }
 
@@ -143,38 +165,18 @@ async fn get_agent(
 async fn get_agent_reputation(
     State(state): State<AppState>,
     Path(id): Path<String>,
-    Query(params): Query<ReputationParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    ValidatedQuery(params): ValidatedQuery<ReputationParams>,
+) -> Result<Json<ReputationResponse>, ApiError> {
     let (chain_id, agent_id) = parse_agent_id(&id)?;
     let range = params.range();
 
-    let history = db::feedbacks::get_reputation_history(&state.pool, agent_id, chain_id, range)
+    let history = state.db.read(|pool| db::feedbacks::get_reputation_history(pool, agent_id, chain_id, range))
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to get reputation history: {:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Internal Server Error".to_string(),
-                    message: "Failed to fetch reputation history".to_string(),
-                    status: 500,
-                }),
-            )
-        })?;
-
-    let feedbacks = db::feedbacks::get_feedbacks_for_agent(&state.pool, agent_id, chain_id, range)
+        .map_err(|e| ApiError::internal(e, "Failed to fetch reputation history"))?;
+
+    let feedbacks = state.db.read(|pool| db::feedbacks::get_feedbacks_for_agent(pool, agent_id, chain_id, range))
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to get feedbacks: {:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Internal Server Error".to_string(),
-                    message: "Failed to fetch feedbacks".to_string(),
-                    status: 500,
-                }),
-            )
-        })?;
+        .map_err(|e| ApiError::internal(e, "Failed to fetch feedbacks"))?;
 
     // Compute current score from the latest history point or overall average
     let current_score = history.last().and_then(|h| h.score);
@@ -188,77 +190,158 @@ async fn get_agent_reputation(
     }))
 }
 
-/// GET /api/agents/:id/activity — get activity log for an agent
-async fn get_agent_activity(
+/// GET /api/agents/:id/feedback-velocity — feedbacks per day over `range`, with spike days
+/// flagged (for spotting brigading)
+async fn get_agent_feedback_velocity(
     State(state): State<AppState>,
     Path(id): Path<String>,
-    Query(params): Query<ActivityParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    ValidatedQuery(params): ValidatedQuery<ReputationParams>,
+) -> Result<Json<FeedbackVelocityResponse>, ApiError> {
     let (chain_id, agent_id) = parse_agent_id(&id)?;
+    let range = params.range();
+
+    let mut days = state.db.read(|pool| db::feedbacks::get_feedback_velocity(pool, agent_id, chain_id, range))
+        .await
+        .map_err(|e| ApiError::internal(e, "Failed to fetch feedback velocity"))?;
+    let spike_factor = db::feedbacks::VELOCITY_SPIKE_FACTOR;
+    let average_per_day = db::feedbacks::mark_spikes(&mut days, spike_factor);
 
-    let (activities, total) = db::activity::get_activities(
-        &state.pool,
+    Ok(Json(FeedbackVelocityResponse {
         agent_id,
         chain_id,
-        params.event_type.as_deref(),
-        params.offset(),
-        params.limit(),
-    )
+        average_per_day,
+        spike_factor,
+        days,
+    }))
+}
+
+/// GET /api/agents/:id/responses — responses to the agent's feedbacks, newest first
+async fn get_agent_responses(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ValidatedQuery(params): ValidatedQuery<FeedbackResponseParams>,
+) -> Result<Json<FeedbackResponseListResponse>, ApiError> {
+    let (chain_id, agent_id) = parse_agent_id(&id)?;
+
+    let (mut responses, total) = state.db.read(|pool| {
+        db::feedbacks::get_feedback_responses(
+            pool,
+            agent_id,
+            chain_id,
+            params.feedback_index,
+            params.offset(),
+            params.limit() + 1,
+        )
+    })
     .await
-    .map_err(|e| {
-        tracing::error!("Failed to get activities: {:?}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Internal Server Error".to_string(),
-                message: "Failed to fetch activities".to_string(),
-                status: 500,
-            }),
+    .map_err(|e| ApiError::internal(e, "Failed to fetch feedback responses"))?;
+
+    let pagination = Pagination::from_rows(&mut responses, TotalCount::exact(total), params.page(), params.limit());
+
+    Ok(Json(FeedbackResponseListResponse {
+        responses,
+        pagination,
+    }))
+}
+
+/// GET /api/agents/:id/activity — get activity log for an agent
+async fn get_agent_activity(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ValidatedQuery(params): ValidatedQuery<ActivityParams>,
+) -> Result<Json<ActivityResponse>, ApiError> {
+    let (chain_id, agent_id) = parse_agent_id(&id)?;
+
+    let (mut activities, total) = state.db.read(|pool| {
+        db::activity::get_activities(
+            pool,
+            agent_id,
+            chain_id,
+            params.event_type.as_deref(),
+            params.include_archived,
+            params.from,
+            params.to,
+            params.offset(),
+            params.limit() + 1,
         )
-    })?;
+    })
+    .await
+    .map_err(|e| ApiError::internal(e, "Failed to fetch activities"))?;
+
+    let pagination = Pagination::from_rows(&mut activities, TotalCount::exact(total), params.page(), params.limit());
 
     Ok(Json(ActivityResponse {
         activities,
-        total,
-        page: params.page(),
-        limit: params.limit(),
+        pagination,
     }))
 }
 
+/// GET /api/agents/:id/owners — ownership history of the agent NFT, mint first
+async fn get_agent_owners(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<AgentOwnersResponse>, ApiError> {
+    let (chain_id, agent_id) = parse_agent_id(&id)?;
+
+    let transfers = state.db.read(|pool| db::agents::get_ownership_history(pool, agent_id, chain_id))
+        .await
+        .map_err(|e| ApiError::internal(e, "Failed to fetch ownership history"))?;
+
+    Ok(Json(AgentOwnersResponse {
+        agent_id,
+        chain_id,
+        transfers,
+    }))
+}
+
+/// GET /api/agents/:id/image — the agent's metadata image, fetched server-side so frontends
+/// avoid mixed-content, CORS and hotlinking problems (see `image_proxy::proxy_image`)
+async fn get_agent_image(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Response, ApiError> {
+    let (chain_id, agent_id) = parse_agent_id(&id)?;
+
+    let image = state.db.read(|pool| db::agents::get_agent_image(pool, agent_id, chain_id))
+        .await
+        .map_err(|e| ApiError::internal(e, "Failed to fetch agent"))?;
+
+    match image {
+        Some(Some(uri)) if !uri.trim().is_empty() => image_proxy::proxy_image(uri.trim()).await,
+        Some(_) => Err(ApiError::NotFound(format!("Agent with id {} has no image", id))),
+        None => Err(ApiError::NotFound(format!("Agent with id {} not found", id))),
+    }
+}
+
 /// GET /api/agents/:id/marketplace — get marketplace activity for an agent NFT
 async fn get_agent_marketplace(
     State(state): State<AppState>,
     Path(id): Path<String>,
-    Query(params): Query<PaginationParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    ValidatedQuery(params): ValidatedQuery<PaginationParams>,
+) -> Result<Json<ActivityResponse>, ApiError> {
     let (chain_id, agent_id) = parse_agent_id(&id)?;
 
     // Fetch marketplace-related activities for this agent
-    let (activities, total) = db::activity::get_activities(
-        &state.pool,
-        agent_id,
-        chain_id,
-        Some("marketplace"),
-        params.offset(),
-        params.limit(),
-    )
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to get agent marketplace history: {:?}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Internal Server Error".to_string(),
-                message: "Failed to fetch marketplace history".to_string(),
-                status: 500,
-            }),
+    let (mut activities, total) = state.db.read(|pool| {
+        db::activity::get_activities(
+            pool,
+            agent_id,
+            chain_id,
+            Some("marketplace"),
+            false,
+            None,
+            None,
+            params.offset(),
+            params.limit() + 1,
         )
-    })?;
+    })
+    .await
+    .map_err(|e| ApiError::internal(e, "Failed to fetch marketplace history"))?;
+
+    let pagination = Pagination::from_rows(&mut activities, TotalCount::exact(total), params.page(), params.limit());
 
     Ok(Json(ActivityResponse {
         activities,
-        total,
-        page: params.page(),
-        limit: params.limit(),
+        pagination,
     }))
 }
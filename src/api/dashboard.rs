@@ -0,0 +1,61 @@
+use axum::{extract::State, routing::get, Json, Router};
+
+use crate::api::stats::query_stats;
+use crate::db;
+use crate::types::{CountMode, DashboardResponse, DashboardWarning};
+use crate::AppState;
+
+const LEADERBOARD_SIZE: i64 = 10;
+const ACTIVITY_SIZE: i64 = 20;
+const SALES_SIZE: i64 = 10;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/dashboard", get(get_dashboard))
+}
+
+/// GET /api/dashboard — stats, leaderboard, activity and sales for the home page in one
+/// call. The four reads run concurrently and fail independently: a failed section is
+/// logged, nulled and listed in `warnings`, so the response is always a 200.
+async fn get_dashboard(State(state): State<AppState>) -> Json<DashboardResponse> {
+    let db = &state.db;
+    let (stats, leaderboard, activities, sales) = tokio::join!(
+        state.stats_cache.get_or_compute(|| db.read(query_stats)),
+        db.read(|pool| db::agents::get_leaderboard(pool, None, None, "desc", false, LEADERBOARD_SIZE)),
+        db.read(|pool| db::activity::get_global_activities(pool, None, None, None, None, None, CountMode::None, 0, ACTIVITY_SIZE)),
+        db.read(|pool| db::marketplace::get_recent_sales(pool, SALES_SIZE)),
+    );
+
+    let mut warnings = Vec::new();
+    Json(DashboardResponse {
+        stats: section(stats, "stats", "Failed to fetch stats", &mut warnings),
+        leaderboard: section(leaderboard, "leaderboard", "Failed to fetch leaderboard", &mut warnings),
+        activities: section(
+            activities.map(|(activities, _)| activities),
+            "activities",
+            "Failed to fetch global activities",
+            &mut warnings,
+        ),
+        sales: section(sales, "sales", "Failed to fetch recent sales", &mut warnings),
+        warnings,
+    })
+}
+
+/// The section's data, or None with a warning (and the error logged) when its read failed.
+fn section<T, E: std::fmt::Debug>(
+    result: Result<T, E>,
+    name: &str,
+    message: &str,
+    warnings: &mut Vec<DashboardWarning>,
+) -> Option<T> {
+    match result {
+        Ok(data) => Some(data),
+        Err(e) => {
+            tracing::error!(section = name, "{}: {:?}", message, e);
+            warnings.push(DashboardWarning {
+                section: name.to_string(),
+                message: message.to_string(),
+            });
+            None
+        }
+    }
+}
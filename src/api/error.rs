@@ -0,0 +1,193 @@
+use std::sync::Arc;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::api::request_id;
+use crate::types::{ErrorResponse, FieldIssue};
+
+/// SQLSTATE `query_canceled`, raised when a statement exceeds `statement_timeout`.
+const QUERY_CANCELED: &str = "57014";
+
+/// Error returned by API handlers, rendered as the standard `ErrorResponse` JSON.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    /// 404 with the given message.
+    #[error("{0}")]
+    NotFound(String),
+    /// 400 with the given message.
+    #[error("{0}")]
+    BadRequest(String),
+    /// 422 with code `INVALID_QUERY`, listing every offending query field or path parameter.
+    #[error("invalid query: {0:?}")]
+    Validation(Vec<FieldError>),
+    /// 401 with the given message.
+    #[error("{0}")]
+    Unauthorized(String),
+    /// 403 with the given message.
+    #[error("{0}")]
+    Forbidden(String),
+    /// 400 with code `UNKNOWN_CHAIN`, for a `chain_id` this deployment doesn't index. Lists
+    /// the configured chain ids so the client can correct the request.
+    #[error("unknown chain_id {chain_id}")]
+    UnknownChain { chain_id: i32, configured: Vec<i32> },
+    /// 413 with code `PAYLOAD_TOO_LARGE`, for a request body over the route's limit.
+    #[error("{0}")]
+    PayloadTooLarge(String),
+    /// 504 with code `REQUEST_TIMEOUT`, for a request that ran past the handler timeout.
+    #[error("request timed out")]
+    RequestTimeout,
+    /// 503 with the given message, for a feature the deployment hasn't configured.
+    #[error("{0}")]
+    Unavailable(String),
+    /// 502 with code `UPSTREAM_ERROR`, when a resource fetched on the client's behalf (e.g. a
+    /// proxied image) couldn't be retrieved or wasn't usable.
+    #[error("{0}")]
+    BadGateway(String),
+    /// A failed query. Statement timeouts become a 504 (code `QUERY_TIMEOUT`) so clients
+    /// can tell an expensive query from a server fault; anything else is a 500 carrying
+    /// `message`. The source is logged, never returned; it is shared so one failed query can
+    /// fail every request waiting on it (see `StatsCache`).
+    #[error("{message}: {source}")]
+    Internal {
+        message: &'static str,
+        #[source]
+        source: Arc<sqlx::Error>,
+    },
+}
+
+/// A query parameter that failed validation. `field` is None when the failure isn't tied
+/// to a single field (e.g. a malformed query string). `issue` is what is wrong with the value
+/// (reported per field); `message` is the full sentence.
+#[derive(Debug, Clone)]
+pub struct FieldError {
+    pub field: Option<String>,
+    pub issue: String,
+    pub message: String,
+}
+
+impl FieldError {
+    /// An error whose message stands on its own as the issue.
+    pub fn new(field: Option<&str>, message: impl Into<String>) -> Self {
+        let message = message.into();
+        FieldError {
+            field: field.map(str::to_string),
+            issue: message.clone(),
+            message,
+        }
+    }
+
+    /// A query parameter whose value is wrong in the way `issue` describes.
+    pub fn param(field: &str, issue: impl Into<String>) -> Self {
+        let issue = issue.into();
+        FieldError {
+            field: Some(field.to_string()),
+            message: format!("Invalid query parameter '{}': {}", field, issue),
+            issue,
+        }
+    }
+}
+
+impl ApiError {
+    /// A database error, reported to the client as `message`.
+    pub fn internal(source: impl Into<Arc<sqlx::Error>>, message: &'static str) -> Self {
+        ApiError::Internal { message, source: source.into() }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(source: sqlx::Error) -> Self {
+        ApiError::internal(source, "Database query failed")
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut fields = None;
+        let (status, error, message, code, field) = match self {
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, "Not found", message, None, None),
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, "Bad Request", message, None, None),
+            ApiError::Validation(errors) => {
+                let field = errors.iter().find_map(|e| e.field.clone());
+                fields = Some(
+                    errors
+                        .iter()
+                        .filter_map(|e| {
+                            let name = e.field.clone()?;
+                            Some(FieldIssue { name, issue: e.issue.clone() })
+                        })
+                        .collect(),
+                );
+                let message = errors
+                    .into_iter()
+                    .map(|e| e.message)
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                (StatusCode::UNPROCESSABLE_ENTITY, "Unprocessable Entity", message, Some("INVALID_QUERY"), field)
+            }
+            ApiError::UnknownChain { chain_id, configured } => {
+                let configured = configured.iter().map(i32::to_string).collect::<Vec<_>>().join(", ");
+                (
+                    StatusCode::BAD_REQUEST,
+                    "Bad Request",
+                    format!("Unknown chain_id {}; configured chains: {}", chain_id, configured),
+                    Some("UNKNOWN_CHAIN"),
+                    Some("chain_id".to_string()),
+                )
+            }
+            ApiError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, "Unauthorized", message, None, None),
+            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, "Forbidden", message, None, None),
+            ApiError::PayloadTooLarge(message) => {
+                (StatusCode::PAYLOAD_TOO_LARGE, "Payload Too Large", message, Some("PAYLOAD_TOO_LARGE"), None)
+            }
+            ApiError::RequestTimeout => (
+                StatusCode::GATEWAY_TIMEOUT,
+                "Gateway Timeout",
+                "Request took too long to complete".to_string(),
+                Some("REQUEST_TIMEOUT"),
+                None,
+            ),
+            ApiError::Unavailable(message) => {
+                (StatusCode::SERVICE_UNAVAILABLE, "Service Unavailable", message, None, None)
+            }
+            ApiError::BadGateway(message) => {
+                (StatusCode::BAD_GATEWAY, "Bad Gateway", message, Some("UPSTREAM_ERROR"), None)
+            }
+            ApiError::Internal { message, source } if is_statement_timeout(&source) => {
+                tracing::warn!("{}: statement timeout: {:?}", message, source);
+                (
+                    StatusCode::GATEWAY_TIMEOUT,
+                    "Gateway Timeout",
+                    "Query took too long; try narrowing the filters".to_string(),
+                    Some("QUERY_TIMEOUT"),
+                    None,
+                )
+            }
+            ApiError::Internal { message, source } => {
+                tracing::error!("{}: {:?}", message, source);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error", message.to_string(), None, None)
+            }
+        };
+
+        (
+            status,
+            Json(ErrorResponse {
+                error: error.to_string(),
+                message,
+                status: status.as_u16(),
+                code: code.map(str::to_string),
+                field,
+                fields,
+                request_id: request_id::current(),
+            }),
+        )
+            .into_response()
+    }
+}
+
+fn is_statement_timeout(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(db) if db.code().as_deref() == Some(QUERY_CANCELED))
+}
@@ -0,0 +1,103 @@
+use axum::{
+    extract::{
+        path::ErrorKind,
+        rejection::PathRejection,
+        FromRequestParts, Path, RawPathParams,
+    },
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+
+use crate::api::error::{ApiError, FieldError};
+use crate::api::validate::{is_strict, strict_paging, ValidateParams};
+
+/// Drop-in replacement for axum's `Query` that rejects with the standard
+/// `ErrorResponse` JSON (422, code `INVALID_QUERY`, plus the offending fields)
+/// instead of axum's plain-text 400. After deserializing it runs the params'
+/// `ValidateParams` checks and reports every failing field together. `strict=true`
+/// (or STRICT_QUERY_PARAMS) adds the paging checks, on every endpoint alike.
+pub struct ValidatedQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + ValidateParams,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let params: T = parse_query(query)?;
+        let mut errors = Vec::new();
+        params.validate(&mut errors);
+        if is_strict(query, &mut errors) {
+            strict_paging(&params, &mut errors);
+        }
+        if errors.is_empty() {
+            Ok(ValidatedQuery(params))
+        } else {
+            Err(ApiError::Validation(errors))
+        }
+    }
+}
+
+/// Deserialize a raw query string, tracking the path of the field that failed.
+fn parse_query<T: DeserializeOwned>(query: &str) -> Result<T, ApiError> {
+    let deserializer = serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+    serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        // serde_path_to_error reports "." when the failure isn't tied to a field
+        let error = match path.as_str() {
+            "." => FieldError::new(None, format!("Invalid query string: {}", e.inner())),
+            field => FieldError::param(field, e.inner().to_string()),
+        };
+        ApiError::Validation(vec![error])
+    })
+}
+
+/// Drop-in replacement for axum's `Path` with the same 422 `INVALID_QUERY` rejection as
+/// `ValidatedQuery`, naming the path parameter that failed (e.g. a malformed `address`).
+/// Route/extractor mismatches are programmer errors and keep axum's 500.
+pub struct ValidatedPath<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidatedPath<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Path::<T>::from_request_parts(parts, state).await {
+            Ok(Path(value)) => Ok(ValidatedPath(value)),
+            Err(PathRejection::FailedToDeserializePathParams(e)) if e.status() == StatusCode::BAD_REQUEST => {
+                let (field, detail) = match e.kind() {
+                    ErrorKind::DeserializeError { key, message, .. } => (Some(key.clone()), message.clone()),
+                    ErrorKind::ParseErrorAtKey { key, .. } | ErrorKind::InvalidUtf8InPathParam { key } => {
+                        (Some(key.clone()), e.kind().to_string())
+                    }
+                    // A single-value `ValidatedPath<T>` reports no key; name the route's only parameter
+                    kind => (single_path_param(parts, state).await, kind.to_string()),
+                };
+                let message = match &field {
+                    Some(f) => format!("Invalid path parameter '{}': {}", f, detail),
+                    None => format!("Invalid path parameter: {}", detail),
+                };
+                let error = FieldError { field, issue: detail, message };
+                Err(ApiError::Validation(vec![error]).into_response())
+            }
+            Err(rejection) => Err(rejection.into_response()),
+        }
+    }
+}
+
+/// The name of the matched route's path parameter, if it has exactly one.
+async fn single_path_param<S: Send + Sync>(parts: &mut Parts, state: &S) -> Option<String> {
+    let params = RawPathParams::from_request_parts(parts, state).await.ok()?;
+    let mut keys = params.iter().map(|(key, _)| key.to_string());
+    match (keys.next(), keys.next()) {
+        (Some(key), None) => Some(key),
+        _ => None,
+    }
+}
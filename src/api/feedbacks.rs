@@ -0,0 +1,25 @@
+use axum::{extract::State, routing::get, Json, Router};
+
+use crate::api::error::ApiError;
+use crate::api::extract::ValidatedQuery;
+use crate::db;
+use crate::types::{RecentFeedbackParams, RecentFeedbackResponse};
+use crate::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/feedbacks/recent", get(get_recent_feedbacks))
+}
+
+/// GET /api/feedbacks/recent — newest non-revoked feedbacks across all agents
+async fn get_recent_feedbacks(
+    State(state): State<AppState>,
+    ValidatedQuery(params): ValidatedQuery<RecentFeedbackParams>,
+) -> Result<Json<RecentFeedbackResponse>, ApiError> {
+    let feedbacks = state
+        .db
+        .read(|pool| db::feedbacks::get_recent_feedbacks(pool, params.chain_id, params.limit()))
+        .await
+        .map_err(|e| ApiError::internal(e, "Failed to fetch recent feedbacks"))?;
+
+    Ok(Json(RecentFeedbackResponse { feedbacks }))
+}
@@ -0,0 +1,213 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::api::error::ApiError;
+use crate::indexer::metadata::{parse_data_uri, resolve_uri, UriSource};
+
+/// Largest image the proxy serves, in bytes.
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Redirects followed before giving up; each target is checked like the original URL.
+const MAX_REDIRECTS: usize = 3;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Cache-Control on proxied images. A day: images behind a metadata URI rarely change, and
+/// an updated URI is picked up once the cached copy expires.
+pub const IMAGE_CACHE_CONTROL: &str = "public, max-age=86400";
+
+/// Keeps an SVG opened directly from the API origin from running scripts or loading anything.
+const IMAGE_CSP: &str = "default-src 'none'; style-src 'unsafe-inline'; sandbox";
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Serve the image behind an agent's `image` URI, resolved like metadata URIs
+/// (`resolve_uri`): `data:` URIs must carry an `image/*` media type, anything else is
+/// fetched (see `fetch_image`). Larger than `MAX_IMAGE_BYTES`, or not an image, is a 502.
+pub async fn proxy_image(uri: &str) -> Result<Response, ApiError> {
+    match resolve_uri(uri) {
+        UriSource::Data(data) => {
+            let (media_type, content) = parse_data_uri(data)
+                .map_err(|e| ApiError::BadGateway(format!("Invalid image data URI: {}", e)))?;
+            if !media_type.starts_with("image/") {
+                return Err(ApiError::BadGateway(format!(
+                    "Image data URI has media type {:?}, not an image",
+                    media_type
+                )));
+            }
+            if content.len() > MAX_IMAGE_BYTES {
+                return Err(too_large());
+            }
+            Ok(image_response(&media_type, Body::from(content)))
+        }
+        UriSource::Url(url) => fetch_image(&url).await,
+    }
+}
+
+/// Fetch `url` and stream it back with the upstream Content-Type, following up to
+/// `MAX_REDIRECTS` redirects by hand so every hop goes through `get_public`.
+async fn fetch_image(url: &str) -> Result<Response, ApiError> {
+    let mut url = reqwest::Url::parse(url).map_err(|_| ApiError::BadGateway("Image URI is not a valid URL".to_string()))?;
+
+    for _ in 0..=MAX_REDIRECTS {
+        let response = get_public(&url).await?;
+        let status = response.status();
+
+        if status.is_redirection() {
+            let location = response
+                .headers()
+                .get(header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| ApiError::BadGateway("Image host redirected without a Location".to_string()))?;
+            url = url
+                .join(location)
+                .map_err(|_| ApiError::BadGateway("Image host redirected to an invalid URL".to_string()))?;
+            continue;
+        }
+        if !status.is_success() {
+            return Err(ApiError::BadGateway(format!("Image host returned HTTP {}", status.as_u16())));
+        }
+
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if !content_type.to_ascii_lowercase().starts_with("image/") {
+            return Err(ApiError::BadGateway(format!(
+                "Image host returned {:?}, not an image",
+                content_type
+            )));
+        }
+        if response.content_length().is_some_and(|len| len > MAX_IMAGE_BYTES as u64) {
+            return Err(too_large());
+        }
+        return Ok(image_response(&content_type, capped_body(response)));
+    }
+
+    Err(ApiError::BadGateway("Image host redirected too many times".to_string()))
+}
+
+/// GET `url` without following redirects, once its scheme is http(s) and every address its
+/// host resolves to is public (`is_public_ip`). The request is pinned to the checked
+/// addresses, so a second DNS answer can't swap in a private one.
+async fn get_public(url: &reqwest::Url) -> Result<reqwest::Response, ApiError> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(ApiError::BadGateway(format!("Image URL scheme {:?} is not supported", url.scheme())));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| ApiError::BadGateway("Image URL has no host".to_string()))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let literal = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().ok();
+    let addrs: Vec<SocketAddr> = match literal {
+        Some(ip) => vec![SocketAddr::new(ip, port)],
+        None => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|_| ApiError::BadGateway(format!("Could not resolve image host {}", host)))?
+            .collect(),
+    };
+    if addrs.is_empty() || !addrs.iter().all(|addr| is_public_ip(addr.ip())) {
+        return Err(ApiError::Forbidden(format!("Image host {} is not a public address", host)));
+    }
+
+    let mut client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none());
+    if literal.is_none() {
+        client = client.resolve_to_addrs(host, &addrs);
+    }
+    let client = client
+        .build()
+        .map_err(|e| ApiError::BadGateway(format!("Failed to build HTTP client: {}", e)))?;
+
+    client.get(url.clone()).send().await.map_err(|e| {
+        tracing::warn!(url = %url, "Image fetch failed: {:?}", e);
+        ApiError::BadGateway(format!("Failed to fetch image from {}", host))
+    })
+}
+
+/// The upstream body as a stream, cut off with an error once it passes `MAX_IMAGE_BYTES`
+/// (for hosts that send no Content-Length, or a wrong one).
+fn capped_body(response: reqwest::Response) -> Body {
+    let chunks = futures_util::stream::try_unfold((response, 0usize), |(mut response, read)| async move {
+        match response.chunk().await? {
+            Some(chunk) => {
+                let read = read + chunk.len();
+                if read > MAX_IMAGE_BYTES {
+                    return Err::<_, BoxError>(format!("image is larger than {} bytes", MAX_IMAGE_BYTES).into());
+                }
+                Ok(Some((chunk, (response, read))))
+            }
+            None => Ok(None),
+        }
+    });
+    Body::from_stream(chunks)
+}
+
+fn image_response(content_type: &str, body: Body) -> Response {
+    let mut response = (StatusCode::OK, body).into_response();
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(content_type) {
+        headers.insert(header::CONTENT_TYPE, value);
+    }
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(IMAGE_CACHE_CONTROL));
+    headers.insert(header::CONTENT_SECURITY_POLICY, HeaderValue::from_static(IMAGE_CSP));
+    headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    response
+}
+
+fn too_large() -> ApiError {
+    ApiError::BadGateway(format!("Image is larger than {} bytes", MAX_IMAGE_BYTES))
+}
+
+/// Whether `ip` is globally routable. Loopback, private, link-local, CGNAT, documentation,
+/// multicast and reserved ranges are not, including IPv4 addresses embedded in IPv6
+/// (mapped `::ffff:a.b.c.d` and NAT64 `64:ff9b::a.b.c.d`).
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_ipv4(v4),
+        IpAddr::V6(v6) => is_public_ipv6(v6),
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0 // "this network"
+        || (a == 100 && (64..128).contains(&b)) // shared address space (CGNAT)
+        || (a == 192 && b == 0 && c == 0) // IETF protocol assignments
+        || (a == 198 && (b == 18 || b == 19)) // benchmarking
+        || a >= 240) // reserved
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_public_ipv4(v4);
+    }
+    let segments = ip.segments();
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [a, b, c, d] = ip.octets()[12..] else { unreachable!() };
+        return is_public_ipv4(Ipv4Addr::new(a, b, c, d));
+    }
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || (segments[0] & 0xfe00) == 0xfc00 // unique local
+        || (segments[0] & 0xffc0) == 0xfe80 // link-local
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8)) // documentation
+}
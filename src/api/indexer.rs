@@ -0,0 +1,44 @@
+use std::sync::atomic::Ordering;
+
+use axum::{
+    extract::State,
+    routing::get,
+    Json, Router,
+};
+
+use crate::api::error::ApiError;
+use crate::db;
+use crate::indexer;
+use crate::types::{ChainIndexerStatus, IndexerStatusResponse};
+use crate::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/indexer/status", get(get_indexer_status))
+}
+
+/// GET /api/indexer/status — per-chain cursors and enable flags, plus pool health
+async fn get_indexer_status(
+    State(state): State<AppState>,
+) -> Result<Json<IndexerStatusResponse>, ApiError> {
+    // Cursors come from the primary so the status never reflects replication lag
+    let cursors = db::indexer_state::get_all(state.db.primary()).await.map_err(|e| ApiError::internal(e, "Failed to fetch indexer status"))?;
+
+    let mut chain_ids: Vec<i32> = state.indexer_flags.keys().copied().collect();
+    chain_ids.sort_unstable();
+    let chains = chain_ids
+        .into_iter()
+        .map(|chain_id| ChainIndexerStatus {
+            chain_id,
+            enabled: indexer::chain_enabled(&state.indexer_flags, chain_id),
+            contracts: cursors.iter().filter(|c| c.chain_id == chain_id).cloned().collect(),
+        })
+        .collect();
+
+    Ok(Json(IndexerStatusResponse {
+        indexer_enabled: state.indexer_enabled,
+        paused: state.indexer_paused.load(Ordering::Acquire),
+        chains,
+        pools: state.pools.stats(),
+        metadata_queue: state.metadata_queue.status(),
+    }))
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
+
+use crate::api::error::ApiError;
+
+/// Default REQUEST_TIMEOUT_SECS: how long a handler may run before the client gets a 504.
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 15;
+
+/// Largest request body the JSON POST endpoints accept (bytes).
+pub const JSON_BODY_LIMIT: usize = 64 * 1024;
+
+/// Cut off any request still running after `timeout` with a 504 `REQUEST_TIMEOUT`. Long-lived
+/// routes (exports, streams) belong outside this layer with their own allowance.
+pub fn with_request_timeout<S: Clone + Send + Sync + 'static>(router: Router<S>, timeout: Duration) -> Router<S> {
+    router
+        .layer(TimeoutLayer::with_status_code(StatusCode::GATEWAY_TIMEOUT, timeout))
+        .layer(middleware::from_fn(json_error_bodies))
+}
+
+/// Reject request bodies over JSON_BODY_LIMIT with a 413 `PAYLOAD_TOO_LARGE`.
+pub fn with_json_body_limit<S: Clone + Send + Sync + 'static>(router: Router<S>) -> Router<S> {
+    router
+        .layer(RequestBodyLimitLayer::new(JSON_BODY_LIMIT))
+        .layer(middleware::from_fn(json_error_bodies))
+}
+
+/// Replace the bare responses tower_http's timeout (empty 504) and body limit (plain-text
+/// 413) layers produce with the standard JSON `ErrorResponse`. Handler errors are already
+/// JSON and pass through.
+async fn json_error_bodies(req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if is_json {
+        return response;
+    }
+    match response.status() {
+        StatusCode::GATEWAY_TIMEOUT => ApiError::RequestTimeout.into_response(),
+        StatusCode::PAYLOAD_TOO_LARGE => payload_too_large(),
+        _ => response,
+    }
+}
+
+/// The 413 for a body over JSON_BODY_LIMIT.
+pub fn payload_too_large() -> Response {
+    ApiError::PayloadTooLarge(format!("Request body is larger than {} bytes", JSON_BODY_LIMIT)).into_response()
+}
@@ -1,18 +1,29 @@
+use std::collections::HashMap;
+
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Path, State},
     routing::get,
     Json, Router,
 };
 
+use bigdecimal::{BigDecimal, RoundingMode, Zero};
+
+use crate::api::error::ApiError;
+use crate::api::extract::{ValidatedPath, ValidatedQuery};
+use crate::api::validate::known_chain;
 use crate::db;
+use crate::indexer::provider::ChainConfig;
 use crate::types::{
-    ErrorResponse, MarketplaceAuctionDetailResponse, MarketplaceAuctionListResponse,
-    MarketplaceAuctionParams, MarketplaceBundleListResponse, MarketplaceBundleParams,
+    MarketplaceAuction, MarketplaceAuctionCurrentPriceResponse, MarketplaceAuctionItem, MarketplaceAuctionDetailResponse, MarketplaceAuctionListResponse,
+    EthAddress, MarketplaceAuctionParams, MarketplaceBundleListResponse, MarketplaceBundleParams,
+    MarketplaceCollectionActivityParams, MarketplaceCollectionActivityResponse, MarketplaceCollectionStatsParams,
+    MarketplaceCollectionStatsResponse,
     MarketplaceCollectionOfferListResponse, MarketplaceCollectionOfferParams,
     MarketplaceDutchAuctionListResponse, MarketplaceListParams, MarketplaceListingListResponse,
-    MarketplaceOfferListResponse, MarketplaceOfferParams, MarketplaceUserParams,
+    MarketplaceOfferListResponse, MarketplaceOfferParams, MarketplaceSellerMetricsResponse, MarketplaceStatsResponse,
+    MarketplaceTokenListingsResponse, MarketplaceUserAuctionsResponse, MarketplaceUserOffersParams,
+    MarketplaceUserOffersResponse, MarketplaceUserParams, MarketplaceUserPortfolioResponse, Pagination, SyncCursor,
+    TotalCount, sync_checkpoint,
 };
 use crate::AppState;
 
@@ -24,86 +35,94 @@ pub fn router() -> Router<AppState> {
         .route("/marketplace/collection-offers", get(list_collection_offers))
         .route("/marketplace/auctions", get(list_auctions))
         .route("/marketplace/auctions/{id}", get(get_auction))
+        .route("/marketplace/auctions/{id}/current-price", get(get_auction_current_price))
         .route("/marketplace/dutch-auctions", get(list_dutch_auctions))
         .route("/marketplace/bundles", get(list_bundles))
+        .route("/marketplace/collections/{nft_contract}/activity", get(get_collection_activity))
+        .route("/marketplace/collections/{nft_contract}/stats", get(get_collection_stats))
+        .route("/marketplace/nft/{chain_id}/{nft_contract}/{token_id}/listings", get(get_token_listings))
         .route("/marketplace/user/{address}", get(get_user_portfolio))
+        .route("/marketplace/user/{address}/auctions", get(get_user_auctions))
+        .route("/marketplace/user/{address}/offers", get(get_user_offers))
+        .route("/marketplace/sellers/{address}/metrics", get(get_seller_metrics))
         .route("/marketplace/stats", get(get_marketplace_stats))
 }
 
 /// Parse a composite ID in the format "chainId-entityId" (e.g., "143-1")
-fn parse_id(id: &str) -> Result<(i32, i64), (StatusCode, Json<ErrorResponse>)> {
+fn parse_id(id: &str) -> Result<(i32, i64), ApiError> {
     let parts: Vec<&str> = id.splitn(2, '-').collect();
     if parts.len() != 2 {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Bad Request".to_string(),
-                message: format!("Invalid id format '{}'. Expected 'chainId-entityId'.", id),
-                status: 400,
-            }),
-        ));
+        return Err(ApiError::BadRequest(format!(
+            "Invalid id format '{}'. Expected 'chainId-entityId'.",
+            id
+        )));
     }
 
-    let chain_id: i32 = parts[0].parse().map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Bad Request".to_string(),
-                message: format!("Invalid chain_id in '{}'", id),
-                status: 400,
-            }),
-        )
-    })?;
-
-    let entity_id: i64 = parts[1].parse().map_err(|_| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Bad Request".to_string(),
-                message: format!("Invalid entity_id in '{}'", id),
-                status: 400,
-            }),
-        )
-    })?;
+    let chain_id: i32 = parts[0]
+        .parse()
+        .map_err(|_| ApiError::BadRequest(format!("Invalid chain_id in '{}'", id)))?;
+
+    let entity_id: i64 = parts[1]
+        .parse()
+        .map_err(|_| ApiError::BadRequest(format!("Invalid entity_id in '{}'", id)))?;
 
     Ok((chain_id, entity_id))
 }
 
-fn map_err(e: sqlx::Error) -> (StatusCode, Json<ErrorResponse>) {
-    tracing::error!("Marketplace DB error: {:?}", e);
-    (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json(ErrorResponse {
-            error: "Internal Server Error".to_string(),
-            message: "Failed to fetch marketplace data".to_string(),
-            status: 500,
-        }),
-    )
+/// Which configured chains have a marketplace contract, keyed by chain_id.
+/// Chains without one are still indexed for identity/reputation but never have marketplace data.
+fn marketplace_enabled_by_chain(chains: &[ChainConfig]) -> HashMap<String, bool> {
+    chains
+        .iter()
+        .map(|c| (c.chain_id.to_string(), c.marketplace_address.is_some()))
+        .collect()
+}
+
+fn map_err(e: sqlx::Error) -> ApiError {
+    ApiError::internal(e, "Failed to fetch marketplace data")
 }
 
 /// GET /api/marketplace/listings
 async fn list_listings(
     State(state): State<AppState>,
-    Query(params): Query<MarketplaceListParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let (listings, total) = db::marketplace::get_listings(
-        &state.pool,
-        params.chain_id,
-        params.nft_contract.as_deref(),
-        params.seller.as_deref(),
-        params.status(),
-        params.sort(),
-        params.offset(),
-        params.limit(),
-    )
+    ValidatedQuery(params): ValidatedQuery<MarketplaceListParams>,
+) -> Result<Json<MarketplaceListingListResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let sync = SyncCursor::start(params.updated_since, params.sync_cursor);
+    let (mut listings, count) = state.db.read(|pool| {
+        db::marketplace::get_listings(
+            pool,
+            params.chain_id,
+            params.nft_contract.as_deref(),
+            params.seller.as_deref(),
+            params.token_id.as_deref(),
+            params.status(),
+            params.exclude_status,
+            sync,
+            params.latest_per_token,
+            params.sort(),
+            params.count_mode,
+            params.offset(),
+            params.limit() + 1,
+        )
+    })
     .await
     .map_err(map_err)?;
 
+    let pagination = Pagination::from_rows(&mut listings, count, params.page(), params.limit());
+    let sync_cursor = sync_checkpoint(
+        sync,
+        &listings,
+        pagination.has_next,
+        state.config.sync_settled_before(),
+        |r| SyncCursor::after(r.updated_at, r.id),
+    )
+    .map(|cursor| cursor.encode());
+
     Ok(Json(MarketplaceListingListResponse {
         listings,
-        total,
-        page: params.page(),
-        limit: params.limit(),
+        pagination,
+        sync_cursor,
     }))
 }
 
@@ -111,120 +130,184 @@ async fn list_listings(
 async fn get_listing(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let (chain_id, listing_id) = parse_id(&id)?;
-    let listing = db::marketplace::get_listing_by_id(&state.pool, listing_id, chain_id)
+    let listing = state.db.read(|pool| db::marketplace::get_listing_by_id(pool, listing_id, chain_id, None))
         .await
         .map_err(map_err)?;
 
     match listing {
         Some(l) => {
-            // Embed agent data in the listing response to avoid a second API call
-            let token_id_i64 = l.token_id.to_string().parse::<i64>().unwrap_or(0);
-            let agent = db::agents::get_agent_by_id(&state.pool, token_id_i64, chain_id)
+            let last_sale = state
+                .db
+                .read(|pool| db::marketplace::get_last_sale_for_token(pool, chain_id, &l.nft_contract, &l.token_id))
                 .await
-                .ok()
-                .flatten();
-            let scores = if agent.is_some() {
-                db::agents::get_scores_by_tag(&state.pool, token_id_i64, chain_id)
+                .map_err(map_err)?;
+
+            // Embed agent data in the listing response to avoid a second API call
+            // Token ids beyond i64 can't be agents
+            let token_id_i64 = l.token_id.to_string().parse::<i64>().ok();
+            let agent = match token_id_i64 {
+                Some(token_id) => state.db.read(|pool| db::agents::get_agent_by_id(pool, token_id, chain_id))
                     .await
-                    .unwrap_or_default()
+                    .ok()
+                    .flatten(),
+                None => None,
+            };
+            let (activity, scores) = if let (Some(token_id), Some(_)) = (token_id_i64, &agent) {
+                let activity = state.db.read(|pool| db::activity::get_activity_span(pool, token_id, chain_id))
+                    .await
+                    .unwrap_or_default();
+                let scores = state.db.read(|pool| db::agents::get_scores_by_tag(pool, token_id, chain_id))
+                    .await
+                    .unwrap_or_default();
+                (activity, scores)
             } else {
-                vec![]
+                Default::default()
             };
 
             let mut response = serde_json::to_value(&l).unwrap();
+            response["last_sale"] = serde_json::to_value(&last_sale).unwrap();
             if let Some(a) = agent {
-                let agent_detail = crate::types::AgentDetailResponse { agent: a, scores };
+                let agent_detail = crate::types::AgentDetailResponse::new(a, activity, scores, last_sale);
                 response["agent"] = serde_json::to_value(&agent_detail).unwrap();
             }
             Ok(Json(response))
         }
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Not found".to_string(),
-                message: format!("Listing {} not found", id),
-                status: 404,
-            }),
-        )),
+        None => Err(ApiError::NotFound(format!("Listing {} not found", id))),
     }
 }
 
 /// GET /api/marketplace/offers
 async fn list_offers(
     State(state): State<AppState>,
-    Query(params): Query<MarketplaceOfferParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let (offers, total) = db::marketplace::get_offers(
-        &state.pool,
-        params.chain_id,
-        params.nft_contract.as_deref(),
-        params.token_id.as_deref(),
-        params.offerer.as_deref(),
-        params.status.as_deref(),
-        params.offset(),
-        params.limit(),
-    )
+    ValidatedQuery(params): ValidatedQuery<MarketplaceOfferParams>,
+) -> Result<Json<MarketplaceOfferListResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let sync = SyncCursor::start(params.updated_since, params.sync_cursor);
+    let (mut offers, count) = state.db.read(|pool| {
+        db::marketplace::get_offers(
+            pool,
+            params.chain_id,
+            params.nft_contract.as_deref(),
+            params.token_id.as_deref(),
+            params.offerer.as_deref(),
+            params.status,
+            params.expiring_after,
+            params.expiring_before,
+            sync,
+            params.sort(),
+            params.count_mode,
+            params.offset(),
+            params.limit() + 1,
+        )
+    })
     .await
     .map_err(map_err)?;
 
+    let pagination = Pagination::from_rows(&mut offers, count, params.page(), params.limit());
+    let sync_cursor = sync_checkpoint(
+        sync,
+        &offers,
+        pagination.has_next,
+        state.config.sync_settled_before(),
+        |r| SyncCursor::after(r.updated_at, r.id),
+    )
+    .map(|cursor| cursor.encode());
+
     Ok(Json(MarketplaceOfferListResponse {
         offers,
-        total,
-        page: params.page(),
-        limit: params.limit(),
+        pagination,
+        sync_cursor,
     }))
 }
 
 /// GET /api/marketplace/collection-offers
 async fn list_collection_offers(
     State(state): State<AppState>,
-    Query(params): Query<MarketplaceCollectionOfferParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let (offers, total) = db::marketplace::get_collection_offers(
-        &state.pool,
-        params.chain_id,
-        params.nft_contract.as_deref(),
-        params.offerer.as_deref(),
-        params.status.as_deref(),
-        params.offset(),
-        params.limit(),
-    )
+    ValidatedQuery(params): ValidatedQuery<MarketplaceCollectionOfferParams>,
+) -> Result<Json<MarketplaceCollectionOfferListResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let sync = SyncCursor::start(params.updated_since, params.sync_cursor);
+    let (mut offers, count) = state.db.read(|pool| {
+        db::marketplace::get_collection_offers(
+            pool,
+            params.chain_id,
+            params.nft_contract.as_deref(),
+            params.offerer.as_deref(),
+            params.status,
+            params.expiring_after,
+            params.expiring_before,
+            sync,
+            params.sort(),
+            params.count_mode,
+            params.offset(),
+            params.limit() + 1,
+        )
+    })
     .await
     .map_err(map_err)?;
 
+    let pagination = Pagination::from_rows(&mut offers, count, params.page(), params.limit());
+    let sync_cursor = sync_checkpoint(
+        sync,
+        &offers,
+        pagination.has_next,
+        state.config.sync_settled_before(),
+        |r| SyncCursor::after(r.updated_at, r.id),
+    )
+    .map(|cursor| cursor.encode());
+
     Ok(Json(MarketplaceCollectionOfferListResponse {
         offers,
-        total,
-        page: params.page(),
-        limit: params.limit(),
+        pagination,
+        sync_cursor,
     }))
 }
 
 /// GET /api/marketplace/auctions
 async fn list_auctions(
     State(state): State<AppState>,
-    Query(params): Query<MarketplaceAuctionParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let (auctions, total) = db::marketplace::get_auctions(
-        &state.pool,
-        params.chain_id,
-        params.nft_contract.as_deref(),
-        params.seller.as_deref(),
-        params.status.as_deref(),
-        params.sort(),
-        params.offset(),
-        params.limit(),
-    )
+    ValidatedQuery(params): ValidatedQuery<MarketplaceAuctionParams>,
+) -> Result<Json<MarketplaceAuctionListResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let sync = SyncCursor::start(params.updated_since, params.sync_cursor);
+    let (mut auctions, count) = state.db.read(|pool| {
+        db::marketplace::get_auctions(
+            pool,
+            params.chain_id,
+            params.nft_contract.as_deref(),
+            params.seller.as_deref(),
+            params.status,
+            params.exclude_malformed,
+            sync,
+            params.sort(),
+            params.count_mode,
+            params.offset(),
+            params.limit() + 1,
+        )
+    })
     .await
     .map_err(map_err)?;
 
+    let pagination = Pagination::from_rows(&mut auctions, count, params.page(), params.limit());
+    let sync_cursor = sync_checkpoint(
+        sync,
+        &auctions,
+        pagination.has_next,
+        state.config.sync_settled_before(),
+        |r| SyncCursor::after(r.updated_at, r.id),
+    )
+    .map(|cursor| cursor.encode());
+    let auctions = auctions
+        .into_iter()
+        .map(|a| auction_item(a, state.config.min_bid_increment_bps))
+        .collect();
+
     Ok(Json(MarketplaceAuctionListResponse {
         auctions,
-        total,
-        page: params.page(),
-        limit: params.limit(),
+        pagination,
+        sync_cursor,
     }))
 }
 
@@ -232,118 +315,367 @@ async fn list_auctions(
 async fn get_auction(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     let (chain_id, auction_id) = parse_id(&id)?;
-    let result = db::marketplace::get_auction_with_bids(&state.pool, auction_id, chain_id)
+    let result = state.db.read(|pool| db::marketplace::get_auction_with_bids(pool, auction_id, chain_id))
         .await
         .map_err(map_err)?;
 
     match result {
         Some((auction, bids)) => {
             // Embed agent data to avoid a second API call from frontend
-            let token_id_i64 = auction.token_id.to_string().parse::<i64>().unwrap_or(0);
-            let agent = db::agents::get_agent_by_id(&state.pool, token_id_i64, chain_id)
-                .await
-                .ok()
-                .flatten();
-            let scores = if agent.is_some() {
-                db::agents::get_scores_by_tag(&state.pool, token_id_i64, chain_id)
+            // Token ids beyond i64 can't be agents
+            let token_id_i64 = auction.token_id.to_string().parse::<i64>().ok();
+            let agent = match token_id_i64 {
+                Some(token_id) => state.db.read(|pool| db::agents::get_agent_by_id(pool, token_id, chain_id))
+                    .await
+                    .ok()
+                    .flatten(),
+                None => None,
+            };
+            let (activity, scores, last_sale) = if let (Some(token_id), Some(_)) = (token_id_i64, &agent) {
+                let activity = state.db.read(|pool| db::activity::get_activity_span(pool, token_id, chain_id))
                     .await
-                    .unwrap_or_default()
+                    .unwrap_or_default();
+                let scores = state.db.read(|pool| db::agents::get_scores_by_tag(pool, token_id, chain_id))
+                    .await
+                    .unwrap_or_default();
+                let last_sale = state
+                    .db
+                    .read(|pool| {
+                        db::marketplace::get_last_sale_for_token(pool, chain_id, &auction.nft_contract, &auction.token_id)
+                    })
+                    .await
+                    .ok()
+                    .flatten();
+                (activity, scores, last_sale)
             } else {
-                vec![]
+                Default::default()
             };
 
+            let auction = auction_item(auction, state.config.min_bid_increment_bps);
             let mut response = serde_json::to_value(&MarketplaceAuctionDetailResponse { auction, bids }).unwrap();
             if let Some(a) = agent {
-                let agent_detail = crate::types::AgentDetailResponse { agent: a, scores };
+                let agent_detail = crate::types::AgentDetailResponse::new(a, activity, scores, last_sale);
                 response["agent"] = serde_json::to_value(&agent_detail).unwrap();
             }
             Ok(Json(response))
         }
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ErrorResponse {
-                error: "Not found".to_string(),
-                message: format!("Auction {} not found", id),
-                status: 404,
-            }),
-        )),
+        None => Err(ApiError::NotFound(format!("Auction {} not found", id))),
+    }
+}
+
+/// Basis points in 100%, as in the contract's BPS_DENOMINATOR.
+pub(crate) const BPS_DENOMINATOR: u32 = 10_000;
+
+/// `max(start_price, highest_bid * (1 + increment_bps / 10000))`, rounded up to a whole
+/// unit; the start price when there are no bids.
+pub fn min_next_bid(start_price: &BigDecimal, highest_bid: Option<&BigDecimal>, increment_bps: u32) -> BigDecimal {
+    match highest_bid {
+        Some(bid) => {
+            let raised = (bid * BigDecimal::from(BPS_DENOMINATOR + increment_bps) / BigDecimal::from(BPS_DENOMINATOR))
+                .with_scale_round(0, RoundingMode::Ceiling);
+            raised.max(start_price.clone())
+        }
+        None => start_price.clone(),
+    }
+}
+
+fn auction_item(auction: MarketplaceAuction, increment_bps: u32) -> MarketplaceAuctionItem {
+    let minimum_next_bid = min_next_bid(&auction.start_price, auction.highest_bid.as_ref(), increment_bps);
+    MarketplaceAuctionItem::new(auction, minimum_next_bid, chrono::Utc::now().timestamp())
+}
+
+/// The bid floor for an auction: `min_next_bid`, raised to the reserve while the reserve
+/// isn't met, then capped at the buy-now price (0 means none), since paying that ends the
+/// auction outright.
+pub fn auction_bid_floor(auction: &MarketplaceAuction, increment_bps: u32) -> MarketplaceAuctionCurrentPriceResponse {
+    let min_next_bid = min_next_bid(&auction.start_price, auction.highest_bid.as_ref(), increment_bps);
+    let zero = BigDecimal::zero();
+    let reserve_met = auction.highest_bid.as_ref().unwrap_or(&zero) >= &auction.reserve_price;
+
+    let mut effective_min_bid = if reserve_met {
+        min_next_bid.clone()
+    } else {
+        min_next_bid.clone().max(auction.reserve_price.clone())
+    };
+    let buy_now_price = auction.buy_now_price.clone().unwrap_or_default();
+    if buy_now_price > zero {
+        effective_min_bid = effective_min_bid.min(buy_now_price.clone());
+    }
+
+    MarketplaceAuctionCurrentPriceResponse {
+        auction_id: auction.auction_id,
+        chain_id: auction.chain_id,
+        status: auction.status,
+        start_price: auction.start_price.clone(),
+        highest_bid: auction.highest_bid.clone(),
+        reserve_price: auction.reserve_price.clone(),
+        reserve_met,
+        buy_now_price,
+        min_bid_increment_bps: increment_bps,
+        min_next_bid,
+        effective_min_bid,
+    }
+}
+
+/// GET /api/marketplace/auctions/:chainId-:auctionId/current-price
+async fn get_auction_current_price(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<MarketplaceAuctionCurrentPriceResponse>, ApiError> {
+    let (chain_id, auction_id) = parse_id(&id)?;
+    let auction = state.db.read(|pool| db::marketplace::get_auction_by_id(pool, auction_id, chain_id, None))
+        .await
+        .map_err(map_err)?;
+
+    match auction {
+        Some(a) => Ok(Json(auction_bid_floor(&a, state.config.min_bid_increment_bps))),
+        None => Err(ApiError::NotFound(format!("Auction {} not found", id))),
     }
 }
 
 /// GET /api/marketplace/dutch-auctions
 async fn list_dutch_auctions(
     State(state): State<AppState>,
-    Query(params): Query<MarketplaceListParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let (auctions, total) = db::marketplace::get_dutch_auctions(
-        &state.pool,
-        params.chain_id,
-        params.nft_contract.as_deref(),
-        params.status.as_deref(),
-        params.offset(),
-        params.limit(),
-    )
+    ValidatedQuery(params): ValidatedQuery<MarketplaceListParams>,
+) -> Result<Json<MarketplaceDutchAuctionListResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let sync = SyncCursor::start(params.updated_since, params.sync_cursor);
+    let (mut auctions, count) = state.db.read(|pool| {
+        db::marketplace::get_dutch_auctions(
+            pool,
+            params.chain_id,
+            params.nft_contract.as_deref(),
+            params.status,
+            params.exclude_malformed,
+            sync,
+            params.count_mode,
+            params.offset(),
+            params.limit() + 1,
+        )
+    })
     .await
     .map_err(map_err)?;
 
+    let pagination = Pagination::from_rows(&mut auctions, count, params.page(), params.limit());
+    let sync_cursor = sync_checkpoint(
+        sync,
+        &auctions,
+        pagination.has_next,
+        state.config.sync_settled_before(),
+        |r| SyncCursor::after(r.updated_at, r.id),
+    )
+    .map(|cursor| cursor.encode());
+
     Ok(Json(MarketplaceDutchAuctionListResponse {
         auctions,
-        total,
-        page: params.page(),
-        limit: params.limit(),
+        pagination,
+        sync_cursor,
     }))
 }
 
 /// GET /api/marketplace/bundles
 async fn list_bundles(
     State(state): State<AppState>,
-    Query(params): Query<MarketplaceBundleParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let (bundles, total) = db::marketplace::get_bundles(
-        &state.pool,
-        params.chain_id,
-        params.seller.as_deref(),
-        params.status.as_deref(),
-        params.offset(),
-        params.limit(),
-    )
+    ValidatedQuery(params): ValidatedQuery<MarketplaceBundleParams>,
+) -> Result<Json<MarketplaceBundleListResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let sync = SyncCursor::start(params.updated_since, params.sync_cursor);
+    let (mut bundles, count) = state.db.read(|pool| {
+        db::marketplace::get_bundles(
+            pool,
+            params.chain_id,
+            params.seller.as_deref(),
+            params.status,
+            sync,
+            params.count_mode,
+            params.offset(),
+            params.limit() + 1,
+        )
+    })
     .await
     .map_err(map_err)?;
 
+    let pagination = Pagination::from_rows(&mut bundles, count, params.page(), params.limit());
+    let sync_cursor = sync_checkpoint(
+        sync,
+        &bundles,
+        pagination.has_next,
+        state.config.sync_settled_before(),
+        |r| SyncCursor::after(r.updated_at, r.id),
+    )
+    .map(|cursor| cursor.encode());
+
     Ok(Json(MarketplaceBundleListResponse {
         bundles,
-        total,
-        page: params.page(),
-        limit: params.limit(),
+        pagination,
+        sync_cursor,
+    }))
+}
+
+/// GET /api/marketplace/collections/:nft_contract/activity
+async fn get_collection_activity(
+    State(state): State<AppState>,
+    ValidatedPath(nft_contract): ValidatedPath<EthAddress>,
+    ValidatedQuery(params): ValidatedQuery<MarketplaceCollectionActivityParams>,
+) -> Result<Json<MarketplaceCollectionActivityResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let (mut activities, total) = state.db.read(|pool| {
+        db::marketplace::get_collection_activity(
+            pool,
+            &nft_contract,
+            params.chain_id,
+            params.offset(),
+            params.limit() + 1,
+        )
+    })
+    .await
+    .map_err(map_err)?;
+
+    let pagination = Pagination::from_rows(&mut activities, TotalCount::exact(total), params.page(), params.limit());
+
+    Ok(Json(MarketplaceCollectionActivityResponse {
+        nft_contract: nft_contract.to_string(),
+        activities,
+        pagination,
+    }))
+}
+
+/// GET /api/marketplace/collections/:nft_contract/stats — average and median sale price per
+/// payment token over `range`
+async fn get_collection_stats(
+    State(state): State<AppState>,
+    ValidatedPath(nft_contract): ValidatedPath<EthAddress>,
+    ValidatedQuery(params): ValidatedQuery<MarketplaceCollectionStatsParams>,
+) -> Result<Json<MarketplaceCollectionStatsResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let prices = state.db.read(|pool| {
+        db::marketplace::get_collection_sale_stats(pool, &nft_contract, params.chain_id, params.range())
+    })
+    .await
+    .map_err(map_err)?;
+
+    Ok(Json(MarketplaceCollectionStatsResponse {
+        nft_contract: nft_contract.to_string(),
+        chain_id: params.chain_id,
+        range: params.range().to_string(),
+        prices,
+    }))
+}
+
+/// GET /api/marketplace/nft/:chain_id/:nft_contract/:token_id/listings
+async fn get_token_listings(
+    State(state): State<AppState>,
+    ValidatedPath((chain_id, nft_contract, token_id)): ValidatedPath<(i32, EthAddress, String)>,
+) -> Result<Json<MarketplaceTokenListingsResponse>, ApiError> {
+    let token_id_bd: BigDecimal = token_id
+        .parse()
+        .ok()
+        .filter(|t: &BigDecimal| t.is_integer() && t >= &BigDecimal::zero())
+        .ok_or_else(|| ApiError::BadRequest(format!("Invalid token_id '{}'", token_id)))?;
+
+    let listings = state.db.read(|pool| {
+        db::marketplace::get_listings_for_token(pool, chain_id, &nft_contract, &token_id_bd)
+    })
+    .await
+    .map_err(map_err)?;
+
+    Ok(Json(MarketplaceTokenListingsResponse {
+        chain_id,
+        nft_contract: nft_contract.to_string(),
+        token_id: token_id_bd.to_plain_string(),
+        listings,
     }))
 }
 
 /// GET /api/marketplace/user/:address
 async fn get_user_portfolio(
     State(state): State<AppState>,
-    Path(address): Path<String>,
-    Query(params): Query<MarketplaceUserParams>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let portfolio = db::marketplace::get_user_portfolio(
-        &state.pool,
-        &address.to_lowercase(),
-        params.chain_id,
-    )
+    ValidatedPath(address): ValidatedPath<EthAddress>,
+    ValidatedQuery(params): ValidatedQuery<MarketplaceUserParams>,
+) -> Result<Json<MarketplaceUserPortfolioResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let portfolio = state.db.read(|pool| {
+        db::marketplace::get_user_portfolio(
+            pool,
+            &address,
+            params.chain_id,
+        )
+    })
     .await
     .map_err(map_err)?;
 
     Ok(Json(portfolio))
 }
 
+/// GET /api/marketplace/user/:address/auctions
+async fn get_user_auctions(
+    State(state): State<AppState>,
+    ValidatedPath(address): ValidatedPath<EthAddress>,
+    ValidatedQuery(params): ValidatedQuery<MarketplaceUserParams>,
+) -> Result<Json<MarketplaceUserAuctionsResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let (winning, outbid) = state.db.read(|pool| {
+        db::marketplace::get_user_auction_positions(pool, &address, params.chain_id)
+    })
+    .await
+    .map_err(map_err)?;
+
+    let increment_bps = state.config.min_bid_increment_bps;
+    Ok(Json(MarketplaceUserAuctionsResponse {
+        winning: winning.into_iter().map(|a| auction_item(a, increment_bps)).collect(),
+        outbid: outbid.into_iter().map(|a| auction_item(a, increment_bps)).collect(),
+    }))
+}
+
+/// GET /api/marketplace/user/:address/offers — token and collection offers the address made
+async fn get_user_offers(
+    State(state): State<AppState>,
+    ValidatedPath(address): ValidatedPath<EthAddress>,
+    ValidatedQuery(params): ValidatedQuery<MarketplaceUserOffersParams>,
+) -> Result<Json<MarketplaceUserOffersResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let (mut offers, total) = state.db.read(|pool| {
+        db::marketplace::get_user_offers(
+            pool,
+            &address,
+            params.chain_id,
+            params.status,
+            params.offset(),
+            params.limit() + 1,
+        )
+    })
+    .await
+    .map_err(map_err)?;
+
+    let pagination = Pagination::from_rows(&mut offers, TotalCount::exact(total), params.page(), params.limit());
+
+    Ok(Json(MarketplaceUserOffersResponse { offers, pagination }))
+}
+
+/// GET /api/marketplace/sellers/:address/metrics
+async fn get_seller_metrics(
+    State(state): State<AppState>,
+    ValidatedPath(address): ValidatedPath<EthAddress>,
+    ValidatedQuery(params): ValidatedQuery<MarketplaceUserParams>,
+) -> Result<Json<MarketplaceSellerMetricsResponse>, ApiError> {
+    known_chain(params.chain_id, &state.chain_ids)?;
+    let metrics = state.db.read(|pool| db::marketplace::get_seller_metrics(pool, &address, params.chain_id))
+        .await
+        .map_err(map_err)?;
+
+    Ok(Json(metrics))
+}
+
 /// GET /api/marketplace/stats
 async fn get_marketplace_stats(
     State(state): State<AppState>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let stats = db::marketplace::get_marketplace_stats(&state.pool)
+) -> Result<Json<MarketplaceStatsResponse>, ApiError> {
+    let mut stats = state.db.read(db::marketplace::get_marketplace_stats)
         .await
         .map_err(map_err)?;
+    stats.marketplace_enabled = marketplace_enabled_by_chain(&state.config.chains);
 
     Ok(Json(stats))
 }
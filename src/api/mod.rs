@@ -1,19 +1,77 @@
-use axum::Router;
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
 
+use crate::api::error::{ApiError, FieldError};
+use crate::db;
+use crate::types::time_format::{self, TimeFormat};
 use crate::AppState;
 
 pub mod activity;
+pub mod admin;
 pub mod agents;
+pub mod dashboard;
+pub mod error;
+pub mod extract;
+pub mod feedbacks;
+pub mod image_proxy;
+pub mod indexer;
 pub mod leaderboard;
+pub mod limits;
 pub mod marketplace;
+pub mod owners;
+pub mod request_id;
 pub mod stats;
+pub mod validate;
+pub mod version;
 
 /// Build the /api router with all sub-routes.
-pub fn router() -> Router<AppState> {
+pub fn router(state: &AppState) -> Router<AppState> {
     Router::new()
         .merge(activity::router())
+        .nest("/admin", admin::router(state))
         .merge(agents::router())
+        .merge(dashboard::router())
+        .merge(feedbacks::router())
+        .merge(indexer::router())
         .merge(leaderboard::router())
         .merge(marketplace::router())
+        .merge(owners::router())
         .merge(stats::router())
+        .merge(version::router())
+        .layer(middleware::from_fn(db_pool_header))
+        .layer(middleware::from_fn(time_format_param))
+}
+
+/// Apply `?time_format=rfc3339|unix` to every time field in the response (default rfc3339).
+async fn time_format_param(req: Request, next: Next) -> Response {
+    let requested = req.uri().query().and_then(|query| {
+        form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == "time_format")
+            .map(|(_, value)| value.into_owned())
+    });
+    let format = match requested.as_deref().map(TimeFormat::parse) {
+        None => TimeFormat::default(),
+        Some(Some(format)) => format,
+        Some(None) => {
+            return ApiError::Validation(vec![FieldError::param("time_format", "must be 'rfc3339' or 'unix'")])
+                .into_response();
+        }
+    };
+    time_format::with_time_format(format, next.run(req)).await
+}
+
+/// Report which pool served the request's reads in `x-db-pool` (primary, replica or mixed).
+async fn db_pool_header(req: Request, next: Next) -> Response {
+    let (mut response, served) = db::replica::track_reads(next.run(req)).await;
+    if let Some(value) = served.header_value() {
+        response
+            .headers_mut()
+            .insert("x-db-pool", HeaderValue::from_static(value));
+    }
+    response
 }
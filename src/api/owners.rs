@@ -0,0 +1,49 @@
+use axum::{
+    extract::State,
+    routing::get,
+    Json, Router,
+};
+
+use crate::api::error::ApiError;
+use crate::api::extract::{ValidatedPath, ValidatedQuery};
+use crate::db;
+use crate::types::{EthAddress, OwnerAgentListResponse, PaginationParams};
+use crate::AppState;
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/owners/{address}/agents", get(get_owner_agents))
+}
+
+/// GET /api/owners/:address/agents — agents owned by an address, with reputation and market status
+async fn get_owner_agents(
+    State(state): State<AppState>,
+    ValidatedPath(owner): ValidatedPath<EthAddress>,
+    ValidatedQuery(params): ValidatedQuery<PaginationParams>,
+) -> Result<Json<OwnerAgentListResponse>, ApiError> {
+    let identity_contracts: Vec<String> = state
+        .config
+        .chains
+        .iter()
+        .map(|c| format!("{:#x}", c.identity_address))
+        .collect();
+
+    let (agents, total) = state.db.read(|pool| {
+        db::agents::get_agents_by_owner(
+            pool,
+            &owner,
+            &identity_contracts,
+            params.offset(),
+            params.limit(),
+        )
+    })
+    .await
+    .map_err(|e| ApiError::internal(e, "Failed to fetch owner agents"))?;
+
+    Ok(Json(OwnerAgentListResponse {
+        owner: owner.to_string(),
+        agents,
+        total,
+        page: params.page(),
+        limit: params.limit(),
+    }))
+}
@@ -0,0 +1,67 @@
+use std::future::Future;
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest incoming `x-request-id` that is kept; anything longer gets a fresh id.
+const MAX_INCOMING_LEN: usize = 128;
+
+tokio::task_local! {
+    /// Id of the request being served (see `request_id`).
+    static REQUEST_ID: String;
+}
+
+/// The request's id, stored in the request extensions by the `request_id` middleware.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Id of the request being served; None outside a request (background tasks, tests).
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Run `fut` as part of request `id`, so `current()` and error bodies report it.
+pub async fn with_request_id<F: Future>(id: String, fut: F) -> F::Output {
+    REQUEST_ID.scope(id, fut).await
+}
+
+/// Tag the request with an id: the proxy's `x-request-id` when it sent a usable one, else
+/// a new UUID. The id goes into the extensions (read by the trace span), into every
+/// `ErrorResponse`, and back out in the `x-request-id` response header.
+pub async fn request_id(mut req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_usable(value))
+        .map(str::to_string)
+        .unwrap_or_else(new_request_id);
+
+    req.extensions_mut().insert(RequestId(id.clone()));
+    let mut response = with_request_id(id.clone(), next.run(req)).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// A random (version 4) UUID in its hyphenated form.
+pub fn new_request_id() -> String {
+    let mut bytes: [u8; 16] = rand::random();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+fn is_usable(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_INCOMING_LEN
+        && value.bytes().all(|b| b.is_ascii_graphic())
+}
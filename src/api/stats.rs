@@ -1,18 +1,26 @@
 use axum::{
     extract::State,
-    http::StatusCode,
-    response::IntoResponse,
     routing::get,
     Json, Router,
 };
 use bigdecimal::BigDecimal;
+use sqlx::PgPool;
 use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
 
-use crate::types::{CategoryCount, ErrorResponse, StatsResponse};
+use crate::api::error::ApiError;
+use crate::db::timing::timed;
+use crate::types::{CategoryCount, StatsResponse, StatsSummaryResponse};
 use crate::AppState;
 
 pub fn router() -> Router<AppState> {
-    Router::new().route("/stats", get(get_stats))
+    Router::new()
+        .route("/stats", get(get_stats))
+        .route("/stats/summary", get(get_stats_summary))
 }
 
 /// Combined agent + feedback stats row (from a single query).
@@ -32,26 +40,113 @@ struct MarketplaceStats {
     active_listings: i64,
     total_sales: i64,
     total_volume: BigDecimal,
+    items_sold: i64,
 }
 
-/// GET /api/stats — get global marketplace statistics
+/// Default STATS_CACHE_SECS.
+pub const DEFAULT_STATS_CACHE_SECS: u64 = 15;
+
+/// The outcome of one stats computation, as every request waiting on it receives it.
+type Shared<E> = Result<StatsResponse, Arc<E>>;
+
+/// The last /api/stats payload, served until it is `ttl` old. A miss starts one computation
+/// that concurrent misses wait on instead of each querying the database (single-flight); its
+/// outcome, failure included, goes to all of them at once. Failures are not cached, so the
+/// next miss after one computes again. A zero `ttl` disables caching.
+pub struct StatsCache<E = sqlx::Error> {
+    ttl: Duration,
+    state: Mutex<CacheState<E>>,
+}
+
+struct CacheState<E> {
+    entry: Option<(Instant, StatsResponse)>,
+    /// Set while a computation runs; its outcome is published here.
+    in_flight: Option<watch::Receiver<Option<Shared<E>>>>,
+}
+
+/// What a miss does: run the computation and publish its outcome, or wait for the one in flight.
+enum Role<E> {
+    Compute(watch::Sender<Option<Shared<E>>>),
+    Wait(watch::Receiver<Option<Shared<E>>>),
+}
+
+impl<E> StatsCache<E> {
+    pub fn new(ttl: Duration) -> Self {
+        StatsCache {
+            ttl,
+            state: Mutex::new(CacheState { entry: None, in_flight: None }),
+        }
+    }
+
+    /// The cached stats while fresh, otherwise the outcome of the computation in flight, or
+    /// of `compute` when none is (cached on success).
+    pub async fn get_or_compute<F, Fut>(&self, compute: F) -> Shared<E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<StatsResponse, E>>,
+    {
+        if self.ttl.is_zero() {
+            return compute().await.map_err(Arc::new);
+        }
+        loop {
+            let role = {
+                let mut state = self.state.lock().expect("stats cache lock");
+                if let Some((computed_at, stats)) = &state.entry {
+                    if computed_at.elapsed() < self.ttl {
+                        return Ok(stats.clone());
+                    }
+                }
+                // A computation whose caller went away (e.g. timed out) never publishes
+                match state.in_flight.as_ref().filter(|rx| rx.has_changed().is_ok()) {
+                    Some(rx) => Role::Wait(rx.clone()),
+                    None => {
+                        let (tx, rx) = watch::channel(None);
+                        state.in_flight = Some(rx);
+                        Role::Compute(tx)
+                    }
+                }
+            };
+            match role {
+                Role::Compute(tx) => {
+                    let result = compute().await.map_err(Arc::new);
+                    let mut state = self.state.lock().expect("stats cache lock");
+                    state.in_flight = None;
+                    if let Ok(stats) = &result {
+                        state.entry = Some((Instant::now(), stats.clone()));
+                    }
+                    tx.send_replace(Some(result.clone()));
+                    return result;
+                }
+                Role::Wait(mut outcome) => {
+                    // Otherwise its caller went away before publishing; start over
+                    let published = outcome.wait_for(Option::is_some).await.ok();
+                    if let Some(result) = published.and_then(|result| result.clone()) {
+                        return result;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// GET /api/stats — get global marketplace statistics, cached for STATS_CACHE_SECS
 async fn get_stats(
     State(state): State<AppState>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let map_err = |e: sqlx::Error| {
-        tracing::error!("Failed to get stats: {:?}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: "Internal Server Error".to_string(),
-                message: "Failed to fetch stats".to_string(),
-                status: 500,
-            }),
-        )
-    };
+) -> Result<Json<StatsResponse>, ApiError> {
+    // All four queries run on one pool so a replica fallback covers the whole response
+    let stats = state
+        .stats_cache
+        .get_or_compute(|| state.db.read(query_stats))
+        .await
+        .map_err(|e| ApiError::internal(e, "Failed to fetch stats"))?;
 
+    Ok(Json(stats))
+}
+
+/// The /api/stats payload, also embedded in /api/dashboard.
+pub(crate) async fn query_stats(pool: &PgPool) -> Result<StatsResponse, sqlx::Error> {
     // Query 1: Combined agent + feedback counts (was 5 separate queries)
-    let af_stats: AgentFeedbackStats = sqlx::query_as(
+    let af_stats = sqlx::query_as(
         r#"
         SELECT
             (SELECT COUNT(*) FROM agents WHERE active = true) AS total_agents,
@@ -61,18 +156,19 @@ async fn get_stats(
             (SELECT COUNT(*) FROM feedbacks WHERE created_at >= NOW() - INTERVAL '24 hours') AS recent_feedbacks_24h
         "#,
     )
-    .fetch_one(&state.pool)
-    .await
-    .map_err(map_err)?;
+    .fetch_one(pool);
+    let af_stats: AgentFeedbackStats = timed("stats.totals", af_stats).await?;
 
     // Query 2: Agents by chain + top categories (run concurrently)
-    let pool = &state.pool;
     let (chain_counts_result, top_categories_result, mp_stats_result) = tokio::join!(
-        sqlx::query_as::<_, (i32, i64)>(
-            "SELECT chain_id, COUNT(*) FROM agents WHERE active = true GROUP BY chain_id"
-        )
-        .fetch_all(pool),
-        sqlx::query_as::<_, CategoryCount>(
+        timed(
+            "stats.agents_by_chain",
+            sqlx::query_as::<_, (i32, i64)>(
+                "SELECT chain_id, COUNT(*) FROM agents WHERE active = true GROUP BY chain_id"
+            )
+            .fetch_all(pool),
+        ),
+        timed("stats.top_categories", sqlx::query_as::<_, CategoryCount>(
             r#"
             SELECT cat AS category, COUNT(*) AS count
             FROM agents, UNNEST(categories) AS cat
@@ -82,31 +178,39 @@ async fn get_stats(
             LIMIT 10
             "#
         )
-        .fetch_all(pool),
-        // Query 3: Marketplace stats combined (was 4 separate queries)
-        sqlx::query_as::<_, MarketplaceStats>(
+        .fetch_all(pool)),
+        // Query 3: Marketplace stats combined; sales cover every sale type
+        timed("stats.marketplace", sqlx::query_as::<_, MarketplaceStats>(
             r#"
-            SELECT
-                COUNT(*) AS total_listings,
-                COUNT(*) FILTER (WHERE status = 'Active') AS active_listings,
-                COUNT(*) FILTER (WHERE status = 'Sold') AS total_sales,
-                COALESCE(SUM(sold_price) FILTER (WHERE status = 'Sold'), 0) AS total_volume
-            FROM marketplace_listings
+            SELECT l.total_listings, l.active_listings, s.total_sales, s.total_volume, s.items_sold
+            FROM (
+                SELECT
+                    COUNT(*) AS total_listings,
+                    COUNT(*) FILTER (WHERE status = 'Active') AS active_listings
+                FROM marketplace_listings
+            ) l,
+            (
+                SELECT
+                    COUNT(*) AS total_sales,
+                    COALESCE(SUM(ms.price), 0) AS total_volume,
+                    COALESCE(SUM(COALESCE(b.item_count, 1)), 0)::BIGINT AS items_sold
+                FROM marketplace_sales ms
+                LEFT JOIN marketplace_bundles b
+                    ON ms.sale_type = 'bundle' AND b.bundle_id = ms.entity_id
+                    AND b.chain_id = ms.chain_id AND b.marketplace_address = ms.marketplace_address
+            ) s
             "#
         )
-        .fetch_one(pool)
+        .fetch_one(pool))
     );
-
-    let chain_counts = chain_counts_result.map_err(map_err)?;
-    let top_categories = top_categories_result.map_err(map_err)?;
-    let mp_stats = mp_stats_result.map_err(map_err)?;
+    let (chain_counts, top_categories, mp_stats) = (chain_counts_result?, top_categories_result?, mp_stats_result?);
 
     let mut agents_by_chain: HashMap<String, i64> = HashMap::new();
     for (chain_id, count) in chain_counts {
         agents_by_chain.insert(chain_id.to_string(), count);
     }
 
-    Ok(Json(StatsResponse {
+    Ok(StatsResponse {
         total_agents: af_stats.total_agents,
         total_feedbacks: af_stats.total_feedbacks,
         total_chains: af_stats.total_chains,
@@ -118,5 +222,32 @@ async fn get_stats(
         active_listings: mp_stats.active_listings,
         total_sales: mp_stats.total_sales,
         total_volume: mp_stats.total_volume,
-    }))
+        items_sold: mp_stats.items_sold,
+    })
+}
+
+/// GET /api/stats/summary — just the agent, feedback and listing totals of /api/stats, for
+/// header badges; skips the per-chain, category and sales aggregation
+async fn get_stats_summary(
+    State(state): State<AppState>,
+) -> Result<Json<StatsSummaryResponse>, ApiError> {
+    let summary = state
+        .db
+        .read(|pool| async move {
+            sqlx::query_as::<_, StatsSummaryResponse>(
+                r#"
+                SELECT
+                    (SELECT COUNT(*) FROM agents WHERE active = true) AS total_agents,
+                    (SELECT COUNT(*) FROM feedbacks WHERE revoked = false) AS total_feedbacks,
+                    (SELECT COUNT(*) FROM marketplace_listings) AS total_listings,
+                    (SELECT COUNT(*) FROM marketplace_listings WHERE status = 'Active') AS active_listings
+                "#,
+            )
+            .fetch_one(pool)
+            .await
+        })
+        .await
+        .map_err(|e| ApiError::internal(e, "Failed to fetch stats summary"))?;
+
+    Ok(Json(summary))
 }
@@ -0,0 +1,266 @@
+use chrono::{DateTime, Utc};
+
+use crate::api::error::{ApiError, FieldError};
+use crate::config::Limits;
+use crate::types::{
+    ActivityParams, AgentListParams, CapabilityParams, FeedbackResponseParams, LeaderboardParams, MarketplaceAuctionParams, MarketplaceBundleParams,
+    MarketplaceCollectionActivityParams, MarketplaceCollectionOfferParams, MarketplaceCollectionStatsParams, MarketplaceListParams, MarketplaceOfferParams, MarketplaceUserOffersParams, MarketplaceUserParams,
+    PaginationParams, RecentFeedbackParams, ReputationParams, SyncCursor,
+};
+
+/// Highest `limit` any list accepts; larger values are clamped (or rejected when strict).
+const MAX_LIMIT: i64 = 100;
+
+// Accepted values, matching the match arms that consume them in src/db
+const AGENT_SORTS: &[&str] = &["recent", "score", "name", "clients"];
+const SEARCH_MODES: &[&str] = &["substring", "rank"];
+const REPUTATION_RANGES: &[&str] = &["7d", "30d", "90d", "all"];
+const COLLECTION_STATS_RANGES: &[&str] = &["7d", "30d", "all"];
+const LEADERBOARD_ORDERS: &[&str] = &["desc", "asc"];
+const LISTING_SORTS: &[&str] = &["recent", "price_asc", "price_desc"];
+const OFFER_SORTS: &[&str] = &["recent", "expiring_soon"];
+const AUCTION_SORTS: &[&str] = &["recent", "ending_soon", "highest_bid"];
+
+/// Whether out-of-range `page` / `limit` values are rejected instead of clamped for every
+/// request. Enabled with STRICT_QUERY_PARAMS=true; off by default so existing clients keep
+/// working, which can still opt in per request with `strict=true`.
+fn strict_query_params() -> bool {
+    Limits::current().strict_query_params
+}
+
+/// Checks on a query params type that serde can't express: values from a fixed set (`sort`,
+/// `search_mode`, ...), combinations, and in strict mode paging values that would otherwise
+/// be clamped. `ValidatedQuery` runs them after deserializing; each failing field pushes one
+/// error, and all of them are reported together. The default accepts anything that
+/// deserialized.
+pub trait ValidateParams {
+    fn validate(&self, _errors: &mut Vec<FieldError>) {}
+
+    /// The `page` and `limit` the params accept, checked by `strict_paging` (None for a
+    /// parameter the endpoint doesn't take).
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (None, None)
+    }
+}
+
+/// Reject a value outside `allowed`.
+fn one_of(errors: &mut Vec<FieldError>, field: &str, value: Option<&str>, allowed: &[&str]) {
+    if let Some(value) = value.filter(|v| !allowed.contains(v)) {
+        errors.push(FieldError::param(
+            field,
+            format!("unknown value '{}', expected one of {}", value, allowed.join(", ")),
+        ));
+    }
+}
+
+/// Checks for an incremental sync (`updated_since`, continued with `sync_cursor`), which
+/// always orders by change time and pages by cursor: no `sort` or `page` alongside it, and
+/// only one of the two starting points.
+fn sync_params(
+    errors: &mut Vec<FieldError>,
+    sort: Option<&str>,
+    page: Option<i64>,
+    updated_since: Option<DateTime<Utc>>,
+    sync_cursor: Option<SyncCursor>,
+) {
+    if updated_since.is_some() && sync_cursor.is_some() {
+        errors.push(FieldError::param("sync_cursor", "cannot be combined with 'updated_since'"));
+    }
+    let field = match (sync_cursor, updated_since) {
+        (Some(_), _) => "sync_cursor",
+        (None, Some(_)) => "updated_since",
+        (None, None) => return,
+    };
+    if sort.is_some() {
+        errors.push(FieldError::param("sort", format!("cannot be combined with '{field}'")));
+    }
+    if page.is_some() {
+        errors.push(FieldError::param("page", format!("cannot be combined with '{field}'")));
+    }
+}
+
+/// Reject a `chain_id` filter naming a chain outside `configured` (the chains from
+/// `Config::chains`), which would otherwise silently match nothing.
+pub fn known_chain(chain_id: Option<i32>, configured: &[i32]) -> Result<(), ApiError> {
+    match chain_id {
+        Some(chain_id) if !configured.contains(&chain_id) => Err(ApiError::UnknownChain {
+            chain_id,
+            configured: configured.to_vec(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Whether this request pages strictly: STRICT_QUERY_PARAMS, or `strict=true` in its query.
+/// A `strict` value other than true/false is itself rejected.
+pub fn is_strict(query: &str, errors: &mut Vec<FieldError>) -> bool {
+    let requested = form_urlencoded::parse(query.as_bytes())
+        .filter(|(key, _)| key == "strict")
+        .last()
+        .map(|(_, value)| value.into_owned());
+    let requested = match requested.as_deref() {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(other) => {
+            errors.push(FieldError::param("strict", format!("unknown value '{}', expected true or false", other)));
+            false
+        }
+    };
+    requested || strict_query_params()
+}
+
+/// Reject a `page` below 1 and a `limit` outside 1..=100, which the params would otherwise clamp.
+pub fn strict_paging<T: ValidateParams>(params: &T, errors: &mut Vec<FieldError>) {
+    let (page, limit) = params.paging();
+    if page.is_some_and(|p| p < 1) {
+        errors.push(FieldError::param("page", "must be at least 1"));
+    }
+    if limit.is_some_and(|l| !(1..=MAX_LIMIT).contains(&l)) {
+        errors.push(FieldError::param("limit", format!("must be between 1 and {}", MAX_LIMIT)));
+    }
+}
+
+impl ValidateParams for PaginationParams {
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (self.page, self.limit)
+    }
+}
+
+impl ValidateParams for AgentListParams {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        one_of(errors, "sort", self.sort.as_deref(), AGENT_SORTS);
+        sync_params(errors, self.sort.as_deref(), self.page, self.updated_since, self.sync_cursor);
+        one_of(errors, "search_mode", self.search_mode.as_deref(), SEARCH_MODES);
+    }
+
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (self.page, self.limit)
+    }
+}
+
+impl ValidateParams for CapabilityParams {}
+
+impl ValidateParams for ReputationParams {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        one_of(errors, "range", self.range.as_deref(), REPUTATION_RANGES);
+    }
+}
+
+impl ValidateParams for ActivityParams {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        if self.before.is_some() && self.page.is_some() {
+            errors.push(FieldError::param("before", "cannot be combined with 'page'"));
+        }
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                errors.push(FieldError::param("from", "must not be after 'to'"));
+            }
+        }
+    }
+
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (self.page, self.limit)
+    }
+}
+
+impl ValidateParams for FeedbackResponseParams {
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (self.page, self.limit)
+    }
+}
+
+impl ValidateParams for RecentFeedbackParams {
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (None, self.limit)
+    }
+}
+
+impl ValidateParams for LeaderboardParams {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        one_of(errors, "order", self.order.as_deref(), LEADERBOARD_ORDERS);
+    }
+
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (None, self.limit)
+    }
+}
+
+impl ValidateParams for MarketplaceListParams {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        if self.status.is_some() && self.exclude_status.is_some() {
+            errors.push(FieldError::param("exclude_status", "cannot be combined with 'status'"));
+        }
+        one_of(errors, "sort", self.sort.as_deref(), LISTING_SORTS);
+        sync_params(errors, self.sort.as_deref(), self.page, self.updated_since, self.sync_cursor);
+        if self.latest_per_token && (self.updated_since.is_some() || self.sync_cursor.is_some()) {
+            errors.push(FieldError::param("latest_per_token", "cannot be combined with 'updated_since' or 'sync_cursor'"));
+        }
+    }
+
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (self.page, self.limit)
+    }
+}
+
+impl ValidateParams for MarketplaceOfferParams {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        one_of(errors, "sort", self.sort.as_deref(), OFFER_SORTS);
+        sync_params(errors, self.sort.as_deref(), self.page, self.updated_since, self.sync_cursor);
+    }
+
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (self.page, self.limit)
+    }
+}
+
+impl ValidateParams for MarketplaceCollectionOfferParams {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        one_of(errors, "sort", self.sort.as_deref(), OFFER_SORTS);
+        sync_params(errors, self.sort.as_deref(), self.page, self.updated_since, self.sync_cursor);
+    }
+
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (self.page, self.limit)
+    }
+}
+
+impl ValidateParams for MarketplaceAuctionParams {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        one_of(errors, "sort", self.sort.as_deref(), AUCTION_SORTS);
+        sync_params(errors, self.sort.as_deref(), self.page, self.updated_since, self.sync_cursor);
+    }
+
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (self.page, self.limit)
+    }
+}
+
+impl ValidateParams for MarketplaceBundleParams {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        sync_params(errors, None, self.page, self.updated_since, self.sync_cursor);
+    }
+
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (self.page, self.limit)
+    }
+}
+
+impl ValidateParams for MarketplaceCollectionActivityParams {
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (self.page, self.limit)
+    }
+}
+
+impl ValidateParams for MarketplaceCollectionStatsParams {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        one_of(errors, "range", self.range.as_deref(), COLLECTION_STATS_RANGES);
+    }
+}
+
+impl ValidateParams for MarketplaceUserParams {}
+
+impl ValidateParams for MarketplaceUserOffersParams {
+    fn paging(&self) -> (Option<i64>, Option<i64>) {
+        (self.page, self.limit)
+    }
+}
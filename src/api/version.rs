@@ -0,0 +1,47 @@
+use axum::{
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+    routing::get,
+    Json, Router,
+};
+use chrono::DateTime;
+
+use crate::types::VersionResponse;
+use crate::AppState;
+
+/// `<crate version>+<short git sha>`; GIT_SHORT_SHA and BUILD_TIMESTAMP come from build.rs.
+pub const SERVICE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "+", env!("GIT_SHORT_SHA"));
+
+pub fn router() -> Router<AppState> {
+    Router::new().route("/version", get(get_version))
+}
+
+/// GET /api/version — crate version, git SHA and build time, plus configured chains
+async fn get_version(State(state): State<AppState>) -> Json<VersionResponse> {
+    let mut chain_ids: Vec<i32> = state.indexer_flags.keys().copied().collect();
+    chain_ids.sort_unstable();
+
+    Json(VersionResponse {
+        service_version: SERVICE_VERSION.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_sha: env!("GIT_SHA").to_string(),
+        build_timestamp: env!("BUILD_TIMESTAMP")
+            .parse()
+            .ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+        chain_ids,
+        indexer_enabled: state.indexer_enabled,
+    })
+}
+
+/// Stamp every response with `x-service-version` so HAR captures show which build served it.
+pub async fn service_version_header(req: Request, next: Next) -> Response {
+    let mut response = next.run(req).await;
+    response.headers_mut().insert(
+        "x-service-version",
+        HeaderValue::from_static(SERVICE_VERSION),
+    );
+    response
+}
@@ -0,0 +1,531 @@
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use axum::http::HeaderValue;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::Executor;
+
+use crate::api::limits::DEFAULT_REQUEST_TIMEOUT_SECS;
+use crate::api::marketplace::BPS_DENOMINATOR;
+use crate::api::stats::DEFAULT_STATS_CACHE_SECS;
+use crate::db::feedbacks::{parse_max_reasonable_score, DEFAULT_MAX_REASONABLE_SCORE};
+use crate::db::timing::DEFAULT_SLOW_QUERY_MS;
+use crate::health::DEFAULT_MAX_INDEXER_LAG_SECS;
+use crate::indexer::provider::{self, ChainConfig, SkippedChain};
+use crate::indexer::retention::retention_days;
+use crate::indexer::CONFIG_SYNC_INTERVAL_SECS;
+use crate::logging::{log_format, LogFormat};
+use crate::shutdown::grace_period;
+use crate::types::{DEFAULT_MAX_PAGE, DEFAULT_SYNC_LAG_SECS};
+
+/// Settings for one Postgres connection pool, read from `<prefix>MAX_CONNECTIONS`,
+/// `<prefix>MIN_CONNECTIONS`, `<prefix>ACQUIRE_TIMEOUT_SECS`, `<prefix>IDLE_TIMEOUT_SECS`
+/// and `<prefix>STATEMENT_TIMEOUT_MS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolConfig {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    /// None keeps idle connections open indefinitely (`IDLE_TIMEOUT_SECS=0`).
+    pub idle_timeout: Option<Duration>,
+    /// Per-connection `statement_timeout`; None leaves the server default (`STATEMENT_TIMEOUT_MS=0`).
+    pub statement_timeout: Option<Duration>,
+}
+
+impl PoolConfig {
+    /// Defaults for the API pool (`DB_*`), matching the previous hardcoded pool.
+    pub const API_DEFAULTS: PoolConfig = PoolConfig {
+        max_connections: 10,
+        min_connections: 0,
+        acquire_timeout: Duration::from_secs(30),
+        idle_timeout: Some(Duration::from_secs(600)),
+        statement_timeout: Some(Duration::from_secs(5)),
+    };
+
+    /// Defaults for the indexer pool (`INDEXER_DB_*`), used by the indexer and background tasks.
+    pub const INDEXER_DEFAULTS: PoolConfig = PoolConfig {
+        max_connections: 5,
+        min_connections: 0,
+        acquire_timeout: Duration::from_secs(30),
+        idle_timeout: Some(Duration::from_secs(600)),
+        // Backfills and migrations legitimately run long statements
+        statement_timeout: None,
+    };
+
+    /// Read a pool config through `lookup`, falling back to `defaults` for unset variables.
+    fn from_lookup(
+        prefix: &str,
+        defaults: &PoolConfig,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, String> {
+        let read = |name: &str, default: u64| -> Result<u64, String> {
+            let key = format!("{}{}", prefix, name);
+            match lookup(&key).as_deref().map(str::trim) {
+                None | Some("") => Ok(default),
+                Some(raw) => raw
+                    .parse::<u64>()
+                    .map_err(|_| format!("{} must be a non-negative integer, got {:?}", key, raw)),
+            }
+        };
+
+        let max_connections = read("MAX_CONNECTIONS", defaults.max_connections as u64)?;
+        let min_connections = read("MIN_CONNECTIONS", defaults.min_connections as u64)?;
+        let acquire_timeout = read("ACQUIRE_TIMEOUT_SECS", defaults.acquire_timeout.as_secs())?;
+        let idle_timeout = read(
+            "IDLE_TIMEOUT_SECS",
+            defaults.idle_timeout.map(|d| d.as_secs()).unwrap_or(0),
+        )?;
+        let statement_timeout = read(
+            "STATEMENT_TIMEOUT_MS",
+            defaults.statement_timeout.map(|d| d.as_millis() as u64).unwrap_or(0),
+        )?;
+
+        if max_connections == 0 || max_connections > u32::MAX as u64 {
+            return Err(format!("{}MAX_CONNECTIONS must be at least 1", prefix));
+        }
+        if min_connections > max_connections {
+            return Err(format!(
+                "{}MIN_CONNECTIONS ({}) must not exceed {}MAX_CONNECTIONS ({})",
+                prefix, min_connections, prefix, max_connections
+            ));
+        }
+        if acquire_timeout == 0 {
+            return Err(format!("{}ACQUIRE_TIMEOUT_SECS must be at least 1", prefix));
+        }
+
+        Ok(PoolConfig {
+            max_connections: max_connections as u32,
+            min_connections: min_connections as u32,
+            acquire_timeout: Duration::from_secs(acquire_timeout),
+            idle_timeout: (idle_timeout > 0).then(|| Duration::from_secs(idle_timeout)),
+            statement_timeout: (statement_timeout > 0).then(|| Duration::from_millis(statement_timeout)),
+        })
+    }
+
+    pub fn pool_options(&self) -> PgPoolOptions {
+        let options = PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .idle_timeout(self.idle_timeout);
+
+        match self.statement_timeout {
+            Some(timeout) => {
+                // SAFETY: the value is an integer we formatted, not user input
+                let set_timeout = format!("SET statement_timeout = {}", timeout.as_millis());
+                options.after_connect(move |conn, _meta| {
+                    let set_timeout = set_timeout.clone();
+                    Box::pin(async move {
+                        conn.execute(set_timeout.as_str()).await?;
+                        Ok(())
+                    })
+                })
+            }
+            None => options,
+        }
+    }
+}
+
+/// Pool settings for the two pools: the API pool serves HTTP requests, the indexer pool
+/// serves the indexer, backfill and background tasks, so catch-up load can't starve the API.
+#[derive(Debug, Clone)]
+pub struct DbConfig {
+    pub api: PoolConfig,
+    pub indexer: PoolConfig,
+}
+
+impl DbConfig {
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        Ok(DbConfig {
+            api: PoolConfig::from_lookup("DB_", &PoolConfig::API_DEFAULTS, &lookup)?,
+            indexer: PoolConfig::from_lookup("INDEXER_DB_", &PoolConfig::INDEXER_DEFAULTS, &lookup)?,
+        })
+    }
+}
+
+/// Port the server listens on when PORT is unset.
+pub const DEFAULT_PORT: u16 = 3001;
+
+/// Everything the service reads from the environment, parsed and validated once at startup
+/// by `Config::from_env`. An unparseable value fails startup with an error naming the
+/// variable instead of silently falling back to its default. Modules take the fields they
+/// need from here (via `AppState::config` in handlers) rather than reading variables.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// DATABASE_URL (required)
+    pub database_url: String,
+    /// DATABASE_READ_URL: optional read replica for API reads
+    pub database_read_url: Option<String>,
+    /// `DB_*` / `INDEXER_DB_*` pool settings
+    pub db: DbConfig,
+    /// PORT
+    pub port: u16,
+    /// CORS_ORIGINS, comma-separated; empty (unset or `*`) allows any origin.
+    pub cors_origins: Vec<HeaderValue>,
+    /// LOG_FORMAT, defaulting by RAILWAY_ENVIRONMENT
+    pub log_format: LogFormat,
+    /// SHUTDOWN_GRACE_SECS
+    pub shutdown_grace: Duration,
+    /// REQUEST_TIMEOUT_SECS: handler time limit before a 504.
+    pub request_timeout: Duration,
+    /// ADMIN_API_KEY (or the deprecated API_KEY); None disables the admin routes.
+    pub admin_api_key: Option<Arc<str>>,
+    /// ENABLE_INDEXER
+    pub enable_indexer: bool,
+    /// Chains to index and serve (INDEX_MAINNET, MONAD_MAINNET_RPC, ...; see
+    /// `provider::chain_configs`).
+    pub chains: Vec<ChainConfig>,
+    /// Enabled chains left out of `chains` because one of their settings is invalid; the
+    /// API and the other chains run without them.
+    pub skipped_chains: Vec<SkippedChain>,
+    /// MARKETPLACE_CONFIG_SYNC_INTERVAL_SECS
+    pub marketplace_config_sync_interval: Duration,
+    /// REBUILD_AGENT_STATS: rebuild agent_stats once after migrations.
+    pub rebuild_agent_stats: bool,
+    /// VERIFY_FEEDBACK_CONTENT: run the feedback_uri content check in the background.
+    pub verify_feedback_content: bool,
+    /// ACTIVITY_RETENTION_DAYS; None disables archival.
+    pub activity_retention_days: Option<i64>,
+    /// HEALTH_MAX_INDEXER_LAG_SECS
+    pub health_max_indexer_lag_secs: i64,
+    /// MIN_BID_INCREMENT_BPS
+    pub min_bid_increment_bps: u32,
+    /// STATS_CACHE_SECS: how long /api/stats serves a computed result; zero disables caching.
+    pub stats_cache_ttl: Duration,
+    /// SYNC_LAG_SECS: how far behind now the last page of an `updated_since` sync holds its
+    /// cursor, covering transactions that commit after their `updated_at` was stamped.
+    pub sync_lag: Duration,
+    /// Settings read where no `AppState` is at hand; see `Limits`.
+    pub limits: Limits,
+    /// Non-fatal findings (deprecated variables), logged with the summary.
+    pub warnings: Vec<String>,
+}
+
+impl Config {
+    /// Read and validate the process environment.
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_lookup(&|key| std::env::var(key).ok())
+    }
+
+    /// Build the config from `lookup` (the environment, or a map in tests).
+    pub fn from_lookup(lookup: &dyn Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let env = Env(lookup);
+        let mut warnings = Vec::new();
+
+        let database_url = env.get("DATABASE_URL").ok_or("DATABASE_URL must be set")?;
+        check_postgres_url("DATABASE_URL", &database_url)?;
+        let database_read_url = env.get("DATABASE_READ_URL");
+        if let Some(url) = &database_read_url {
+            check_postgres_url("DATABASE_READ_URL", url)?;
+        }
+
+        let admin_api_key = match (env.get("ADMIN_API_KEY"), env.get("API_KEY")) {
+            (Some(key), _) => Some(key.into()),
+            (None, Some(legacy)) => {
+                warnings.push("API_KEY is deprecated, set ADMIN_API_KEY instead".to_string());
+                Some(legacy.into())
+            }
+            (None, None) => None,
+        };
+
+        let health_max_indexer_lag_secs = env.parse("HEALTH_MAX_INDEXER_LAG_SECS", DEFAULT_MAX_INDEXER_LAG_SECS)?;
+        if health_max_indexer_lag_secs < 1 {
+            return Err("HEALTH_MAX_INDEXER_LAG_SECS must be at least 1".to_string());
+        }
+        let min_bid_increment_bps = env.parse("MIN_BID_INCREMENT_BPS", 0)?;
+        if min_bid_increment_bps > BPS_DENOMINATOR {
+            return Err(format!("MIN_BID_INCREMENT_BPS must be at most {} (100%)", BPS_DENOMINATOR));
+        }
+        let request_timeout_secs = env.parse("REQUEST_TIMEOUT_SECS", DEFAULT_REQUEST_TIMEOUT_SECS)?;
+        if request_timeout_secs == 0 {
+            return Err("REQUEST_TIMEOUT_SECS must be at least 1".to_string());
+        }
+        let sync_interval_secs = env.parse("MARKETPLACE_CONFIG_SYNC_INTERVAL_SECS", CONFIG_SYNC_INTERVAL_SECS)?;
+        if sync_interval_secs == 0 {
+            return Err("MARKETPLACE_CONFIG_SYNC_INTERVAL_SECS must be at least 1".to_string());
+        }
+
+        let (chains, skipped_chains) = provider::chain_configs(&env)?;
+
+        Ok(Config {
+            database_url,
+            database_read_url,
+            db: DbConfig::from_lookup(|key| env.get(key))?,
+            port: env.parse("PORT", DEFAULT_PORT)?,
+            cors_origins: cors_origins(env.get("CORS_ORIGINS").as_deref())?,
+            log_format: log_format(env.get("LOG_FORMAT").as_deref(), env.get("RAILWAY_ENVIRONMENT").is_some())?,
+            shutdown_grace: grace_period(env.get("SHUTDOWN_GRACE_SECS").as_deref())?,
+            request_timeout: Duration::from_secs(request_timeout_secs),
+            admin_api_key,
+            enable_indexer: env.flag("ENABLE_INDEXER", false)?,
+            chains,
+            skipped_chains,
+            marketplace_config_sync_interval: Duration::from_secs(sync_interval_secs),
+            rebuild_agent_stats: env.flag("REBUILD_AGENT_STATS", false)?,
+            verify_feedback_content: env.flag("VERIFY_FEEDBACK_CONTENT", false)?,
+            activity_retention_days: retention_days(env.get("ACTIVITY_RETENTION_DAYS").as_deref())?,
+            health_max_indexer_lag_secs,
+            min_bid_increment_bps,
+            stats_cache_ttl: Duration::from_secs(env.parse("STATS_CACHE_SECS", DEFAULT_STATS_CACHE_SECS)?),
+            sync_lag: Duration::from_secs(env.parse("SYNC_LAG_SECS", DEFAULT_SYNC_LAG_SECS)?),
+            limits: Limits::from_env(&env)?,
+            warnings,
+        })
+    }
+
+    /// The newest position the last page of a sync may hand out: now minus SYNC_LAG_SECS.
+    pub fn sync_settled_before(&self) -> DateTime<Utc> {
+        Utc::now() - self.sync_lag
+    }
+
+    /// Ids of the configured chains.
+    pub fn chain_ids(&self) -> Vec<i32> {
+        self.chains.iter().map(|c| c.chain_id).collect()
+    }
+
+    /// The effective settings as `(name, value)` pairs for the startup log, with credentials
+    /// masked: database passwords, the admin key, and the middle of RPC URLs (which often
+    /// embed a provider key).
+    pub fn summary(&self) -> Vec<(String, String)> {
+        let mut lines = vec![
+            ("database_url".to_string(), mask_password(&self.database_url)),
+            (
+                "database_read_url".to_string(),
+                self.database_read_url.as_deref().map(mask_password).unwrap_or_else(|| "none".to_string()),
+            ),
+            ("db.api".to_string(), format!("{:?}", self.db.api)),
+            ("db.indexer".to_string(), format!("{:?}", self.db.indexer)),
+            ("port".to_string(), self.port.to_string()),
+            (
+                "cors_origins".to_string(),
+                if self.cors_origins.is_empty() {
+                    "*".to_string()
+                } else {
+                    self.cors_origins.iter().filter_map(|o| o.to_str().ok()).collect::<Vec<_>>().join(",")
+                },
+            ),
+            ("log_format".to_string(), format!("{:?}", self.log_format)),
+            ("shutdown_grace_secs".to_string(), self.shutdown_grace.as_secs().to_string()),
+            ("request_timeout_secs".to_string(), self.request_timeout.as_secs().to_string()),
+            (
+                "admin_api_key".to_string(),
+                if self.admin_api_key.is_some() { "****" } else { "none" }.to_string(),
+            ),
+            ("enable_indexer".to_string(), self.enable_indexer.to_string()),
+        ];
+        for chain in &self.chains {
+            lines.push((
+                format!("chain.{}", chain.chain_id),
+                format!(
+                    "rpc={} marketplace={} dry_run={} skip_block_timestamps={} confirmations={}",
+                    mask_rpc_url(&chain.rpc_url),
+                    chain.marketplace_address.map(|a| a.to_string()).unwrap_or_else(|| "none".to_string()),
+                    chain.dry_run,
+                    chain.skip_block_timestamps,
+                    chain.confirmations,
+                ),
+            ));
+        }
+        for skipped in &self.skipped_chains {
+            lines.push((format!("chain.{}", skipped.chain_id), format!("skipped ({})", skipped.error)));
+        }
+        lines.extend([
+            (
+                "marketplace_config_sync_interval_secs".to_string(),
+                self.marketplace_config_sync_interval.as_secs().to_string(),
+            ),
+            ("rebuild_agent_stats".to_string(), self.rebuild_agent_stats.to_string()),
+            ("verify_feedback_content".to_string(), self.verify_feedback_content.to_string()),
+            (
+                "activity_retention_days".to_string(),
+                self.activity_retention_days.map(|d| d.to_string()).unwrap_or_else(|| "none".to_string()),
+            ),
+            ("health_max_indexer_lag_secs".to_string(), self.health_max_indexer_lag_secs.to_string()),
+            ("min_bid_increment_bps".to_string(), self.min_bid_increment_bps.to_string()),
+            ("stats_cache_secs".to_string(), self.stats_cache_ttl.as_secs().to_string()),
+            ("sync_lag_secs".to_string(), self.sync_lag.as_secs().to_string()),
+            ("max_page".to_string(), self.limits.max_page.to_string()),
+            ("strict_query_params".to_string(), self.limits.strict_query_params.to_string()),
+            ("max_reasonable_score".to_string(), self.limits.max_reasonable_score.to_string()),
+            (
+                "agent_stats_legacy_aggregation".to_string(),
+                self.limits.agent_stats_legacy_aggregation.to_string(),
+            ),
+            (
+                "slow_query_ms".to_string(),
+                self.limits.slow_query_threshold.as_millis().to_string(),
+            ),
+        ]);
+        lines
+    }
+
+    /// Log `summary` and any warnings. Called once logging is set up.
+    pub fn log_summary(&self) {
+        tracing::info!("Effective configuration:");
+        for (name, value) in self.summary() {
+            tracing::info!("  {} = {}", name, value);
+        }
+        for warning in &self.warnings {
+            tracing::warn!("{}", warning);
+        }
+        for skipped in &self.skipped_chains {
+            tracing::error!("Chain {} is not indexed or served: {}", skipped.chain_id, skipped.error);
+        }
+    }
+}
+
+/// Settings read deep inside request handling (query deserializers, SQL builders), where no
+/// `AppState` is at hand. `main` installs them once from `Config`; until then, as in tests,
+/// `Limits::current` returns the defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limits {
+    /// MAX_PAGE: highest `page` accepted by paginated endpoints
+    pub max_page: i64,
+    /// STRICT_QUERY_PARAMS: reject out-of-range paging values for every request
+    pub strict_query_params: bool,
+    /// MAX_REASONABLE_SCORE: largest |value_normalized| counted toward reputation
+    pub max_reasonable_score: f64,
+    /// AGENT_STATS_LEGACY_AGGREGATION: aggregate feedbacks per request instead of agent_stats
+    pub agent_stats_legacy_aggregation: bool,
+    /// SLOW_QUERY_MS: queries wrapped in `db::timing::timed` taking longer are logged as slow
+    pub slow_query_threshold: Duration,
+}
+
+static LIMITS: OnceLock<Limits> = OnceLock::new();
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_page: DEFAULT_MAX_PAGE,
+            strict_query_params: false,
+            max_reasonable_score: DEFAULT_MAX_REASONABLE_SCORE,
+            agent_stats_legacy_aggregation: false,
+            slow_query_threshold: Duration::from_millis(DEFAULT_SLOW_QUERY_MS),
+        }
+    }
+}
+
+impl Limits {
+    fn from_env(env: &Env<'_>) -> Result<Self, String> {
+        let max_page = env.parse("MAX_PAGE", DEFAULT_MAX_PAGE)?;
+        if max_page < 1 {
+            return Err("MAX_PAGE must be at least 1".to_string());
+        }
+        Ok(Limits {
+            max_page,
+            strict_query_params: env.flag("STRICT_QUERY_PARAMS", false)?,
+            max_reasonable_score: parse_max_reasonable_score(env.get("MAX_REASONABLE_SCORE").as_deref())?,
+            agent_stats_legacy_aggregation: env.flag("AGENT_STATS_LEGACY_AGGREGATION", false)?,
+            slow_query_threshold: Duration::from_millis(env.parse("SLOW_QUERY_MS", DEFAULT_SLOW_QUERY_MS)?),
+        })
+    }
+
+    /// Make these the process-wide limits. Only the first call takes effect.
+    pub fn install(self) {
+        if LIMITS.set(self).is_err() {
+            tracing::warn!("Limits were already installed; keeping the first");
+        }
+    }
+
+    /// The installed limits, or the defaults when none were installed.
+    pub fn current() -> &'static Limits {
+        LIMITS.get_or_init(Limits::default)
+    }
+}
+
+/// Variable lookups for `Config::from_lookup`. Values are trimmed and blank ones count as
+/// unset; parse failures name the variable and the rejected value.
+pub(crate) struct Env<'a>(&'a dyn Fn(&str) -> Option<String>);
+
+impl Env<'_> {
+    pub(crate) fn get(&self, key: &str) -> Option<String> {
+        (self.0)(key).map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+    }
+
+    /// `key` parsed as a `T`, or `default` when unset.
+    pub(crate) fn parse<T: FromStr>(&self, key: &str, default: T) -> Result<T, String> {
+        match self.get(key) {
+            None => Ok(default),
+            Some(raw) => raw
+                .parse()
+                .map_err(|_| format!("{} must be a {}, got {:?}", key, type_name::<T>(), raw)),
+        }
+    }
+
+    /// `key` as `true` or `false`, or `default` when unset.
+    pub(crate) fn flag(&self, key: &str, default: bool) -> Result<bool, String> {
+        match self.get(key).map(|v| v.to_ascii_lowercase()).as_deref() {
+            None => Ok(default),
+            Some("true") => Ok(true),
+            Some("false") => Ok(false),
+            Some(other) => Err(format!("{} must be true or false, got {:?}", key, other)),
+        }
+    }
+}
+
+/// How `Env::parse` describes the expected type in its errors.
+fn type_name<T>() -> &'static str {
+    match std::any::type_name::<T>() {
+        "u16" | "u32" | "u64" => "non-negative integer",
+        "i32" | "i64" => "whole number",
+        "f64" => "number",
+        _ => "valid value",
+    }
+}
+
+fn check_postgres_url(key: &str, url: &str) -> Result<(), String> {
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+        Ok(())
+    } else {
+        Err(format!("{} must be a postgres:// URL", key))
+    }
+}
+
+/// Parse CORS_ORIGINS: comma-separated `http(s)://` origins. Unset or `*` allows any origin
+/// (an empty list).
+fn cors_origins(raw: Option<&str>) -> Result<Vec<HeaderValue>, String> {
+    let Some(raw) = raw.filter(|r| r.trim() != "*") else {
+        return Ok(Vec::new());
+    };
+    raw.split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| {
+            let valid = origin.starts_with("http://") || origin.starts_with("https://");
+            HeaderValue::from_str(origin)
+                .ok()
+                .filter(|_| valid)
+                .ok_or_else(|| format!("CORS_ORIGINS entries must be http(s) origins, got {:?}", origin))
+        })
+        .collect()
+}
+
+/// `url` with the password of its userinfo (`user:password@`) replaced by `****`.
+fn mask_password(url: &str) -> String {
+    let Some(scheme_end) = url.find("://").map(|i| i + 3) else {
+        return url.to_string();
+    };
+    let authority_end = url[scheme_end..].find('/').map_or(url.len(), |i| scheme_end + i);
+    let Some(at) = url[scheme_end..authority_end].rfind('@').map(|i| scheme_end + i) else {
+        return url.to_string();
+    };
+    match url[scheme_end..at].find(':') {
+        Some(colon) => format!("{}:****{}", &url[..scheme_end + colon], &url[at..]),
+        None => url.to_string(),
+    }
+}
+
+/// An RPC URL cut down to its scheme and host, e.g. `https://rpc.provider.example/***`.
+/// Providers put the API key in the userinfo, path or query, so none of those are shown.
+pub fn mask_rpc_url(url: &str) -> String {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return "***".to_string();
+    };
+    match (parsed.host_str(), parsed.port()) {
+        (Some(host), Some(port)) => format!("{}://{}:{}/***", parsed.scheme(), host, port),
+        (Some(host), None) => format!("{}://{}/***", parsed.scheme(), host),
+        (None, _) => "***".to_string(),
+    }
+}
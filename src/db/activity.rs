@@ -1,75 +1,138 @@
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
-use crate::types::{Activity, GlobalActivity, NewActivity};
+use crate::db::count::{count_rows, CountArgs};
+use crate::types::{Activity, ActivityCursor, AgentActivitySpan, CountMode, GlobalActivity, NewActivity, TotalCount};
+
+/// Columns shared by activity_log and activity_log_archive, in table order.
+const ACTIVITY_COLUMNS: &str =
+    "id, agent_id, chain_id, event_type, event_data, block_number, tx_hash, log_index, created_at, block_timestamp";
+
+/// Row source for per-agent activity: the live table, or live + archive when old history is wanted.
+fn activity_source(include_archived: bool) -> String {
+    if include_archived {
+        format!(
+            "(SELECT {cols} FROM activity_log UNION ALL SELECT {cols} FROM activity_log_archive) AS activity_log",
+            cols = ACTIVITY_COLUMNS
+        )
+    } else {
+        "activity_log".to_string()
+    }
+}
 
 /// Get paginated activity log for an agent, optionally filtered by event_type.
+/// Rows moved to activity_log_archive by the retention task are only included with `include_archived`.
+#[allow(clippy::too_many_arguments)]
 pub async fn get_activities(
     pool: &PgPool,
     agent_id: i64,
     chain_id: i32,
     event_type: Option<&str>,
+    include_archived: bool,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
     offset: i64,
     limit: i64,
 ) -> Result<(Vec<Activity>, i64), sqlx::Error> {
+    // SAFETY: activity_source only returns fixed table expressions, never user input
+    let source = activity_source(include_archived);
+
     // Map category names to actual event types stored in the DB.
     // Frontend sends 'identity' or 'reputation', but the DB stores specific event names.
-    let activities: Vec<Activity> = sqlx::query_as(
+    let query = format!(
         r#"
         SELECT id, agent_id, chain_id, event_type, event_data,
                block_number, COALESCE(block_timestamp, created_at) AS block_timestamp, tx_hash, log_index
-        FROM activity_log
+        FROM {}
         WHERE agent_id = $1 AND chain_id = $2
           AND ($3::TEXT IS NULL
             OR ($3 = 'identity' AND event_type IN ('Registered', 'URIUpdated', 'MetadataSet'))
             OR ($3 = 'reputation' AND event_type IN ('NewFeedback', 'FeedbackRevoked', 'ResponseAppended'))
             OR ($3 = 'marketplace' AND event_type LIKE 'marketplace:%')
             OR event_type = $3)
+          AND COALESCE(block_timestamp, created_at)
+            BETWEEN COALESCE($6::TIMESTAMPTZ, '-infinity') AND COALESCE($7::TIMESTAMPTZ, 'infinity')
         ORDER BY block_number DESC, log_index DESC
         LIMIT $4 OFFSET $5
         "#,
-    )
+        source
+    );
+    let activities: Vec<Activity> = sqlx::query_as(&query)
     .bind(agent_id)
     .bind(chain_id)
     .bind(event_type)
     .bind(limit)
     .bind(offset)
+    .bind(from)
+    .bind(to)
     .fetch_all(pool)
     .await?;
 
-    let total: (i64,) = sqlx::query_as(
+    let count_query = format!(
         r#"
         SELECT COUNT(*)
-        FROM activity_log
+        FROM {}
         WHERE agent_id = $1 AND chain_id = $2
           AND ($3::TEXT IS NULL
             OR ($3 = 'identity' AND event_type IN ('Registered', 'URIUpdated', 'MetadataSet'))
             OR ($3 = 'reputation' AND event_type IN ('NewFeedback', 'FeedbackRevoked', 'ResponseAppended'))
             OR ($3 = 'marketplace' AND event_type LIKE 'marketplace:%')
             OR event_type = $3)
+          AND COALESCE(block_timestamp, created_at)
+            BETWEEN COALESCE($4::TIMESTAMPTZ, '-infinity') AND COALESCE($5::TIMESTAMPTZ, 'infinity')
         "#,
-    )
+        source
+    );
+    let total: (i64,) = sqlx::query_as(&count_query)
     .bind(agent_id)
     .bind(chain_id)
     .bind(event_type)
+    .bind(from)
+    .bind(to)
     .fetch_one(pool)
     .await?;
 
     Ok((activities, total.0))
 }
 
+/// First and last event time for an agent, archived history included (the retention task
+/// moves old rows out of activity_log, and first_seen must not move forward with it).
+pub async fn get_activity_span(pool: &PgPool, agent_id: i64, chain_id: i32) -> Result<AgentActivitySpan, sqlx::Error> {
+    // SAFETY: activity_source only returns fixed table expressions, never user input
+    let query = format!(
+        r#"
+        SELECT MIN(COALESCE(block_timestamp, created_at)) AS first_seen,
+               MAX(COALESCE(block_timestamp, created_at)) AS last_active
+        FROM {}
+        WHERE agent_id = $1 AND chain_id = $2
+        "#,
+        activity_source(true)
+    );
+    sqlx::query_as(&query)
+        .bind(agent_id)
+        .bind(chain_id)
+        .fetch_one(pool)
+        .await
+}
+
 /// Get paginated global activity log (all agents), optionally filtered by event_type.
 /// Includes agent name via LEFT JOIN.
+#[allow(clippy::too_many_arguments)]
 pub async fn get_global_activities(
     pool: &PgPool,
     event_type: Option<&str>,
     chain_id: Option<i32>,
+    before: Option<ActivityCursor>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    count_mode: CountMode,
     offset: i64,
     limit: i64,
-) -> Result<(Vec<GlobalActivity>, i64), sqlx::Error> {
+) -> Result<(Vec<GlobalActivity>, TotalCount), sqlx::Error> {
     let activities: Vec<GlobalActivity> = sqlx::query_as(
         r#"
         SELECT a.id, a.agent_id, a.chain_id, a.event_type, a.event_data,
-               a.block_number, COALESCE(a.block_timestamp, a.created_at) AS block_timestamp,
+               a.block_number, a.block_timestamp,
                a.tx_hash, a.log_index,
                ag.name AS agent_name, ag.image AS agent_image
         FROM activity_log a
@@ -80,7 +143,10 @@ pub async fn get_global_activities(
             OR ($1 = 'marketplace' AND a.event_type LIKE 'marketplace:%')
             OR a.event_type = $1)
           AND ($4::INT IS NULL OR a.chain_id = $4)
-        ORDER BY COALESCE(a.block_timestamp, a.created_at) DESC, a.id DESC
+          AND ($5::TIMESTAMPTZ IS NULL OR (a.block_timestamp, a.id) < ($5, $6))
+          AND a.block_timestamp
+            BETWEEN COALESCE($7::TIMESTAMPTZ, '-infinity') AND COALESCE($8::TIMESTAMPTZ, 'infinity')
+        ORDER BY a.block_timestamp DESC, a.id DESC
         LIMIT $2 OFFSET $3
         "#,
     )
@@ -88,12 +154,18 @@ pub async fn get_global_activities(
     .bind(limit)
     .bind(offset)
     .bind(chain_id)
+    .bind(before.map(|c| c.timestamp))
+    .bind(before.map(|c| c.id))
+    .bind(from)
+    .bind(to)
     .fetch_all(pool)
     .await?;
 
-    let total: (i64,) = sqlx::query_as(
+    let count = count_rows(
+        pool,
+        count_mode,
+        "activity_log",
         r#"
-        SELECT COUNT(*)
         FROM activity_log a
         WHERE ($1::TEXT IS NULL
             OR ($1 = 'identity' AND a.event_type IN ('Registered', 'URIUpdated', 'MetadataSet'))
@@ -101,34 +173,101 @@ pub async fn get_global_activities(
             OR ($1 = 'marketplace' AND a.event_type LIKE 'marketplace:%')
             OR a.event_type = $1)
           AND ($2::INT IS NULL OR a.chain_id = $2)
+          AND a.block_timestamp
+            BETWEEN COALESCE($3::TIMESTAMPTZ, '-infinity') AND COALESCE($4::TIMESTAMPTZ, 'infinity')
         "#,
+        event_type.is_some() || chain_id.is_some() || from.is_some() || to.is_some(),
+        CountArgs::new()
+            .bind(event_type)
+            .bind(chain_id)
+            .bind(from)
+            .bind(to),
     )
-    .bind(event_type)
-    .bind(chain_id)
-    .fetch_one(pool)
     .await?;
 
-    Ok((activities, total.0))
+    Ok((activities, count))
 }
 
-/// Insert a new activity log entry.
-pub async fn insert_activity(pool: &PgPool, activity: &NewActivity) -> Result<(), sqlx::Error> {
-    sqlx::query(
+/// Insert a block range's activity entries in one statement (UNNEST over per-column arrays).
+/// Rows already indexed — same chain, tx, log, agent and event type — are skipped, so
+/// replaying a range is harmless. Returns the number of rows actually inserted.
+/// Entries without a block timestamp get the insert time, flagged estimated for the backfill.
+pub async fn insert_activities<'e, E>(executor: E, activities: &[NewActivity]) -> Result<u64, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    if activities.is_empty() {
+        return Ok(0);
+    }
+
+    let agent_ids: Vec<i64> = activities.iter().map(|a| a.agent_id).collect();
+    let chain_ids: Vec<i32> = activities.iter().map(|a| a.chain_id).collect();
+    let event_types: Vec<&str> = activities.iter().map(|a| a.event_type.as_str()).collect();
+    let event_data: Vec<Option<serde_json::Value>> = activities.iter().map(|a| a.event_data.clone()).collect();
+    let block_numbers: Vec<i64> = activities.iter().map(|a| a.block_number).collect();
+    let block_timestamps: Vec<Option<DateTime<Utc>>> = activities.iter().map(|a| a.block_timestamp).collect();
+    let tx_hashes: Vec<&str> = activities.iter().map(|a| a.tx_hash.as_str()).collect();
+    let log_indexes: Vec<i32> = activities.iter().map(|a| a.log_index).collect();
+
+    let result = sqlx::query(
         r#"
-        INSERT INTO activity_log (agent_id, chain_id, event_type, event_data, block_number, block_timestamp, tx_hash, log_index)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        INSERT INTO activity_log (agent_id, chain_id, event_type, event_data, block_number, block_timestamp, block_timestamp_estimated, tx_hash, log_index)
+        SELECT t.agent_id, t.chain_id, t.event_type, t.event_data, t.block_number,
+               COALESCE(t.block_timestamp, NOW()), t.block_timestamp IS NULL, t.tx_hash, t.log_index
+        FROM UNNEST($1::BIGINT[], $2::INT[], $3::TEXT[], $4::JSONB[], $5::BIGINT[], $6::TIMESTAMPTZ[], $7::TEXT[], $8::INT[])
+            AS t(agent_id, chain_id, event_type, event_data, block_number, block_timestamp, tx_hash, log_index)
+        ON CONFLICT (chain_id, tx_hash, log_index, agent_id, event_type) DO NOTHING
         "#,
     )
-    .bind(activity.agent_id)
-    .bind(activity.chain_id)
-    .bind(&activity.event_type)
-    .bind(&activity.event_data)
-    .bind(activity.block_number)
-    .bind(activity.block_timestamp)
-    .bind(&activity.tx_hash)
-    .bind(activity.log_index)
-    .execute(pool)
+    .bind(agent_ids)
+    .bind(chain_ids)
+    .bind(event_types)
+    .bind(event_data)
+    .bind(block_numbers)
+    .bind(block_timestamps)
+    .bind(tx_hashes)
+    .bind(log_indexes)
+    .execute(executor)
     .await?;
 
-    Ok(())
+    Ok(result.rows_affected())
+}
+
+/// Move up to `batch_size` activity_log rows older than `cutoff` into activity_log_archive.
+/// The delete and insert run as one statement, so each batch is its own transaction.
+/// Returns the number of rows moved; fewer than `batch_size` means nothing older is left.
+/// Rows with an estimated block_timestamp stay until the backfill has corrected them: the
+/// archive has no estimated flag, so the backfill would never find them there.
+pub async fn archive_activities_before(
+    pool: &PgPool,
+    cutoff: DateTime<Utc>,
+    batch_size: i64,
+) -> Result<u64, sqlx::Error> {
+    // SAFETY: ACTIVITY_COLUMNS is a fixed column list
+    let query = format!(
+        r#"
+        WITH moved AS (
+            DELETE FROM activity_log
+            WHERE id IN (
+                SELECT id FROM activity_log
+                WHERE block_timestamp < $1 AND NOT block_timestamp_estimated
+                ORDER BY id
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {cols}
+        )
+        INSERT INTO activity_log_archive ({cols})
+        SELECT {cols} FROM moved
+        "#,
+        cols = ACTIVITY_COLUMNS
+    );
+
+    let result = sqlx::query(&query)
+        .bind(cutoff)
+        .bind(batch_size)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
 }
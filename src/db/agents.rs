@@ -1,29 +1,150 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
-use crate::types::{AgentDetailRow, AgentListItem, NewAgent, ScoreByTag, ScoreByTagRow};
+use crate::config::Limits;
+use crate::db::count::{count_rows, CountArgs};
+use crate::db::escape_like;
+use crate::db::feedbacks::{counted_feedback_sql, max_reasonable_score};
+use crate::db::timing::timed;
+use crate::types::{
+    AgentDetailRow, AgentListItem, AgentScoreByTagRow, CapabilityCount, CountMode, LeaderboardEntry, NewAgent,
+    NewOwnershipTransfer, OwnerAgentItem, OwnershipTransfer, ScoreByTag, ScoreByTagRow, SyncCursor, TopTag, TotalCount,
+};
+
+/// Whether agent reads should re-aggregate `feedbacks` per request instead of reading the
+/// denormalized `agent_stats` table. Enabled with `AGENT_STATS_LEGACY_AGGREGATION=true`;
+/// kept so the two can be compared while `agent_stats` is being verified.
+fn use_legacy_aggregation() -> bool {
+    Limits::current().agent_stats_legacy_aggregation
+}
+
+/// `agent_stats` with a live fallback: agents without a stats row (never reviewed, or
+/// not yet backfilled) aggregate their feedbacks instead. The LATERAL subquery is gated on the
+/// missing row, so cached agents pay nothing and uncached ones an index probe on feedbacks.
+fn stats_join(counted: &str) -> String {
+    format!(
+        "LEFT JOIN agent_stats s ON a.agent_id = s.agent_id AND a.chain_id = s.chain_id
+        LEFT JOIN LATERAL (
+            SELECT
+                AVG(f.value_normalized)::FLOAT8 AS avg_score,
+                COUNT(*) AS feedback_count,
+                COUNT(DISTINCT f.client_address) AS unique_clients,
+                COUNT(CASE WHEN f.value_normalized >= 3 THEN 1 ELSE NULL END) AS positive_count,
+                COUNT(CASE WHEN f.value_normalized < 3 THEN 1 ELSE NULL END) AS negative_count
+            FROM feedbacks f
+            WHERE s.agent_id IS NULL
+              AND f.agent_id = a.agent_id AND f.chain_id = a.chain_id AND {counted}
+        ) live ON true"
+    )
+}
+
+/// SELECT columns, JOIN and GROUP BY used to attach reputation_score / feedback_count /
+/// unique_clients (plus positive/negative counts when `with_sentiment`) to an `agents a` query.
+/// Only feedbacks within `MAX_REASONABLE_SCORE` count, as in `agent_stats`.
+fn reputation_fragments(with_sentiment: bool) -> (String, String, &'static str) {
+    // SAFETY: `counted` is built from a number, not user input
+    let counted = counted_feedback_sql("f.", max_reasonable_score());
+    if use_legacy_aggregation() {
+        let mut cols = format!(
+            "AVG(CASE WHEN {counted} THEN f.value_normalized ELSE NULL END)::FLOAT8 AS reputation_score,
+            COUNT(CASE WHEN {counted} THEN 1 ELSE NULL END) AS feedback_count,
+            COUNT(DISTINCT CASE WHEN {counted} THEN f.client_address ELSE NULL END) AS unique_clients",
+        );
+        if with_sentiment {
+            cols.push_str(&format!(",
+            COUNT(CASE WHEN {counted} AND f.value_normalized >= 3 THEN 1 ELSE NULL END) AS positive_feedback_count,
+            COUNT(CASE WHEN {counted} AND f.value_normalized < 3 THEN 1 ELSE NULL END) AS negative_feedback_count"));
+        }
+        (
+            cols,
+            "LEFT JOIN feedbacks f ON a.agent_id = f.agent_id AND a.chain_id = f.chain_id".to_string(),
+            "GROUP BY a.id",
+        )
+    } else {
+        let mut cols = String::from(
+            "COALESCE(s.avg_score, live.avg_score) AS reputation_score,
+            COALESCE(s.feedback_count, live.feedback_count) AS feedback_count,
+            COALESCE(s.unique_clients, live.unique_clients) AS unique_clients",
+        );
+        if with_sentiment {
+            cols.push_str(",
+            COALESCE(s.positive_count, live.positive_count) AS positive_feedback_count,
+            COALESCE(s.negative_count, live.negative_count) AS negative_feedback_count");
+        }
+        (cols, stats_join(&counted), "")
+    }
+}
+
+/// Agents with at least one `metadata.endpoints[]` entry whose `protocol` is $5. The value
+/// goes in as a jsonpath variable; entries without a protocol, and metadata with no
+/// endpoints array, never match.
+const PROTOCOL_FILTER: &str =
+    "jsonb_path_exists(a.metadata, '$.endpoints[*] ? (@.protocol == $protocol)', jsonb_build_object('protocol', $5::TEXT))";
+
+/// Agents whose `metadata.capabilities` array contains the string $7 (exact, case-sensitive).
+const CAPABILITY_FILTER: &str = "a.metadata->'capabilities' @> jsonb_build_array($7::TEXT)";
 
 /// Get a paginated list of agents with optional filtering, search, and sorting.
-/// LEFT JOINs feedbacks to compute average reputation score and feedback count.
+/// Reputation score and feedback count come from `agent_stats`, falling back to the agent's
+/// feedbacks when it has no stats row (or always, when legacy aggregation is enabled).
+/// With `sync`, only agents after that `(updated_at, id)` position, oldest change first
+/// (for incremental sync; `sort` and search ranking don't apply).
+#[allow(clippy::too_many_arguments)]
 pub async fn get_agents(
     pool: &PgPool,
     chain_id: Option<i32>,
     search: Option<&str>,
     category: Option<&str>,
     owner: Option<&str>,
+    protocol: Option<&str>,
+    capability: Option<&str>,
+    sync: Option<SyncCursor>,
     sort: &str,
+    search_mode: &str,
+    count_mode: CountMode,
     offset: i64,
     limit: i64,
-) -> Result<(Vec<AgentListItem>, i64), sqlx::Error> {
+) -> Result<(Vec<AgentListItem>, TotalCount), sqlx::Error> {
     // Build the ORDER BY clause based on sort parameter
     // SAFETY: order_clause is from a hardcoded whitelist, not user input
     let order_clause = match sort {
         "score" => "reputation_score DESC NULLS LAST",
         "name" => "a.name ASC NULLS LAST",
+        // Ties (e.g. every agent with no feedback) keep a stable order across pages
+        "clients" => "unique_clients DESC NULLS LAST, feedback_count DESC, a.chain_id, a.agent_id",
         _ => "a.created_at DESC NULLS LAST", // "recent" default
     };
 
+    // Ranked full-text search for multi-word queries; a single token keeps the substring
+    // match, which also finds partial words ("arb" in "arbitrage"), with `%` and `_` taken
+    // literally.
+    // SAFETY: the search fragments are fixed strings; the search text is bound as $2
+    let ranked = search_mode == "rank" && search.is_some_and(|s| s.split_whitespace().count() > 1);
+    let search = if ranked { search.map(str::to_string) } else { search.map(escape_like) };
+    let search = search.as_deref();
+    let (search_filter, rank_col, order_clause) = if ranked {
+        (
+            "a.search_tsv @@ websearch_to_tsquery('english', $2)",
+            ",\n            ts_rank(a.search_tsv, websearch_to_tsquery('english', $2)) AS search_rank",
+            format!("search_rank DESC, {order_clause}"),
+        )
+    } else {
+        (
+            r"a.name ILIKE '%' || $2 || '%' ESCAPE '\' OR a.description ILIKE '%' || $2 || '%' ESCAPE '\'",
+            "",
+            order_clause.to_string(),
+        )
+    };
+    let order_clause = match sync {
+        Some(_) => "a.updated_at ASC, a.id ASC".to_string(),
+        None => order_clause,
+    };
+
     // We use a raw query approach with format since sqlx doesn't support dynamic ORDER BY
     // in the macro. We build the query as a string.
+    let (stats_cols, stats_join, group_by) = reputation_fragments(false);
     let base_query = format!(
         r#"
         SELECT
@@ -36,14 +157,15 @@ pub async fn get_agents(
             a.categories,
             a.x402_support,
             a.active,
-            AVG(CASE WHEN f.revoked = false THEN f.value / POWER(10, COALESCE(f.value_decimals, 0)) ELSE NULL END)::FLOAT8 AS reputation_score,
-            COUNT(CASE WHEN f.revoked = false THEN 1 ELSE NULL END) AS feedback_count,
-            COALESCE(a.block_timestamp, a.created_at) AS block_timestamp
+            {stats_cols},
+            COALESCE(a.block_timestamp, a.created_at) AS block_timestamp,
+            a.updated_at,
+            a.id{rank_col}
         FROM agents a
-        LEFT JOIN feedbacks f ON a.agent_id = f.agent_id AND a.chain_id = f.chain_id
+        {stats_join}
         WHERE 1=1
             AND ($1::INT IS NULL OR a.chain_id = $1)
-            AND ($2::TEXT IS NULL OR a.name ILIKE '%' || $2 || '%' OR a.description ILIKE '%' || $2 || '%')
+            AND ($2::TEXT IS NULL OR {search_filter})
             AND ($3::TEXT IS NULL OR (
                 CASE WHEN $3 = 'others'
                     THEN (a.categories IS NULL OR cardinality(a.categories) = 0 OR NOT (a.categories && ARRAY['defi','analytics','security','identity','trading','ai','compute','gaming','social','dao']))
@@ -51,31 +173,39 @@ pub async fn get_agents(
                 END
             ))
             AND ($4::TEXT IS NULL OR LOWER(a.owner) = LOWER($4))
-        GROUP BY a.id
-        ORDER BY {}
-        LIMIT $5 OFFSET $6
-        "#,
-        order_clause
+            AND ($5::TEXT IS NULL OR {PROTOCOL_FILTER})
+            AND ($6::TIMESTAMPTZ IS NULL OR (a.updated_at, a.id) > ($6, $8))
+            AND ($7::TEXT IS NULL OR {CAPABILITY_FILTER})
+        {group_by}
+        ORDER BY {order_clause}
+        LIMIT $9 OFFSET $10
+        "#
     );
 
-    let agents: Vec<AgentListItem> = sqlx::query_as(&base_query)
+    let agents = sqlx::query_as(&base_query)
         .bind(chain_id)
         .bind(search)
         .bind(category)
         .bind(owner)
+        .bind(protocol)
+        .bind(sync.map(|c| c.updated_at))
+        .bind(capability)
+        .bind(sync.map(|c| c.id))
         .bind(limit)
         .bind(offset)
-        .fetch_all(pool)
-        .await?;
+        .fetch_all(pool);
+    let agents: Vec<AgentListItem> = timed("get_agents", agents).await?;
 
     // Count total matching agents
-    let total: (i64,) = sqlx::query_as(
-        r#"
-        SELECT COUNT(*)
+    let count = timed("get_agents.count", count_rows(
+        pool,
+        count_mode,
+        "agents",
+        &format!(r#"
         FROM agents a
         WHERE 1=1
             AND ($1::INT IS NULL OR a.chain_id = $1)
-            AND ($2::TEXT IS NULL OR a.name ILIKE '%' || $2 || '%' OR a.description ILIKE '%' || $2 || '%')
+            AND ($2::TEXT IS NULL OR {search_filter})
             AND ($3::TEXT IS NULL OR (
                 CASE WHEN $3 = 'others'
                     THEN (a.categories IS NULL OR cardinality(a.categories) = 0 OR NOT (a.categories && ARRAY['defi','analytics','security','identity','trading','ai','compute','gaming','social','dao']))
@@ -83,16 +213,148 @@ pub async fn get_agents(
                 END
             ))
             AND ($4::TEXT IS NULL OR LOWER(a.owner) = LOWER($4))
+            AND ($5::TEXT IS NULL OR {PROTOCOL_FILTER})
+            AND ($6::TIMESTAMPTZ IS NULL OR (a.updated_at, a.id) > ($6, $8))
+            AND ($7::TEXT IS NULL OR {CAPABILITY_FILTER})
+        "#),
+        chain_id.is_some()
+            || search.is_some()
+            || category.is_some()
+            || owner.is_some()
+            || protocol.is_some()
+            || sync.is_some()
+            || capability.is_some(),
+        CountArgs::new()
+            .bind(chain_id)
+            .bind(search)
+            .bind(category)
+            .bind(owner)
+            .bind(protocol)
+            .bind(sync.map(|c| c.updated_at))
+            .bind(capability)
+            .bind(sync.map(|c| c.id)),
+    ))
+    .await?;
+
+    Ok((agents, count))
+}
+
+/// Distinct `metadata.capabilities` strings with how many agents list each, most common
+/// first. Agents whose capabilities aren't an array are skipped, and an agent repeating a
+/// capability counts once.
+pub async fn get_capabilities(pool: &PgPool, chain_id: Option<i32>) -> Result<Vec<CapabilityCount>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT cap AS capability, COUNT(DISTINCT (a.chain_id, a.agent_id)) AS count
+        FROM agents a,
+            jsonb_array_elements_text(
+                CASE WHEN jsonb_typeof(a.metadata->'capabilities') = 'array' THEN a.metadata->'capabilities' END
+            ) AS cap
+        WHERE cap <> ''
+          AND ($1::INT IS NULL OR a.chain_id = $1)
+        GROUP BY cap
+        ORDER BY count DESC, cap
         "#,
     )
     .bind(chain_id)
-    .bind(search)
-    .bind(category)
-    .bind(owner)
-    .fetch_one(pool)
-    .await?;
+    .fetch_all(pool)
+    .await
+}
+
+/// Get agents owned by `owner` (lowercased) with their reputation summary and current
+/// market status, in one query. `identity_contracts` are the lowercased identity registry
+/// addresses, so that only listings/auctions of the agent NFT itself match.
+/// A listing takes precedence over an auction when both are somehow active.
+pub async fn get_agents_by_owner(
+    pool: &PgPool,
+    owner: &str,
+    identity_contracts: &[String],
+    offset: i64,
+    limit: i64,
+) -> Result<(Vec<OwnerAgentItem>, i64), sqlx::Error> {
+    // Paginate owned agents first, then attach at most one active listing/auction each
+    let (stats_cols, stats_join, group_by) = reputation_fragments(false);
+    let query = format!(
+        r#"
+        SELECT
+            o.*,
+            CASE
+                WHEN ml.listing_id IS NOT NULL THEN 'listed'
+                WHEN ma.auction_id IS NOT NULL OR md.auction_id IS NOT NULL THEN 'auctioned'
+                ELSE 'none'
+            END AS market_status,
+            ml.listing_id,
+            CASE WHEN ml.listing_id IS NULL THEN COALESCE(ma.auction_id, md.auction_id) END AS auction_id,
+            CASE
+                WHEN ml.listing_id IS NOT NULL THEN NULL
+                WHEN ma.auction_id IS NOT NULL THEN 'english'
+                WHEN md.auction_id IS NOT NULL THEN 'dutch'
+            END AS auction_type,
+            COALESCE(ml.price, ma.price, md.price) AS price,
+            COALESCE(ml.payment_token, ma.payment_token, md.payment_token) AS payment_token
+        FROM (
+            SELECT
+                a.agent_id,
+                a.chain_id,
+                a.owner,
+                a.name,
+                a.description,
+                a.image,
+                a.categories,
+                a.x402_support,
+                a.active,
+                {stats_cols},
+                COALESCE(a.block_timestamp, a.created_at) AS block_timestamp,
+                a.created_at
+            FROM agents a
+            {stats_join}
+            WHERE LOWER(a.owner) = $1
+            {group_by}
+            ORDER BY a.created_at DESC NULLS LAST
+            LIMIT $3 OFFSET $4
+        ) o
+        LEFT JOIN LATERAL (
+            SELECT l.listing_id, l.price, l.payment_token
+            FROM marketplace_listings l
+            WHERE l.chain_id = o.chain_id AND l.token_id = o.agent_id::NUMERIC
+              AND l.nft_contract = ANY($2) AND l.status = 'Active'
+            ORDER BY l.block_number DESC
+            LIMIT 1
+        ) ml ON true
+        LEFT JOIN LATERAL (
+            SELECT au.auction_id, COALESCE(NULLIF(au.highest_bid, 0), au.start_price) AS price, au.payment_token
+            FROM marketplace_auctions au
+            WHERE au.chain_id = o.chain_id AND au.token_id = o.agent_id::NUMERIC
+              AND au.nft_contract = ANY($2) AND au.status = 'Active'
+            ORDER BY au.block_number DESC
+            LIMIT 1
+        ) ma ON true
+        LEFT JOIN LATERAL (
+            SELECT d.auction_id, d.start_price AS price, d.payment_token
+            FROM marketplace_dutch_auctions d
+            WHERE d.chain_id = o.chain_id AND d.token_id = o.agent_id::NUMERIC
+              AND d.nft_contract = ANY($2) AND d.status = 'Active'
+            ORDER BY d.block_number DESC
+            LIMIT 1
+        ) md ON true
+        ORDER BY o.created_at DESC NULLS LAST
+        "#
+    );
 
-    Ok((agents, total.0))
+    let agents: Vec<OwnerAgentItem> = sqlx::query_as(&query)
+        .bind(owner)
+        .bind(identity_contracts)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+    let (total,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE LOWER(owner) = $1")
+        .bind(owner)
+        .fetch_one(pool)
+        .await?;
+
+    Ok((agents, total))
 }
 
 /// Classify a score into a scale type based on tag name and value range.
@@ -126,6 +388,32 @@ fn scale_priority(scale: &str) -> u8 {
     }
 }
 
+/// Classify a tag's aggregate row by scale.
+fn score_by_tag(r: ScoreByTagRow) -> ScoreByTag {
+    let scale = classify_scale(
+        &r.score_type,
+        r.min_value.unwrap_or(0.0),
+        r.max_value.unwrap_or(0.0),
+    );
+    ScoreByTag {
+        score_type: r.score_type,
+        label: r.label,
+        value: r.value,
+        count: r.count,
+        min_value: r.min_value,
+        max_value: r.max_value,
+        scale: scale.to_string(),
+    }
+}
+
+/// Display order of an agent's tags: percentage first, then elo, then boolean, then raw.
+/// Within same scale, by count desc.
+fn score_order(a: &ScoreByTag, b: &ScoreByTag) -> std::cmp::Ordering {
+    scale_priority(&a.scale)
+        .cmp(&scale_priority(&b.scale))
+        .then(b.count.cmp(&a.count))
+}
+
 /// Get scores grouped by tag1 for an agent, classified by scale and sorted.
 pub async fn get_scores_by_tag(
     pool: &PgPool,
@@ -137,10 +425,10 @@ pub async fn get_scores_by_tag(
         SELECT
             tag1 AS score_type,
             MODE() WITHIN GROUP (ORDER BY tag2) AS label,
-            AVG(value / POWER(10, COALESCE(value_decimals, 0)))::FLOAT8 AS value,
+            AVG(value_normalized)::FLOAT8 AS value,
             COUNT(*)::BIGINT AS count,
-            MIN(value / POWER(10, COALESCE(value_decimals, 0)))::FLOAT8 AS min_value,
-            MAX(value / POWER(10, COALESCE(value_decimals, 0)))::FLOAT8 AS max_value
+            MIN(value_normalized)::FLOAT8 AS min_value,
+            MAX(value_normalized)::FLOAT8 AS max_value
         FROM feedbacks
         WHERE agent_id = $1 AND chain_id = $2 AND revoked = false AND tag1 IS NOT NULL
         GROUP BY tag1
@@ -152,43 +440,71 @@ pub async fn get_scores_by_tag(
     .fetch_all(pool)
     .await?;
 
-    let mut scores: Vec<ScoreByTag> = rows
-        .into_iter()
-        .map(|r| {
-            let scale = classify_scale(
-                &r.score_type,
-                r.min_value.unwrap_or(0.0),
-                r.max_value.unwrap_or(0.0),
-            );
-            ScoreByTag {
-                score_type: r.score_type,
-                label: r.label,
-                value: r.value,
-                count: r.count,
-                min_value: r.min_value,
-                max_value: r.max_value,
-                scale: scale.to_string(),
-            }
-        })
-        .collect();
-
-    // Sort: percentage first, then elo, then boolean, then raw. Within same scale, by count desc.
-    scores.sort_by(|a, b| {
-        scale_priority(&a.scale)
-            .cmp(&scale_priority(&b.scale))
-            .then(b.count.cmp(&a.count))
-    });
+    let mut scores: Vec<ScoreByTag> = rows.into_iter().map(score_by_tag).collect();
+    scores.sort_by(score_order);
 
     Ok(scores)
 }
 
+/// The first of each agent's `get_scores_by_tag` scores, for a page of (chain_id, agent_id)
+/// pairs, from one grouped query. Agents without tagged feedback are left out of the map.
+pub async fn get_top_tags(
+    pool: &PgPool,
+    agents: &[(i32, i64)],
+) -> Result<HashMap<(i32, i64), TopTag>, sqlx::Error> {
+    if agents.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let (chain_ids, agent_ids): (Vec<i32>, Vec<i64>) = agents.iter().copied().unzip();
+    let rows: Vec<AgentScoreByTagRow> = sqlx::query_as(
+        r#"
+        SELECT
+            chain_id,
+            agent_id,
+            tag1 AS score_type,
+            MODE() WITHIN GROUP (ORDER BY tag2) AS label,
+            AVG(value_normalized)::FLOAT8 AS value,
+            COUNT(*)::BIGINT AS count,
+            MIN(value_normalized)::FLOAT8 AS min_value,
+            MAX(value_normalized)::FLOAT8 AS max_value
+        FROM feedbacks
+        WHERE (chain_id, agent_id) IN (SELECT * FROM UNNEST($1::INT[], $2::BIGINT[]))
+          AND revoked = false AND tag1 IS NOT NULL
+        GROUP BY chain_id, agent_id, tag1
+        ORDER BY count DESC
+        "#,
+    )
+    .bind(&chain_ids)
+    .bind(&agent_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let mut top: HashMap<(i32, i64), ScoreByTag> = HashMap::new();
+    for row in rows {
+        let key = (row.chain_id, row.agent_id);
+        let score = score_by_tag(row.score);
+        match top.get(&key) {
+            Some(best) if !score_order(&score, best).is_lt() => {}
+            _ => {
+                top.insert(key, score);
+            }
+        }
+    }
+
+    Ok(top
+        .into_iter()
+        .map(|(key, s)| (key, TopTag { score_type: s.score_type, label: s.label, value: s.value, scale: s.scale }))
+        .collect())
+}
+
 /// Get a single agent by agent_id and chain_id, with reputation data.
 pub async fn get_agent_by_id(
     pool: &PgPool,
     agent_id: i64,
     chain_id: i32,
 ) -> Result<Option<AgentDetailRow>, sqlx::Error> {
-    let row: Option<AgentDetailRow> = sqlx::query_as(
+    let (stats_cols, stats_join, group_by) = reputation_fragments(true);
+    let query = format!(
         r#"
         SELECT
             a.agent_id,
@@ -202,25 +518,94 @@ pub async fn get_agent_by_id(
             a.x402_support,
             a.active,
             a.metadata,
-            AVG(CASE WHEN f.revoked = false THEN f.value / POWER(10, COALESCE(f.value_decimals, 0)) ELSE NULL END)::FLOAT8 AS reputation_score,
-            COUNT(CASE WHEN f.revoked = false THEN 1 ELSE NULL END) AS feedback_count,
-            COUNT(CASE WHEN f.revoked = false AND f.value / POWER(10, COALESCE(f.value_decimals, 0)) >= 3 THEN 1 ELSE NULL END) AS positive_feedback_count,
-            COUNT(CASE WHEN f.revoked = false AND f.value / POWER(10, COALESCE(f.value_decimals, 0)) < 3 THEN 1 ELSE NULL END) AS negative_feedback_count,
+            {stats_cols},
             COALESCE(a.block_timestamp, a.created_at) AS block_timestamp
         FROM agents a
-        LEFT JOIN feedbacks f ON a.agent_id = f.agent_id AND a.chain_id = f.chain_id
+        {stats_join}
         WHERE a.agent_id = $1 AND a.chain_id = $2
-        GROUP BY a.id
-        "#,
-    )
-    .bind(agent_id)
-    .bind(chain_id)
-    .fetch_optional(pool)
-    .await?;
+        {group_by}
+        "#
+    );
+    let row: Option<AgentDetailRow> = sqlx::query_as(&query)
+        .bind(agent_id)
+        .bind(chain_id)
+        .fetch_optional(pool)
+        .await?;
 
     Ok(row)
 }
 
+/// Get active agents (every agent with `include_inactive`) with at least one non-revoked
+/// feedback, ranked by reputation score. `order` is "desc" (best first) or "asc" (worst
+/// first); rank 1 is the first row either way.
+pub async fn get_leaderboard(
+    pool: &PgPool,
+    chain_id: Option<i32>,
+    category: Option<&str>,
+    order: &str,
+    include_inactive: bool,
+    limit: i64,
+) -> Result<Vec<LeaderboardEntry>, sqlx::Error> {
+    // SAFETY: all interpolated fragments are hardcoded, not user input
+    let (stats_cols, stats_join, group_by) = reputation_fragments(false);
+    let (rank_expr, stats_filter, has_feedback) = if use_legacy_aggregation() {
+        let counted = counted_feedback_sql("f.", max_reasonable_score());
+        (
+            format!("AVG(CASE WHEN {counted} THEN f.value_normalized ELSE NULL END)"),
+            "",
+            format!("HAVING COUNT(CASE WHEN {counted} THEN 1 ELSE NULL END) > 0"),
+        )
+    } else {
+        (
+            "COALESCE(s.avg_score, live.avg_score)".to_string(),
+            "AND COALESCE(s.feedback_count, live.feedback_count) > 0",
+            String::new(),
+        )
+    };
+    let direction = match order {
+        "asc" => "ASC",
+        _ => "DESC",
+    };
+
+    let query = format!(
+        r#"
+        SELECT
+            ROW_NUMBER() OVER (ORDER BY {rank_expr} {direction} NULLS LAST) AS rank,
+            a.agent_id,
+            a.chain_id,
+            a.name,
+            a.image,
+            a.categories,
+            a.x402_support,
+            {stats_cols},
+            a.owner
+        FROM agents a
+        {stats_join}
+        WHERE ($4::BOOL OR a.active = true)
+          AND ($1::INT IS NULL OR a.chain_id = $1)
+          AND ($2::TEXT IS NULL OR (
+                CASE WHEN $2 = 'others'
+                    THEN (a.categories IS NULL OR cardinality(a.categories) = 0 OR NOT (a.categories && ARRAY['defi','analytics','security','identity','trading','ai','compute','gaming','social','dao']))
+                    ELSE $2 = ANY(a.categories)
+                END
+            ))
+          {stats_filter}
+        {group_by}
+        {has_feedback}
+        ORDER BY rank
+        LIMIT $3
+        "#
+    );
+
+    let entries = sqlx::query_as(&query)
+        .bind(chain_id)
+        .bind(category)
+        .bind(limit)
+        .bind(include_inactive)
+        .fetch_all(pool);
+    timed("get_leaderboard", entries).await
+}
+
 /// Update the owner of an agent when a Transfer event is detected.
 pub async fn update_agent_owner(
     pool: &PgPool,
@@ -231,7 +616,7 @@ pub async fn update_agent_owner(
     sqlx::query(
         r#"
         UPDATE agents
-        SET owner = $3, updated_at = NOW()
+        SET owner = $3
         WHERE agent_id = $1 AND chain_id = $2
         "#,
     )
@@ -244,28 +629,112 @@ pub async fn update_agent_owner(
     Ok(())
 }
 
-/// Insert or update an agent on conflict (agent_id, chain_id).
+/// An agent's `image` URI: None when the agent doesn't exist, Some(None) when it has no image.
+pub async fn get_agent_image(pool: &PgPool, agent_id: i64, chain_id: i32) -> Result<Option<Option<String>>, sqlx::Error> {
+    let row: Option<(Option<String>,)> = sqlx::query_as("SELECT image FROM agents WHERE agent_id = $1 AND chain_id = $2")
+        .bind(agent_id)
+        .bind(chain_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|(image,)| image))
+}
+
+/// Every recorded transfer of an agent NFT, mint first (chronological by block and log index).
+pub async fn get_ownership_history(
+    pool: &PgPool,
+    agent_id: i64,
+    chain_id: i32,
+) -> Result<Vec<OwnershipTransfer>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT from_address AS "from", to_address AS "to", block_number, block_timestamp, tx_hash, log_index
+        FROM agent_ownership_history
+        WHERE agent_id = $1 AND chain_id = $2
+        ORDER BY block_number, log_index
+        "#,
+    )
+    .bind(agent_id)
+    .bind(chain_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Insert a block range's ownership transfers in one statement (UNNEST over per-column arrays).
+/// Transfers already indexed (same chain, tx and log) are skipped. Returns rows inserted.
+pub async fn insert_ownership_transfers<'e, E>(
+    executor: E,
+    transfers: &[NewOwnershipTransfer],
+) -> Result<u64, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+{
+    if transfers.is_empty() {
+        return Ok(0);
+    }
+
+    let agent_ids: Vec<i64> = transfers.iter().map(|t| t.agent_id).collect();
+    let chain_ids: Vec<i32> = transfers.iter().map(|t| t.chain_id).collect();
+    let froms: Vec<&str> = transfers.iter().map(|t| t.from_address.as_str()).collect();
+    let tos: Vec<&str> = transfers.iter().map(|t| t.to_address.as_str()).collect();
+    let block_numbers: Vec<i64> = transfers.iter().map(|t| t.block_number).collect();
+    let block_timestamps: Vec<Option<DateTime<Utc>>> = transfers.iter().map(|t| t.block_timestamp).collect();
+    let tx_hashes: Vec<&str> = transfers.iter().map(|t| t.tx_hash.as_str()).collect();
+    let log_indexes: Vec<i32> = transfers.iter().map(|t| t.log_index).collect();
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO agent_ownership_history (agent_id, chain_id, from_address, to_address, block_number, block_timestamp, tx_hash, log_index)
+        SELECT * FROM UNNEST($1::BIGINT[], $2::INT[], $3::TEXT[], $4::TEXT[], $5::BIGINT[], $6::TIMESTAMPTZ[], $7::TEXT[], $8::INT[])
+        ON CONFLICT (chain_id, tx_hash, log_index) DO NOTHING
+        "#,
+    )
+    .bind(agent_ids)
+    .bind(chain_ids)
+    .bind(froms)
+    .bind(tos)
+    .bind(block_numbers)
+    .bind(block_timestamps)
+    .bind(tx_hashes)
+    .bind(log_indexes)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Whether the incoming row of an agents upsert is at least as new as the stored one (true
+/// when either block is unknown).
+const AGENT_ROW_IS_NEWER: &str = "COALESCE(EXCLUDED.block_number >= agents.block_number, TRUE)";
+
+/// Insert or update an agent on conflict (agent_id, chain_id). The on-chain fields (owner,
+/// uri, flags, block and tx) only change when the incoming event's block is not older than
+/// the stored one, so a `Registered` replayed after a later `URIUpdated` (parallel batches,
+/// retries) can't roll them back.
 pub async fn upsert_agent(pool: &PgPool, agent: &NewAgent) -> Result<(), sqlx::Error> {
-    sqlx::query(
+    let query = format!(
         r#"
         INSERT INTO agents (agent_id, chain_id, owner, uri, metadata, name, description, image, categories, x402_support, active, block_number, block_timestamp, tx_hash)
         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
         ON CONFLICT (agent_id, chain_id) DO UPDATE SET
-            owner = CASE WHEN EXCLUDED.owner = '' THEN agents.owner ELSE EXCLUDED.owner END,
-            uri = COALESCE(EXCLUDED.uri, agents.uri),
+            owner = CASE WHEN EXCLUDED.owner = '' OR NOT {newer} THEN agents.owner ELSE EXCLUDED.owner END,
+            uri = CASE WHEN {newer} THEN COALESCE(EXCLUDED.uri, agents.uri) ELSE agents.uri END,
             metadata = COALESCE(EXCLUDED.metadata, agents.metadata),
             name = COALESCE(EXCLUDED.name, agents.name),
             description = COALESCE(EXCLUDED.description, agents.description),
             image = COALESCE(EXCLUDED.image, agents.image),
             categories = COALESCE(EXCLUDED.categories, agents.categories),
-            x402_support = EXCLUDED.x402_support,
-            active = EXCLUDED.active,
-            block_number = EXCLUDED.block_number,
-            block_timestamp = COALESCE(EXCLUDED.block_timestamp, agents.block_timestamp),
-            tx_hash = EXCLUDED.tx_hash,
-            updated_at = NOW()
+            x402_support = CASE WHEN {newer} THEN EXCLUDED.x402_support ELSE agents.x402_support END,
+            active = CASE WHEN {newer} THEN EXCLUDED.active ELSE agents.active END,
+            block_number = CASE WHEN {newer} THEN EXCLUDED.block_number ELSE agents.block_number END,
+            block_timestamp = CASE
+                WHEN {newer} THEN COALESCE(EXCLUDED.block_timestamp, agents.block_timestamp)
+                ELSE agents.block_timestamp
+            END,
+            tx_hash = CASE WHEN {newer} THEN EXCLUDED.tx_hash ELSE agents.tx_hash END
         "#,
-    )
+        newer = AGENT_ROW_IS_NEWER,
+    );
+    sqlx::query(&query)
     .bind(agent.agent_id)
     .bind(agent.chain_id)
     .bind(&agent.owner)
@@ -285,3 +754,32 @@ pub async fn upsert_agent(pool: &PgPool, agent: &NewAgent) -> Result<(), sqlx::E
 
     Ok(())
 }
+
+/// `(chain_id, agent_id, uri)` of agents with a URI to fetch metadata from, for the admin
+/// refetch. `ids` (composite `(chain_id, agent_id)` pairs), `chain_id` and `missing_name`
+/// (no name parsed yet) each narrow the selection when given.
+pub async fn get_agent_uris(
+    pool: &PgPool,
+    ids: Option<&[(i32, i64)]>,
+    chain_id: Option<i32>,
+    missing_name: bool,
+) -> Result<Vec<(i32, i64, String)>, sqlx::Error> {
+    let (chain_ids, agent_ids): (Vec<i32>, Vec<i64>) = ids.unwrap_or_default().iter().copied().unzip();
+    sqlx::query_as(
+        r#"
+        SELECT chain_id, agent_id, uri FROM agents
+        WHERE uri IS NOT NULL AND uri <> ''
+          AND (NOT $1 OR (chain_id, agent_id) IN (SELECT * FROM UNNEST($2::INT[], $3::BIGINT[])))
+          AND ($4::INT IS NULL OR chain_id = $4)
+          AND (NOT $5 OR name IS NULL)
+        ORDER BY chain_id, agent_id
+        "#,
+    )
+    .bind(ids.is_some())
+    .bind(&chain_ids)
+    .bind(&agent_ids)
+    .bind(chain_id)
+    .bind(missing_name)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,118 @@
+use sqlx::postgres::PgArguments;
+use sqlx::{Arguments, PgPool, Postgres};
+
+use crate::types::{CountAccuracy, CountMode, TotalCount};
+
+/// Upper bound for `count_mode=estimate` on filtered queries. Counting stops here,
+/// so deep pages of a very large filtered result report a lower bound.
+pub const COUNT_ESTIMATE_CAP: i64 = 10_000;
+
+/// Bind values for the COUNT query, collected with the same `.bind()` chaining
+/// the list queries use. Encode errors are surfaced when the count runs.
+#[derive(Default)]
+pub struct CountArgs<'q> {
+    args: PgArguments,
+    error: Option<sqlx::error::BoxDynError>,
+    _marker: std::marker::PhantomData<&'q ()>,
+}
+
+impl<'q> CountArgs<'q> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bind<T>(mut self, value: T) -> Self
+    where
+        T: 'q + sqlx::Encode<'q, Postgres> + sqlx::Type<Postgres>,
+    {
+        if self.error.is_none() {
+            if let Err(e) = self.args.add(value) {
+                self.error = Some(e);
+            }
+        }
+        self
+    }
+}
+
+/// Count the rows matched by `from_where` (a `FROM ... WHERE ...` clause over `table`)
+/// according to `mode`:
+/// - `exact`: plain `COUNT(*)`
+/// - `estimate`: the planner's `pg_class.reltuples` when the query is unfiltered, otherwise
+///   a `COUNT(*)` that stops at [`COUNT_ESTIMATE_CAP`]
+/// - `none`: no query at all
+pub async fn count_rows(
+    pool: &PgPool,
+    mode: CountMode,
+    table: &str,
+    from_where: &str,
+    filtered: bool,
+    args: CountArgs<'_>,
+) -> Result<TotalCount, sqlx::Error> {
+    if let Some(e) = args.error {
+        return Err(sqlx::Error::Encode(e));
+    }
+
+    match mode {
+        CountMode::None => Ok(TotalCount::none()),
+        CountMode::Estimate if !filtered => {
+            let reltuples: Option<i64> = sqlx::query_scalar(
+                "SELECT reltuples::BIGINT FROM pg_class WHERE oid = to_regclass($1)",
+            )
+            .bind(table)
+            .fetch_optional(pool)
+            .await?;
+            match estimate_total(reltuples) {
+                Some(count) => Ok(count),
+                // Table never analyzed — fall back to an exact count
+                None => exact_count(pool, from_where, args.args).await,
+            }
+        }
+        CountMode::Estimate => {
+            // SAFETY: from_where is a hardcoded clause from the calling db function
+            let query = format!(
+                "SELECT COUNT(*) FROM (SELECT 1 {} LIMIT {}) capped",
+                from_where, COUNT_ESTIMATE_CAP
+            );
+            let count: i64 = sqlx::query_scalar_with(&query, args.args)
+                .fetch_one(pool)
+                .await?;
+            Ok(capped_total(count))
+        }
+        CountMode::Exact => exact_count(pool, from_where, args.args).await,
+    }
+}
+
+async fn exact_count(
+    pool: &PgPool,
+    from_where: &str,
+    args: PgArguments,
+) -> Result<TotalCount, sqlx::Error> {
+    let query = format!("SELECT COUNT(*) {}", from_where);
+    let count: i64 = sqlx::query_scalar_with(&query, args)
+        .fetch_one(pool)
+        .await?;
+    Ok(TotalCount::exact(count))
+}
+
+/// `reltuples` is -1 for tables that have never been vacuumed/analyzed.
+fn estimate_total(reltuples: Option<i64>) -> Option<TotalCount> {
+    match reltuples {
+        Some(n) if n >= 0 => Some(TotalCount {
+            total: Some(n),
+            total_accuracy: CountAccuracy::Estimate,
+        }),
+        _ => None,
+    }
+}
+
+/// Below the cap the capped count is exact; at the cap it is only a lower bound.
+fn capped_total(count: i64) -> TotalCount {
+    TotalCount {
+        total: Some(count),
+        total_accuracy: if count >= COUNT_ESTIMATE_CAP {
+            CountAccuracy::LowerBound
+        } else {
+            CountAccuracy::Exact
+        },
+    }
+}
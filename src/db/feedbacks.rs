@@ -1,6 +1,49 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
-use crate::types::{Feedback, NewFeedback, ReputationHistoryPoint};
+use crate::config::Limits;
+use crate::types::{
+    Feedback, FeedbackContentCheck, FeedbackResponse, FeedbackVelocityDay, NewFeedback, NewFeedbackResponse,
+    RecentFeedback, ReputationHistoryPoint,
+};
+
+/// A day is a feedback velocity spike when its count exceeds this many times the daily average.
+pub const VELOCITY_SPIKE_FACTOR: f64 = 3.0;
+
+/// `MAX_REASONABLE_SCORE` when unset: far beyond any real scale, so nothing is excluded.
+pub const DEFAULT_MAX_REASONABLE_SCORE: f64 = 1e18;
+
+/// Largest |value_normalized| that counts toward reputation aggregates (scores, counts,
+/// history). Set `MAX_REASONABLE_SCORE` to keep a malformed int128 value from swamping an
+/// agent's average; `agent_stats` picks up a new limit on `REBUILD_AGENT_STATS=true`.
+pub fn max_reasonable_score() -> f64 {
+    Limits::current().max_reasonable_score
+}
+
+/// Parse `MAX_REASONABLE_SCORE`; unset uses the default, and non-numeric, non-finite or
+/// non-positive values are rejected.
+pub fn parse_max_reasonable_score(raw: Option<&str>) -> Result<f64, String> {
+    match raw.map(str::trim).filter(|s| !s.is_empty()) {
+        None => Ok(DEFAULT_MAX_REASONABLE_SCORE),
+        Some(raw) => match raw.parse::<f64>() {
+            Ok(max) if max.is_finite() && max > 0.0 => Ok(max),
+            _ => Err(format!("MAX_REASONABLE_SCORE must be a positive number, got {:?}", raw)),
+        },
+    }
+}
+
+/// Whether a feedback with this normalized value counts toward reputation aggregates.
+pub fn is_reasonable_score(value_normalized: f64, max: f64) -> bool {
+    value_normalized.abs() <= max
+}
+
+/// SQL condition for a feedback row that counts toward reputation aggregates: not revoked and
+/// within `max` (`is_reasonable_score`). `prefix` is the table alias with its dot ("f.") or "".
+pub fn counted_feedback_sql(prefix: &str, max: f64) -> String {
+    // SAFETY: `max` is a finite f64 we format ourselves, not user input
+    format!("{prefix}revoked = false AND ABS({prefix}value_normalized) <= {max}")
+}
 
 /// Get feedbacks for an agent with optional time range filtering.
 /// Range is one of: "7d", "30d", "90d", "all"
@@ -22,8 +65,9 @@ pub async fn get_feedbacks_for_agent(
         let query = format!(
             r#"
             SELECT id, agent_id, chain_id, client_address, feedback_index,
-                   value, value_decimals, tag1, tag2, endpoint, feedback_uri,
-                   feedback_hash, revoked, block_number, block_timestamp, tx_hash, created_at
+                   value, COALESCE(value_decimals, 0) AS value_decimals, tag1, tag2, endpoint, feedback_uri,
+                   feedback_hash, revoked, block_number, block_timestamp, tx_hash, created_at,
+                   content_verified
             FROM feedbacks
             WHERE agent_id = $1 AND chain_id = $2
               AND created_at >= NOW() - INTERVAL '{}'
@@ -42,8 +86,9 @@ pub async fn get_feedbacks_for_agent(
         sqlx::query_as(
             r#"
             SELECT id, agent_id, chain_id, client_address, feedback_index,
-                   value, value_decimals, tag1, tag2, endpoint, feedback_uri,
-                   feedback_hash, revoked, block_number, block_timestamp, tx_hash, created_at
+                   value, COALESCE(value_decimals, 0) AS value_decimals, tag1, tag2, endpoint, feedback_uri,
+                   feedback_hash, revoked, block_number, block_timestamp, tx_hash, created_at,
+                   content_verified
             FROM feedbacks
             WHERE agent_id = $1 AND chain_id = $2
             ORDER BY created_at DESC
@@ -59,6 +104,49 @@ pub async fn get_feedbacks_for_agent(
     Ok(feedbacks)
 }
 
+/// An agent's feedback responses, newest block first, optionally only those to
+/// `feedback_index`. Returns the page and the total matching.
+pub async fn get_feedback_responses(
+    pool: &PgPool,
+    agent_id: i64,
+    chain_id: i32,
+    feedback_index: Option<i64>,
+    offset: i64,
+    limit: i64,
+) -> Result<(Vec<FeedbackResponse>, i64), sqlx::Error> {
+    let responses: Vec<FeedbackResponse> = sqlx::query_as(
+        r#"
+        SELECT id, feedback_id, agent_id, chain_id, response_uri,
+               block_number, block_timestamp, tx_hash, created_at
+        FROM feedback_responses
+        WHERE agent_id = $1 AND chain_id = $2 AND ($3::BIGINT IS NULL OR feedback_id = $3)
+        ORDER BY block_number DESC, id DESC
+        LIMIT $4 OFFSET $5
+        "#,
+    )
+    .bind(agent_id)
+    .bind(chain_id)
+    .bind(feedback_index)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+
+    let (total,): (i64,) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) FROM feedback_responses
+        WHERE agent_id = $1 AND chain_id = $2 AND ($3::BIGINT IS NULL OR feedback_id = $3)
+        "#,
+    )
+    .bind(agent_id)
+    .bind(chain_id)
+    .bind(feedback_index)
+    .fetch_one(pool)
+    .await?;
+
+    Ok((responses, total))
+}
+
 /// Get daily aggregated reputation scores for an agent within a time range.
 pub async fn get_reputation_history(
     pool: &PgPool,
@@ -73,22 +161,22 @@ pub async fn get_reputation_history(
         _ => None,
     };
 
-    // SAFETY: interval_str is from a hardcoded whitelist, not user input
+    // SAFETY: interval_str is from a hardcoded whitelist, `counted` is built from a number
+    let counted = counted_feedback_sql("", max_reasonable_score());
     let rows: Vec<ReputationHistoryPoint> = if let Some(interval_str) = interval {
         let query = format!(
             r#"
             SELECT
                 DATE(created_at) AS date,
-                AVG(CASE WHEN revoked = false THEN value / POWER(10, COALESCE(value_decimals, 0)) ELSE NULL END)::FLOAT8 AS score,
-                COUNT(CASE WHEN revoked = false THEN 1 ELSE NULL END) AS feedback_count
+                AVG(CASE WHEN {counted} THEN value_normalized ELSE NULL END)::FLOAT8 AS score,
+                COUNT(CASE WHEN {counted} THEN 1 ELSE NULL END) AS feedback_count
             FROM feedbacks
             WHERE agent_id = $1 AND chain_id = $2
-              AND created_at >= NOW() - INTERVAL '{}'
+              AND created_at >= NOW() - INTERVAL '{interval_str}'
             GROUP BY DATE(created_at)
             ORDER BY date ASC
             LIMIT 365
-            "#,
-            interval_str
+            "#
         );
         sqlx::query_as(&query)
             .bind(agent_id)
@@ -97,73 +185,401 @@ pub async fn get_reputation_history(
             .await?
     } else {
         // "all" range — cap at 365 days of history to prevent unbounded results
-        sqlx::query_as(
+        let query = format!(
             r#"
             SELECT
                 DATE(created_at) AS date,
-                AVG(CASE WHEN revoked = false THEN value / POWER(10, COALESCE(value_decimals, 0)) ELSE NULL END)::FLOAT8 AS score,
-                COUNT(CASE WHEN revoked = false THEN 1 ELSE NULL END) AS feedback_count
+                AVG(CASE WHEN {counted} THEN value_normalized ELSE NULL END)::FLOAT8 AS score,
+                COUNT(CASE WHEN {counted} THEN 1 ELSE NULL END) AS feedback_count
             FROM feedbacks
             WHERE agent_id = $1 AND chain_id = $2
             GROUP BY DATE(created_at)
             ORDER BY date ASC
             LIMIT 365
-            "#,
-        )
-        .bind(agent_id)
-        .bind(chain_id)
-        .fetch_all(pool)
-        .await?
+            "#
+        );
+        sqlx::query_as(&query)
+            .bind(agent_id)
+            .bind(chain_id)
+            .fetch_all(pool)
+            .await?
     };
 
     Ok(rows)
 }
 
-/// Insert a new feedback record.
-pub async fn insert_feedback(pool: &PgPool, feedback: &NewFeedback) -> Result<(), sqlx::Error> {
-    sqlx::query(
+/// Feedbacks an agent received per day over `range` (`7d`, `30d`, `90d`, or `all` = from its
+/// first feedback, at most 365 days), oldest first with empty days as zeros. Counts every
+/// feedback, revoked and out-of-range scores included, with the revoked ones also counted
+/// separately. Days are by block time, falling back to when the row was indexed.
+pub async fn get_feedback_velocity(
+    pool: &PgPool,
+    agent_id: i64,
+    chain_id: i32,
+    range: &str,
+) -> Result<Vec<FeedbackVelocityDay>, sqlx::Error> {
+    let days: Option<i32> = match range {
+        "7d" => Some(7),
+        "30d" => Some(30),
+        "90d" => Some(90),
+        _ => None,
+    };
+
+    sqlx::query_as(
         r#"
-        INSERT INTO feedbacks (agent_id, chain_id, client_address, feedback_index, value, value_decimals, tag1, tag2, endpoint, feedback_uri, feedback_hash, block_number, block_timestamp, tx_hash)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+        WITH agent_feedbacks AS (
+            SELECT DATE(COALESCE(block_timestamp, created_at)) AS day, revoked
+            FROM feedbacks
+            WHERE agent_id = $1 AND chain_id = $2
+        ),
+        span AS (
+            SELECT COALESCE(
+                CURRENT_DATE - ($3::INT - 1),
+                GREATEST((SELECT MIN(day) FROM agent_feedbacks), CURRENT_DATE - 364),
+                CURRENT_DATE
+            ) AS first_day
+        )
+        SELECT
+            d::DATE AS date,
+            COUNT(f.day) AS feedback_count,
+            COUNT(f.day) FILTER (WHERE f.revoked) AS revoked_count
+        FROM span, generate_series(span.first_day, CURRENT_DATE, INTERVAL '1 day') AS d
+        LEFT JOIN agent_feedbacks f ON f.day = d::DATE
+        GROUP BY d
+        ORDER BY d
         "#,
     )
-    .bind(feedback.agent_id)
-    .bind(feedback.chain_id)
-    .bind(&feedback.client_address)
-    .bind(feedback.feedback_index)
-    .bind(&feedback.value)
-    .bind(feedback.value_decimals)
-    .bind(&feedback.tag1)
-    .bind(&feedback.tag2)
-    .bind(&feedback.endpoint)
-    .bind(&feedback.feedback_uri)
-    .bind(&feedback.feedback_hash)
-    .bind(feedback.block_number)
-    .bind(feedback.block_timestamp)
-    .bind(&feedback.tx_hash)
-    .execute(pool)
-    .await?;
+    .bind(agent_id)
+    .bind(chain_id)
+    .bind(days)
+    .fetch_all(pool)
+    .await
+}
 
-    Ok(())
+/// Flag each day whose count is over `factor` times the mean of `days`, and return that
+/// mean. A range with no feedback has no spikes.
+pub fn mark_spikes(days: &mut [FeedbackVelocityDay], factor: f64) -> f64 {
+    if days.is_empty() {
+        return 0.0;
+    }
+    let average = days.iter().map(|d| d.feedback_count).sum::<i64>() as f64 / days.len() as f64;
+    for day in days.iter_mut() {
+        day.spike = average > 0.0 && day.feedback_count as f64 > average * factor;
+    }
+    average
 }
 
-/// Mark a feedback as revoked by agent_id, chain_id, and feedback_index.
-pub async fn revoke_feedback(
+/// Get the newest non-revoked feedbacks across all agents (optionally one chain), with the
+/// agent's name and image. Agents not indexed yet come back with NULL name/image.
+pub async fn get_recent_feedbacks(
     pool: &PgPool,
-    agent_id: i64,
+    chain_id: Option<i32>,
+    limit: i64,
+) -> Result<Vec<RecentFeedback>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT f.id, f.agent_id, f.chain_id, f.client_address, f.feedback_index,
+               f.value, COALESCE(f.value_decimals, 0) AS value_decimals, f.value_normalized, f.tag1, f.tag2, f.endpoint, f.feedback_uri,
+               f.block_number, COALESCE(f.block_timestamp, f.created_at) AS block_timestamp, f.tx_hash,
+               ag.name AS agent_name, ag.image AS agent_image
+        FROM feedbacks f
+        LEFT JOIN agents ag ON ag.agent_id = f.agent_id AND ag.chain_id = f.chain_id
+        WHERE f.revoked = false
+          AND ($1::INT IS NULL OR f.chain_id = $1)
+        ORDER BY COALESCE(f.block_timestamp, f.created_at) DESC, f.id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(chain_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Recompute the `agent_stats` rows of the given agents from their feedbacks, in one statement.
+/// Recomputing (rather than incrementing) keeps each row equal to the ground-truth
+/// aggregate even when an event is replayed or a revoke targets an unknown feedback.
+///
+/// The indexer writes several block ranges at once, often for the same agents. Each agent is
+/// locked for the rest of the transaction first (in agent_id order, so two writers can't
+/// deadlock), and the aggregate runs after the lock is granted: under READ COMMITTED its
+/// snapshot then includes the feedbacks of whichever writer held the lock before.
+pub async fn refresh_agent_stats(
+    conn: &mut sqlx::PgConnection,
     chain_id: i32,
-    feedback_index: i64,
+    agent_ids: &[i64],
 ) -> Result<(), sqlx::Error> {
-    sqlx::query(
+    if agent_ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut locked = agent_ids.to_vec();
+    locked.sort_unstable();
+    locked.dedup();
+    sqlx::query("SELECT pg_advisory_xact_lock($2, hashint8(t.agent_id)) FROM UNNEST($1::BIGINT[]) AS t(agent_id)")
+        .bind(&locked)
+        .bind(chain_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let counted = counted_feedback_sql("f.", max_reasonable_score());
+    let query = format!(
         r#"
-        UPDATE feedbacks
+        INSERT INTO agent_stats (agent_id, chain_id, feedback_count, score_sum, avg_score, positive_count, negative_count, unique_clients, last_feedback_at)
+        SELECT
+            t.agent_id,
+            $2,
+            COUNT(CASE WHEN {counted} THEN 1 ELSE NULL END),
+            SUM(CASE WHEN {counted} THEN f.value_normalized ELSE NULL END)::FLOAT8,
+            AVG(CASE WHEN {counted} THEN f.value_normalized ELSE NULL END)::FLOAT8,
+            COUNT(CASE WHEN {counted} AND f.value_normalized >= 3 THEN 1 ELSE NULL END),
+            COUNT(CASE WHEN {counted} AND f.value_normalized < 3 THEN 1 ELSE NULL END),
+            COUNT(DISTINCT CASE WHEN {counted} THEN f.client_address ELSE NULL END),
+            MAX(CASE WHEN {counted} THEN f.created_at ELSE NULL END)
+        FROM UNNEST($1::BIGINT[]) AS t(agent_id)
+        LEFT JOIN feedbacks f ON f.agent_id = t.agent_id AND f.chain_id = $2
+        GROUP BY t.agent_id
+        ON CONFLICT (agent_id, chain_id) DO UPDATE SET
+            feedback_count = EXCLUDED.feedback_count,
+            score_sum = EXCLUDED.score_sum,
+            avg_score = EXCLUDED.avg_score,
+            positive_count = EXCLUDED.positive_count,
+            negative_count = EXCLUDED.negative_count,
+            unique_clients = EXCLUDED.unique_clients,
+            last_feedback_at = EXCLUDED.last_feedback_at
+        "#
+    );
+    sqlx::query(&query)
+        .bind(agent_ids)
+        .bind(chain_id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Rebuild `agent_stats` from scratch for one chain (every chain when None): every agent with
+/// feedbacks or an existing stats row is recomputed the same way as `refresh_agent_stats`.
+/// Used to backfill the table after a restore or a manual feedbacks fix; returns rows written.
+pub async fn rebuild_agent_stats(pool: &PgPool, chain_id: Option<i32>) -> Result<u64, sqlx::Error> {
+    let counted = counted_feedback_sql("f.", max_reasonable_score());
+    let query = format!(
+        r#"
+        INSERT INTO agent_stats (agent_id, chain_id, feedback_count, score_sum, avg_score, positive_count, negative_count, unique_clients, last_feedback_at)
+        SELECT
+            t.agent_id,
+            t.chain_id,
+            COUNT(CASE WHEN {counted} THEN 1 ELSE NULL END),
+            SUM(CASE WHEN {counted} THEN f.value_normalized ELSE NULL END)::FLOAT8,
+            AVG(CASE WHEN {counted} THEN f.value_normalized ELSE NULL END)::FLOAT8,
+            COUNT(CASE WHEN {counted} AND f.value_normalized >= 3 THEN 1 ELSE NULL END),
+            COUNT(CASE WHEN {counted} AND f.value_normalized < 3 THEN 1 ELSE NULL END),
+            COUNT(DISTINCT CASE WHEN {counted} THEN f.client_address ELSE NULL END),
+            MAX(CASE WHEN {counted} THEN f.created_at ELSE NULL END)
+        FROM (
+            SELECT agent_id, chain_id FROM feedbacks WHERE $1::INT IS NULL OR chain_id = $1
+            UNION
+            SELECT agent_id, chain_id FROM agent_stats WHERE $1::INT IS NULL OR chain_id = $1
+        ) t
+        LEFT JOIN feedbacks f ON f.agent_id = t.agent_id AND f.chain_id = t.chain_id
+        GROUP BY t.agent_id, t.chain_id
+        ON CONFLICT (agent_id, chain_id) DO UPDATE SET
+            feedback_count = EXCLUDED.feedback_count,
+            score_sum = EXCLUDED.score_sum,
+            avg_score = EXCLUDED.avg_score,
+            positive_count = EXCLUDED.positive_count,
+            negative_count = EXCLUDED.negative_count,
+            unique_clients = EXCLUDED.unique_clients,
+            last_feedback_at = EXCLUDED.last_feedback_at
+        "#
+    );
+    let result = sqlx::query(&query)
+        .bind(chain_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Insert a block range's feedbacks in one statement (UNNEST over per-column arrays).
+/// Feedbacks already indexed (same agent, client and feedback_index) are skipped.
+/// Callers refresh agent_stats in the same transaction.
+pub async fn insert_feedbacks(
+    conn: &mut sqlx::PgConnection,
+    feedbacks: &[NewFeedback],
+) -> Result<u64, sqlx::Error> {
+    if feedbacks.is_empty() {
+        return Ok(0);
+    }
+
+    let agent_ids: Vec<i64> = feedbacks.iter().map(|f| f.agent_id).collect();
+    let chain_ids: Vec<i32> = feedbacks.iter().map(|f| f.chain_id).collect();
+    let clients: Vec<&str> = feedbacks.iter().map(|f| f.client_address.as_str()).collect();
+    let feedback_indexes: Vec<i64> = feedbacks.iter().map(|f| f.feedback_index).collect();
+    let values: Vec<BigDecimal> = feedbacks.iter().map(|f| f.value.clone()).collect();
+    let value_decimals: Vec<i32> = feedbacks.iter().map(|f| f.value_decimals).collect();
+    let tag1s: Vec<Option<&str>> = feedbacks.iter().map(|f| f.tag1.as_deref()).collect();
+    let tag2s: Vec<Option<&str>> = feedbacks.iter().map(|f| f.tag2.as_deref()).collect();
+    let endpoints: Vec<Option<&str>> = feedbacks.iter().map(|f| f.endpoint.as_deref()).collect();
+    let uris: Vec<Option<&str>> = feedbacks.iter().map(|f| f.feedback_uri.as_deref()).collect();
+    let hashes: Vec<Option<&str>> = feedbacks.iter().map(|f| f.feedback_hash.as_deref()).collect();
+    let block_numbers: Vec<i64> = feedbacks.iter().map(|f| f.block_number).collect();
+    let block_timestamps: Vec<Option<DateTime<Utc>>> = feedbacks.iter().map(|f| f.block_timestamp).collect();
+    let tx_hashes: Vec<&str> = feedbacks.iter().map(|f| f.tx_hash.as_str()).collect();
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO feedbacks (agent_id, chain_id, client_address, feedback_index, value, value_decimals, value_normalized, tag1, tag2, endpoint, feedback_uri, feedback_hash, block_number, block_timestamp, tx_hash)
+        SELECT agent_id, chain_id, client_address, feedback_index, value, value_decimals,
+               value / POWER(10, COALESCE(value_decimals, 0)),
+               tag1, tag2, endpoint, feedback_uri, feedback_hash, block_number, block_timestamp, tx_hash
+        FROM UNNEST(
+            $1::BIGINT[], $2::INT[], $3::TEXT[], $4::BIGINT[], $5::NUMERIC[], $6::INT[], $7::TEXT[],
+            $8::TEXT[], $9::TEXT[], $10::TEXT[], $11::TEXT[], $12::BIGINT[], $13::TIMESTAMPTZ[], $14::TEXT[]
+        ) AS f(agent_id, chain_id, client_address, feedback_index, value, value_decimals, tag1, tag2,
+               endpoint, feedback_uri, feedback_hash, block_number, block_timestamp, tx_hash)
+        ON CONFLICT (agent_id, chain_id, client_address, feedback_index) DO NOTHING
+        "#,
+    )
+    .bind(agent_ids)
+    .bind(chain_ids)
+    .bind(clients)
+    .bind(feedback_indexes)
+    .bind(values)
+    .bind(value_decimals)
+    .bind(tag1s)
+    .bind(tag2s)
+    .bind(endpoints)
+    .bind(uris)
+    .bind(hashes)
+    .bind(block_numbers)
+    .bind(block_timestamps)
+    .bind(tx_hashes)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Mark feedbacks revoked by (agent_id, client_address, feedback_index), in one statement.
+/// Feedback indexes count per client, so the client is part of the key.
+/// Callers refresh agent_stats in the same transaction.
+pub async fn revoke_feedbacks(
+    conn: &mut sqlx::PgConnection,
+    chain_id: i32,
+    revocations: &[(i64, String, i64)],
+) -> Result<u64, sqlx::Error> {
+    if revocations.is_empty() {
+        return Ok(0);
+    }
+
+    let agent_ids: Vec<i64> = revocations.iter().map(|(agent_id, _, _)| *agent_id).collect();
+    let clients: Vec<&str> = revocations.iter().map(|(_, client, _)| client.as_str()).collect();
+    let feedback_indexes: Vec<i64> = revocations.iter().map(|(_, _, index)| *index).collect();
+
+    let result = sqlx::query(
+        r#"
+        UPDATE feedbacks f
         SET revoked = true
-        WHERE agent_id = $1 AND chain_id = $2 AND feedback_index = $3
+        FROM UNNEST($1::BIGINT[], $2::TEXT[], $3::BIGINT[]) AS r(agent_id, client_address, feedback_index)
+        WHERE f.chain_id = $4 AND f.agent_id = r.agent_id
+          AND f.client_address = r.client_address AND f.feedback_index = r.feedback_index
         "#,
     )
-    .bind(agent_id)
+    .bind(agent_ids)
+    .bind(clients)
+    .bind(feedback_indexes)
     .bind(chain_id)
-    .bind(feedback_index)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Insert a block range's feedback responses in one statement (UNNEST over per-column arrays).
+/// Responses already indexed (same chain, tx and log) are skipped, so replaying a range is
+/// harmless. Returns the number of rows actually inserted.
+pub async fn insert_feedback_responses(
+    conn: &mut sqlx::PgConnection,
+    responses: &[NewFeedbackResponse],
+) -> Result<u64, sqlx::Error> {
+    if responses.is_empty() {
+        return Ok(0);
+    }
+
+    let feedback_ids: Vec<i64> = responses.iter().map(|r| r.feedback_index).collect();
+    let agent_ids: Vec<i64> = responses.iter().map(|r| r.agent_id).collect();
+    let chain_ids: Vec<i32> = responses.iter().map(|r| r.chain_id).collect();
+    let uris: Vec<&str> = responses.iter().map(|r| r.response_uri.as_str()).collect();
+    let block_numbers: Vec<i64> = responses.iter().map(|r| r.block_number).collect();
+    let block_timestamps: Vec<Option<DateTime<Utc>>> = responses.iter().map(|r| r.block_timestamp).collect();
+    let tx_hashes: Vec<&str> = responses.iter().map(|r| r.tx_hash.as_str()).collect();
+    let log_indexes: Vec<i32> = responses.iter().map(|r| r.log_index).collect();
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO feedback_responses (feedback_id, agent_id, chain_id, response_uri, block_number, block_timestamp, tx_hash, log_index)
+        SELECT * FROM UNNEST($1::BIGINT[], $2::BIGINT[], $3::INT[], $4::TEXT[], $5::BIGINT[], $6::TIMESTAMPTZ[], $7::TEXT[], $8::INT[])
+        ON CONFLICT (chain_id, tx_hash, log_index) DO NOTHING
+        "#,
+    )
+    .bind(feedback_ids)
+    .bind(agent_ids)
+    .bind(chain_ids)
+    .bind(uris)
+    .bind(block_numbers)
+    .bind(block_timestamps)
+    .bind(tx_hashes)
+    .bind(log_indexes)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Feedbacks whose content hasn't been verified against feedback_hash yet. Rows whose last
+/// fetch failed are retried once `retry_after_hours` have passed. Zero hashes (no commitment)
+/// are never returned.
+pub async fn get_feedbacks_pending_content_check(
+    pool: &PgPool,
+    retry_after_hours: i32,
+    limit: i64,
+) -> Result<Vec<FeedbackContentCheck>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT id, feedback_uri, feedback_hash
+        FROM feedbacks
+        WHERE content_verified IS NULL
+          AND feedback_uri IS NOT NULL AND feedback_uri <> ''
+          AND feedback_hash IS NOT NULL
+          AND feedback_hash <> '0x0000000000000000000000000000000000000000000000000000000000000000'
+          AND (content_checked_at IS NULL
+            OR content_checked_at < NOW() - make_interval(hours => $1))
+        ORDER BY id
+        LIMIT $2
+        "#,
+    )
+    .bind(retry_after_hours)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Record a content check. `verified` is None when the content couldn't be fetched.
+pub async fn set_content_verified(
+    pool: &PgPool,
+    id: i32,
+    verified: Option<bool>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE feedbacks
+        SET content_verified = $2, content_checked_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(verified)
     .execute(pool)
     .await?;
 
@@ -1,5 +1,7 @@
 use sqlx::PgPool;
 
+use crate::types::IndexerState;
+
 /// Get the last indexed block number for a given chain_id and contract_address.
 pub async fn get_last_block(
     pool: &PgPool,
@@ -42,12 +44,11 @@ pub async fn update_last_block_with_name(
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
-        INSERT INTO indexer_state (chain_id, contract_address, last_block, contract_name, updated_at)
-        VALUES ($1, $2, $3, $4, NOW())
+        INSERT INTO indexer_state (chain_id, contract_address, last_block, contract_name)
+        VALUES ($1, $2, $3, $4)
         ON CONFLICT (chain_id, contract_address) DO UPDATE SET
             last_block = EXCLUDED.last_block,
-            contract_name = COALESCE(EXCLUDED.contract_name, indexer_state.contract_name),
-            updated_at = NOW()
+            contract_name = COALESCE(EXCLUDED.contract_name, indexer_state.contract_name)
         "#,
     )
     .bind(chain_id)
@@ -59,3 +60,16 @@ pub async fn update_last_block_with_name(
 
     Ok(())
 }
+
+/// Get every indexer cursor, ordered by chain and contract.
+pub async fn get_all(pool: &PgPool) -> Result<Vec<IndexerState>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT chain_id, contract_address, contract_name, last_block, updated_at
+        FROM indexer_state
+        ORDER BY chain_id, contract_address
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -1,23 +1,49 @@
+use std::collections::HashMap;
+
 use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
+use crate::db::count::{count_rows, CountArgs};
+use crate::db::timing::timed;
 use crate::types::{
-    MarketplaceAuction, MarketplaceAuctionBid, MarketplaceBundle, MarketplaceCollectionOffer,
-    MarketplaceDutchAuction, MarketplaceListing, MarketplaceOffer, MarketplaceStatsResponse,
-    MarketplaceUserPortfolioResponse, NewMarketplaceAuction, NewMarketplaceBundle,
+    AuctionStatus, CountMode, ListingStatus, MarketplaceAuction, MarketplaceAuctionBid, MarketplaceBundle, MarketplaceCollectionOffer,
+    CollectionSalePriceStats, LastSale, MarketplaceCollectionActivity, MarketplaceDutchAuction, MarketplaceListing, MarketplaceOffer, MarketplaceSale,
+    MarketplaceSellerMetricsResponse, MarketplaceStatsResponse,
+    MarketplaceUserBid, MarketplaceUserOffer, MarketplaceUserPortfolioResponse, NewMarketplaceAuction, NewMarketplaceBundle,
     NewMarketplaceCollectionOffer, NewMarketplaceDutchAuction, NewMarketplaceListing,
-    NewMarketplaceOffer,
+    NewMarketplaceOffer, NewMarketplaceSale, OfferStatus, SyncCursor, TotalCount,
 };
 
+/// SQL expression for seller proceeds after the platform fee, mirroring the contract's
+/// integer math: `price - floor(price * bps / 10000)`. NULL when no fee config is stored.
+/// Expects `marketplace_config` joined as `mc`.
+fn net_proceeds_sql(price_col: &str) -> String {
+    format!(
+        "({p} - FLOOR({p} * mc.platform_fee_bps / 10000))",
+        p = price_col
+    )
+}
+
+/// SQL expression for the agent id a token_id names, for joining `agents`. Token ids of other
+/// collections can exceed BIGINT, where a plain `::BIGINT` cast fails the whole query; those
+/// become NULL and join nothing.
+fn agent_id_sql(token_col: &str) -> String {
+    format!(
+        "CASE WHEN {t} <= 9223372036854775807 THEN {t}::BIGINT END",
+        t = token_col
+    )
+}
+
 // ─── Listings ───────────────────────────────────────────────────────────
 
 pub async fn upsert_listing(pool: &PgPool, l: &NewMarketplaceListing) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         INSERT INTO marketplace_listings
-            (listing_id, chain_id, seller, nft_contract, token_id, payment_token, price, expiry, block_number, block_timestamp, tx_hash)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-        ON CONFLICT (listing_id, chain_id) DO UPDATE SET
+            (listing_id, chain_id, seller, nft_contract, token_id, payment_token, price, expiry, block_number, block_timestamp, tx_hash, marketplace_address)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        ON CONFLICT (listing_id, chain_id, marketplace_address) DO UPDATE SET
             seller = EXCLUDED.seller,
             nft_contract = EXCLUDED.nft_contract,
             token_id = EXCLUDED.token_id,
@@ -26,8 +52,7 @@ pub async fn upsert_listing(pool: &PgPool, l: &NewMarketplaceListing) -> Result<
             expiry = EXCLUDED.expiry,
             block_number = EXCLUDED.block_number,
             block_timestamp = EXCLUDED.block_timestamp,
-            tx_hash = EXCLUDED.tx_hash,
-            updated_at = NOW()
+            tx_hash = EXCLUDED.tx_hash
         "#,
     )
     .bind(l.listing_id)
@@ -41,6 +66,7 @@ pub async fn upsert_listing(pool: &PgPool, l: &NewMarketplaceListing) -> Result<
     .bind(l.block_number)
     .bind(l.block_timestamp)
     .bind(&l.tx_hash)
+    .bind(&l.marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
@@ -50,15 +76,16 @@ pub async fn update_listing_status(
     pool: &PgPool,
     listing_id: i64,
     chain_id: i32,
-    status: &str,
+    marketplace_address: &str,
+    status: ListingStatus,
     buyer: Option<&str>,
     sold_price: Option<&BigDecimal>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         UPDATE marketplace_listings
-        SET status = $3, buyer = COALESCE($4, buyer), sold_price = COALESCE($5, sold_price), updated_at = NOW()
-        WHERE listing_id = $1 AND chain_id = $2
+        SET status = $3, buyer = COALESCE($4, buyer), sold_price = COALESCE($5, sold_price)
+        WHERE listing_id = $1 AND chain_id = $2 AND marketplace_address = $6
         "#,
     )
     .bind(listing_id)
@@ -66,6 +93,7 @@ pub async fn update_listing_status(
     .bind(status)
     .bind(buyer)
     .bind(sold_price)
+    .bind(marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
@@ -75,51 +103,87 @@ pub async fn update_listing_price(
     pool: &PgPool,
     listing_id: i64,
     chain_id: i32,
+    marketplace_address: &str,
     new_price: &BigDecimal,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         UPDATE marketplace_listings
-        SET price = $3, updated_at = NOW()
-        WHERE listing_id = $1 AND chain_id = $2
+        SET price = $3
+        WHERE listing_id = $1 AND chain_id = $2 AND marketplace_address = $4
         "#,
     )
     .bind(listing_id)
     .bind(chain_id)
     .bind(new_price)
+    .bind(marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
 }
 
+/// Listings matching the filters. With `latest_per_token`, only the newest match (highest
+/// block) of each token is kept before sorting and paging, and the count is of tokens.
+#[allow(clippy::too_many_arguments)]
 pub async fn get_listings(
     pool: &PgPool,
     chain_id: Option<i32>,
     nft_contract: Option<&str>,
     seller: Option<&str>,
-    status: &str,
+    token_id: Option<&str>,
+    status: Option<ListingStatus>,
+    exclude_status: Option<ListingStatus>,
+    sync: Option<SyncCursor>,
+    latest_per_token: bool,
     sort: &str,
+    count_mode: CountMode,
     offset: i64,
     limit: i64,
-) -> Result<(Vec<MarketplaceListing>, i64), sqlx::Error> {
-    let order_clause = match sort {
-        "price_asc" => "l.price ASC",
-        "price_desc" => "l.price DESC",
+) -> Result<(Vec<MarketplaceListing>, TotalCount), sqlx::Error> {
+    let order_clause = match (sync, sort) {
+        (Some(_), _) => "l.updated_at ASC, l.id ASC",
+        (None, "price_asc") => "l.price ASC",
+        (None, "price_desc") => "l.price DESC",
         _ => "l.block_number DESC",
     };
 
+    // Non-numeric token ids are ignored rather than rejected, as in get_offers
+    let token_id_bd: Option<BigDecimal> = token_id.and_then(|t| t.parse().ok());
+
+    // SAFETY: the DISTINCT ON fragments are fixed strings
+    let (distinct_on, latest_first) = if latest_per_token {
+        (
+            "DISTINCT ON (l.chain_id, l.nft_contract, l.token_id)",
+            "ORDER BY l.chain_id, l.nft_contract, l.token_id, l.block_number DESC, l.id DESC",
+        )
+    } else {
+        ("", "")
+    };
+
     let query = format!(
         r#"
-        SELECT l.*, a.name AS agent_name, a.image AS agent_image
-        FROM marketplace_listings l
-        LEFT JOIN agents a ON a.agent_id = l.token_id::BIGINT AND a.chain_id = l.chain_id
-        WHERE l.status = $1
-          AND ($2::INT IS NULL OR l.chain_id = $2)
-          AND ($3::TEXT IS NULL OR l.nft_contract = $3)
-          AND ($4::TEXT IS NULL OR l.seller = $4)
+        SELECT * FROM (
+            SELECT {} l.*, a.name AS agent_name, a.image AS agent_image,
+                   CASE WHEN l.status = 'Sold' THEN {} END AS net_proceeds
+            FROM marketplace_listings l
+            LEFT JOIN agents a ON a.agent_id = {} AND a.chain_id = l.chain_id
+            LEFT JOIN marketplace_config mc ON mc.chain_id = l.chain_id
+            WHERE ($1::TEXT IS NULL OR l.status = $1)
+              AND ($2::INT IS NULL OR l.chain_id = $2)
+              AND ($3::TEXT IS NULL OR l.nft_contract = $3)
+              AND ($4::TEXT IS NULL OR l.seller = $4)
+              AND ($5::TEXT IS NULL OR l.status <> $5)
+              AND ($6::NUMERIC IS NULL OR l.token_id = $6)
+              AND ($7::TIMESTAMPTZ IS NULL OR (l.updated_at, l.id) > ($7, $8))
+            {}
+        ) l
         ORDER BY {}
-        LIMIT $5 OFFSET $6
+        LIMIT $9 OFFSET $10
         "#,
+        distinct_on,
+        net_proceeds_sql("l.sold_price"),
+        agent_id_sql("l.token_id"),
+        latest_first,
         order_clause
     );
 
@@ -128,60 +192,139 @@ pub async fn get_listings(
         .bind(chain_id)
         .bind(nft_contract)
         .bind(seller)
+        .bind(exclude_status)
+        .bind(&token_id_bd)
+        .bind(sync.map(|c| c.updated_at))
+        .bind(sync.map(|c| c.id))
         .bind(limit)
         .bind(offset)
         .fetch_all(pool)
         .await?;
 
-    let (total,): (i64,) = sqlx::query_as(
-        r#"
-        SELECT COUNT(*) FROM marketplace_listings
-        WHERE status = $1
+    let filters = r#"
+        WHERE ($1::TEXT IS NULL OR status = $1)
           AND ($2::INT IS NULL OR chain_id = $2)
           AND ($3::TEXT IS NULL OR nft_contract = $3)
           AND ($4::TEXT IS NULL OR seller = $4)
-        "#,
+          AND ($5::TEXT IS NULL OR status <> $5)
+          AND ($6::NUMERIC IS NULL OR token_id = $6)
+          AND ($7::TIMESTAMPTZ IS NULL OR (updated_at, id) > ($7, $8))
+        "#;
+    // With `latest_per_token` the list pages through tokens, so count those
+    let from_where = if latest_per_token {
+        format!("FROM (SELECT DISTINCT chain_id, nft_contract, token_id FROM marketplace_listings {filters}) tokens")
+    } else {
+        format!("FROM marketplace_listings {filters}")
+    };
+    let count = count_rows(
+        pool,
+        count_mode,
+        "marketplace_listings",
+        &from_where,
+        latest_per_token
+            || status.is_some()
+            || exclude_status.is_some()
+            || chain_id.is_some()
+            || nft_contract.is_some()
+            || seller.is_some()
+            || token_id_bd.is_some()
+            || sync.is_some(),
+        CountArgs::new()
+            .bind(status)
+            .bind(chain_id)
+            .bind(nft_contract)
+            .bind(seller)
+            .bind(exclude_status)
+            .bind(&token_id_bd)
+            .bind(sync.map(|c| c.updated_at))
+            .bind(sync.map(|c| c.id)),
     )
-    .bind(status)
-    .bind(chain_id)
-    .bind(nft_contract)
-    .bind(seller)
-    .fetch_one(pool)
     .await?;
 
-    Ok((listings, total))
+    Ok((listings, count))
 }
 
+/// A listing by id. Listing ids restart when the marketplace is redeployed, so `None` for
+/// `marketplace_address` picks the most recently indexed contract's listing.
 pub async fn get_listing_by_id(
     pool: &PgPool,
     listing_id: i64,
     chain_id: i32,
+    marketplace_address: Option<&str>,
 ) -> Result<Option<MarketplaceListing>, sqlx::Error> {
-    sqlx::query_as(
-        "SELECT * FROM marketplace_listings WHERE listing_id = $1 AND chain_id = $2",
-    )
-    .bind(listing_id)
-    .bind(chain_id)
-    .fetch_optional(pool)
-    .await
+    let query = format!(
+        r#"
+        SELECT l.*, CASE WHEN l.status = 'Sold' THEN {} END AS net_proceeds
+        FROM marketplace_listings l
+        LEFT JOIN marketplace_config mc ON mc.chain_id = l.chain_id
+        WHERE l.listing_id = $1 AND l.chain_id = $2 AND ($3::TEXT IS NULL OR l.marketplace_address = $3)
+        ORDER BY l.id DESC
+        LIMIT 1
+        "#,
+        net_proceeds_sql("l.sold_price")
+    );
+    sqlx::query_as(&query)
+        .bind(listing_id)
+        .bind(chain_id)
+        .bind(marketplace_address)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Every listing of one token, any status and any marketplace contract, newest first: a
+/// cancel-and-relist leaves the old listing here next to the new one.
+pub async fn get_listings_for_token(
+    pool: &PgPool,
+    chain_id: i32,
+    nft_contract: &str,
+    token_id: &BigDecimal,
+) -> Result<Vec<MarketplaceListing>, sqlx::Error> {
+    let query = format!(
+        r#"
+        SELECT l.*, CASE WHEN l.status = 'Sold' THEN {} END AS net_proceeds
+        FROM marketplace_listings l
+        LEFT JOIN marketplace_config mc ON mc.chain_id = l.chain_id
+        WHERE l.chain_id = $1 AND l.nft_contract = $2 AND l.token_id = $3
+        ORDER BY l.block_number DESC, l.id DESC
+        "#,
+        net_proceeds_sql("l.sold_price")
+    );
+    sqlx::query_as(&query)
+        .bind(chain_id)
+        .bind(nft_contract)
+        .bind(token_id)
+        .fetch_all(pool)
+        .await
 }
 
 // ─── Offers ─────────────────────────────────────────────────────────────
 
+/// ORDER BY for offer and collection offer lists. `expiring_soon` puts Active offers first,
+/// soonest expiry first; anything else is newest first. An `updated_since` sync orders by
+/// change time instead.
+fn offer_order_clause(sort: &str, syncing: bool) -> &'static str {
+    if syncing {
+        return "updated_at ASC, id ASC";
+    }
+    match sort {
+        "expiring_soon" => "CASE WHEN status = 'Active' THEN 0 ELSE 1 END ASC, expiry ASC, offer_id ASC",
+        _ => "block_number DESC",
+    }
+}
+
 pub async fn upsert_offer(pool: &PgPool, o: &NewMarketplaceOffer) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         INSERT INTO marketplace_offers
-            (offer_id, chain_id, offerer, nft_contract, token_id, payment_token, amount, expiry, block_number, block_timestamp, tx_hash)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-        ON CONFLICT (offer_id, chain_id) DO UPDATE SET
+            (offer_id, chain_id, offerer, nft_contract, token_id, payment_token, amount, expiry, block_number, block_timestamp, tx_hash, marketplace_address)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        ON CONFLICT (offer_id, chain_id, marketplace_address) DO UPDATE SET
             offerer = EXCLUDED.offerer,
             nft_contract = EXCLUDED.nft_contract,
             token_id = EXCLUDED.token_id,
             payment_token = EXCLUDED.payment_token,
             amount = EXCLUDED.amount,
-            expiry = EXCLUDED.expiry,
-            updated_at = NOW()
+            expiry = EXCLUDED.expiry
         "#,
     )
     .bind(o.offer_id)
@@ -195,6 +338,7 @@ pub async fn upsert_offer(pool: &PgPool, o: &NewMarketplaceOffer) -> Result<(),
     .bind(o.block_number)
     .bind(o.block_timestamp)
     .bind(&o.tx_hash)
+    .bind(&o.marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
@@ -204,38 +348,67 @@ pub async fn update_offer_status(
     pool: &PgPool,
     offer_id: i64,
     chain_id: i32,
-    status: &str,
+    marketplace_address: &str,
+    status: OfferStatus,
     accepted_by: Option<&str>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         UPDATE marketplace_offers
-        SET status = $3, accepted_by = COALESCE($4, accepted_by), updated_at = NOW()
-        WHERE offer_id = $1 AND chain_id = $2
+        SET status = $3, accepted_by = COALESCE($4, accepted_by)
+        WHERE offer_id = $1 AND chain_id = $2 AND marketplace_address = $5
         "#,
     )
     .bind(offer_id)
     .bind(chain_id)
     .bind(status)
     .bind(accepted_by)
+    .bind(marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
 }
 
+/// An offer by id on one marketplace contract, for the indexer to find the NFT an
+/// OfferAccepted or OfferCancelled event refers to.
+pub async fn get_offer_by_id(
+    pool: &PgPool,
+    offer_id: i64,
+    chain_id: i32,
+    marketplace_address: &str,
+) -> Result<Option<MarketplaceOffer>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT * FROM marketplace_offers
+        WHERE offer_id = $1 AND chain_id = $2 AND marketplace_address = $3
+        "#,
+    )
+    .bind(offer_id)
+    .bind(chain_id)
+    .bind(marketplace_address)
+    .fetch_optional(pool)
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn get_offers(
     pool: &PgPool,
     chain_id: Option<i32>,
     nft_contract: Option<&str>,
     token_id: Option<&str>,
     offerer: Option<&str>,
-    status: Option<&str>,
+    status: Option<OfferStatus>,
+    expiring_after: Option<i64>,
+    expiring_before: Option<i64>,
+    sync: Option<SyncCursor>,
+    sort: &str,
+    count_mode: CountMode,
     offset: i64,
     limit: i64,
-) -> Result<(Vec<MarketplaceOffer>, i64), sqlx::Error> {
+) -> Result<(Vec<MarketplaceOffer>, TotalCount), sqlx::Error> {
     let token_id_bd: Option<BigDecimal> = token_id.and_then(|t| t.parse().ok());
 
-    let offers: Vec<MarketplaceOffer> = sqlx::query_as(
+    let query = format!(
         r#"
         SELECT * FROM marketplace_offers
         WHERE ($1::INT IS NULL OR chain_id = $1)
@@ -243,39 +416,66 @@ pub async fn get_offers(
           AND ($3::NUMERIC IS NULL OR token_id = $3)
           AND ($4::TEXT IS NULL OR offerer = $4)
           AND ($5::TEXT IS NULL OR status = $5)
-        ORDER BY block_number DESC
-        LIMIT $6 OFFSET $7
+          AND ($6::BIGINT IS NULL OR expiry >= $6)
+          AND ($7::BIGINT IS NULL OR expiry <= $7)
+          AND ($8::TIMESTAMPTZ IS NULL OR (updated_at, id) > ($8, $9))
+        ORDER BY {}
+        LIMIT $10 OFFSET $11
         "#,
-    )
-    .bind(chain_id)
-    .bind(nft_contract)
-    .bind(&token_id_bd)
-    .bind(offerer)
-    .bind(status)
-    .bind(limit)
-    .bind(offset)
-    .fetch_all(pool)
-    .await?;
+        offer_order_clause(sort, sync.is_some())
+    );
+    let offers: Vec<MarketplaceOffer> = sqlx::query_as(&query)
+        .bind(chain_id)
+        .bind(nft_contract)
+        .bind(&token_id_bd)
+        .bind(offerer)
+        .bind(status)
+        .bind(expiring_after)
+        .bind(expiring_before)
+        .bind(sync.map(|c| c.updated_at))
+        .bind(sync.map(|c| c.id))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
 
-    let (total,): (i64,) = sqlx::query_as(
+    let count = count_rows(
+        pool,
+        count_mode,
+        "marketplace_offers",
         r#"
-        SELECT COUNT(*) FROM marketplace_offers
+        FROM marketplace_offers
         WHERE ($1::INT IS NULL OR chain_id = $1)
           AND ($2::TEXT IS NULL OR nft_contract = $2)
           AND ($3::NUMERIC IS NULL OR token_id = $3)
           AND ($4::TEXT IS NULL OR offerer = $4)
           AND ($5::TEXT IS NULL OR status = $5)
+          AND ($6::BIGINT IS NULL OR expiry >= $6)
+          AND ($7::BIGINT IS NULL OR expiry <= $7)
+          AND ($8::TIMESTAMPTZ IS NULL OR (updated_at, id) > ($8, $9))
         "#,
+        chain_id.is_some()
+            || nft_contract.is_some()
+            || token_id_bd.is_some()
+            || offerer.is_some()
+            || status.is_some()
+            || expiring_after.is_some()
+            || expiring_before.is_some()
+            || sync.is_some(),
+        CountArgs::new()
+            .bind(chain_id)
+            .bind(nft_contract)
+            .bind(&token_id_bd)
+            .bind(offerer)
+            .bind(status)
+            .bind(expiring_after)
+            .bind(expiring_before)
+            .bind(sync.map(|c| c.updated_at))
+            .bind(sync.map(|c| c.id)),
     )
-    .bind(chain_id)
-    .bind(nft_contract)
-    .bind(&token_id_bd)
-    .bind(offerer)
-    .bind(status)
-    .fetch_one(pool)
     .await?;
 
-    Ok((offers, total))
+    Ok((offers, count))
 }
 
 // ─── Collection Offers ──────────────────────────────────────────────────
@@ -287,15 +487,14 @@ pub async fn upsert_collection_offer(
     sqlx::query(
         r#"
         INSERT INTO marketplace_collection_offers
-            (offer_id, chain_id, offerer, nft_contract, payment_token, amount, expiry, block_number, block_timestamp, tx_hash)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-        ON CONFLICT (offer_id, chain_id) DO UPDATE SET
+            (offer_id, chain_id, offerer, nft_contract, payment_token, amount, expiry, block_number, block_timestamp, tx_hash, marketplace_address)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        ON CONFLICT (offer_id, chain_id, marketplace_address) DO UPDATE SET
             offerer = EXCLUDED.offerer,
             nft_contract = EXCLUDED.nft_contract,
             payment_token = EXCLUDED.payment_token,
             amount = EXCLUDED.amount,
-            expiry = EXCLUDED.expiry,
-            updated_at = NOW()
+            expiry = EXCLUDED.expiry
         "#,
     )
     .bind(o.offer_id)
@@ -308,6 +507,7 @@ pub async fn upsert_collection_offer(
     .bind(o.block_number)
     .bind(o.block_timestamp)
     .bind(&o.tx_hash)
+    .bind(&o.marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
@@ -317,7 +517,8 @@ pub async fn update_collection_offer_status(
     pool: &PgPool,
     offer_id: i64,
     chain_id: i32,
-    status: &str,
+    marketplace_address: &str,
+    status: OfferStatus,
     accepted_by: Option<&str>,
     accepted_token_id: Option<&BigDecimal>,
 ) -> Result<(), sqlx::Error> {
@@ -326,9 +527,8 @@ pub async fn update_collection_offer_status(
         UPDATE marketplace_collection_offers
         SET status = $3,
             accepted_by = COALESCE($4, accepted_by),
-            accepted_token_id = COALESCE($5, accepted_token_id),
-            updated_at = NOW()
-        WHERE offer_id = $1 AND chain_id = $2
+            accepted_token_id = COALESCE($5, accepted_token_id)
+        WHERE offer_id = $1 AND chain_id = $2 AND marketplace_address = $6
         "#,
     )
     .bind(offer_id)
@@ -336,57 +536,110 @@ pub async fn update_collection_offer_status(
     .bind(status)
     .bind(accepted_by)
     .bind(accepted_token_id)
+    .bind(marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
 }
 
+/// A collection offer by id on one marketplace contract (see `get_offer_by_id`).
+pub async fn get_collection_offer_by_id(
+    pool: &PgPool,
+    offer_id: i64,
+    chain_id: i32,
+    marketplace_address: &str,
+) -> Result<Option<MarketplaceCollectionOffer>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT * FROM marketplace_collection_offers
+        WHERE offer_id = $1 AND chain_id = $2 AND marketplace_address = $3
+        "#,
+    )
+    .bind(offer_id)
+    .bind(chain_id)
+    .bind(marketplace_address)
+    .fetch_optional(pool)
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn get_collection_offers(
     pool: &PgPool,
     chain_id: Option<i32>,
     nft_contract: Option<&str>,
     offerer: Option<&str>,
-    status: Option<&str>,
+    status: Option<OfferStatus>,
+    expiring_after: Option<i64>,
+    expiring_before: Option<i64>,
+    sync: Option<SyncCursor>,
+    sort: &str,
+    count_mode: CountMode,
     offset: i64,
     limit: i64,
-) -> Result<(Vec<MarketplaceCollectionOffer>, i64), sqlx::Error> {
-    let offers: Vec<MarketplaceCollectionOffer> = sqlx::query_as(
+) -> Result<(Vec<MarketplaceCollectionOffer>, TotalCount), sqlx::Error> {
+    let query = format!(
         r#"
         SELECT * FROM marketplace_collection_offers
         WHERE ($1::INT IS NULL OR chain_id = $1)
           AND ($2::TEXT IS NULL OR nft_contract = $2)
           AND ($3::TEXT IS NULL OR offerer = $3)
           AND ($4::TEXT IS NULL OR status = $4)
-        ORDER BY block_number DESC
-        LIMIT $5 OFFSET $6
+          AND ($5::BIGINT IS NULL OR expiry >= $5)
+          AND ($6::BIGINT IS NULL OR expiry <= $6)
+          AND ($7::TIMESTAMPTZ IS NULL OR (updated_at, id) > ($7, $8))
+        ORDER BY {}
+        LIMIT $9 OFFSET $10
         "#,
-    )
-    .bind(chain_id)
-    .bind(nft_contract)
-    .bind(offerer)
-    .bind(status)
-    .bind(limit)
-    .bind(offset)
-    .fetch_all(pool)
-    .await?;
+        offer_order_clause(sort, sync.is_some())
+    );
+    let offers: Vec<MarketplaceCollectionOffer> = sqlx::query_as(&query)
+        .bind(chain_id)
+        .bind(nft_contract)
+        .bind(offerer)
+        .bind(status)
+        .bind(expiring_after)
+        .bind(expiring_before)
+        .bind(sync.map(|c| c.updated_at))
+        .bind(sync.map(|c| c.id))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
 
-    let (total,): (i64,) = sqlx::query_as(
+    let count = count_rows(
+        pool,
+        count_mode,
+        "marketplace_collection_offers",
         r#"
-        SELECT COUNT(*) FROM marketplace_collection_offers
+        FROM marketplace_collection_offers
         WHERE ($1::INT IS NULL OR chain_id = $1)
           AND ($2::TEXT IS NULL OR nft_contract = $2)
           AND ($3::TEXT IS NULL OR offerer = $3)
           AND ($4::TEXT IS NULL OR status = $4)
+          AND ($5::BIGINT IS NULL OR expiry >= $5)
+          AND ($6::BIGINT IS NULL OR expiry <= $6)
+          AND ($7::TIMESTAMPTZ IS NULL OR (updated_at, id) > ($7, $8))
         "#,
+        chain_id.is_some()
+            || nft_contract.is_some()
+            || offerer.is_some()
+            || status.is_some()
+            || expiring_after.is_some()
+            || expiring_before.is_some()
+            || sync.is_some(),
+        CountArgs::new()
+            .bind(chain_id)
+            .bind(nft_contract)
+            .bind(offerer)
+            .bind(status)
+            .bind(expiring_after)
+            .bind(expiring_before)
+            .bind(sync.map(|c| c.updated_at))
+            .bind(sync.map(|c| c.id)),
     )
-    .bind(chain_id)
-    .bind(nft_contract)
-    .bind(offerer)
-    .bind(status)
-    .fetch_one(pool)
     .await?;
 
-    Ok((offers, total))
+    Ok((offers, count))
 }
 
 // ─── Auctions ───────────────────────────────────────────────────────────
@@ -400,9 +653,9 @@ pub async fn upsert_auction(
         INSERT INTO marketplace_auctions
             (auction_id, chain_id, seller, nft_contract, token_id, payment_token,
              start_price, reserve_price, buy_now_price, start_time, end_time,
-             block_number, block_timestamp, tx_hash)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
-        ON CONFLICT (auction_id, chain_id) DO UPDATE SET
+             block_number, block_timestamp, tx_hash, marketplace_address, malformed)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+        ON CONFLICT (auction_id, chain_id, marketplace_address) DO UPDATE SET
             seller = EXCLUDED.seller,
             nft_contract = EXCLUDED.nft_contract,
             token_id = EXCLUDED.token_id,
@@ -412,7 +665,7 @@ pub async fn upsert_auction(
             buy_now_price = EXCLUDED.buy_now_price,
             start_time = EXCLUDED.start_time,
             end_time = EXCLUDED.end_time,
-            updated_at = NOW()
+            malformed = EXCLUDED.malformed
         "#,
     )
     .bind(a.auction_id)
@@ -429,29 +682,39 @@ pub async fn upsert_auction(
     .bind(a.block_number)
     .bind(a.block_timestamp)
     .bind(&a.tx_hash)
+    .bind(&a.marketplace_address)
+    .bind(a.malformed)
     .execute(pool)
     .await?;
     Ok(())
 }
 
+/// Record a new highest bid. bid_count is recounted from marketplace_auction_bids, so call
+/// this after `insert_auction_bid`; replaying a bid leaves the count unchanged.
 pub async fn update_auction_bid(
     pool: &PgPool,
     auction_id: i64,
     chain_id: i32,
+    marketplace_address: &str,
     highest_bid: &BigDecimal,
     highest_bidder: &str,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         UPDATE marketplace_auctions
-        SET highest_bid = $3, highest_bidder = $4, bid_count = COALESCE(bid_count, 0) + 1, updated_at = NOW()
-        WHERE auction_id = $1 AND chain_id = $2
+        SET highest_bid = $3, highest_bidder = $4,
+            bid_count = (
+                SELECT COUNT(*) FROM marketplace_auction_bids
+                WHERE auction_id = $1 AND chain_id = $2 AND marketplace_address = $5
+            )
+        WHERE auction_id = $1 AND chain_id = $2 AND marketplace_address = $5
         "#,
     )
     .bind(auction_id)
     .bind(chain_id)
     .bind(highest_bid)
     .bind(highest_bidder)
+    .bind(marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
@@ -461,18 +724,20 @@ pub async fn update_auction_end_time(
     pool: &PgPool,
     auction_id: i64,
     chain_id: i32,
+    marketplace_address: &str,
     new_end_time: i64,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         UPDATE marketplace_auctions
-        SET end_time = $3, updated_at = NOW()
-        WHERE auction_id = $1 AND chain_id = $2
+        SET end_time = $3, malformed = $3 <= start_time
+        WHERE auction_id = $1 AND chain_id = $2 AND marketplace_address = $4
         "#,
     )
     .bind(auction_id)
     .bind(chain_id)
     .bind(new_end_time)
+    .bind(marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
@@ -482,15 +747,16 @@ pub async fn update_auction_status(
     pool: &PgPool,
     auction_id: i64,
     chain_id: i32,
-    status: &str,
+    marketplace_address: &str,
+    status: AuctionStatus,
     winner: Option<&str>,
     settled_price: Option<&BigDecimal>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         UPDATE marketplace_auctions
-        SET status = $3, winner = COALESCE($4, winner), settled_price = COALESCE($5, settled_price), updated_at = NOW()
-        WHERE auction_id = $1 AND chain_id = $2
+        SET status = $3, winner = COALESCE($4, winner), settled_price = COALESCE($5, settled_price)
+        WHERE auction_id = $1 AND chain_id = $2 AND marketplace_address = $6
         "#,
     )
     .bind(auction_id)
@@ -498,26 +764,32 @@ pub async fn update_auction_status(
     .bind(status)
     .bind(winner)
     .bind(settled_price)
+    .bind(marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
 }
 
+/// Store a BidPlaced event. A replayed log (same tx_hash/log_index) is ignored.
+#[allow(clippy::too_many_arguments)]
 pub async fn insert_auction_bid(
     pool: &PgPool,
     auction_id: i64,
     chain_id: i32,
+    marketplace_address: &str,
     bidder: &str,
     amount: &BigDecimal,
     block_number: i64,
     block_timestamp: Option<chrono::DateTime<chrono::Utc>>,
     tx_hash: &str,
+    log_index: i32,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         INSERT INTO marketplace_auction_bids
-            (auction_id, chain_id, bidder, amount, block_number, block_timestamp, tx_hash)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+            (auction_id, chain_id, bidder, amount, block_number, block_timestamp, tx_hash, log_index, marketplace_address)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (chain_id, tx_hash, log_index) DO NOTHING
         "#,
     )
     .bind(auction_id)
@@ -527,39 +799,52 @@ pub async fn insert_auction_bid(
     .bind(block_number)
     .bind(block_timestamp)
     .bind(tx_hash)
+    .bind(log_index)
+    .bind(marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_auctions(
     pool: &PgPool,
     chain_id: Option<i32>,
     nft_contract: Option<&str>,
     seller: Option<&str>,
-    status: Option<&str>,
+    status: Option<AuctionStatus>,
+    exclude_malformed: bool,
+    sync: Option<SyncCursor>,
     sort: &str,
+    count_mode: CountMode,
     offset: i64,
     limit: i64,
-) -> Result<(Vec<MarketplaceAuction>, i64), sqlx::Error> {
-    let order_clause = match sort {
-        "ending_soon" => "CASE WHEN a.status = 'Active' THEN 0 ELSE 1 END ASC, a.end_time ASC",
-        "highest_bid" => "a.highest_bid DESC NULLS LAST",
+) -> Result<(Vec<MarketplaceAuction>, TotalCount), sqlx::Error> {
+    let order_clause = match (sync, sort) {
+        (Some(_), _) => "a.updated_at ASC, a.id ASC",
+        (None, "ending_soon") => "CASE WHEN a.status = 'Active' THEN 0 ELSE 1 END ASC, a.end_time ASC",
+        (None, "highest_bid") => "a.highest_bid DESC NULLS LAST",
         _ => "a.block_number DESC",
     };
 
     let query = format!(
         r#"
-        SELECT a.*, ag.name AS agent_name, ag.image AS agent_image
+        SELECT a.*, ag.name AS agent_name, ag.image AS agent_image,
+               CASE WHEN a.status = 'Ended' THEN {} END AS net_proceeds
         FROM marketplace_auctions a
-        LEFT JOIN agents ag ON ag.agent_id = a.token_id::BIGINT AND ag.chain_id = a.chain_id
+        LEFT JOIN agents ag ON ag.agent_id = {} AND ag.chain_id = a.chain_id
+        LEFT JOIN marketplace_config mc ON mc.chain_id = a.chain_id
         WHERE ($1::INT IS NULL OR a.chain_id = $1)
           AND ($2::TEXT IS NULL OR a.nft_contract = $2)
           AND ($3::TEXT IS NULL OR a.seller = $3)
           AND ($4::TEXT IS NULL OR a.status = $4)
+          AND ($5::TIMESTAMPTZ IS NULL OR (a.updated_at, a.id) > ($5, $7))
+          AND NOT ($6 AND a.malformed)
         ORDER BY {}
-        LIMIT $5 OFFSET $6
+        LIMIT $8 OFFSET $9
         "#,
+        net_proceeds_sql("a.settled_price"),
+        agent_id_sql("a.token_id"),
         order_clause
     );
 
@@ -568,28 +853,72 @@ pub async fn get_auctions(
         .bind(nft_contract)
         .bind(seller)
         .bind(status)
+        .bind(sync.map(|c| c.updated_at))
+        .bind(exclude_malformed)
+        .bind(sync.map(|c| c.id))
         .bind(limit)
         .bind(offset)
         .fetch_all(pool)
         .await?;
 
-    let (total,): (i64,) = sqlx::query_as(
+    let count = count_rows(
+        pool,
+        count_mode,
+        "marketplace_auctions",
         r#"
-        SELECT COUNT(*) FROM marketplace_auctions
+        FROM marketplace_auctions
         WHERE ($1::INT IS NULL OR chain_id = $1)
           AND ($2::TEXT IS NULL OR nft_contract = $2)
           AND ($3::TEXT IS NULL OR seller = $3)
           AND ($4::TEXT IS NULL OR status = $4)
+          AND ($5::TIMESTAMPTZ IS NULL OR (updated_at, id) > ($5, $7))
+          AND NOT ($6 AND malformed)
         "#,
+        chain_id.is_some()
+            || nft_contract.is_some()
+            || seller.is_some()
+            || status.is_some()
+            || sync.is_some()
+            || exclude_malformed,
+        CountArgs::new()
+            .bind(chain_id)
+            .bind(nft_contract)
+            .bind(seller)
+            .bind(status)
+            .bind(sync.map(|c| c.updated_at))
+            .bind(exclude_malformed)
+            .bind(sync.map(|c| c.id)),
     )
-    .bind(chain_id)
-    .bind(nft_contract)
-    .bind(seller)
-    .bind(status)
-    .fetch_one(pool)
     .await?;
 
-    Ok((auctions, total))
+    Ok((auctions, count))
+}
+
+/// An auction by id; `None` for `marketplace_address` picks the most recently indexed
+/// contract's auction (see `get_listing_by_id`).
+pub async fn get_auction_by_id(
+    pool: &PgPool,
+    auction_id: i64,
+    chain_id: i32,
+    marketplace_address: Option<&str>,
+) -> Result<Option<MarketplaceAuction>, sqlx::Error> {
+    let query = format!(
+        r#"
+        SELECT a.*, CASE WHEN a.status = 'Ended' THEN {} END AS net_proceeds
+        FROM marketplace_auctions a
+        LEFT JOIN marketplace_config mc ON mc.chain_id = a.chain_id
+        WHERE a.auction_id = $1 AND a.chain_id = $2 AND ($3::TEXT IS NULL OR a.marketplace_address = $3)
+        ORDER BY a.id DESC
+        LIMIT 1
+        "#,
+        net_proceeds_sql("a.settled_price")
+    );
+    sqlx::query_as(&query)
+        .bind(auction_id)
+        .bind(chain_id)
+        .bind(marketplace_address)
+        .fetch_optional(pool)
+        .await
 }
 
 pub async fn get_auction_with_bids(
@@ -597,21 +926,20 @@ pub async fn get_auction_with_bids(
     auction_id: i64,
     chain_id: i32,
 ) -> Result<Option<(MarketplaceAuction, Vec<MarketplaceAuctionBid>)>, sqlx::Error> {
-    let auction: Option<MarketplaceAuction> = sqlx::query_as(
-        "SELECT * FROM marketplace_auctions WHERE auction_id = $1 AND chain_id = $2",
-    )
-    .bind(auction_id)
-    .bind(chain_id)
-    .fetch_optional(pool)
-    .await?;
+    let auction = get_auction_by_id(pool, auction_id, chain_id, None).await?;
 
     match auction {
         Some(a) => {
             let bids: Vec<MarketplaceAuctionBid> = sqlx::query_as(
-                "SELECT * FROM marketplace_auction_bids WHERE auction_id = $1 AND chain_id = $2 ORDER BY amount DESC",
+                r#"
+                SELECT * FROM marketplace_auction_bids
+                WHERE auction_id = $1 AND chain_id = $2 AND marketplace_address = $3
+                ORDER BY amount DESC
+                "#,
             )
             .bind(auction_id)
             .bind(chain_id)
+            .bind(&a.marketplace_address)
             .fetch_all(pool)
             .await?;
             Ok(Some((a, bids)))
@@ -630,9 +958,10 @@ pub async fn upsert_dutch_auction(
         r#"
         INSERT INTO marketplace_dutch_auctions
             (auction_id, chain_id, seller, nft_contract, token_id, payment_token,
-             start_price, end_price, start_time, end_time, block_number, block_timestamp, tx_hash)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
-        ON CONFLICT (auction_id, chain_id) DO UPDATE SET
+             start_price, end_price, start_time, end_time, block_number, block_timestamp, tx_hash, marketplace_address,
+             malformed)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        ON CONFLICT (auction_id, chain_id, marketplace_address) DO UPDATE SET
             seller = EXCLUDED.seller,
             nft_contract = EXCLUDED.nft_contract,
             token_id = EXCLUDED.token_id,
@@ -641,7 +970,7 @@ pub async fn upsert_dutch_auction(
             end_price = EXCLUDED.end_price,
             start_time = EXCLUDED.start_time,
             end_time = EXCLUDED.end_time,
-            updated_at = NOW()
+            malformed = EXCLUDED.malformed
         "#,
     )
     .bind(a.auction_id)
@@ -657,6 +986,8 @@ pub async fn upsert_dutch_auction(
     .bind(a.block_number)
     .bind(a.block_timestamp)
     .bind(&a.tx_hash)
+    .bind(&a.marketplace_address)
+    .bind(a.malformed)
     .execute(pool)
     .await?;
     Ok(())
@@ -666,15 +997,16 @@ pub async fn update_dutch_auction_status(
     pool: &PgPool,
     auction_id: i64,
     chain_id: i32,
-    status: &str,
+    marketplace_address: &str,
+    status: ListingStatus,
     buyer: Option<&str>,
     sold_price: Option<&BigDecimal>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         UPDATE marketplace_dutch_auctions
-        SET status = $3, buyer = COALESCE($4, buyer), sold_price = COALESCE($5, sold_price), updated_at = NOW()
-        WHERE auction_id = $1 AND chain_id = $2
+        SET status = $3, buyer = COALESCE($4, buyer), sold_price = COALESCE($5, sold_price)
+        WHERE auction_id = $1 AND chain_id = $2 AND marketplace_address = $6
         "#,
     )
     .bind(auction_id)
@@ -682,52 +1014,97 @@ pub async fn update_dutch_auction_status(
     .bind(status)
     .bind(buyer)
     .bind(sold_price)
+    .bind(marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_dutch_auctions(
     pool: &PgPool,
     chain_id: Option<i32>,
     nft_contract: Option<&str>,
-    status: Option<&str>,
+    status: Option<ListingStatus>,
+    exclude_malformed: bool,
+    sync: Option<SyncCursor>,
+    count_mode: CountMode,
     offset: i64,
     limit: i64,
-) -> Result<(Vec<MarketplaceDutchAuction>, i64), sqlx::Error> {
-    let auctions: Vec<MarketplaceDutchAuction> = sqlx::query_as(
+) -> Result<(Vec<MarketplaceDutchAuction>, TotalCount), sqlx::Error> {
+    // SAFETY: the ORDER BY is one of two fixed strings
+    let order_clause = match sync {
+        Some(_) => "updated_at ASC, id ASC",
+        None => "block_number DESC",
+    };
+    let auctions: Vec<MarketplaceDutchAuction> = sqlx::query_as(&format!(
         r#"
         SELECT * FROM marketplace_dutch_auctions
         WHERE ($1::INT IS NULL OR chain_id = $1)
           AND ($2::TEXT IS NULL OR nft_contract = $2)
           AND ($3::TEXT IS NULL OR status = $3)
-        ORDER BY block_number DESC
-        LIMIT $4 OFFSET $5
+          AND ($4::TIMESTAMPTZ IS NULL OR (updated_at, id) > ($4, $6))
+          AND NOT ($5 AND malformed)
+        ORDER BY {order_clause}
+        LIMIT $7 OFFSET $8
         "#,
-    )
+    ))
     .bind(chain_id)
     .bind(nft_contract)
     .bind(status)
+    .bind(sync.map(|c| c.updated_at))
+    .bind(exclude_malformed)
+    .bind(sync.map(|c| c.id))
     .bind(limit)
     .bind(offset)
     .fetch_all(pool)
     .await?;
 
-    let (total,): (i64,) = sqlx::query_as(
+    let count = count_rows(
+        pool,
+        count_mode,
+        "marketplace_dutch_auctions",
         r#"
-        SELECT COUNT(*) FROM marketplace_dutch_auctions
+        FROM marketplace_dutch_auctions
         WHERE ($1::INT IS NULL OR chain_id = $1)
           AND ($2::TEXT IS NULL OR nft_contract = $2)
           AND ($3::TEXT IS NULL OR status = $3)
+          AND ($4::TIMESTAMPTZ IS NULL OR (updated_at, id) > ($4, $6))
+          AND NOT ($5 AND malformed)
         "#,
+        chain_id.is_some() || nft_contract.is_some() || status.is_some() || sync.is_some() || exclude_malformed,
+        CountArgs::new()
+            .bind(chain_id)
+            .bind(nft_contract)
+            .bind(status)
+            .bind(sync.map(|c| c.updated_at))
+            .bind(exclude_malformed)
+            .bind(sync.map(|c| c.id)),
     )
-    .bind(chain_id)
-    .bind(nft_contract)
-    .bind(status)
-    .fetch_one(pool)
     .await?;
 
-    Ok((auctions, total))
+    Ok((auctions, count))
+}
+
+/// A Dutch auction by id on one marketplace contract, for the indexer to find the NFT a
+/// DutchAuctionBought or DutchAuctionCancelled event refers to.
+pub async fn get_dutch_auction_by_id(
+    pool: &PgPool,
+    auction_id: i64,
+    chain_id: i32,
+    marketplace_address: &str,
+) -> Result<Option<MarketplaceDutchAuction>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT * FROM marketplace_dutch_auctions
+        WHERE auction_id = $1 AND chain_id = $2 AND marketplace_address = $3
+        "#,
+    )
+    .bind(auction_id)
+    .bind(chain_id)
+    .bind(marketplace_address)
+    .fetch_optional(pool)
+    .await
 }
 
 // ─── Bundles ────────────────────────────────────────────────────────────
@@ -736,17 +1113,16 @@ pub async fn upsert_bundle(pool: &PgPool, b: &NewMarketplaceBundle) -> Result<()
     sqlx::query(
         r#"
         INSERT INTO marketplace_bundles
-            (bundle_id, chain_id, seller, nft_contracts, token_ids, payment_token, price, expiry, item_count, block_number, block_timestamp, tx_hash)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
-        ON CONFLICT (bundle_id, chain_id) DO UPDATE SET
+            (bundle_id, chain_id, seller, nft_contracts, token_ids, payment_token, price, expiry, item_count, block_number, block_timestamp, tx_hash, marketplace_address)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        ON CONFLICT (bundle_id, chain_id, marketplace_address) DO UPDATE SET
             seller = EXCLUDED.seller,
             nft_contracts = EXCLUDED.nft_contracts,
             token_ids = EXCLUDED.token_ids,
             payment_token = EXCLUDED.payment_token,
             price = EXCLUDED.price,
             expiry = EXCLUDED.expiry,
-            item_count = EXCLUDED.item_count,
-            updated_at = NOW()
+            item_count = EXCLUDED.item_count
         "#,
     )
     .bind(b.bundle_id)
@@ -761,6 +1137,7 @@ pub async fn upsert_bundle(pool: &PgPool, b: &NewMarketplaceBundle) -> Result<()
     .bind(b.block_number)
     .bind(b.block_timestamp)
     .bind(&b.tx_hash)
+    .bind(&b.marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
@@ -770,15 +1147,16 @@ pub async fn update_bundle_status(
     pool: &PgPool,
     bundle_id: i64,
     chain_id: i32,
-    status: &str,
+    marketplace_address: &str,
+    status: ListingStatus,
     buyer: Option<&str>,
     sold_price: Option<&BigDecimal>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
         UPDATE marketplace_bundles
-        SET status = $3, buyer = COALESCE($4, buyer), sold_price = COALESCE($5, sold_price), updated_at = NOW()
-        WHERE bundle_id = $1 AND chain_id = $2
+        SET status = $3, buyer = COALESCE($4, buyer), sold_price = COALESCE($5, sold_price)
+        WHERE bundle_id = $1 AND chain_id = $2 AND marketplace_address = $6
         "#,
     )
     .bind(bundle_id)
@@ -786,52 +1164,94 @@ pub async fn update_bundle_status(
     .bind(status)
     .bind(buyer)
     .bind(sold_price)
+    .bind(marketplace_address)
     .execute(pool)
     .await?;
     Ok(())
 }
 
+/// A bundle by id; `None` for `marketplace_address` picks the most recently indexed
+/// contract's bundle (see `get_listing_by_id`).
+pub async fn get_bundle_by_id(
+    pool: &PgPool,
+    bundle_id: i64,
+    chain_id: i32,
+    marketplace_address: Option<&str>,
+) -> Result<Option<MarketplaceBundle>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT * FROM marketplace_bundles
+        WHERE bundle_id = $1 AND chain_id = $2 AND ($3::TEXT IS NULL OR marketplace_address = $3)
+        ORDER BY id DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(bundle_id)
+    .bind(chain_id)
+    .bind(marketplace_address)
+    .fetch_optional(pool)
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn get_bundles(
     pool: &PgPool,
     chain_id: Option<i32>,
     seller: Option<&str>,
-    status: Option<&str>,
+    status: Option<ListingStatus>,
+    sync: Option<SyncCursor>,
+    count_mode: CountMode,
     offset: i64,
     limit: i64,
-) -> Result<(Vec<MarketplaceBundle>, i64), sqlx::Error> {
-    let bundles: Vec<MarketplaceBundle> = sqlx::query_as(
+) -> Result<(Vec<MarketplaceBundle>, TotalCount), sqlx::Error> {
+    // SAFETY: the ORDER BY is one of two fixed strings
+    let order_clause = match sync {
+        Some(_) => "updated_at ASC, id ASC",
+        None => "block_number DESC",
+    };
+    let bundles: Vec<MarketplaceBundle> = sqlx::query_as(&format!(
         r#"
         SELECT * FROM marketplace_bundles
         WHERE ($1::INT IS NULL OR chain_id = $1)
           AND ($2::TEXT IS NULL OR seller = $2)
           AND ($3::TEXT IS NULL OR status = $3)
-        ORDER BY block_number DESC
-        LIMIT $4 OFFSET $5
+          AND ($4::TIMESTAMPTZ IS NULL OR (updated_at, id) > ($4, $5))
+        ORDER BY {order_clause}
+        LIMIT $6 OFFSET $7
         "#,
-    )
+    ))
     .bind(chain_id)
     .bind(seller)
     .bind(status)
+    .bind(sync.map(|c| c.updated_at))
+    .bind(sync.map(|c| c.id))
     .bind(limit)
     .bind(offset)
     .fetch_all(pool)
     .await?;
 
-    let (total,): (i64,) = sqlx::query_as(
+    let count = count_rows(
+        pool,
+        count_mode,
+        "marketplace_bundles",
         r#"
-        SELECT COUNT(*) FROM marketplace_bundles
+        FROM marketplace_bundles
         WHERE ($1::INT IS NULL OR chain_id = $1)
           AND ($2::TEXT IS NULL OR seller = $2)
           AND ($3::TEXT IS NULL OR status = $3)
+          AND ($4::TIMESTAMPTZ IS NULL OR (updated_at, id) > ($4, $5))
         "#,
+        chain_id.is_some() || seller.is_some() || status.is_some() || sync.is_some(),
+        CountArgs::new()
+            .bind(chain_id)
+            .bind(seller)
+            .bind(status)
+            .bind(sync.map(|c| c.updated_at))
+            .bind(sync.map(|c| c.id)),
     )
-    .bind(chain_id)
-    .bind(seller)
-    .bind(status)
-    .fetch_one(pool)
     .await?;
 
-    Ok((bundles, total))
+    Ok((bundles, count))
 }
 
 // ─── Config ─────────────────────────────────────────────────────────────
@@ -844,12 +1264,11 @@ pub async fn upsert_marketplace_config(
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
-        INSERT INTO marketplace_config (chain_id, platform_fee_bps, fee_recipient, updated_at)
-        VALUES ($1, $2, $3, NOW())
+        INSERT INTO marketplace_config (chain_id, platform_fee_bps, fee_recipient)
+        VALUES ($1, $2, $3)
         ON CONFLICT (chain_id) DO UPDATE SET
             platform_fee_bps = COALESCE($2, marketplace_config.platform_fee_bps),
-            fee_recipient = COALESCE($3, marketplace_config.fee_recipient),
-            updated_at = NOW()
+            fee_recipient = COALESCE($3, marketplace_config.fee_recipient)
         "#,
     )
     .bind(chain_id)
@@ -860,6 +1279,32 @@ pub async fn upsert_marketplace_config(
     Ok(())
 }
 
+/// Get the stored (platform_fee_bps, fee_recipient) for a chain, if any config row exists.
+pub async fn get_marketplace_config(
+    pool: &PgPool,
+    chain_id: i32,
+) -> Result<Option<(Option<i32>, Option<String>)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT platform_fee_bps, fee_recipient FROM marketplace_config WHERE chain_id = $1",
+    )
+    .bind(chain_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Get all known payment tokens for a chain as (token_address, active) pairs.
+pub async fn get_payment_tokens(
+    pool: &PgPool,
+    chain_id: i32,
+) -> Result<Vec<(String, bool)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT token_address, active FROM marketplace_payment_tokens WHERE chain_id = $1 ORDER BY token_address",
+    )
+    .bind(chain_id)
+    .fetch_all(pool)
+    .await
+}
+
 pub async fn upsert_payment_token(
     pool: &PgPool,
     chain_id: i32,
@@ -869,12 +1314,11 @@ pub async fn upsert_payment_token(
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
-        INSERT INTO marketplace_payment_tokens (chain_id, token_address, active, block_number, updated_at)
-        VALUES ($1, $2, $3, $4, NOW())
+        INSERT INTO marketplace_payment_tokens (chain_id, token_address, active, block_number)
+        VALUES ($1, $2, $3, $4)
         ON CONFLICT (chain_id, token_address) DO UPDATE SET
             active = $3,
-            block_number = COALESCE($4, marketplace_payment_tokens.block_number),
-            updated_at = NOW()
+            block_number = COALESCE($4, marketplace_payment_tokens.block_number)
         "#,
     )
     .bind(chain_id)
@@ -919,11 +1363,25 @@ pub async fn get_user_portfolio(
     .fetch_all(pool)
     .await?;
 
-    let bids: Vec<MarketplaceAuctionBid> = sqlx::query_as(
+    // Addresses are compared lowercased: highest_bidder and winner come from other events
+    // than the bid and need not share its casing
+    let bids: Vec<MarketplaceUserBid> = sqlx::query_as(
         r#"
-        SELECT * FROM marketplace_auction_bids
-        WHERE bidder = $1 AND ($2::INT IS NULL OR chain_id = $2)
-        ORDER BY block_number DESC
+        SELECT b.*,
+               CASE
+                   WHEN a.id IS NULL THEN NULL
+                   WHEN a.status = 'Active' THEN
+                       CASE WHEN LOWER(a.highest_bidder) = LOWER(b.bidder) THEN 'winning' ELSE 'outbid' END
+                   WHEN LOWER(a.winner) = LOWER(b.bidder) THEN 'won'
+                   ELSE 'lost'
+               END AS bid_status,
+               a.highest_bid,
+               a.end_time
+        FROM marketplace_auction_bids b
+        LEFT JOIN marketplace_auctions a
+            ON a.auction_id = b.auction_id AND a.chain_id = b.chain_id AND a.marketplace_address = b.marketplace_address
+        WHERE b.bidder = $1 AND ($2::INT IS NULL OR b.chain_id = $2)
+        ORDER BY b.block_number DESC
         LIMIT 50
         "#,
     )
@@ -939,23 +1397,428 @@ pub async fn get_user_portfolio(
     })
 }
 
+const USER_OFFERS: &str = r#"
+    SELECT 'offer' AS offer_type, offer_id, chain_id, offerer, nft_contract, token_id,
+           payment_token, amount, expiry, status, accepted_by, NULL::NUMERIC AS accepted_token_id,
+           block_number, block_timestamp, tx_hash, marketplace_address, created_at
+    FROM marketplace_offers
+    WHERE offerer = $1 AND ($2::INT IS NULL OR chain_id = $2) AND ($3::TEXT IS NULL OR status = $3)
+    UNION ALL
+    SELECT 'collection_offer', offer_id, chain_id, offerer, nft_contract, NULL,
+           payment_token, amount, expiry, status, accepted_by, accepted_token_id,
+           block_number, block_timestamp, tx_hash, marketplace_address, created_at
+    FROM marketplace_collection_offers
+    WHERE offerer = $1 AND ($2::INT IS NULL OR chain_id = $2) AND ($3::TEXT IS NULL OR status = $3)
+"#;
+
+/// Token offers and collection offers made by `address`, newest first. Like
+/// `get_collection_activity`, rows without a block timestamp sort by when they were stored.
+pub async fn get_user_offers(
+    pool: &PgPool,
+    address: &str,
+    chain_id: Option<i32>,
+    status: Option<OfferStatus>,
+    offset: i64,
+    limit: i64,
+) -> Result<(Vec<MarketplaceUserOffer>, i64), sqlx::Error> {
+    let query = format!(
+        r#"
+        SELECT offer_type, offer_id, chain_id, offerer, nft_contract, token_id, payment_token, amount,
+               expiry, status, accepted_by, accepted_token_id, block_number, block_timestamp, tx_hash,
+               marketplace_address, created_at
+        FROM ({USER_OFFERS}) o
+        ORDER BY COALESCE(block_timestamp, created_at) DESC, block_number DESC, offer_type, offer_id DESC
+        LIMIT $4 OFFSET $5
+        "#
+    );
+    let offers: Vec<MarketplaceUserOffer> = sqlx::query_as(&query)
+        .bind(address)
+        .bind(chain_id)
+        .bind(status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+    let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM ({USER_OFFERS}) o"))
+        .bind(address)
+        .bind(chain_id)
+        .bind(status)
+        .fetch_one(pool)
+        .await?;
+
+    Ok((offers, total))
+}
+
+/// Active English auctions `address` has bid on, split by whether it is still the highest
+/// bidder (`winning`) or has been outbid, soonest ending first.
+pub async fn get_user_auction_positions(
+    pool: &PgPool,
+    address: &str,
+    chain_id: Option<i32>,
+) -> Result<(Vec<MarketplaceAuction>, Vec<MarketplaceAuction>), sqlx::Error> {
+    let auctions: Vec<MarketplaceAuction> = sqlx::query_as(
+        r#"
+        SELECT a.* FROM marketplace_auctions a
+        WHERE a.status = 'Active' AND ($2::INT IS NULL OR a.chain_id = $2)
+          AND EXISTS (
+              SELECT 1 FROM marketplace_auction_bids b
+              WHERE b.auction_id = a.auction_id AND b.chain_id = a.chain_id
+                AND b.marketplace_address = a.marketplace_address AND b.bidder = $1
+          )
+        ORDER BY a.end_time ASC, a.id ASC
+        "#,
+    )
+    .bind(address)
+    .bind(chain_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(auctions
+        .into_iter()
+        .partition(|a| a.highest_bidder.as_deref() == Some(address)))
+}
+
+// ─── Collection Activity ────────────────────────────────────────────────
+
+/// Every marketplace event on `$1`'s tokens (optionally on chain `$2`), one branch per table
+/// that records one. Listings, offers and auctions contribute their creation; fills come from
+/// marketplace_sales. Bundles span collections and are left out.
+const COLLECTION_EVENTS: &str = r#"
+    SELECT 'Listed' AS event_type, chain_id, nft_contract, token_id, listing_id AS entity_id,
+           NULL::TEXT AS sale_type, seller AS actor, price, payment_token,
+           block_number, block_timestamp, tx_hash, created_at
+    FROM marketplace_listings
+    WHERE nft_contract = $1 AND ($2::INT IS NULL OR chain_id = $2)
+    UNION ALL
+    SELECT 'OfferMade', chain_id, nft_contract, token_id, offer_id,
+           NULL, offerer, amount, payment_token,
+           block_number, block_timestamp, tx_hash, created_at
+    FROM marketplace_offers
+    WHERE nft_contract = $1 AND ($2::INT IS NULL OR chain_id = $2)
+    UNION ALL
+    SELECT 'CollectionOfferMade', chain_id, nft_contract, NULL, offer_id,
+           NULL, offerer, amount, payment_token,
+           block_number, block_timestamp, tx_hash, created_at
+    FROM marketplace_collection_offers
+    WHERE nft_contract = $1 AND ($2::INT IS NULL OR chain_id = $2)
+    UNION ALL
+    SELECT 'AuctionCreated', chain_id, nft_contract, token_id, auction_id,
+           NULL, seller, start_price, payment_token,
+           block_number, block_timestamp, tx_hash, created_at
+    FROM marketplace_auctions
+    WHERE nft_contract = $1 AND ($2::INT IS NULL OR chain_id = $2)
+    UNION ALL
+    SELECT 'BidPlaced', b.chain_id, a.nft_contract, a.token_id, b.auction_id,
+           NULL, b.bidder, b.amount, a.payment_token,
+           b.block_number, b.block_timestamp, b.tx_hash, b.created_at
+    FROM marketplace_auction_bids b
+    JOIN marketplace_auctions a
+        ON a.auction_id = b.auction_id AND a.chain_id = b.chain_id AND a.marketplace_address = b.marketplace_address
+    WHERE a.nft_contract = $1 AND ($2::INT IS NULL OR a.chain_id = $2)
+    UNION ALL
+    SELECT 'DutchAuctionCreated', chain_id, nft_contract, token_id, auction_id,
+           NULL, seller, start_price, payment_token,
+           block_number, block_timestamp, tx_hash, created_at
+    FROM marketplace_dutch_auctions
+    WHERE nft_contract = $1 AND ($2::INT IS NULL OR chain_id = $2)
+    UNION ALL
+    SELECT 'Sale', chain_id, nft_contract, token_id, entity_id,
+           sale_type, buyer, price, payment_token,
+           block_number, block_timestamp, tx_hash, created_at
+    FROM marketplace_sales
+    WHERE nft_contract = $1 AND ($2::INT IS NULL OR chain_id = $2)
+"#;
+
+/// Marketplace activity across a collection, newest first. Rows indexed without a block
+/// timestamp (SKIP_BLOCK_TIMESTAMPS) sort by when they were stored.
+pub async fn get_collection_activity(
+    pool: &PgPool,
+    nft_contract: &str,
+    chain_id: Option<i32>,
+    offset: i64,
+    limit: i64,
+) -> Result<(Vec<MarketplaceCollectionActivity>, i64), sqlx::Error> {
+    let query = format!(
+        r#"
+        SELECT event_type, chain_id, nft_contract, token_id, entity_id, sale_type, actor, price,
+               payment_token, block_number, block_timestamp, tx_hash
+        FROM ({COLLECTION_EVENTS}) e
+        ORDER BY COALESCE(block_timestamp, created_at) DESC, block_number DESC NULLS LAST, event_type, entity_id
+        LIMIT $3 OFFSET $4
+        "#
+    );
+    let activities: Vec<MarketplaceCollectionActivity> = sqlx::query_as(&query)
+        .bind(nft_contract)
+        .bind(chain_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+    let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM ({COLLECTION_EVENTS}) e"))
+        .bind(nft_contract)
+        .bind(chain_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok((activities, total))
+}
+
+/// Sale price stats of a collection per payment token, over sales in `range` (7d, 30d, or
+/// anything else for all time). Every payment token the collection was listed or sold in gets
+/// a row, so one without sales in range reads as 0 sales and null prices.
+pub async fn get_collection_sale_stats(
+    pool: &PgPool,
+    nft_contract: &str,
+    chain_id: Option<i32>,
+    range: &str,
+) -> Result<Vec<CollectionSalePriceStats>, sqlx::Error> {
+    let interval = match range {
+        "7d" => Some("7 days"),
+        "30d" => Some("30 days"),
+        _ => None, // "all"
+    };
+    // percentile_cont works in float8, which rounds wei amounts; the median here averages
+    // the lower and upper middle prices (percentile_disc over both orders) in NUMERIC instead
+    sqlx::query_as(
+        r#"
+        WITH sales AS (
+            SELECT payment_token, price
+            FROM marketplace_sales
+            WHERE nft_contract = $1 AND ($2::INT IS NULL OR chain_id = $2)
+              AND ($3::INTERVAL IS NULL OR COALESCE(block_timestamp, created_at) >= NOW() - $3::INTERVAL)
+        ),
+        tokens AS (
+            SELECT payment_token FROM marketplace_listings
+            WHERE nft_contract = $1 AND ($2::INT IS NULL OR chain_id = $2)
+            UNION
+            SELECT payment_token FROM marketplace_sales
+            WHERE nft_contract = $1 AND ($2::INT IS NULL OR chain_id = $2) AND payment_token IS NOT NULL
+        )
+        SELECT t.payment_token,
+               COUNT(s.price) AS sales,
+               COALESCE(SUM(s.price), 0) AS volume,
+               ROUND(AVG(s.price)) AS avg_sale_price,
+               ROUND((
+                   percentile_disc(0.5) WITHIN GROUP (ORDER BY s.price)
+                   - percentile_disc(0.5) WITHIN GROUP (ORDER BY -s.price)
+               ) / 2) AS median_sale_price
+        FROM tokens t
+        LEFT JOIN sales s ON s.payment_token = t.payment_token
+        GROUP BY t.payment_token
+        ORDER BY t.payment_token
+        "#,
+    )
+    .bind(nft_contract)
+    .bind(chain_id)
+    .bind(interval)
+    .fetch_all(pool)
+    .await
+}
+
+/// Share of `listed` items that sold, 0 when nothing was listed.
+pub fn sell_through_rate(sold: i64, listed: i64) -> f64 {
+    if listed <= 0 {
+        return 0.0;
+    }
+    sold as f64 / listed as f64
+}
+
+/// A seller's listed/sold counts and average sale price over their listings, English
+/// auctions and Dutch auctions. An auction counts as sold once it ended with a winner.
+pub async fn get_seller_metrics(
+    pool: &PgPool,
+    seller: &str,
+    chain_id: Option<i32>,
+) -> Result<MarketplaceSellerMetricsResponse, sqlx::Error> {
+    let (total_listed, total_sold, avg_sale_price): (i64, i64, Option<BigDecimal>) = sqlx::query_as(
+        r#"
+        WITH items AS (
+            SELECT status = 'Sold' AS sold, COALESCE(sold_price, price) AS sale_price
+            FROM marketplace_listings
+            WHERE seller = $1 AND ($2::INT IS NULL OR chain_id = $2)
+            UNION ALL
+            SELECT status = 'Ended' AND winner IS NOT NULL, settled_price
+            FROM marketplace_auctions
+            WHERE seller = $1 AND ($2::INT IS NULL OR chain_id = $2)
+            UNION ALL
+            SELECT status = 'Sold', sold_price
+            FROM marketplace_dutch_auctions
+            WHERE seller = $1 AND ($2::INT IS NULL OR chain_id = $2)
+        )
+        SELECT COUNT(*),
+               COUNT(*) FILTER (WHERE sold),
+               ROUND(AVG(sale_price) FILTER (WHERE sold))
+        FROM items
+        "#,
+    )
+    .bind(seller)
+    .bind(chain_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(MarketplaceSellerMetricsResponse {
+        seller: seller.to_string(),
+        chain_id,
+        total_listed,
+        total_sold,
+        sell_through_rate: sell_through_rate(total_sold, total_listed),
+        avg_sale_price,
+    })
+}
+
+// ─── Sales ──────────────────────────────────────────────────────────────
+
+/// Insert a block range's sales into marketplace_sales in one statement. Fields a sale log
+/// doesn't carry (NFT, seller or buyer, payment token, offer amount) come from the sold
+/// listing/offer/auction/bundle row, which earlier logs have already written. Sales whose
+/// entity was never indexed and whose log has no price are skipped; replays are no-ops.
+/// Entities are looked up on the `marketplace_address` contract that emitted the sales.
+pub async fn insert_sales(
+    pool: &PgPool,
+    chain_id: i32,
+    marketplace_address: &str,
+    sales: &[NewMarketplaceSale],
+) -> Result<u64, sqlx::Error> {
+    if sales.is_empty() {
+        return Ok(0);
+    }
+
+    let sale_types: Vec<&str> = sales.iter().map(|s| s.sale_type).collect();
+    let entity_ids: Vec<i64> = sales.iter().map(|s| s.entity_id).collect();
+    let token_ids: Vec<Option<BigDecimal>> = sales.iter().map(|s| s.token_id.clone()).collect();
+    let sellers: Vec<Option<&str>> = sales.iter().map(|s| s.seller.as_deref()).collect();
+    let buyers: Vec<Option<&str>> = sales.iter().map(|s| s.buyer.as_deref()).collect();
+    let prices: Vec<Option<BigDecimal>> = sales.iter().map(|s| s.price.clone()).collect();
+    let block_numbers: Vec<i64> = sales.iter().map(|s| s.block_number).collect();
+    let block_timestamps: Vec<Option<DateTime<Utc>>> = sales.iter().map(|s| s.block_timestamp).collect();
+    let tx_hashes: Vec<&str> = sales.iter().map(|s| s.tx_hash.as_str()).collect();
+    let log_indexes: Vec<i32> = sales.iter().map(|s| s.log_index).collect();
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO marketplace_sales
+            (chain_id, sale_type, entity_id, nft_contract, token_id, seller, buyer, payment_token, price,
+             block_number, block_timestamp, tx_hash, log_index, marketplace_address)
+        SELECT $11, s.sale_type, s.entity_id,
+               COALESCE(l.nft_contract, o.nft_contract, co.nft_contract, a.nft_contract, d.nft_contract),
+               COALESCE(s.token_id, l.token_id, o.token_id, a.token_id, d.token_id),
+               COALESCE(s.seller, l.seller, a.seller, d.seller, b.seller),
+               COALESCE(s.buyer, o.offerer, co.offerer),
+               COALESCE(l.payment_token, o.payment_token, co.payment_token, a.payment_token, d.payment_token, b.payment_token),
+               COALESCE(s.price, o.amount, co.amount),
+               s.block_number, s.block_timestamp, s.tx_hash, s.log_index, $12
+        FROM UNNEST(
+            $1::TEXT[], $2::BIGINT[], $3::NUMERIC[], $4::TEXT[], $5::TEXT[],
+            $6::NUMERIC[], $7::BIGINT[], $8::TIMESTAMPTZ[], $9::TEXT[], $10::INT[]
+        ) AS s(sale_type, entity_id, token_id, seller, buyer, price, block_number, block_timestamp, tx_hash, log_index)
+        LEFT JOIN marketplace_listings l
+            ON s.sale_type = 'listing' AND l.listing_id = s.entity_id AND l.chain_id = $11 AND l.marketplace_address = $12
+        LEFT JOIN marketplace_offers o
+            ON s.sale_type = 'offer' AND o.offer_id = s.entity_id AND o.chain_id = $11 AND o.marketplace_address = $12
+        LEFT JOIN marketplace_collection_offers co
+            ON s.sale_type = 'collection_offer' AND co.offer_id = s.entity_id AND co.chain_id = $11 AND co.marketplace_address = $12
+        LEFT JOIN marketplace_auctions a
+            ON s.sale_type = 'auction' AND a.auction_id = s.entity_id AND a.chain_id = $11 AND a.marketplace_address = $12
+        LEFT JOIN marketplace_dutch_auctions d
+            ON s.sale_type = 'dutch_auction' AND d.auction_id = s.entity_id AND d.chain_id = $11 AND d.marketplace_address = $12
+        LEFT JOIN marketplace_bundles b
+            ON s.sale_type = 'bundle' AND b.bundle_id = s.entity_id AND b.chain_id = $11 AND b.marketplace_address = $12
+        WHERE COALESCE(s.price, o.amount, co.amount) IS NOT NULL
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(sale_types)
+    .bind(entity_ids)
+    .bind(token_ids)
+    .bind(sellers)
+    .bind(buyers)
+    .bind(prices)
+    .bind(block_numbers)
+    .bind(block_timestamps)
+    .bind(tx_hashes)
+    .bind(log_indexes)
+    .bind(chain_id)
+    .bind(marketplace_address)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// The `limit` most recent sales on any chain, newest first.
+pub async fn get_recent_sales(pool: &PgPool, limit: i64) -> Result<Vec<MarketplaceSale>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT chain_id, marketplace_address, sale_type, entity_id, nft_contract, token_id,
+               seller, buyer, payment_token, price, block_number,
+               COALESCE(block_timestamp, created_at) AS block_timestamp, tx_hash
+        FROM marketplace_sales
+        ORDER BY COALESCE(block_timestamp, created_at) DESC, id DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// The latest sale of one NFT across every single-token sale type, by block time (indexing
+/// time when unknown); None if it never sold.
+pub async fn get_last_sale_for_token(
+    pool: &PgPool,
+    chain_id: i32,
+    nft_contract: &str,
+    token_id: &BigDecimal,
+) -> Result<Option<LastSale>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT price, payment_token, COALESCE(block_timestamp, created_at) AS timestamp, tx_hash
+        FROM marketplace_sales
+        WHERE chain_id = $1 AND nft_contract = $2 AND token_id = $3
+        ORDER BY COALESCE(block_timestamp, created_at) DESC, id DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(chain_id)
+    .bind(nft_contract)
+    .bind(token_id)
+    .fetch_optional(pool)
+    .await
+}
+
 // ─── Marketplace Stats ──────────────────────────────────────────────────
 
 pub async fn get_marketplace_stats(pool: &PgPool) -> Result<MarketplaceStatsResponse, sqlx::Error> {
-    // Combined marketplace listing stats in a single query (was 4 separate queries)
-    let (total_listings, active_listings, total_sales, total_volume): (i64, i64, i64, BigDecimal) =
-        sqlx::query_as(
-            r#"
-            SELECT
-                COUNT(*),
-                COUNT(*) FILTER (WHERE status = 'Active'),
-                COUNT(*) FILTER (WHERE status = 'Sold'),
-                COALESCE(SUM(sold_price) FILTER (WHERE status = 'Sold'), 0)
+    // Listing counts, plus sales and volume across every sale type
+    let totals = sqlx::query_as(
+        r#"
+        SELECT
+            l.total,
+            l.active,
+            s.total_sales,
+            s.total_volume,
+            s.items_sold
+        FROM (
+            SELECT COUNT(*) AS total, COUNT(*) FILTER (WHERE status = 'Active') AS active
             FROM marketplace_listings
-            "#,
-        )
-        .fetch_one(pool)
-        .await?;
+        ) l,
+        (
+            SELECT
+                COUNT(*) AS total_sales,
+                COALESCE(SUM(ms.price), 0) AS total_volume,
+                COALESCE(SUM(COALESCE(b.item_count, 1)), 0)::BIGINT AS items_sold
+            FROM marketplace_sales ms
+            LEFT JOIN marketplace_bundles b
+                ON ms.sale_type = 'bundle' AND b.bundle_id = ms.entity_id
+                AND b.chain_id = ms.chain_id AND b.marketplace_address = ms.marketplace_address
+        ) s
+        "#,
+    )
+    .fetch_one(pool);
+    let (total_listings, active_listings, total_sales, total_volume, items_sold): (i64, i64, i64, BigDecimal, i64) =
+        timed("get_marketplace_stats", totals).await?;
 
     let (active_auctions,): (i64,) =
         sqlx::query_as("SELECT COUNT(*) FROM marketplace_auctions WHERE status = 'Active'")
@@ -967,6 +1830,8 @@ pub async fn get_marketplace_stats(pool: &PgPool) -> Result<MarketplaceStatsResp
         active_listings,
         total_sales,
         total_volume,
+        items_sold,
         active_auctions,
+        marketplace_enabled: HashMap::new(),
     })
 }
@@ -1,5 +1,22 @@
 pub mod activity;
 pub mod agents;
+pub mod count;
 pub mod feedbacks;
 pub mod indexer_state;
 pub mod marketplace;
+pub mod pool;
+pub mod replica;
+pub mod timing;
+
+/// Escape LIKE/ILIKE metacharacters (`%`, `_` and the escape character `\`) so user text
+/// matches literally. Use with an explicit `ESCAPE '\'` clause.
+pub fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use sqlx::PgPool;
+
+use crate::types::PoolStats;
+
+/// How often the acquire probe times a connection checkout on each pool (in seconds).
+pub const POOL_PROBE_INTERVAL_SECS: u64 = 10;
+
+/// The API and indexer pools (and the read replica, if any) plus the results of a periodic acquire probe. The probe
+/// times how long a checkout waits, which is what an API request experiences when the
+/// pool is saturated.
+pub struct PoolMonitor {
+    pub api: PgPool,
+    pub indexer: PgPool,
+    pub replica: Option<PgPool>,
+    api_probe: AcquireProbe,
+    indexer_probe: AcquireProbe,
+    replica_probe: AcquireProbe,
+}
+
+#[derive(Default)]
+struct AcquireProbe {
+    last_wait_micros: AtomicU64,
+    max_wait_micros: AtomicU64,
+    timeouts: AtomicU64,
+}
+
+impl AcquireProbe {
+    async fn sample(&self, pool: &PgPool) {
+        let started = Instant::now();
+        let result = pool.acquire().await;
+        let waited = started.elapsed().as_micros() as u64;
+        self.last_wait_micros.store(waited, Ordering::Relaxed);
+        self.max_wait_micros.fetch_max(waited, Ordering::Relaxed);
+        if let Err(e) = result {
+            self.timeouts.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!("Pool acquire probe failed after {}ms: {:?}", waited / 1000, e);
+        }
+    }
+}
+
+impl PoolMonitor {
+    pub fn new(api: PgPool, indexer: PgPool, replica: Option<PgPool>) -> Self {
+        PoolMonitor {
+            api,
+            indexer,
+            replica,
+            api_probe: AcquireProbe::default(),
+            indexer_probe: AcquireProbe::default(),
+            replica_probe: AcquireProbe::default(),
+        }
+    }
+
+    /// Current stats for each pool: API, indexer, then the replica when configured.
+    pub fn stats(&self) -> Vec<PoolStats> {
+        let mut stats = vec![
+            pool_stats("api", &self.api, &self.api_probe),
+            pool_stats("indexer", &self.indexer, &self.indexer_probe),
+        ];
+        if let Some(replica) = &self.replica {
+            stats.push(pool_stats("replica", replica, &self.replica_probe));
+        }
+        stats
+    }
+
+    /// Probe every pool each POOL_PROBE_INTERVAL_SECS, forever.
+    pub async fn run_probe(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(POOL_PROBE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            tokio::join!(
+                self.api_probe.sample(&self.api),
+                self.indexer_probe.sample(&self.indexer),
+                async {
+                    if let Some(replica) = &self.replica {
+                        self.replica_probe.sample(replica).await;
+                    }
+                },
+            );
+        }
+    }
+}
+
+fn pool_stats(name: &str, pool: &PgPool, probe: &AcquireProbe) -> PoolStats {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+    PoolStats {
+        pool: name.to_string(),
+        max_connections: pool.options().get_max_connections(),
+        size,
+        idle,
+        in_use: size.saturating_sub(idle),
+        acquire_wait_ms: probe.last_wait_micros.load(Ordering::Relaxed) as f64 / 1000.0,
+        acquire_wait_max_ms: probe.max_wait_micros.load(Ordering::Relaxed) as f64 / 1000.0,
+        acquire_timeouts: probe.timeouts.load(Ordering::Relaxed),
+    }
+}
@@ -0,0 +1,141 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sqlx::PgPool;
+
+/// How long reads skip the replica after it fails to connect, so a dead replica
+/// doesn't cost every request an acquire timeout.
+pub const REPLICA_RETRY_SECS: u64 = 30;
+
+tokio::task_local! {
+    /// Pools that served reads during the current request (see `track_reads`).
+    static SERVED: Cell<Served>;
+}
+
+/// Which pools served a request's reads, reported in the `x-db-pool` response header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Served {
+    pub primary: bool,
+    pub replica: bool,
+}
+
+impl Served {
+    /// `primary`, `replica`, `mixed` (a replica read fell back), or None when nothing was read.
+    pub fn header_value(self) -> Option<&'static str> {
+        match (self.primary, self.replica) {
+            (false, false) => None,
+            (true, false) => Some("primary"),
+            (false, true) => Some("replica"),
+            (true, true) => Some("mixed"),
+        }
+    }
+}
+
+/// Run `fut` (a request) recording which pools its `Db::read` calls used.
+pub async fn track_reads<F: Future>(fut: F) -> (F::Output, Served) {
+    SERVED
+        .scope(Cell::new(Served::default()), async move {
+            let output = fut.await;
+            (output, SERVED.with(Cell::get))
+        })
+        .await
+}
+
+fn record(replica: bool) {
+    // Outside track_reads (e.g. background tasks) there is nothing to record into
+    let _ = SERVED.try_with(|served| {
+        let mut s = served.get();
+        if replica {
+            s.replica = true;
+        } else {
+            s.primary = true;
+        }
+        served.set(s);
+    });
+}
+
+/// API database handle: the primary pool plus an optional read replica (DATABASE_READ_URL).
+///
+/// Replicas lag the primary, so a row the indexer just wrote may not be visible on the
+/// replica yet. Reads that must see the newest state should use `primary()`.
+#[derive(Clone)]
+pub struct Db {
+    primary: PgPool,
+    replica: Option<PgPool>,
+    /// Unix millis until which the replica is skipped after a connection failure.
+    replica_down_until: Arc<AtomicU64>,
+}
+
+impl Db {
+    pub fn new(primary: PgPool, replica: Option<PgPool>) -> Self {
+        Db {
+            primary,
+            replica,
+            replica_down_until: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// The primary pool, for reads that must not lag the indexer.
+    pub fn primary(&self) -> &PgPool {
+        record(false);
+        &self.primary
+    }
+
+    /// Run a read on the replica when one is configured, retrying on the primary if the
+    /// replica can't be reached. Query errors (bad SQL, timeouts) are returned as is.
+    pub async fn read<'a, T, F, Fut>(&'a self, query: F) -> Result<T, sqlx::Error>
+    where
+        F: Fn(&'a PgPool) -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        let replica_up = now_millis() >= self.replica_down_until.load(Ordering::Relaxed);
+        if let Some(replica) = self.replica.as_ref().filter(|_| replica_up) {
+            match query(replica).await {
+                Err(e) if is_connection_error(&e) => {
+                    tracing::warn!(
+                        "Read replica unavailable, using primary for the next {}s: {:?}",
+                        REPLICA_RETRY_SECS,
+                        e
+                    );
+                    let retry_at = now_millis() + Duration::from_secs(REPLICA_RETRY_SECS).as_millis() as u64;
+                    self.replica_down_until.store(retry_at, Ordering::Relaxed);
+                }
+                result => {
+                    record(true);
+                    return result;
+                }
+            }
+        }
+        record(false);
+        query(&self.primary).await
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Errors that mean the pool couldn't reach the server, as opposed to a failed query.
+fn is_connection_error(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::Protocol(_)
+        | sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => true,
+        sqlx::Error::Database(db) => db.code().is_some_and(|code| is_connection_sqlstate(&code)),
+        _ => false,
+    }
+}
+
+/// SQLSTATE class 08 (connection exception), admin/crash shutdown and cannot_connect_now.
+fn is_connection_sqlstate(code: &str) -> bool {
+    code.starts_with("08") || matches!(code, "57P01" | "57P02" | "57P03")
+}
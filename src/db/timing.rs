@@ -0,0 +1,34 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::config::Limits;
+
+/// Default SLOW_QUERY_MS.
+pub const DEFAULT_SLOW_QUERY_MS: u64 = 500;
+
+/// Whether a query that took `elapsed` exceeded `threshold` and should be logged as slow.
+pub fn is_slow(elapsed: Duration, threshold: Duration) -> bool {
+    elapsed > threshold
+}
+
+/// Await `query`, logging a warning tagged with `label` when it takes longer than
+/// SLOW_QUERY_MS (`Limits::slow_query_threshold`), and its duration at debug level otherwise.
+pub async fn timed<F: Future>(label: &'static str, query: F) -> F::Output {
+    let started = Instant::now();
+    let output = query.await;
+    let elapsed = started.elapsed();
+    let threshold = Limits::current().slow_query_threshold;
+    if is_slow(elapsed, threshold) {
+        tracing::warn!(
+            query = label,
+            elapsed_ms = elapsed.as_millis() as u64,
+            threshold_ms = threshold.as_millis() as u64,
+            "Slow query"
+        );
+    } else {
+        tracing::debug!(query = label, elapsed_ms = elapsed.as_millis() as u64, "Query finished");
+    }
+    output
+}
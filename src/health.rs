@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+
+use crate::indexer;
+use crate::startup::StartupStatus;
+use crate::types::{ChainIndexerLag, ReadinessResponse};
+use crate::AppState;
+
+/// How long the readiness `SELECT 1` may take before the database counts as unreachable.
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Default HEALTH_MAX_INDEXER_LAG_SECS: cursors advance every cycle on Monad's ~1s blocks,
+/// so minutes without progress means the indexer is stuck or its RPC is down.
+pub const DEFAULT_MAX_INDEXER_LAG_SECS: i64 = 300;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        // Kept for the Railway healthcheck and older probes; same as /health/ready
+        .route("/health", get(readiness))
+        .route("/health/live", get(liveness))
+        .route("/health/ready", get(readiness))
+}
+
+/// GET /health/live — the process is up and serving HTTP; never touches the database
+async fn liveness() -> impl IntoResponse {
+    (StatusCode::OK, "OK")
+}
+
+/// GET /health/ready — 200 once migrations have run and the database answers `SELECT 1`,
+/// 503 otherwise, with the reason when startup failed for good (so the orchestrator
+/// restarts the instance). A lagging indexer is reported as `degraded` but stays 200: the API can
+/// still serve (slightly stale) data.
+async fn readiness(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    match state.startup.status() {
+        StartupStatus::Ready => {}
+        StartupStatus::Starting => return unready("starting", None),
+        StartupStatus::Failed { reason } => {
+            let (status, Json(mut body)) = unready("failed", None);
+            body.reason = Some(reason);
+            return (status, Json(body));
+        }
+    }
+
+    let pool = state.db.primary();
+    match tokio::time::timeout(DB_CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(pool)).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => {
+            tracing::warn!("Readiness check failed: {:?}", e);
+            return unready("unavailable", Some(false));
+        }
+        Err(_) => {
+            tracing::warn!("Readiness check timed out after {:?}", DB_CHECK_TIMEOUT);
+            return unready("unavailable", Some(false));
+        }
+    }
+
+    let indexer = if state.indexer_enabled {
+        indexer_lag(&state).await
+    } else {
+        Vec::new()
+    };
+    let status = if indexer.iter().any(|c| c.lagging) { "degraded" } else { "ready" };
+
+    (
+        StatusCode::OK,
+        Json(ReadinessResponse {
+            status: status.to_string(),
+            database: Some(true),
+            reason: None,
+            indexer,
+        }),
+    )
+}
+
+fn unready(status: &str, database: Option<bool>) -> (StatusCode, Json<ReadinessResponse>) {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(ReadinessResponse {
+            status: status.to_string(),
+            database,
+            reason: None,
+            indexer: Vec::new(),
+        }),
+    )
+}
+
+/// Seconds since each enabled chain's stalest cursor moved. Best effort: a failed query
+/// leaves the lag out rather than failing readiness.
+async fn indexer_lag(state: &AppState) -> Vec<ChainIndexerLag> {
+    let rows: Vec<ChainIndexerLag> = match sqlx::query_as(
+        r#"
+        SELECT chain_id, EXTRACT(EPOCH FROM NOW() - MIN(updated_at))::BIGINT AS lag_secs
+        FROM indexer_state
+        WHERE updated_at IS NOT NULL
+        GROUP BY chain_id
+        ORDER BY chain_id
+        "#,
+    )
+    .fetch_all(state.db.primary())
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            tracing::warn!("Failed to read indexer lag for readiness: {:?}", e);
+            return Vec::new();
+        }
+    };
+
+    let max_lag = state.config.health_max_indexer_lag_secs;
+    rows.into_iter()
+        .filter(|c| state.indexer_flags.contains_key(&c.chain_id))
+        .filter(|c| indexer::chain_enabled(&state.indexer_flags, c.chain_id))
+        .map(|c| ChainIndexerLag { lagging: c.lag_secs > max_lag, ..c })
+        .collect()
+}
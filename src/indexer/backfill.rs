@@ -5,11 +5,12 @@ use sqlx::PgPool;
 
 use super::provider::{self, ChainConfig};
 
-/// Backfill block_timestamp for all rows that have a block_number but NULL block_timestamp.
-/// Groups by (chain_id, block_number), fetches timestamp from RPC, then batch-updates all 4 tables.
-/// Runs once on startup before the indexer loop.
-pub async fn backfill_block_timestamps(pool: &PgPool) {
-    let chains = provider::get_chain_configs();
+/// Backfill block_timestamp for all rows that have a block_number but NULL block_timestamp
+/// (in activity_log: an estimated one, see insert_activities).
+/// Groups by (chain_id, block_number), fetches timestamp from RPC, then batch-updates the agent tables.
+/// Runs once on startup before the indexer loop; with SKIP_BLOCK_TIMESTAMPS this is what fills
+/// the timestamps the indexer left NULL.
+pub async fn backfill_block_timestamps(pool: &PgPool, chains: &[ChainConfig]) {
     if chains.is_empty() {
         return;
     }
@@ -27,7 +28,9 @@ pub async fn backfill_block_timestamps(pool: &PgPool) {
             UNION
             SELECT chain_id, block_number FROM feedback_responses WHERE block_timestamp IS NULL
             UNION
-            SELECT chain_id, block_number FROM activity_log WHERE block_timestamp IS NULL
+            SELECT chain_id, block_number FROM activity_log WHERE block_timestamp_estimated
+            UNION
+            SELECT chain_id, block_number FROM agent_ownership_history WHERE block_timestamp IS NULL
         ) AS t
         ORDER BY chain_id, block_number
         "#,
@@ -148,7 +151,19 @@ async fn update_timestamps(
     .await?;
 
     sqlx::query(
-        "UPDATE activity_log SET block_timestamp = $1 WHERE chain_id = $2 AND block_number = $3 AND block_timestamp IS NULL",
+        r#"
+        UPDATE activity_log SET block_timestamp = $1, block_timestamp_estimated = false
+        WHERE chain_id = $2 AND block_number = $3 AND block_timestamp_estimated
+        "#,
+    )
+    .bind(ts)
+    .bind(chain_id)
+    .bind(block_number)
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "UPDATE agent_ownership_history SET block_timestamp = $1 WHERE chain_id = $2 AND block_number = $3 AND block_timestamp IS NULL",
     )
     .bind(ts)
     .bind(chain_id)
@@ -0,0 +1,79 @@
+use alloy::primitives::{keccak256, B256};
+use sqlx::PgPool;
+
+use super::metadata;
+use crate::db;
+
+/// Feedbacks checked per pass.
+pub const VERIFY_BATCH_SIZE: i64 = 50;
+
+/// Pause between passes once the pending backlog is empty (in seconds).
+pub const VERIFY_INTERVAL_SECS: u64 = 60;
+
+/// How long to wait before re-fetching content that previously failed to resolve (in hours).
+pub const VERIFY_RETRY_AFTER_HOURS: i32 = 24;
+
+/// Compare content against an on-chain `feedbackHash` (keccak256 of the content).
+/// Returns None when there's nothing to compare against: an unparseable or all-zero hash,
+/// which is how clients signal that no hash was committed.
+pub fn content_matches_hash(content: &[u8], expected_hash: &str) -> Option<bool> {
+    let expected: B256 = expected_hash.trim().parse().ok()?;
+    if expected == B256::ZERO {
+        return None;
+    }
+    Some(keccak256(content) == expected)
+}
+
+/// Verify pending feedback content forever. Each row's verdict lands in
+/// feedbacks.content_verified; fetch failures leave it NULL and are retried later.
+pub async fn run_feedback_content_verification(pool: PgPool) {
+    tracing::info!("Feedback content verification enabled");
+
+    loop {
+        let pending = match db::feedbacks::get_feedbacks_pending_content_check(
+            &pool,
+            VERIFY_RETRY_AFTER_HOURS,
+            VERIFY_BATCH_SIZE,
+        )
+        .await
+        {
+            Ok(rows) => rows,
+            Err(e) => {
+                tracing::error!("Failed to load feedbacks pending content check: {:?}", e);
+                Vec::new()
+            }
+        };
+        let full_batch = pending.len() as i64 == VERIFY_BATCH_SIZE;
+
+        for feedback in pending {
+            let verified = match metadata::fetch_uri_content(&feedback.feedback_uri).await {
+                Ok(content) => content_matches_hash(&content, &feedback.feedback_hash),
+                Err(e) => {
+                    tracing::debug!(
+                        feedback_id = feedback.id,
+                        "Failed to fetch feedback content from {}: {:?}",
+                        feedback.feedback_uri,
+                        e
+                    );
+                    None
+                }
+            };
+            if verified == Some(false) {
+                tracing::warn!(
+                    feedback_id = feedback.id,
+                    "Feedback content at {} does not match feedback_hash {}",
+                    feedback.feedback_uri,
+                    feedback.feedback_hash
+                );
+            }
+            if let Err(e) = db::feedbacks::set_content_verified(&pool, feedback.id, verified).await {
+                tracing::error!(feedback_id = feedback.id, "Failed to store content check: {:?}", e);
+            }
+        }
+
+        // Keep draining while there's a backlog, otherwise wait for new feedback
+        if !full_batch {
+            tokio::time::sleep(std::time::Duration::from_secs(VERIFY_INTERVAL_SECS)).await;
+        }
+    }
+}
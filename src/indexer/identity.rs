@@ -1,16 +1,14 @@
-use std::collections::HashMap;
-
 use alloy::providers::Provider;
 use alloy::rpc::types::Filter;
 use alloy::sol;
 use alloy::sol_types::SolEvent;
-use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
+use super::{log_dry_run, log_dry_run_batch};
 use super::metadata;
-use super::provider::{self, ChainConfig, HttpProvider};
+use super::provider::{BlockTimestamps, ChainConfig, HttpProvider};
 use crate::db;
-use crate::types::{NewActivity, NewAgent};
+use crate::types::{NewActivity, NewAgent, NewOwnershipTransfer};
 
 // Load IdentityRegistry ABI from official erc-8004 contracts
 sol!(IdentityRegistry, "abi/IdentityRegistry.json");
@@ -41,6 +39,8 @@ pub async fn index_identity_events(
 
     tracing::debug!(
         chain_id = chain.chain_id,
+        contract = "identity",
+        log_count = logs.len(),
         "Fetched {} identity logs for blocks {} - {}",
         logs.len(),
         from_block,
@@ -48,7 +48,12 @@ pub async fn index_identity_events(
     );
 
     // Cache block timestamps to avoid duplicate RPC calls for the same block
-    let mut block_ts_cache: HashMap<u64, DateTime<Utc>> = HashMap::new();
+    let mut block_timestamps = BlockTimestamps::new(chain);
+
+    // Activities and ownership transfers are accumulated for the whole range and inserted in
+    // one statement each at the end
+    let mut activities: Vec<NewActivity> = Vec::new();
+    let mut transfers: Vec<NewOwnershipTransfer> = Vec::new();
 
     for log in logs {
         let block_num_raw = log.block_number.unwrap_or(0);
@@ -59,21 +64,8 @@ pub async fn index_identity_events(
             .unwrap_or_default();
         let log_index = log.log_index.unwrap_or(0) as i32;
 
-        // Fetch block timestamp (cached per block)
-        let block_timestamp = if let Some(ts) = block_ts_cache.get(&block_num_raw) {
-            Some(*ts)
-        } else {
-            match provider::get_block_timestamp(provider, block_num_raw).await {
-                Ok(ts) => {
-                    block_ts_cache.insert(block_num_raw, ts);
-                    Some(ts)
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to fetch timestamp for block {}: {:?}", block_num_raw, e);
-                    None
-                }
-            }
-        };
+        // Fetch block timestamp (cached per block, skipped with SKIP_BLOCK_TIMESTAMPS)
+        let block_timestamp = block_timestamps.get(provider, block_num_raw).await;
 
         // Determine event type by matching topic0
         let topic0 = match log.topic0() {
@@ -117,7 +109,9 @@ pub async fn index_identity_events(
                         block_timestamp,
                         tx_hash: Some(tx_hash.clone()),
                     };
-                    if let Err(e) = db::agents::upsert_agent(pool, &new_agent).await {
+                    if chain.dry_run {
+                        log_dry_run(chain, "upsert_agent", &new_agent);
+                    } else if let Err(e) = db::agents::upsert_agent(pool, &new_agent).await {
                         tracing::error!("Failed to upsert agent {}: {:?}", agent_id, e);
                     }
 
@@ -135,12 +129,10 @@ pub async fn index_identity_events(
                         tx_hash: tx_hash.clone(),
                         log_index,
                     };
-                    if let Err(e) = db::activity::insert_activity(pool, &activity).await {
-                        tracing::error!("Failed to insert Registered activity: {:?}", e);
-                    }
+                    activities.push(activity);
 
                     // Trigger metadata fetch in background
-                    if !uri.is_empty() {
+                    if !uri.is_empty() && !chain.dry_run {
                         let pool_clone = pool.clone();
                         let uri_clone = uri.clone();
                         let chain_id = chain.chain_id;
@@ -195,7 +187,9 @@ pub async fn index_identity_events(
                         block_timestamp,
                         tx_hash: Some(tx_hash.clone()),
                     };
-                    if let Err(e) = db::agents::upsert_agent(pool, &update_agent).await {
+                    if chain.dry_run {
+                        log_dry_run(chain, "upsert_agent", &update_agent);
+                    } else if let Err(e) = db::agents::upsert_agent(pool, &update_agent).await {
                         tracing::error!("Failed to update agent {} URI: {:?}", agent_id, e);
                     }
 
@@ -213,12 +207,10 @@ pub async fn index_identity_events(
                         tx_hash: tx_hash.clone(),
                         log_index,
                     };
-                    if let Err(e) = db::activity::insert_activity(pool, &activity).await {
-                        tracing::error!("Failed to insert URIUpdated activity: {:?}", e);
-                    }
+                    activities.push(activity);
 
                     // Trigger metadata re-fetch
-                    if !new_uri.is_empty() {
+                    if !new_uri.is_empty() && !chain.dry_run {
                         let pool_clone = pool.clone();
                         let uri_clone = new_uri.clone();
                         let chain_id = chain.chain_id;
@@ -258,7 +250,9 @@ pub async fn index_identity_events(
                     );
 
                     // Update the agent's metadata field with the new key-value pair
-                    if let Err(e) = update_agent_metadata_field(pool, agent_id, chain.chain_id, &key, &value).await {
+                    if chain.dry_run {
+                        log_dry_run(chain, "update_agent_metadata_field", &(agent_id, &key, &value));
+                    } else if let Err(e) = update_agent_metadata_field(pool, agent_id, chain.chain_id, &key, &value).await {
                         tracing::error!(
                             "Failed to update metadata field for agent {}: {:?}",
                             agent_id,
@@ -280,9 +274,7 @@ pub async fn index_identity_events(
                         tx_hash: tx_hash.clone(),
                         log_index,
                     };
-                    if let Err(e) = db::activity::insert_activity(pool, &activity).await {
-                        tracing::error!("Failed to insert MetadataSet activity: {:?}", e);
-                    }
+                    activities.push(activity);
                 }
                 Err(e) => {
                     tracing::error!(
@@ -300,7 +292,19 @@ pub async fn index_identity_events(
                     let to = format!("{:#x}", event.to);
                     let token_id = event.tokenId.to::<u64>() as i64;
 
-                    // Skip mint events (from == zero address) — handled by Registered
+                    // Every transfer, the mint included, goes into the ownership history
+                    transfers.push(NewOwnershipTransfer {
+                        agent_id: token_id,
+                        chain_id: chain.chain_id,
+                        from_address: from.clone(),
+                        to_address: to.clone(),
+                        block_number,
+                        block_timestamp,
+                        tx_hash: tx_hash.clone(),
+                        log_index,
+                    });
+
+                    // Otherwise skip mint events (from == zero address) — handled by Registered
                     let zero = "0x0000000000000000000000000000000000000000";
                     if from == zero {
                         continue;
@@ -316,7 +320,9 @@ pub async fn index_identity_events(
                     );
 
                     // Update agent owner in DB
-                    if let Err(e) = db::agents::update_agent_owner(pool, token_id, chain.chain_id, &to).await {
+                    if chain.dry_run {
+                        log_dry_run(chain, "update_agent_owner", &(token_id, &to));
+                    } else if let Err(e) = db::agents::update_agent_owner(pool, token_id, chain.chain_id, &to).await {
                         tracing::error!("Failed to update owner for agent {}: {:?}", token_id, e);
                     }
 
@@ -334,9 +340,7 @@ pub async fn index_identity_events(
                         tx_hash: tx_hash.clone(),
                         log_index,
                     };
-                    if let Err(e) = db::activity::insert_activity(pool, &activity).await {
-                        tracing::error!("Failed to insert Transfer activity: {:?}", e);
-                    }
+                    activities.push(activity);
                 }
                 Err(e) => {
                     tracing::error!(
@@ -349,6 +353,15 @@ pub async fn index_identity_events(
         }
     }
 
+    if chain.dry_run {
+        log_dry_run_batch(chain, "insert_activities", &activities);
+        log_dry_run_batch(chain, "insert_ownership_transfers", &transfers);
+        return Ok(());
+    }
+
+    db::activity::insert_activities(pool, &activities).await?;
+    db::agents::insert_ownership_transfers(pool, &transfers).await?;
+
     Ok(())
 }
 
@@ -365,8 +378,7 @@ async fn update_agent_metadata_field(
     sqlx::query(
         r#"
         UPDATE agents
-        SET metadata = COALESCE(metadata, '{}'::jsonb) || jsonb_build_object($3::text, $4::text),
-            updated_at = NOW()
+        SET metadata = COALESCE(metadata, '{}'::jsonb) || jsonb_build_object($3::text, $4::text)
         WHERE agent_id = $1 AND chain_id = $2
         "#,
     )
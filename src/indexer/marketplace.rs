@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::str::FromStr;
 
 use alloy::providers::Provider;
@@ -9,11 +8,12 @@ use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
-use super::provider::{self, ChainConfig, HttpProvider};
+use super::{log_dry_run, log_dry_run_batch};
+use super::provider::{BlockTimestamps, ChainConfig, HttpProvider};
 use crate::db;
 use crate::types::{
-    NewActivity, NewMarketplaceAuction, NewMarketplaceBundle, NewMarketplaceCollectionOffer,
-    NewMarketplaceDutchAuction, NewMarketplaceListing, NewMarketplaceOffer,
+    AuctionStatus, ListingStatus, NewActivity, NewMarketplaceAuction, NewMarketplaceBundle, NewMarketplaceCollectionOffer,
+    NewMarketplaceDutchAuction, NewMarketplaceListing, NewMarketplaceOffer, NewMarketplaceSale, OfferStatus,
 };
 
 // Load MoltMarketplace ABI
@@ -79,13 +79,23 @@ pub async fn index_marketplace_events(
 
     tracing::debug!(
         chain_id = chain.chain_id,
+        contract = "marketplace",
+        log_count = logs.len(),
         "Fetched {} marketplace logs for blocks {} - {}",
         logs.len(),
         from_block,
         to_block
     );
 
-    let mut block_ts_cache: HashMap<u64, DateTime<Utc>> = HashMap::new();
+    let mut block_timestamps = BlockTimestamps::new(chain);
+
+    // Entity ids restart with each marketplace deployment; rows are keyed by contract too
+    let marketplace = format!("{:#x}", marketplace_address);
+
+    // Agent activities are accumulated for the whole range and inserted in one statement at the end
+    let mut activities: Vec<NewActivity> = Vec::new();
+    // Sales likewise; inserted after the loop, once every entity they reference is stored
+    let mut sales: Vec<NewMarketplaceSale> = Vec::new();
 
     for log in logs {
         let block_num_raw = log.block_number.unwrap_or(0);
@@ -96,20 +106,7 @@ pub async fn index_marketplace_events(
             .unwrap_or_default();
         let log_index = log.log_index.unwrap_or(0) as i32;
 
-        let block_timestamp = if let Some(ts) = block_ts_cache.get(&block_num_raw) {
-            Some(*ts)
-        } else {
-            match provider::get_block_timestamp(provider, block_num_raw).await {
-                Ok(ts) => {
-                    block_ts_cache.insert(block_num_raw, ts);
-                    Some(ts)
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to fetch timestamp for block {}: {:?}", block_num_raw, e);
-                    None
-                }
-            }
-        };
+        let block_timestamp = block_timestamps.get(provider, block_num_raw).await;
 
         let topic0 = match log.topic0() {
             Some(t) => *t,
@@ -142,16 +139,19 @@ pub async fn index_marketplace_events(
                     block_number,
                     block_timestamp,
                     tx_hash: tx_hash.clone(),
+                    marketplace_address: marketplace.clone(),
                 };
-                if let Err(err) = db::marketplace::upsert_listing(pool, &new_listing).await {
+                if chain.dry_run {
+                    log_dry_run(chain, "upsert_listing", &new_listing);
+                } else if let Err(err) = db::marketplace::upsert_listing(pool, &new_listing).await {
                     tracing::error!("Failed to upsert listing {}: {:?}", listing_id, err);
                 }
 
-                maybe_insert_agent_activity(
-                    pool, chain, &nft_contract, &token_id, "marketplace:Listed",
+                maybe_push_agent_activity(
+                    &mut activities, chain, &nft_contract, &token_id, "marketplace:Listed",
                     serde_json::json!({"listing_id": listing_id, "seller": seller, "price": price.to_string(), "payment_token": payment_token}),
                     block_number, block_timestamp, &tx_hash, log_index,
-                ).await;
+                );
             }
         } else if topic0 == Bought::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<Bought>() {
@@ -162,30 +162,54 @@ pub async fn index_marketplace_events(
 
                 tracing::info!(chain_id = chain.chain_id, "Bought #{}", listing_id);
 
-                if let Err(err) = db::marketplace::update_listing_status(
-                    pool, listing_id, chain.chain_id, "Sold", Some(&buyer), Some(&price),
+                if chain.dry_run {
+                    log_dry_run(chain, "update_listing_status", &(listing_id, ListingStatus::Sold, &buyer, &price));
+                } else if let Err(err) = db::marketplace::update_listing_status(
+                    pool, listing_id, chain.chain_id, &marketplace, ListingStatus::Sold, Some(&buyer), Some(&price),
                 ).await {
                     tracing::error!("Failed to update listing {} as Sold: {:?}", listing_id, err);
                 }
+                sales.push(NewMarketplaceSale {
+                    sale_type: "listing",
+                    entity_id: listing_id,
+                    token_id: None,
+                    seller: None,
+                    buyer: Some(buyer.clone()),
+                    price: Some(price.clone()),
+                    block_number,
+                    block_timestamp,
+                    tx_hash: tx_hash.clone(),
+                    log_index,
+                });
 
                 // Cross-reference: look up NFT info from listing for activity
-                if let Ok(Some(listing)) = db::marketplace::get_listing_by_id(pool, listing_id, chain.chain_id).await {
-                    maybe_insert_agent_activity(
-                        pool, chain, &listing.nft_contract, &listing.token_id, "marketplace:Bought",
+                if let Ok(Some(listing)) = db::marketplace::get_listing_by_id(pool, listing_id, chain.chain_id, Some(&marketplace)).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &listing.nft_contract, &listing.token_id, "marketplace:Bought",
                         serde_json::json!({"listing_id": listing_id, "buyer": buyer, "price": price.to_string()}),
                         block_number, block_timestamp, &tx_hash, log_index,
-                    ).await;
+                    );
                 }
             }
         } else if topic0 == ListingCancelled::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<ListingCancelled>() {
                 let listing_id = decoded.inner.data.listingId.to::<u64>() as i64;
                 tracing::info!(chain_id = chain.chain_id, "ListingCancelled #{}", listing_id);
-                if let Err(err) = db::marketplace::update_listing_status(
-                    pool, listing_id, chain.chain_id, "Cancelled", None, None,
+                if chain.dry_run {
+                    log_dry_run(chain, "update_listing_status", &(listing_id, ListingStatus::Cancelled));
+                } else if let Err(err) = db::marketplace::update_listing_status(
+                    pool, listing_id, chain.chain_id, &marketplace, ListingStatus::Cancelled, None, None,
                 ).await {
                     tracing::error!("Failed to cancel listing {}: {:?}", listing_id, err);
                 }
+
+                if let Ok(Some(listing)) = db::marketplace::get_listing_by_id(pool, listing_id, chain.chain_id, Some(&marketplace)).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &listing.nft_contract, &listing.token_id, "marketplace:ListingCancelled",
+                        serde_json::json!({"listing_id": listing_id, "seller": listing.seller, "price": listing.price.to_string()}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         } else if topic0 == ListingPriceUpdated::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<ListingPriceUpdated>() {
@@ -193,11 +217,21 @@ pub async fn index_marketplace_events(
                 let listing_id = e.listingId.to::<u64>() as i64;
                 let new_price = BigDecimal::from_str(&e.newPrice.to_string()).unwrap_or_default();
                 tracing::info!(chain_id = chain.chain_id, "ListingPriceUpdated #{}", listing_id);
-                if let Err(err) = db::marketplace::update_listing_price(
-                    pool, listing_id, chain.chain_id, &new_price,
+                if chain.dry_run {
+                    log_dry_run(chain, "update_listing_price", &(listing_id, &new_price));
+                } else if let Err(err) = db::marketplace::update_listing_price(
+                    pool, listing_id, chain.chain_id, &marketplace, &new_price,
                 ).await {
                     tracing::error!("Failed to update listing {} price: {:?}", listing_id, err);
                 }
+
+                if let Ok(Some(listing)) = db::marketplace::get_listing_by_id(pool, listing_id, chain.chain_id, Some(&marketplace)).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &listing.nft_contract, &listing.token_id, "marketplace:ListingPriceUpdated",
+                        serde_json::json!({"listing_id": listing_id, "seller": listing.seller, "old_price": e.oldPrice.to_string(), "new_price": new_price.to_string()}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         }
         // ─── Offers ─────────────────────────────────────────────────
@@ -226,16 +260,19 @@ pub async fn index_marketplace_events(
                     block_number,
                     block_timestamp,
                     tx_hash: tx_hash.clone(),
+                    marketplace_address: marketplace.clone(),
                 };
-                if let Err(err) = db::marketplace::upsert_offer(pool, &new_offer).await {
+                if chain.dry_run {
+                    log_dry_run(chain, "upsert_offer", &new_offer);
+                } else if let Err(err) = db::marketplace::upsert_offer(pool, &new_offer).await {
                     tracing::error!("Failed to upsert offer {}: {:?}", offer_id, err);
                 }
 
-                maybe_insert_agent_activity(
-                    pool, chain, &nft_contract, &token_id, "marketplace:OfferMade",
+                maybe_push_agent_activity(
+                    &mut activities, chain, &nft_contract, &token_id, "marketplace:OfferMade",
                     serde_json::json!({"offer_id": offer_id, "offerer": offerer}),
                     block_number, block_timestamp, &tx_hash, log_index,
-                ).await;
+                );
             }
         } else if topic0 == OfferAccepted::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<OfferAccepted>() {
@@ -243,21 +280,53 @@ pub async fn index_marketplace_events(
                 let offer_id = e.offerId.to::<u64>() as i64;
                 let seller = format!("{:#x}", e.seller);
                 tracing::info!(chain_id = chain.chain_id, "OfferAccepted #{}", offer_id);
-                if let Err(err) = db::marketplace::update_offer_status(
-                    pool, offer_id, chain.chain_id, "Accepted", Some(&seller),
+                if chain.dry_run {
+                    log_dry_run(chain, "update_offer_status", &(offer_id, OfferStatus::Accepted, &seller));
+                } else if let Err(err) = db::marketplace::update_offer_status(
+                    pool, offer_id, chain.chain_id, &marketplace, OfferStatus::Accepted, Some(&seller),
                 ).await {
                     tracing::error!("Failed to accept offer {}: {:?}", offer_id, err);
                 }
+                sales.push(NewMarketplaceSale {
+                    sale_type: "offer",
+                    entity_id: offer_id,
+                    token_id: None,
+                    seller: Some(seller.clone()),
+                    buyer: None,
+                    price: None,
+                    block_number,
+                    block_timestamp,
+                    tx_hash: tx_hash.clone(),
+                    log_index,
+                });
+
+                if let Ok(Some(offer)) = db::marketplace::get_offer_by_id(pool, offer_id, chain.chain_id, &marketplace).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &offer.nft_contract, &offer.token_id, "marketplace:OfferAccepted",
+                        serde_json::json!({"offer_id": offer_id, "seller": seller, "offerer": offer.offerer, "amount": offer.amount.to_string()}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         } else if topic0 == OfferCancelled::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<OfferCancelled>() {
                 let offer_id = decoded.inner.data.offerId.to::<u64>() as i64;
                 tracing::info!(chain_id = chain.chain_id, "OfferCancelled #{}", offer_id);
-                if let Err(err) = db::marketplace::update_offer_status(
-                    pool, offer_id, chain.chain_id, "Cancelled", None,
+                if chain.dry_run {
+                    log_dry_run(chain, "update_offer_status", &(offer_id, OfferStatus::Cancelled));
+                } else if let Err(err) = db::marketplace::update_offer_status(
+                    pool, offer_id, chain.chain_id, &marketplace, OfferStatus::Cancelled, None,
                 ).await {
                     tracing::error!("Failed to cancel offer {}: {:?}", offer_id, err);
                 }
+
+                if let Ok(Some(offer)) = db::marketplace::get_offer_by_id(pool, offer_id, chain.chain_id, &marketplace).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &offer.nft_contract, &offer.token_id, "marketplace:OfferCancelled",
+                        serde_json::json!({"offer_id": offer_id, "offerer": offer.offerer, "amount": offer.amount.to_string()}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         }
         // ─── Collection Offers ──────────────────────────────────────
@@ -284,8 +353,11 @@ pub async fn index_marketplace_events(
                     block_number,
                     block_timestamp,
                     tx_hash: tx_hash.clone(),
+                    marketplace_address: marketplace.clone(),
                 };
-                if let Err(err) = db::marketplace::upsert_collection_offer(pool, &new_offer).await {
+                if chain.dry_run {
+                    log_dry_run(chain, "upsert_collection_offer", &new_offer);
+                } else if let Err(err) = db::marketplace::upsert_collection_offer(pool, &new_offer).await {
                     tracing::error!("Failed to upsert collection offer {}: {:?}", offer_id, err);
                 }
             }
@@ -296,18 +368,43 @@ pub async fn index_marketplace_events(
                 let seller = format!("{:#x}", e.seller);
                 let token_id = BigDecimal::from_str(&e.tokenId.to_string()).unwrap_or_default();
                 tracing::info!(chain_id = chain.chain_id, "CollectionOfferAccepted #{}", offer_id);
-                if let Err(err) = db::marketplace::update_collection_offer_status(
-                    pool, offer_id, chain.chain_id, "Accepted", Some(&seller), Some(&token_id),
+                if chain.dry_run {
+                    log_dry_run(chain, "update_collection_offer_status", &(offer_id, OfferStatus::Accepted, &seller, &token_id));
+                } else if let Err(err) = db::marketplace::update_collection_offer_status(
+                    pool, offer_id, chain.chain_id, &marketplace, OfferStatus::Accepted, Some(&seller), Some(&token_id),
                 ).await {
                     tracing::error!("Failed to accept collection offer {}: {:?}", offer_id, err);
                 }
+                sales.push(NewMarketplaceSale {
+                    sale_type: "collection_offer",
+                    entity_id: offer_id,
+                    token_id: Some(token_id.clone()),
+                    seller: Some(seller.clone()),
+                    buyer: None,
+                    price: None,
+                    block_number,
+                    block_timestamp,
+                    tx_hash: tx_hash.clone(),
+                    log_index,
+                });
+
+                // The offer covers the whole collection; the activity goes to the token accepted
+                if let Ok(Some(offer)) = db::marketplace::get_collection_offer_by_id(pool, offer_id, chain.chain_id, &marketplace).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &offer.nft_contract, &token_id, "marketplace:CollectionOfferAccepted",
+                        serde_json::json!({"offer_id": offer_id, "seller": seller, "offerer": offer.offerer, "amount": offer.amount.to_string()}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         } else if topic0 == CollectionOfferCancelled::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<CollectionOfferCancelled>() {
                 let offer_id = decoded.inner.data.offerId.to::<u64>() as i64;
                 tracing::info!(chain_id = chain.chain_id, "CollectionOfferCancelled #{}", offer_id);
-                if let Err(err) = db::marketplace::update_collection_offer_status(
-                    pool, offer_id, chain.chain_id, "Cancelled", None, None,
+                if chain.dry_run {
+                    log_dry_run(chain, "update_collection_offer_status", &(offer_id, OfferStatus::Cancelled));
+                } else if let Err(err) = db::marketplace::update_collection_offer_status(
+                    pool, offer_id, chain.chain_id, &marketplace, OfferStatus::Cancelled, None, None,
                 ).await {
                     tracing::error!("Failed to cancel collection offer {}: {:?}", offer_id, err);
                 }
@@ -327,8 +424,16 @@ pub async fn index_marketplace_events(
                 let buy_now_price = BigDecimal::from_str(&e.buyNowPrice.to_string()).unwrap_or_default();
                 let start_time = e.startTime.to::<u64>() as i64;
                 let end_time = e.endTime.to::<u64>() as i64;
+                let malformed = auction_times_malformed(start_time, end_time);
 
                 tracing::info!(chain_id = chain.chain_id, "AuctionCreated #{}", auction_id);
+                if malformed {
+                    tracing::warn!(
+                        chain_id = chain.chain_id,
+                        "AuctionCreated #{} ends at {} but starts at {}; flagged malformed",
+                        auction_id, end_time, start_time
+                    );
+                }
 
                 let new_auction = NewMarketplaceAuction {
                     auction_id,
@@ -342,19 +447,23 @@ pub async fn index_marketplace_events(
                     buy_now_price,
                     start_time,
                     end_time,
+                    malformed,
                     block_number,
                     block_timestamp,
                     tx_hash: tx_hash.clone(),
+                    marketplace_address: marketplace.clone(),
                 };
-                if let Err(err) = db::marketplace::upsert_auction(pool, &new_auction).await {
+                if chain.dry_run {
+                    log_dry_run(chain, "upsert_auction", &new_auction);
+                } else if let Err(err) = db::marketplace::upsert_auction(pool, &new_auction).await {
                     tracing::error!("Failed to upsert auction {}: {:?}", auction_id, err);
                 }
 
-                maybe_insert_agent_activity(
-                    pool, chain, &nft_contract, &token_id, "marketplace:AuctionCreated",
+                maybe_push_agent_activity(
+                    &mut activities, chain, &nft_contract, &token_id, "marketplace:AuctionCreated",
                     serde_json::json!({"auction_id": auction_id, "seller": seller}),
                     block_number, block_timestamp, &tx_hash, log_index,
-                ).await;
+                );
             }
         } else if topic0 == BidPlaced::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<BidPlaced>() {
@@ -365,17 +474,34 @@ pub async fn index_marketplace_events(
 
                 tracing::info!(chain_id = chain.chain_id, "BidPlaced #{} by {}", auction_id, bidder);
 
-                if let Err(err) = db::marketplace::update_auction_bid(
-                    pool, auction_id, chain.chain_id, &amount, &bidder,
+                // Bid first: update_auction_bid recounts bid_count from the stored bids
+                if chain.dry_run {
+                    log_dry_run(
+                        chain,
+                        "insert_auction_bid",
+                        &(auction_id, &bidder, &amount, block_number, block_timestamp, &tx_hash, log_index),
+                    );
+                } else if let Err(err) = db::marketplace::insert_auction_bid(
+                    pool, auction_id, chain.chain_id, &marketplace, &bidder, &amount,
+                    block_number, block_timestamp, &tx_hash, log_index,
                 ).await {
-                    tracing::error!("Failed to update auction {} bid: {:?}", auction_id, err);
+                    tracing::error!("Failed to insert auction bid: {:?}", err);
                 }
 
-                if let Err(err) = db::marketplace::insert_auction_bid(
-                    pool, auction_id, chain.chain_id, &bidder, &amount,
-                    block_number, block_timestamp, &tx_hash,
+                if chain.dry_run {
+                    log_dry_run(chain, "update_auction_bid", &(auction_id, &amount, &bidder));
+                } else if let Err(err) = db::marketplace::update_auction_bid(
+                    pool, auction_id, chain.chain_id, &marketplace, &amount, &bidder,
                 ).await {
-                    tracing::error!("Failed to insert auction bid: {:?}", err);
+                    tracing::error!("Failed to update auction {} bid: {:?}", auction_id, err);
+                }
+
+                if let Ok(Some(auction)) = db::marketplace::get_auction_by_id(pool, auction_id, chain.chain_id, Some(&marketplace)).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &auction.nft_contract, &auction.token_id, "marketplace:BidPlaced",
+                        serde_json::json!({"auction_id": auction_id, "bidder": bidder, "amount": amount.to_string()}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
                 }
             }
         } else if topic0 == AuctionSettled::SIGNATURE_HASH {
@@ -385,21 +511,53 @@ pub async fn index_marketplace_events(
                 let winner = format!("{:#x}", e.winner);
                 let amount = BigDecimal::from_str(&e.amount.to_string()).unwrap_or_default();
                 tracing::info!(chain_id = chain.chain_id, "AuctionSettled #{}", auction_id);
-                if let Err(err) = db::marketplace::update_auction_status(
-                    pool, auction_id, chain.chain_id, "Ended", Some(&winner), Some(&amount),
+                if chain.dry_run {
+                    log_dry_run(chain, "update_auction_status", &(auction_id, AuctionStatus::Ended, &winner, &amount));
+                } else if let Err(err) = db::marketplace::update_auction_status(
+                    pool, auction_id, chain.chain_id, &marketplace, AuctionStatus::Ended, Some(&winner), Some(&amount),
                 ).await {
                     tracing::error!("Failed to settle auction {}: {:?}", auction_id, err);
                 }
+                sales.push(NewMarketplaceSale {
+                    sale_type: "auction",
+                    entity_id: auction_id,
+                    token_id: None,
+                    seller: None,
+                    buyer: Some(winner.clone()),
+                    price: Some(amount.clone()),
+                    block_number,
+                    block_timestamp,
+                    tx_hash: tx_hash.clone(),
+                    log_index,
+                });
+
+                if let Ok(Some(auction)) = db::marketplace::get_auction_by_id(pool, auction_id, chain.chain_id, Some(&marketplace)).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &auction.nft_contract, &auction.token_id, "marketplace:AuctionSettled",
+                        serde_json::json!({"auction_id": auction_id, "seller": auction.seller, "winner": winner, "price": amount.to_string()}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         } else if topic0 == AuctionCancelled::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<AuctionCancelled>() {
                 let auction_id = decoded.inner.data.auctionId.to::<u64>() as i64;
                 tracing::info!(chain_id = chain.chain_id, "AuctionCancelled #{}", auction_id);
-                if let Err(err) = db::marketplace::update_auction_status(
-                    pool, auction_id, chain.chain_id, "Cancelled", None, None,
+                if chain.dry_run {
+                    log_dry_run(chain, "update_auction_status", &(auction_id, AuctionStatus::Cancelled));
+                } else if let Err(err) = db::marketplace::update_auction_status(
+                    pool, auction_id, chain.chain_id, &marketplace, AuctionStatus::Cancelled, None, None,
                 ).await {
                     tracing::error!("Failed to cancel auction {}: {:?}", auction_id, err);
                 }
+
+                if let Ok(Some(auction)) = db::marketplace::get_auction_by_id(pool, auction_id, chain.chain_id, Some(&marketplace)).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &auction.nft_contract, &auction.token_id, "marketplace:AuctionCancelled",
+                        serde_json::json!({"auction_id": auction_id, "seller": auction.seller}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         } else if topic0 == AuctionExtended::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<AuctionExtended>() {
@@ -407,11 +565,21 @@ pub async fn index_marketplace_events(
                 let auction_id = e.auctionId.to::<u64>() as i64;
                 let new_end_time = e.newEndTime.to::<u64>() as i64;
                 tracing::info!(chain_id = chain.chain_id, "AuctionExtended #{}", auction_id);
-                if let Err(err) = db::marketplace::update_auction_end_time(
-                    pool, auction_id, chain.chain_id, new_end_time,
+                if chain.dry_run {
+                    log_dry_run(chain, "update_auction_end_time", &(auction_id, new_end_time));
+                } else if let Err(err) = db::marketplace::update_auction_end_time(
+                    pool, auction_id, chain.chain_id, &marketplace, new_end_time,
                 ).await {
                     tracing::error!("Failed to extend auction {}: {:?}", auction_id, err);
                 }
+
+                if let Ok(Some(auction)) = db::marketplace::get_auction_by_id(pool, auction_id, chain.chain_id, Some(&marketplace)).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &auction.nft_contract, &auction.token_id, "marketplace:AuctionExtended",
+                        serde_json::json!({"auction_id": auction_id, "new_end_time": new_end_time}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         } else if topic0 == AuctionBuyNow::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<AuctionBuyNow>() {
@@ -420,21 +588,54 @@ pub async fn index_marketplace_events(
                 let buyer = format!("{:#x}", e.buyer);
                 let price = BigDecimal::from_str(&e.price.to_string()).unwrap_or_default();
                 tracing::info!(chain_id = chain.chain_id, "AuctionBuyNow #{}", auction_id);
-                if let Err(err) = db::marketplace::update_auction_status(
-                    pool, auction_id, chain.chain_id, "Ended", Some(&buyer), Some(&price),
+                if chain.dry_run {
+                    log_dry_run(chain, "update_auction_status", &(auction_id, AuctionStatus::Ended, &buyer, &price));
+                } else if let Err(err) = db::marketplace::update_auction_status(
+                    pool, auction_id, chain.chain_id, &marketplace, AuctionStatus::Ended, Some(&buyer), Some(&price),
                 ).await {
                     tracing::error!("Failed to buy-now auction {}: {:?}", auction_id, err);
                 }
+                sales.push(NewMarketplaceSale {
+                    sale_type: "auction",
+                    entity_id: auction_id,
+                    token_id: None,
+                    seller: None,
+                    buyer: Some(buyer.clone()),
+                    price: Some(price.clone()),
+                    block_number,
+                    block_timestamp,
+                    tx_hash: tx_hash.clone(),
+                    log_index,
+                });
+
+                if let Ok(Some(auction)) = db::marketplace::get_auction_by_id(pool, auction_id, chain.chain_id, Some(&marketplace)).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &auction.nft_contract, &auction.token_id, "marketplace:AuctionBuyNow",
+                        serde_json::json!({"auction_id": auction_id, "seller": auction.seller, "buyer": buyer, "price": price.to_string()}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         } else if topic0 == AuctionReserveNotMet::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<AuctionReserveNotMet>() {
-                let auction_id = decoded.inner.data.auctionId.to::<u64>() as i64;
+                let e = &decoded.inner.data;
+                let auction_id = e.auctionId.to::<u64>() as i64;
                 tracing::info!(chain_id = chain.chain_id, "AuctionReserveNotMet #{}", auction_id);
-                if let Err(err) = db::marketplace::update_auction_status(
-                    pool, auction_id, chain.chain_id, "ReserveNotMet", None, None,
+                if chain.dry_run {
+                    log_dry_run(chain, "update_auction_status", &(auction_id, AuctionStatus::ReserveNotMet));
+                } else if let Err(err) = db::marketplace::update_auction_status(
+                    pool, auction_id, chain.chain_id, &marketplace, AuctionStatus::ReserveNotMet, None, None,
                 ).await {
                     tracing::error!("Failed to mark auction {} reserve not met: {:?}", auction_id, err);
                 }
+
+                if let Ok(Some(auction)) = db::marketplace::get_auction_by_id(pool, auction_id, chain.chain_id, Some(&marketplace)).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &auction.nft_contract, &auction.token_id, "marketplace:AuctionReserveNotMet",
+                        serde_json::json!({"auction_id": auction_id, "seller": auction.seller, "highest_bid": e.highestBid.to_string(), "reserve_price": e.reservePrice.to_string()}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         }
         // ─── Dutch Auctions ────────────────────────────────────────
@@ -450,8 +651,16 @@ pub async fn index_marketplace_events(
                 let end_price = BigDecimal::from_str(&e.endPrice.to_string()).unwrap_or_default();
                 let start_time = e.startTime.to::<u64>() as i64;
                 let end_time = e.endTime.to::<u64>() as i64;
+                let malformed = auction_times_malformed(start_time, end_time);
 
                 tracing::info!(chain_id = chain.chain_id, "DutchAuctionCreated #{}", auction_id);
+                if malformed {
+                    tracing::warn!(
+                        chain_id = chain.chain_id,
+                        "DutchAuctionCreated #{} ends at {} but starts at {}; flagged malformed",
+                        auction_id, end_time, start_time
+                    );
+                }
 
                 let new_auction = NewMarketplaceDutchAuction {
                     auction_id,
@@ -464,19 +673,23 @@ pub async fn index_marketplace_events(
                     end_price,
                     start_time,
                     end_time,
+                    malformed,
                     block_number,
                     block_timestamp,
                     tx_hash: tx_hash.clone(),
+                    marketplace_address: marketplace.clone(),
                 };
-                if let Err(err) = db::marketplace::upsert_dutch_auction(pool, &new_auction).await {
+                if chain.dry_run {
+                    log_dry_run(chain, "upsert_dutch_auction", &new_auction);
+                } else if let Err(err) = db::marketplace::upsert_dutch_auction(pool, &new_auction).await {
                     tracing::error!("Failed to upsert dutch auction {}: {:?}", auction_id, err);
                 }
 
-                maybe_insert_agent_activity(
-                    pool, chain, &nft_contract, &token_id, "marketplace:DutchAuctionCreated",
+                maybe_push_agent_activity(
+                    &mut activities, chain, &nft_contract, &token_id, "marketplace:DutchAuctionCreated",
                     serde_json::json!({"auction_id": auction_id, "seller": seller}),
                     block_number, block_timestamp, &tx_hash, log_index,
-                ).await;
+                );
             }
         } else if topic0 == DutchAuctionBought::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<DutchAuctionBought>() {
@@ -485,21 +698,53 @@ pub async fn index_marketplace_events(
                 let buyer = format!("{:#x}", e.buyer);
                 let price = BigDecimal::from_str(&e.price.to_string()).unwrap_or_default();
                 tracing::info!(chain_id = chain.chain_id, "DutchAuctionBought #{}", auction_id);
-                if let Err(err) = db::marketplace::update_dutch_auction_status(
-                    pool, auction_id, chain.chain_id, "Sold", Some(&buyer), Some(&price),
+                if chain.dry_run {
+                    log_dry_run(chain, "update_dutch_auction_status", &(auction_id, ListingStatus::Sold, &buyer, &price));
+                } else if let Err(err) = db::marketplace::update_dutch_auction_status(
+                    pool, auction_id, chain.chain_id, &marketplace, ListingStatus::Sold, Some(&buyer), Some(&price),
                 ).await {
                     tracing::error!("Failed to update dutch auction {} as Sold: {:?}", auction_id, err);
                 }
+                sales.push(NewMarketplaceSale {
+                    sale_type: "dutch_auction",
+                    entity_id: auction_id,
+                    token_id: None,
+                    seller: None,
+                    buyer: Some(buyer.clone()),
+                    price: Some(price.clone()),
+                    block_number,
+                    block_timestamp,
+                    tx_hash: tx_hash.clone(),
+                    log_index,
+                });
+
+                if let Ok(Some(auction)) = db::marketplace::get_dutch_auction_by_id(pool, auction_id, chain.chain_id, &marketplace).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &auction.nft_contract, &auction.token_id, "marketplace:DutchAuctionBought",
+                        serde_json::json!({"auction_id": auction_id, "seller": auction.seller, "buyer": buyer, "price": price.to_string()}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         } else if topic0 == DutchAuctionCancelled::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<DutchAuctionCancelled>() {
                 let auction_id = decoded.inner.data.auctionId.to::<u64>() as i64;
                 tracing::info!(chain_id = chain.chain_id, "DutchAuctionCancelled #{}", auction_id);
-                if let Err(err) = db::marketplace::update_dutch_auction_status(
-                    pool, auction_id, chain.chain_id, "Cancelled", None, None,
+                if chain.dry_run {
+                    log_dry_run(chain, "update_dutch_auction_status", &(auction_id, ListingStatus::Cancelled));
+                } else if let Err(err) = db::marketplace::update_dutch_auction_status(
+                    pool, auction_id, chain.chain_id, &marketplace, ListingStatus::Cancelled, None, None,
                 ).await {
                     tracing::error!("Failed to cancel dutch auction {}: {:?}", auction_id, err);
                 }
+
+                if let Ok(Some(auction)) = db::marketplace::get_dutch_auction_by_id(pool, auction_id, chain.chain_id, &marketplace).await {
+                    maybe_push_agent_activity(
+                        &mut activities, chain, &auction.nft_contract, &auction.token_id, "marketplace:DutchAuctionCancelled",
+                        serde_json::json!({"auction_id": auction_id, "seller": auction.seller}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         }
         // ─── Bundles ────────────────────────────────────────────────
@@ -545,6 +790,12 @@ pub async fn index_marketplace_events(
                     }
                 };
 
+                push_bundle_agent_activities(
+                    &mut activities, chain, &nft_contracts, &token_ids, "marketplace:BundleListed",
+                    serde_json::json!({"bundle_id": bundle_id, "seller": seller, "bundle_price": price.to_string(), "item_count": item_count, "payment_token": payment_token}),
+                    block_number, block_timestamp, &tx_hash, log_index,
+                );
+
                 let new_bundle = NewMarketplaceBundle {
                     bundle_id,
                     chain_id: chain.chain_id,
@@ -558,8 +809,11 @@ pub async fn index_marketplace_events(
                     block_number,
                     block_timestamp,
                     tx_hash: tx_hash.clone(),
+                    marketplace_address: marketplace.clone(),
                 };
-                if let Err(err) = db::marketplace::upsert_bundle(pool, &new_bundle).await {
+                if chain.dry_run {
+                    log_dry_run(chain, "upsert_bundle", &new_bundle);
+                } else if let Err(err) = db::marketplace::upsert_bundle(pool, &new_bundle).await {
                     tracing::error!("Failed to upsert bundle {}: {:?}", bundle_id, err);
                 }
             }
@@ -570,21 +824,54 @@ pub async fn index_marketplace_events(
                 let buyer = format!("{:#x}", e.buyer);
                 let price = BigDecimal::from_str(&e.price.to_string()).unwrap_or_default();
                 tracing::info!(chain_id = chain.chain_id, "BundleBought #{}", bundle_id);
-                if let Err(err) = db::marketplace::update_bundle_status(
-                    pool, bundle_id, chain.chain_id, "Sold", Some(&buyer), Some(&price),
+                if chain.dry_run {
+                    log_dry_run(chain, "update_bundle_status", &(bundle_id, ListingStatus::Sold, &buyer, &price));
+                } else if let Err(err) = db::marketplace::update_bundle_status(
+                    pool, bundle_id, chain.chain_id, &marketplace, ListingStatus::Sold, Some(&buyer), Some(&price),
                 ).await {
                     tracing::error!("Failed to update bundle {} as Sold: {:?}", bundle_id, err);
                 }
+                sales.push(NewMarketplaceSale {
+                    sale_type: "bundle",
+                    entity_id: bundle_id,
+                    token_id: None,
+                    seller: None,
+                    buyer: Some(buyer.clone()),
+                    price: Some(price.clone()),
+                    block_number,
+                    block_timestamp,
+                    tx_hash: tx_hash.clone(),
+                    log_index,
+                });
+
+                // Cross-reference: look up bundle items for per-agent activity
+                if let Ok(Some(bundle)) = db::marketplace::get_bundle_by_id(pool, bundle_id, chain.chain_id, Some(&marketplace)).await {
+                    push_bundle_agent_activities(
+                        &mut activities, chain, &bundle.nft_contracts, &bundle.token_ids, "marketplace:BundleBought",
+                        serde_json::json!({"bundle_id": bundle_id, "buyer": buyer, "bundle_price": price.to_string(), "item_count": bundle.item_count}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         } else if topic0 == BundleListingCancelled::SIGNATURE_HASH {
             if let Ok(decoded) = log.log_decode::<BundleListingCancelled>() {
                 let bundle_id = decoded.inner.data.bundleId.to::<u64>() as i64;
                 tracing::info!(chain_id = chain.chain_id, "BundleListingCancelled #{}", bundle_id);
-                if let Err(err) = db::marketplace::update_bundle_status(
-                    pool, bundle_id, chain.chain_id, "Cancelled", None, None,
+                if chain.dry_run {
+                    log_dry_run(chain, "update_bundle_status", &(bundle_id, ListingStatus::Cancelled));
+                } else if let Err(err) = db::marketplace::update_bundle_status(
+                    pool, bundle_id, chain.chain_id, &marketplace, ListingStatus::Cancelled, None, None,
                 ).await {
                     tracing::error!("Failed to cancel bundle {}: {:?}", bundle_id, err);
                 }
+
+                if let Ok(Some(bundle)) = db::marketplace::get_bundle_by_id(pool, bundle_id, chain.chain_id, Some(&marketplace)).await {
+                    push_bundle_agent_activities(
+                        &mut activities, chain, &bundle.nft_contracts, &bundle.token_ids, "marketplace:BundleListingCancelled",
+                        serde_json::json!({"bundle_id": bundle_id, "seller": bundle.seller}),
+                        block_number, block_timestamp, &tx_hash, log_index,
+                    );
+                }
             }
         }
         // ─── Config Events ──────────────────────────────────────────
@@ -592,7 +879,9 @@ pub async fn index_marketplace_events(
             if let Ok(decoded) = log.log_decode::<PlatformFeeUpdated>() {
                 let new_fee = decoded.inner.data.newFee.to::<u32>() as i32;
                 tracing::info!(chain_id = chain.chain_id, "PlatformFeeUpdated: {} bps", new_fee);
-                if let Err(err) = db::marketplace::upsert_marketplace_config(
+                if chain.dry_run {
+                    log_dry_run(chain, "upsert_marketplace_config", &new_fee);
+                } else if let Err(err) = db::marketplace::upsert_marketplace_config(
                     pool, chain.chain_id, Some(new_fee), None,
                 ).await {
                     tracing::error!("Failed to update platform fee: {:?}", err);
@@ -602,7 +891,9 @@ pub async fn index_marketplace_events(
             if let Ok(decoded) = log.log_decode::<FeeRecipientUpdated>() {
                 let new_recipient = format!("{:#x}", decoded.inner.data.newRecipient);
                 tracing::info!(chain_id = chain.chain_id, "FeeRecipientUpdated: {}", new_recipient);
-                if let Err(err) = db::marketplace::upsert_marketplace_config(
+                if chain.dry_run {
+                    log_dry_run(chain, "upsert_marketplace_config", &new_recipient);
+                } else if let Err(err) = db::marketplace::upsert_marketplace_config(
                     pool, chain.chain_id, None, Some(&new_recipient),
                 ).await {
                     tracing::error!("Failed to update fee recipient: {:?}", err);
@@ -612,7 +903,9 @@ pub async fn index_marketplace_events(
             if let Ok(decoded) = log.log_decode::<PaymentTokenAdded>() {
                 let token = format!("{:#x}", decoded.inner.data.token);
                 tracing::info!(chain_id = chain.chain_id, "PaymentTokenAdded: {}", token);
-                if let Err(err) = db::marketplace::upsert_payment_token(
+                if chain.dry_run {
+                    log_dry_run(chain, "upsert_payment_token", &(&token, true, block_number));
+                } else if let Err(err) = db::marketplace::upsert_payment_token(
                     pool, chain.chain_id, &token, true, Some(block_number),
                 ).await {
                     tracing::error!("Failed to add payment token: {:?}", err);
@@ -622,7 +915,9 @@ pub async fn index_marketplace_events(
             if let Ok(decoded) = log.log_decode::<PaymentTokenRemoved>() {
                 let token = format!("{:#x}", decoded.inner.data.token);
                 tracing::info!(chain_id = chain.chain_id, "PaymentTokenRemoved: {}", token);
-                if let Err(err) = db::marketplace::upsert_payment_token(
+                if chain.dry_run {
+                    log_dry_run(chain, "upsert_payment_token", &(&token, false, block_number));
+                } else if let Err(err) = db::marketplace::upsert_payment_token(
                     pool, chain.chain_id, &token, false, Some(block_number),
                 ).await {
                     tracing::error!("Failed to remove payment token: {:?}", err);
@@ -631,13 +926,29 @@ pub async fn index_marketplace_events(
         }
     }
 
+    if chain.dry_run {
+        log_dry_run_batch(chain, "insert_sales", &sales);
+        log_dry_run_batch(chain, "insert_activities", &activities);
+        return Ok(());
+    }
+
+    db::marketplace::insert_sales(pool, chain.chain_id, &marketplace, &sales).await?;
+    db::activity::insert_activities(pool, &activities).await?;
+
     Ok(())
 }
 
-/// If the nft_contract matches the chain's identity_address, insert an activity log entry
+/// An auction that ends at or before it starts: bad contract input or a decode error. Such
+/// rows are stored with `malformed` set so the list endpoints can leave them out.
+pub fn auction_times_malformed(start_time: i64, end_time: i64) -> bool {
+    end_time <= start_time
+}
+
+/// If the nft_contract matches the chain's identity_address, queue an activity log entry
 /// for the agent NFT so marketplace events appear in the agent's activity feed.
-async fn maybe_insert_agent_activity(
-    pool: &PgPool,
+#[allow(clippy::too_many_arguments)]
+fn maybe_push_agent_activity(
+    activities: &mut Vec<NewActivity>,
     chain: &ChainConfig,
     nft_contract: &str,
     token_id: &BigDecimal,
@@ -659,7 +970,7 @@ async fn maybe_insert_agent_activity(
         Err(_) => return,
     };
 
-    let activity = NewActivity {
+    activities.push(NewActivity {
         agent_id,
         chain_id: chain.chain_id,
         event_type: event_type.to_string(),
@@ -668,16 +979,38 @@ async fn maybe_insert_agent_activity(
         block_timestamp,
         tx_hash: tx_hash.to_string(),
         log_index,
-    };
+    });
+}
 
-    if let Err(e) = db::activity::insert_activity(pool, &activity).await {
-        tracing::error!("Failed to insert {} activity for agent {}: {:?}", event_type, agent_id, e);
+/// Run `maybe_push_agent_activity` for every item in a bundle, so agent NFTs sold
+/// or listed as part of a bundle still show up in that agent's activity feed.
+/// Non-agent items are skipped by the identity_address check.
+#[allow(clippy::too_many_arguments)]
+fn push_bundle_agent_activities(
+    activities: &mut Vec<NewActivity>,
+    chain: &ChainConfig,
+    nft_contracts: &[String],
+    token_ids: &[BigDecimal],
+    event_type: &str,
+    event_data: serde_json::Value,
+    block_number: i64,
+    block_timestamp: Option<DateTime<Utc>>,
+    tx_hash: &str,
+    log_index: i32,
+) {
+    for (nft_contract, token_id) in nft_contracts.iter().zip(token_ids) {
+        maybe_push_agent_activity(
+            activities, chain, nft_contract, token_id, event_type, event_data.clone(),
+            block_number, block_timestamp, tx_hash, log_index,
+        );
     }
 }
 
 /// Read current marketplace config (platformFeeBps, feeRecipient) from on-chain
-/// and upsert into the DB. Called at indexer startup to ensure config is in sync
-/// even if initialize() didn't emit events.
+/// and upsert into the DB. Called at indexer startup and then periodically, so
+/// admin changes that never reached us as events (e.g. made before
+/// `marketplace_start_block`) still land. Also re-checks every known payment token
+/// via `isPaymentTokenAllowed`, since the contract has no enumeration view.
 pub async fn sync_marketplace_config(
     pool: &PgPool,
     provider: &HttpProvider,
@@ -721,14 +1054,73 @@ pub async fn sync_marketplace_config(
     };
 
     if fee_bps.is_some() || fee_recipient.is_some() {
+        // Log values that drifted without us seeing the corresponding event
+        if let Some((stored_fee, stored_recipient)) =
+            db::marketplace::get_marketplace_config(pool, chain.chain_id).await?
+        {
+            if fee_bps.is_some() && fee_bps != stored_fee {
+                tracing::warn!(
+                    chain_id = chain.chain_id,
+                    "Platform fee changed outside of events: {:?} -> {:?} bps",
+                    stored_fee, fee_bps
+                );
+            }
+            if fee_recipient.is_some() && fee_recipient != stored_recipient {
+                tracing::warn!(
+                    chain_id = chain.chain_id,
+                    "Fee recipient changed outside of events: {:?} -> {:?}",
+                    stored_recipient, fee_recipient
+                );
+            }
+        }
+
         tracing::info!(
             chain_id = chain.chain_id,
             "Synced marketplace config: fee={:?} bps, recipient={:?}",
             fee_bps, fee_recipient
         );
-        db::marketplace::upsert_marketplace_config(
-            pool, chain.chain_id, fee_bps, fee_recipient.as_deref(),
-        ).await?;
+        if chain.dry_run {
+            log_dry_run(chain, "upsert_marketplace_config", &(fee_bps, &fee_recipient));
+        } else {
+            db::marketplace::upsert_marketplace_config(
+                pool, chain.chain_id, fee_bps, fee_recipient.as_deref(),
+            ).await?;
+        }
+    }
+
+    // Re-check allowance of every payment token we have seen
+    for (token, stored_active) in db::marketplace::get_payment_tokens(pool, chain.chain_id).await? {
+        let token_address = match token.parse::<alloy::primitives::Address>() {
+            Ok(a) => a,
+            Err(e) => {
+                tracing::warn!("Skipping unparseable payment token {}: {:?}", token, e);
+                continue;
+            }
+        };
+        let allowed_call = MoltMarketplace::isPaymentTokenAllowedCall { token: token_address };
+        let allowed_tx = alloy::rpc::types::TransactionRequest::default()
+            .to(marketplace_address)
+            .input(alloy::primitives::Bytes::from(allowed_call.abi_encode()).into());
+        let allowed = match provider.call(allowed_tx).await {
+            Ok(bytes) => match MoltMarketplace::isPaymentTokenAllowedCall::abi_decode_returns(&bytes) {
+                Ok(decoded) => decoded,
+                Err(e) => { tracing::warn!("Failed to decode isPaymentTokenAllowed({}): {:?}", token, e); continue; }
+            },
+            Err(e) => { tracing::warn!("Failed to read isPaymentTokenAllowed({}): {:?}", token, e); continue; }
+        };
+
+        if allowed != stored_active {
+            tracing::warn!(
+                chain_id = chain.chain_id,
+                "Payment token {} allowance changed outside of events: {} -> {}",
+                token, stored_active, allowed
+            );
+            if chain.dry_run {
+                log_dry_run(chain, "upsert_payment_token", &(&token, allowed));
+            } else {
+                db::marketplace::upsert_payment_token(pool, chain.chain_id, &token, allowed, None).await?;
+            }
+        }
     }
 
     Ok(())
@@ -1,5 +1,16 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::types::MetadataQueueStatus;
+
+/// Jobs the metadata queue holds before `try_enqueue` starts refusing them.
+pub const METADATA_QUEUE_CAPACITY: usize = 1_000;
+/// Metadata fetches the queue runs at once.
+pub const METADATA_FETCH_CONCURRENCY: usize = 4;
 
 /// EIP-8004 agent metadata schema returned from the agent URI.
 #[derive(Debug, Deserialize, Serialize)]
@@ -19,11 +30,92 @@ pub struct AgentEndpointMeta {
     pub protocol: Option<String>,
 }
 
-/// Fetch metadata from an agent URI and update the agents table.
+/// One agent whose metadata should be (re)fetched from `uri`.
+#[derive(Debug, Clone)]
+pub struct MetadataJob {
+    pub agent_id: i64,
+    pub chain_id: i32,
+    pub uri: String,
+}
+
+/// Bounded queue of metadata fetches, drained by a background task that runs at most
+/// `concurrency` of them at once. Counters are reported on /api/indexer/status.
+pub struct MetadataQueue {
+    tx: mpsc::Sender<MetadataJob>,
+    queued: AtomicU64,
+    dropped: AtomicU64,
+    in_flight: AtomicU64,
+    fetched: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl MetadataQueue {
+    /// Create the queue and spawn its worker, which writes fetched metadata through `pool`.
+    pub fn start(pool: PgPool, capacity: usize, concurrency: usize) -> Arc<Self> {
+        let (tx, mut rx) = mpsc::channel::<MetadataJob>(capacity);
+        let queue = Arc::new(MetadataQueue {
+            tx,
+            queued: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            in_flight: AtomicU64::new(0),
+            fetched: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        });
+
+        let worker = queue.clone();
+        let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+        tokio::spawn(async move {
+            while let Some(job) = rx.recv().await {
+                let Ok(permit) = permits.clone().acquire_owned().await else { break };
+                worker.in_flight.fetch_add(1, Ordering::Relaxed);
+                let pool = pool.clone();
+                let worker = worker.clone();
+                tokio::spawn(async move {
+                    let ok = fetch_and_update_metadata(&pool, job.agent_id, job.chain_id, &job.uri).await;
+                    let counter = if ok { &worker.fetched } else { &worker.failed };
+                    counter.fetch_add(1, Ordering::Relaxed);
+                    worker.in_flight.fetch_sub(1, Ordering::Relaxed);
+                    drop(permit);
+                });
+            }
+        });
+        queue
+    }
+
+    /// Queue `job` without waiting; false (and counted as dropped) when the queue is full.
+    pub fn try_enqueue(&self, job: MetadataJob) -> bool {
+        match self.tx.try_send(job) {
+            Ok(()) => {
+                self.queued.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(err) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!("Metadata queue refused a job: {}", err);
+                false
+            }
+        }
+    }
+
+    pub fn status(&self) -> MetadataQueueStatus {
+        MetadataQueueStatus {
+            capacity: self.tx.max_capacity(),
+            pending: self.tx.max_capacity() - self.tx.capacity(),
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            queued: self.queued.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            fetched: self.fetched.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Fetch metadata from an agent URI and update the agents table. Returns whether the agent
+/// row was updated.
 ///
 /// This function is designed to be called from a spawned tokio task.
 /// It logs errors internally and never panics — failures are gracefully skipped.
-pub async fn fetch_and_update_metadata(pool: &PgPool, agent_id: i64, chain_id: i32, uri: &str) {
+pub async fn fetch_and_update_metadata(pool: &PgPool, agent_id: i64, chain_id: i32, uri: &str) -> bool {
     tracing::info!(
         agent_id = agent_id,
         chain_id = chain_id,
@@ -40,6 +132,7 @@ pub async fn fetch_and_update_metadata(pool: &PgPool, agent_id: i64, chain_id: i
                     "Failed to update agent with metadata: {:?}",
                     e
                 );
+                false
             } else {
                 tracing::info!(
                     agent_id = agent_id,
@@ -47,6 +140,7 @@ pub async fn fetch_and_update_metadata(pool: &PgPool, agent_id: i64, chain_id: i
                     "Successfully updated agent metadata (name={:?})",
                     meta.name
                 );
+                true
             }
         }
         Err(e) => {
@@ -57,49 +151,50 @@ pub async fn fetch_and_update_metadata(pool: &PgPool, agent_id: i64, chain_id: i
                 "Failed to fetch metadata from URI: {:?}",
                 e
             );
+            false
         }
     }
 }
 
 /// Resolve an agent URI and parse the response as EIP-8004 metadata JSON.
-///
-/// Supports:
-/// - `data:application/json;base64,<base64>` — inline base64 JSON
-/// - `data:application/json,<json>` — inline raw JSON (URL-encoded)
-/// - `ipfs://<cid>` — resolved via public IPFS gateway
-/// - `http(s)://...` — standard HTTP fetch
 async fn fetch_metadata(uri: &str) -> Result<AgentUriMetadata, Box<dyn std::error::Error + Send + Sync>> {
-    use base64::Engine as _;
+    let content = fetch_uri_content(uri).await?;
+    let meta: AgentUriMetadata = serde_json::from_slice(&content)?;
+    Ok(meta)
+}
 
-    // Handle data: URIs
-    if uri.starts_with("data:") {
-        let rest = uri.strip_prefix("data:").unwrap();
-
-        // data:application/json;base64,<payload>
-        if let Some(payload) = rest
-            .strip_prefix("application/json;base64,")
-            .or_else(|| rest.strip_prefix("application/json; base64,"))
-        {
-            let decoded = base64::engine::general_purpose::STANDARD.decode(payload.trim())?;
-            let meta: AgentUriMetadata = serde_json::from_slice(&decoded)?;
-            return Ok(meta);
-        }
+/// Public gateway that `ipfs://` URIs are fetched through.
+pub const IPFS_GATEWAY: &str = "https://ipfs.io/ipfs/";
 
-        // data:application/json,<payload> (URL-encoded or raw)
-        if let Some(payload) = rest.strip_prefix("application/json,") {
-            let decoded_str = urlencoding::decode(payload)?;
-            let meta: AgentUriMetadata = serde_json::from_str(&decoded_str)?;
-            return Ok(meta);
-        }
+/// Where the content behind an agent URI (metadata or image) comes from.
+#[derive(Debug, PartialEq, Eq)]
+pub enum UriSource<'a> {
+    /// An inline `data:` URI, decoded rather than fetched
+    Data(&'a str),
+    /// A URL to fetch
+    Url(String),
+}
 
-        return Err(format!("Unsupported data URI format: {}", &uri[..uri.len().min(80)]).into());
+/// Resolve an agent URI:
+/// - `data:...` — inline content (see `parse_data_uri`)
+/// - `ipfs://<cid>[/path]` — fetched through `IPFS_GATEWAY`
+/// - anything else (`http(s)://...`) — fetched as is
+pub fn resolve_uri(uri: &str) -> UriSource<'_> {
+    if uri.get(..5).is_some_and(|scheme| scheme.eq_ignore_ascii_case("data:")) {
+        return UriSource::Data(uri);
+    }
+    match uri.strip_prefix("ipfs://") {
+        Some(cid_path) => UriSource::Url(format!("{}{}", IPFS_GATEWAY, cid_path)),
+        None => UriSource::Url(uri.to_string()),
     }
+}
 
-    // Handle ipfs:// URIs — resolve via public gateway
-    let fetch_url = if let Some(cid_path) = uri.strip_prefix("ipfs://") {
-        format!("https://ipfs.io/ipfs/{}", cid_path)
-    } else {
-        uri.to_string()
+/// Resolve a URI (see `resolve_uri`) and return the raw content bytes, exactly as served.
+/// `data:` URIs must carry JSON (see `decode_data_uri`).
+pub async fn fetch_uri_content(uri: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let fetch_url = match resolve_uri(uri) {
+        UriSource::Data(data) => return decode_data_uri(data),
+        UriSource::Url(url) => url,
     };
 
     // Standard HTTP fetch
@@ -113,8 +208,51 @@ async fn fetch_metadata(uri: &str) -> Result<AgentUriMetadata, Box<dyn std::erro
         return Err(format!("HTTP {} from URI: {}", response.status(), fetch_url).into());
     }
 
-    let meta: AgentUriMetadata = response.json().await?;
-    Ok(meta)
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Decode an RFC 2397 `data:` URI carrying JSON: `data:<mediatype>[;<param>]*[;base64],<data>`.
+/// The media type must be `application/json` or a `+json` type; see `parse_data_uri`.
+pub fn decode_data_uri(uri: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let (media_type, content) = parse_data_uri(uri)?;
+    if media_type != "application/json" && !media_type.ends_with("+json") {
+        let preview = &uri[..uri.floor_char_boundary(80)];
+        return Err(format!("Unsupported data URI media type {:?}: {}", media_type, preview).into());
+    }
+    Ok(content)
+}
+
+/// Split an RFC 2397 `data:` URI into its lowercased media type and decoded content.
+///
+/// The part before the first `,` is split on `;`: the first item is the media type, and
+/// `base64` may appear among the parameters in any position (`;charset=utf-8;base64` and
+/// `;base64;charset=utf-8` both work). Other parameters such as `charset` are ignored. The
+/// payload is percent-decoded, then base64 decoded when flagged.
+pub fn parse_data_uri(uri: &str) -> Result<(String, Vec<u8>), Box<dyn std::error::Error + Send + Sync>> {
+    use base64::Engine as _;
+
+    let preview = &uri[..uri.floor_char_boundary(80)];
+    let rest = uri
+        .get(..5)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("data:"))
+        .map(|_| &uri[5..])
+        .ok_or_else(|| format!("Not a data URI: {}", preview))?;
+    let (header, payload) = rest
+        .split_once(',')
+        .ok_or_else(|| format!("Data URI without a ',' separator: {}", preview))?;
+
+    let mut parts = header.split(';').map(str::trim);
+    let media_type = parts.next().unwrap_or_default().to_ascii_lowercase();
+    let is_base64 = parts.any(|param| param.eq_ignore_ascii_case("base64"));
+
+    let payload = urlencoding::decode_binary(payload.as_bytes());
+    let content = if is_base64 {
+        let compact: Vec<u8> = payload.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+        base64::engine::general_purpose::STANDARD.decode(compact)?
+    } else {
+        payload.into_owned()
+    };
+    Ok((media_type, content))
 }
 
 /// Update the agents table with parsed metadata fields.
@@ -139,8 +277,7 @@ async fn update_agent_with_metadata(
             image = COALESCE($5, image),
             categories = COALESCE($6, categories),
             x402_support = COALESCE($7, x402_support),
-            metadata = COALESCE($8, metadata),
-            updated_at = NOW()
+            metadata = COALESCE($8, metadata)
         WHERE agent_id = $1 AND chain_id = $2
         "#,
     )
@@ -1,12 +1,23 @@
 pub mod backfill;
+pub mod feedback_content;
 pub mod identity;
 pub mod marketplace;
 pub mod metadata;
 pub mod provider;
 pub mod reputation;
+pub mod retention;
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use provider::ChainConfig;
 use sqlx::PgPool;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+use crate::startup::Startup;
 
 /// Block batch size per eth_getLogs call to avoid RPC limits.
 /// Both mainnet and testnet RPCs are limited to 100 block range.
@@ -21,11 +32,83 @@ pub const PARALLEL_BATCHES: u64 = 10;
 /// Monad has ~1s block time, so 500ms keeps us responsive.
 pub const POLL_INTERVAL_MS: u64 = 500;
 
-/// Run the indexer loop for all configured chains.
-/// This function runs forever, polling for new events every POLL_INTERVAL_MS.
-pub async fn run_indexer(pool: PgPool) {
-    let chains = provider::get_chain_configs();
+/// Default interval between on-chain marketplace config re-syncs (in seconds).
+/// Override with MARKETPLACE_CONFIG_SYNC_INTERVAL_SECS.
+pub const CONFIG_SYNC_INTERVAL_SECS: u64 = 3600;
+
+/// Upper bound on the backoff before restarting a panicked chain task (in seconds).
+pub const MAX_RESTART_BACKOFF_SECS: u64 = 60;
+
+/// Runtime per-chain enable flags, keyed by chain_id. Shared with the admin API so a
+/// misbehaving chain can be paused without a redeploy.
+pub type ChainFlags = Arc<HashMap<i32, AtomicBool>>;
+
+/// Build enable flags for the given chains, all initially enabled.
+pub fn chain_flags(chains: &[ChainConfig]) -> ChainFlags {
+    Arc::new(
+        chains
+            .iter()
+            .map(|c| (c.chain_id, AtomicBool::new(true)))
+            .collect(),
+    )
+}
+
+/// Whether indexing is currently enabled for a chain. Chains without a flag are enabled.
+pub fn chain_enabled(flags: &ChainFlags, chain_id: i32) -> bool {
+    flags
+        .get(&chain_id)
+        .map(|f| f.load(Ordering::Acquire))
+        .unwrap_or(true)
+}
+
+/// Log a write that dry-run mode skipped; `row` is what would have been written.
+pub fn log_dry_run(chain: &ChainConfig, write: &str, row: &dyn Debug) {
+    tracing::info!(chain_id = chain.chain_id, "[dry run] {}: {:?}", write, row);
+}
+
+/// `log_dry_run` for each row of a batched write.
+pub fn log_dry_run_batch<T: Debug>(chain: &ChainConfig, write: &str, rows: &[T]) {
+    for row in rows {
+        log_dry_run(chain, write, row);
+    }
+}
 
+/// Wait for `startup` to be Ready, which main.rs sets once migrations and the startup
+/// backfills are done, so neither the marketplace config sync nor the first cycle races their
+/// table updates. False if startup Failed or `shutdown` came first.
+pub async fn wait_for_database(startup: &Startup, shutdown: &CancellationToken) -> bool {
+    tracing::info!("Indexer waiting for migrations and backfill to finish");
+    tokio::select! {
+        biased;
+        _ = shutdown.cancelled() => {
+            tracing::info!("Shutdown before the database was ready; indexer not started");
+            false
+        }
+        ready = startup.settled() => {
+            if ready {
+                tracing::info!("Database ready; starting indexer cycles");
+            } else {
+                tracing::error!("Startup failed; indexer not started");
+            }
+            ready
+        }
+    }
+}
+
+/// Run the indexer for `chains`, re-syncing marketplace config every `config_sync_interval`.
+/// Waits for `startup` (see `wait_for_database`), then spawns one supervised task per chain;
+/// each polls for new events every POLL_INTERVAL_MS once caught up, skipping cycles while its
+/// flag in `flags` is disabled or `paused` is set. Runs until `shutdown` is cancelled, then
+/// returns once every chain has stopped (see `run_chain`).
+pub async fn run_indexer(
+    pool: PgPool,
+    chains: Vec<ChainConfig>,
+    config_sync_interval: std::time::Duration,
+    flags: ChainFlags,
+    paused: Arc<AtomicBool>,
+    startup: Arc<Startup>,
+    shutdown: CancellationToken,
+) {
     if chains.is_empty() {
         tracing::warn!("No chain configs found — indexer has nothing to index");
         return;
@@ -39,87 +122,245 @@ pub async fn run_indexer(pool: PgPool) {
         POLL_INTERVAL_MS
     );
     for chain in &chains {
-        let masked_rpc = if chain.rpc_url.len() > 20 {
-            format!("{}...{}", &chain.rpc_url[..16], &chain.rpc_url[chain.rpc_url.len()-8..])
-        } else {
-            chain.rpc_url.clone()
-        };
+        let masked_rpc = crate::config::mask_rpc_url(&chain.rpc_url);
         tracing::info!(
-            "  Chain {} | rpc={} | start_block={} | identity={} | reputation={} | marketplace={}",
+            "  Chain {} | rpc={} | identity={}@{} | reputation={}@{} | marketplace={}@{}",
             chain.chain_id,
             masked_rpc,
-            chain.start_block,
             chain.identity_address,
+            chain.start_block,
             chain.reputation_address,
-            chain.marketplace_address.map(|a| a.to_string()).unwrap_or_else(|| "none".to_string())
+            chain.reputation_start_block.unwrap_or(chain.start_block),
+            chain.marketplace_address.map(|a| a.to_string()).unwrap_or_else(|| "none".to_string()),
+            chain.marketplace_start_block.unwrap_or(chain.start_block)
         );
+        if chain.dry_run {
+            tracing::warn!(
+                "  Chain {} is in DRY_RUN mode: events are decoded and logged, nothing is written",
+                chain.chain_id
+            );
+        }
+    }
+
+    if !wait_for_database(&startup, &shutdown).await {
+        return;
     }
 
     // Sync marketplace config from on-chain at startup (initialize() doesn't emit events)
-    for chain in &chains {
-        if chain.marketplace_address.is_some() {
-            match provider::create_provider(chain) {
-                Ok(prov) => {
-                    if let Err(e) = marketplace::sync_marketplace_config(&pool, &prov, chain).await {
-                        tracing::error!(chain_id = chain.chain_id, "Failed to sync marketplace config: {:?}", e);
-                    }
-                }
-                Err(e) => {
-                    tracing::error!(chain_id = chain.chain_id, "Failed to create provider for config sync: {:?}", e);
-                }
+    sync_marketplace_configs(&pool, &chains).await;
+
+    // Keep re-syncing on a timer so admin changes we never saw as events still land
+    if chains.iter().any(|c| c.marketplace_address.is_some()) {
+        let sync_pool = pool.clone();
+        let sync_chains = chains.clone();
+        let sync_shutdown = shutdown.clone();
+        tokio::spawn(sync_shutdown.run_until_cancelled_owned(async move {
+            let period = config_sync_interval;
+            let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                interval.tick().await;
+                sync_marketplace_configs(&sync_pool, &sync_chains).await;
             }
+        }));
+        tracing::info!("Marketplace config re-sync every {}s", config_sync_interval.as_secs());
+    }
+
+    // One long-lived task per chain so a slow RPC on one chain never stalls the others
+    let mut supervisors = Vec::new();
+    for chain in chains {
+        supervisors.push(tokio::spawn(supervise_chain(
+            pool.clone(),
+            chain,
+            flags.clone(),
+            paused.clone(),
+            shutdown.clone(),
+        )));
+    }
+    for handle in supervisors {
+        if let Err(e) = handle.await {
+            tracing::error!("Chain supervisor exited unexpectedly: {:?}", e);
         }
     }
+    tracing::info!("Indexer stopped");
+}
+
+/// Sleep for `duration`, returning early (with false) when `shutdown` is cancelled.
+async fn sleep_unless_shutdown(shutdown: &CancellationToken, duration: std::time::Duration) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => true,
+        _ = shutdown.cancelled() => false,
+    }
+}
+
+/// How long supervise_chain waits before the `restarts`-th restart of a panicked chain task:
+/// 5s more per restart, capped at MAX_RESTART_BACKOFF_SECS.
+pub fn restart_backoff(restarts: u64) -> std::time::Duration {
+    std::time::Duration::from_secs(std::cmp::min(5 * restarts, MAX_RESTART_BACKOFF_SECS))
+}
 
+/// Keep a chain's indexer task alive, restarting it with backoff if it panics.
+pub async fn supervise_chain(
+    pool: PgPool,
+    chain: ChainConfig,
+    flags: ChainFlags,
+    paused: Arc<AtomicBool>,
+    shutdown: CancellationToken,
+) {
+    let mut restarts: u64 = 0;
     loop {
-        let mut all_caught_up = true;
-        for chain in &chains {
-            match index_chain(&pool, chain).await {
-                Ok(caught_up) => {
-                    if !caught_up {
-                        all_caught_up = false;
+        let task = tokio::spawn(run_chain(
+            pool.clone(),
+            chain.clone(),
+            flags.clone(),
+            paused.clone(),
+            shutdown.clone(),
+        ));
+        match task.await {
+            Ok(()) => return,
+            Err(e) => {
+                restarts += 1;
+                let backoff = restart_backoff(restarts);
+                tracing::error!(
+                    chain_id = chain.chain_id,
+                    "Indexer task for chain {} panicked (restart #{}): {:?} — restarting in {}s",
+                    chain.chain_id,
+                    restarts,
+                    e,
+                    backoff.as_secs()
+                );
+                if !sleep_unless_shutdown(&shutdown, backoff).await {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Index a single chain until `shutdown` is cancelled. Sleeps POLL_INTERVAL_MS only when this
+/// chain is caught up (or errored, or disabled), independently of any other chain. `paused`
+/// (the indexer-wide switch) and the chain's flag are read before each cycle, so a cycle in
+/// progress when either is flipped runs to completion and saves its cursors. Shutdown instead
+/// abandons the cycle in progress, aborting its batches: a batch that had already committed
+/// keeps its rows but not its cursor, and the range is indexed again (idempotently) on the
+/// next start.
+pub async fn run_chain(
+    pool: PgPool,
+    chain: ChainConfig,
+    flags: ChainFlags,
+    paused: Arc<AtomicBool>,
+    shutdown: CancellationToken,
+) {
+    let provider = match provider::create_provider(&chain) {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!(
+                chain_id = chain.chain_id,
+                "Failed to create provider for chain {}, not indexing it: {:?}",
+                chain.chain_id,
+                e
+            );
+            return;
+        }
+    };
+
+    // Dry runs never write indexer_state, so progress is kept here instead
+    let mut dry_run_cursors: HashMap<String, i64> = HashMap::new();
+    let mut was_enabled = true;
+    while !shutdown.is_cancelled() {
+        let enabled = !paused.load(Ordering::Acquire) && chain_enabled(&flags, chain.chain_id);
+        if enabled != was_enabled {
+            tracing::info!(
+                chain_id = chain.chain_id,
+                "Indexing for chain {} {}",
+                chain.chain_id,
+                if enabled { "resumed" } else { "paused" }
+            );
+            was_enabled = enabled;
+        }
+        if !enabled {
+            sleep_unless_shutdown(&shutdown, std::time::Duration::from_millis(POLL_INTERVAL_MS)).await;
+            continue;
+        }
+
+        // A hung RPC call must not hold up shutdown. Dropping the cycle drops its batch tasks'
+        // JoinSet, which aborts them; an interrupted batch is redone next start.
+        let cycle = tokio::select! {
+            result = index_chain(&pool, &provider, &chain, &mut dry_run_cursors) => result,
+            _ = shutdown.cancelled() => break,
+        };
+        let caught_up = match cycle {
+            Ok(caught_up) => caught_up,
+            Err(e) => {
+                tracing::error!(
+                    chain_id = chain.chain_id,
+                    "Indexer error for chain {}: {:?}",
+                    chain.chain_id,
+                    e
+                );
+                true
+            }
+        };
+
+        if caught_up {
+            sleep_unless_shutdown(&shutdown, std::time::Duration::from_millis(POLL_INTERVAL_MS)).await;
+        }
+    }
+    tracing::info!(chain_id = chain.chain_id, "Indexer for chain {} stopped", chain.chain_id);
+}
+
+/// The newest block with at least `confirmations` blocks built on it: what `index_chain`
+/// indexes up to. Before the chain is that long, block 0.
+pub fn confirmed_tip(latest_block: u64, confirmations: u64) -> u64 {
+    latest_block.saturating_sub(confirmations)
+}
+
+/// Sync on-chain marketplace config for every chain that has a marketplace deployed.
+async fn sync_marketplace_configs(pool: &PgPool, chains: &[ChainConfig]) {
+    for chain in chains {
+        if chain.marketplace_address.is_some() {
+            match provider::create_provider(chain) {
+                Ok(prov) => {
+                    if let Err(e) = marketplace::sync_marketplace_config(pool, &prov, chain).await {
+                        tracing::error!(chain_id = chain.chain_id, "Failed to sync marketplace config: {:?}", e);
                     }
                 }
                 Err(e) => {
-                    tracing::error!(
-                        chain_id = chain.chain_id,
-                        "Indexer error for chain {}: {:?}",
-                        chain.chain_id,
-                        e
-                    );
+                    tracing::error!(chain_id = chain.chain_id, "Failed to create provider for config sync: {:?}", e);
                 }
             }
         }
-
-        // Only sleep when fully caught up to latest block
-        if all_caught_up {
-            tokio::time::sleep(std::time::Duration::from_millis(POLL_INTERVAL_MS)).await;
-        }
     }
 }
 
 /// Index a single cycle for a chain: run PARALLEL_BATCHES concurrent batches for identity + reputation.
 /// Returns Ok(true) if caught up to latest block, Ok(false) if still behind.
-async fn index_chain(pool: &PgPool, chain: &ChainConfig) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-    let provider = provider::create_provider(chain)?;
+/// In dry-run mode cursors advance in `dry_run_cursors` rather than indexer_state.
+async fn index_chain(
+    pool: &PgPool,
+    provider: &provider::HttpProvider,
+    chain: &ChainConfig,
+    dry_run_cursors: &mut HashMap<String, i64>,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
     let batch_size = BLOCK_BATCH_SIZE;
 
-    // Get the latest block number from the RPC
-    let latest_block = provider::get_latest_block(&provider).await?;
+    // Index up to the latest block the RPC reports, less the confirmation depth; every
+    // behind/caught-up check below compares against this
+    let latest_block = confirmed_tip(provider::get_latest_block(provider).await?, chain.confirmations);
 
     let identity_addr = chain.identity_address.to_string();
     let reputation_addr = chain.reputation_address.to_string();
     let marketplace_addr = chain.marketplace_address.map(|a| a.to_string());
 
-    let identity_last = crate::db::indexer_state::get_last_block(pool, chain.chain_id, &identity_addr)
+    let identity_last = last_indexed_block(pool, chain, dry_run_cursors, &identity_addr)
         .await?
         .unwrap_or(chain.start_block as i64 - 1);
-    let reputation_last = crate::db::indexer_state::get_last_block(pool, chain.chain_id, &reputation_addr)
+    let reputation_start = chain.reputation_start_block.unwrap_or(chain.start_block);
+    let reputation_last = last_indexed_block(pool, chain, dry_run_cursors, &reputation_addr)
         .await?
-        .unwrap_or(chain.start_block as i64 - 1);
+        .unwrap_or(reputation_start as i64 - 1);
     let marketplace_last = if let Some(ref addr) = marketplace_addr {
         let mp_start = chain.marketplace_start_block.unwrap_or(chain.start_block);
-        crate::db::indexer_state::get_last_block(pool, chain.chain_id, addr)
+        last_indexed_block(pool, chain, dry_run_cursors, addr)
             .await?
             .unwrap_or(mp_start as i64 - 1)
     } else {
@@ -138,7 +379,7 @@ async fn index_chain(pool: &PgPool, chain: &ChainConfig) -> Result<bool, Box<dyn
     let (identity_result, reputation_result, marketplace_result) = tokio::join!(
         index_contract_parallel(
             pool,
-            &provider,
+            provider,
             chain,
             identity_last,
             latest_block,
@@ -147,7 +388,7 @@ async fn index_chain(pool: &PgPool, chain: &ChainConfig) -> Result<bool, Box<dyn
         ),
         index_contract_parallel(
             pool,
-            &provider,
+            provider,
             chain,
             reputation_last,
             latest_block,
@@ -156,7 +397,7 @@ async fn index_chain(pool: &PgPool, chain: &ChainConfig) -> Result<bool, Box<dyn
         ),
         index_contract_parallel(
             pool,
-            &provider,
+            provider,
             chain,
             marketplace_last,
             latest_block,
@@ -167,36 +408,62 @@ async fn index_chain(pool: &PgPool, chain: &ChainConfig) -> Result<bool, Box<dyn
 
     // Update indexer state for identity
     if let Ok(Some(last)) = identity_result {
-        crate::db::indexer_state::update_last_block_with_name(
-            pool, chain.chain_id, &identity_addr, last, Some("IdentityRegistry"),
-        ).await?;
+        save_last_block(pool, chain, dry_run_cursors, &identity_addr, last, "IdentityRegistry").await?;
     } else if let Err(e) = identity_result {
-        tracing::error!(chain_id = chain.chain_id, "Identity indexing error: {:?}", e);
+        tracing::error!(chain_id = chain.chain_id, contract = "identity", "Identity indexing error: {:?}", e);
     }
 
     // Update indexer state for reputation
     if let Ok(Some(last)) = reputation_result {
-        crate::db::indexer_state::update_last_block_with_name(
-            pool, chain.chain_id, &reputation_addr, last, Some("ReputationRegistry"),
-        ).await?;
+        save_last_block(pool, chain, dry_run_cursors, &reputation_addr, last, "ReputationRegistry").await?;
     } else if let Err(e) = reputation_result {
-        tracing::error!(chain_id = chain.chain_id, "Reputation indexing error: {:?}", e);
+        tracing::error!(chain_id = chain.chain_id, contract = "reputation", "Reputation indexing error: {:?}", e);
     }
 
     // Update indexer state for marketplace
     if let Some(ref addr) = marketplace_addr {
         if let Ok(Some(last)) = marketplace_result {
-            crate::db::indexer_state::update_last_block_with_name(
-                pool, chain.chain_id, addr, last, Some("MoltMarketplace"),
-            ).await?;
+            save_last_block(pool, chain, dry_run_cursors, addr, last, "MoltMarketplace").await?;
         } else if let Err(e) = marketplace_result {
-            tracing::error!(chain_id = chain.chain_id, "Marketplace indexing error: {:?}", e);
+            tracing::error!(chain_id = chain.chain_id, contract = "marketplace", "Marketplace indexing error: {:?}", e);
         }
     }
 
     Ok(false)
 }
 
+/// A contract's last indexed block. A dry run starts from indexer_state, then follows its
+/// own in-memory cursor.
+async fn last_indexed_block(
+    pool: &PgPool,
+    chain: &ChainConfig,
+    dry_run_cursors: &HashMap<String, i64>,
+    address: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    if let Some(last) = dry_run_cursors.get(address).filter(|_| chain.dry_run) {
+        return Ok(Some(*last));
+    }
+    crate::db::indexer_state::get_last_block(pool, chain.chain_id, address).await
+}
+
+/// Record a contract's progress in indexer_state, or only in memory for a dry run.
+async fn save_last_block(
+    pool: &PgPool,
+    chain: &ChainConfig,
+    dry_run_cursors: &mut HashMap<String, i64>,
+    address: &str,
+    last: i64,
+    contract_name: &str,
+) -> Result<(), sqlx::Error> {
+    if chain.dry_run {
+        log_dry_run(chain, "update_last_block_with_name", &(address, last, contract_name));
+        dry_run_cursors.insert(address.to_string(), last);
+        return Ok(());
+    }
+    crate::db::indexer_state::update_last_block_with_name(pool, chain.chain_id, address, last, Some(contract_name))
+        .await
+}
+
 #[derive(Clone, Copy)]
 enum ContractType {
     Identity,
@@ -208,7 +475,7 @@ enum ContractType {
 /// Returns the highest successfully completed block number, or None if already caught up.
 async fn index_contract_parallel(
     pool: &PgPool,
-    _provider: &provider::HttpProvider,
+    provider: &provider::HttpProvider,
     chain: &ChainConfig,
     last_block: i64,
     latest_block: u64,
@@ -252,19 +519,21 @@ async fn index_contract_parallel(
         let (from, to) = batches[0];
         tracing::info!(
             chain_id = chain.chain_id,
+            contract = contract_name,
+            from_block = from,
+            to_block = to,
             "Indexing {} events blocks {} - {}",
             contract_name, from, to
         );
-        let prov = provider::create_provider(chain)?;
         match contract_type {
             ContractType::Identity => {
-                identity::index_identity_events(pool, &prov, chain, from, to).await?;
+                identity::index_identity_events(pool, provider, chain, from, to).await?;
             }
             ContractType::Reputation => {
-                reputation::index_reputation_events(pool, &prov, chain, from, to).await?;
+                reputation::index_reputation_events(pool, provider, chain, from, to).await?;
             }
             ContractType::Marketplace => {
-                marketplace::index_marketplace_events(pool, &prov, chain, from, to).await?;
+                marketplace::index_marketplace_events(pool, provider, chain, from, to).await?;
             }
         }
         return Ok(Some(to as i64));
@@ -274,22 +543,22 @@ async fn index_contract_parallel(
     let total_to = batches.last().unwrap().1;
     tracing::info!(
         chain_id = chain.chain_id,
+        contract = contract_name,
+        from_block = total_from,
+        to_block = total_to,
         "Indexing {} events blocks {} - {} ({} parallel batches)",
         contract_name, total_from, total_to, batches.len()
     );
 
-    // Run all batches concurrently
-    let mut handles = Vec::new();
-    for (from, to) in &batches {
+    // Run all batches concurrently. The set owns the tasks: if this cycle is dropped (at
+    // shutdown), they are aborted with it rather than left writing in the background.
+    let mut tasks = JoinSet::new();
+    let mut batch_of = HashMap::new();
+    for (i, &(from, to)) in batches.iter().enumerate() {
         let pool = pool.clone();
         let chain = chain.clone();
-        let from = *from;
-        let to = *to;
-        // contract_type is Copy, no need to rebind
-        // We need to create a new provider per task since HttpProvider is not Send-safe across awaits
-        // in different tasks. Instead, share the provider ref — alloy providers are Send+Sync.
-        let prov = provider::create_provider(&chain)?;
-        handles.push(tokio::spawn(async move {
+        let prov = provider.clone();
+        let task = tasks.spawn(async move {
             match contract_type {
                 ContractType::Identity => {
                     identity::index_identity_events(&pool, &prov, &chain, from, to).await
@@ -301,44 +570,37 @@ async fn index_contract_parallel(
                     marketplace::index_marketplace_events(&pool, &prov, &chain, from, to).await
                 }
             }
-        }));
+        });
+        batch_of.insert(task.id(), i);
+    }
+
+    // Collect every outcome, then keep the run of successful batches from the first on
+    let mut outcomes: Vec<Option<Result<(), String>>> = vec![None; batches.len()];
+    while let Some(joined) = tasks.join_next_with_id().await {
+        let (id, outcome) = match joined {
+            Ok((id, result)) => (id, result.map_err(|e| format!("failed: {:?}", e))),
+            Err(e) => (e.id(), Err(format!("panicked: {:?}", e))),
+        };
+        outcomes[batch_of[&id]] = Some(outcome);
     }
 
-    // Wait for all and find the highest completed block
     let mut highest_completed: Option<i64> = None;
-    let mut all_ok = true;
-    for (i, handle) in handles.into_iter().enumerate() {
-        match handle.await {
-            Ok(Ok(())) => {
-                let (_, to) = batches[i];
-                highest_completed = Some(to as i64);
-            }
-            Ok(Err(e)) => {
-                let (from, to) = batches[i];
+    for (&(from, to), outcome) in batches.iter().zip(outcomes) {
+        match outcome {
+            Some(Ok(())) => highest_completed = Some(to as i64),
+            Some(Err(failure)) => {
                 tracing::error!(
                     chain_id = chain.chain_id,
-                    "Batch {} {}-{} failed: {:?}", contract_name, from, to, e
+                    contract = contract_name,
+                    from_block = from,
+                    to_block = to,
+                    "Batch {} {}-{} {}", contract_name, from, to, failure
                 );
-                all_ok = false;
                 break; // Stop at first failure to keep sequential consistency
             }
-            Err(e) => {
-                let (from, to) = batches[i];
-                tracing::error!(
-                    chain_id = chain.chain_id,
-                    "Batch {} {}-{} panicked: {:?}", contract_name, from, to, e
-                );
-                all_ok = false;
-                break;
-            }
+            None => break,
         }
     }
 
-    // Only update to highest block if all preceding batches succeeded
-    if !all_ok {
-        // Find last successful sequential block
-        // highest_completed already has the last successful one before the break
-    }
-
     Ok(highest_completed)
 }
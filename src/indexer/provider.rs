@@ -1,8 +1,13 @@
+use std::collections::HashMap;
+use std::future::Future;
+
 use alloy::eips::BlockNumberOrTag;
-use alloy::primitives::Address;
+use alloy::primitives::{address, Address};
 use alloy::providers::{Provider, ProviderBuilder};
 use chrono::{DateTime, Utc};
 
+use crate::config::Env;
+
 /// Configuration for a single chain to index.
 #[derive(Debug, Clone)]
 pub struct ChainConfig {
@@ -12,10 +17,20 @@ pub struct ChainConfig {
     pub reputation_address: Address,
     /// MoltMarketplace contract address (None if not deployed yet).
     pub marketplace_address: Option<Address>,
-    /// Block number where the identity/reputation contracts were deployed. Indexer starts from here.
+    /// Block number where the identity contract was deployed. Indexer starts from here.
     pub start_block: u64,
+    /// Block number where the reputation contract was deployed (defaults to start_block).
+    pub reputation_start_block: Option<u64>,
     /// Block number where the marketplace contract was deployed (defaults to start_block).
     pub marketplace_start_block: Option<u64>,
+    /// Decode and log events without writing anything or advancing indexer_state (DRY_RUN).
+    pub dry_run: bool,
+    /// Leave block_timestamp NULL instead of fetching each block (SKIP_BLOCK_TIMESTAMPS);
+    /// reads fall back to created_at and the startup backfill fills them in.
+    pub skip_block_timestamps: bool,
+    /// Blocks to stay behind the RPC's latest block (CONFIRMATIONS), so blocks are indexed
+    /// only once this many more have been built on them. 0 indexes up to the tip.
+    pub confirmations: u64,
 }
 
 /// The alloy HTTP provider type returned by ProviderBuilder::new().connect_http().
@@ -66,66 +81,190 @@ pub async fn get_block_timestamp(
     Ok(dt)
 }
 
-/// Build all chain configs from environment variables with fallback defaults.
+/// Per-range block timestamp lookups for the index_*_events functions: one RPC call per
+/// distinct block, or none at all when the chain has `skip_block_timestamps`.
+pub struct BlockTimestamps {
+    skip: bool,
+    cache: HashMap<u64, DateTime<Utc>>,
+}
+
+impl BlockTimestamps {
+    pub fn new(chain: &ChainConfig) -> Self {
+        BlockTimestamps {
+            skip: chain.skip_block_timestamps,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Timestamp of `block_number`, None when skipped or when the lookup fails.
+    pub async fn get(&mut self, provider: &HttpProvider, block_number: u64) -> Option<DateTime<Utc>> {
+        self.get_with(block_number, |block| get_block_timestamp(provider, block)).await
+    }
+
+    /// `get` with the lookup supplied by the caller.
+    pub async fn get_with<F, Fut>(&mut self, block_number: u64, fetch: F) -> Option<DateTime<Utc>>
+    where
+        F: FnOnce(u64) -> Fut,
+        Fut: Future<Output = Result<DateTime<Utc>, Box<dyn std::error::Error + Send + Sync>>>,
+    {
+        if self.skip {
+            return None;
+        }
+        if let Some(ts) = self.cache.get(&block_number) {
+            return Some(*ts);
+        }
+        match fetch(block_number).await {
+            Ok(ts) => {
+                self.cache.insert(block_number, ts);
+                Some(ts)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to fetch timestamp for block {}: {:?}", block_number, e);
+                None
+            }
+        }
+    }
+}
+
+/// Variables that look like chain settings but aren't read, with the one that is.
+const MISSPELLED: &[(&str, &str)] = &[
+    ("MONAD_MAINNET_RPC_URL", "MONAD_MAINNET_RPC"),
+    ("MONAD_TESTNET_RPC_URL", "MONAD_TESTNET_RPC"),
+];
+
+/// A chain left out of `Config::chains` because one of its own settings is invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedChain {
+    pub chain_id: i32,
+    /// Names the offending variable
+    pub error: String,
+}
+
+/// A chain this service knows the contracts of, and the variables that configure it.
+struct KnownChain {
+    chain_id: i32,
+    /// INDEX_<name> toggles it; MONAD_<name>_{RPC,MARKETPLACE} configure it
+    name: &'static str,
+    default_rpc: &'static str,
+    identity_address: Address,
+    reputation_address: Address,
+    identity_start_block: u64,
+    reputation_start_block: u64,
+    marketplace_start_block: u64,
+}
+
+const KNOWN_CHAINS: &[KnownChain] = &[
+    KnownChain {
+        chain_id: 143,
+        name: "MAINNET",
+        default_rpc: "https://rpc.monad.xyz",
+        identity_address: address!("8004A169FB4a3325136EB29fA0ceB6D2e539a432"),
+        reputation_address: address!("8004BAa17C55a88189AE136b182e5fdA19dE9b63"),
+        identity_start_block: 52_952_790,
+        reputation_start_block: 52_952_790,
+        marketplace_start_block: 54_839_731,
+    },
+    KnownChain {
+        chain_id: 10143,
+        name: "TESTNET",
+        default_rpc: "https://testnet-rpc.monad.xyz",
+        identity_address: address!("8004A818BFB912233c491871b3d84c89A494BD9e"),
+        reputation_address: address!("8004B663056A597Dffe9eCcC1965A193B7388713"),
+        identity_start_block: 10_391_697,
+        reputation_start_block: 10_391_697,
+        marketplace_start_block: 12_269_357,
+    },
+];
+
+/// Build the chain configs for `Config` from environment variables with fallback defaults.
 ///
 /// Environment variables:
-/// - MONAD_MAINNET_RPC_URL (default: https://rpc.monad.xyz)
-/// - MONAD_TESTNET_RPC_URL (default: https://testnet-rpc.monad.xyz)
+/// - MONAD_MAINNET_RPC (default: https://rpc.monad.xyz)
+/// - MONAD_TESTNET_RPC (default: https://testnet-rpc.monad.xyz)
+/// - MONAD_{MAINNET,TESTNET}_MARKETPLACE — marketplace contract address (unset: none)
 /// - INDEX_MAINNET (default: "true") — set to "false" to skip mainnet
 /// - INDEX_TESTNET (default: "true") — set to "false" to skip testnet
-pub fn get_chain_configs() -> Vec<ChainConfig> {
-    let mut configs = Vec::new();
+/// - CHAIN_<id>_{IDENTITY,REPUTATION,MARKETPLACE}_START_BLOCK — override a contract's
+///   deployment block, e.g. `CHAIN_143_MARKETPLACE_START_BLOCK` after a redeploy
+/// - DRY_RUN (default: "false") — set to "true" to decode and log events without writing
+/// - SKIP_BLOCK_TIMESTAMPS (default: "false") — set to "true" to skip per-block timestamp RPC
+///   calls while indexing
+/// - CONFIRMATIONS (default: 0) — blocks to stay behind the chain tip, to avoid indexing
+///   blocks that may still be reorged
+///
+/// A malformed RPC URL, address or block number takes only its own chain out: that chain
+/// is returned in the skipped list and the others still load. Malformed shared settings
+/// (the flags above) are an error.
+pub(crate) fn chain_configs(env: &Env<'_>) -> Result<(Vec<ChainConfig>, Vec<SkippedChain>), String> {
+    for (wrong, right) in MISSPELLED {
+        if env.get(wrong).is_some() && env.get(right).is_none() {
+            return Err(format!("{} is not read; did you mean {}?", wrong, right));
+        }
+    }
+
+    let dry_run = env.flag("DRY_RUN", false)?;
+    let skip_block_timestamps = env.flag("SKIP_BLOCK_TIMESTAMPS", false)?;
+    let confirmations = env.parse("CONFIRMATIONS", 0)?;
 
-    let index_mainnet = std::env::var("INDEX_MAINNET")
-        .unwrap_or_else(|_| "true".to_string());
-    let index_testnet = std::env::var("INDEX_TESTNET")
-        .unwrap_or_else(|_| "true".to_string());
-
-    if index_mainnet != "false" {
-        let rpc_url = std::env::var("MONAD_MAINNET_RPC")
-            .unwrap_or_else(|_| "https://rpc.monad.xyz".to_string());
-
-        let marketplace_address = std::env::var("MONAD_MAINNET_MARKETPLACE")
-            .ok()
-            .and_then(|s| s.parse::<Address>().ok());
-
-        configs.push(ChainConfig {
-            chain_id: 143,
-            rpc_url,
-            identity_address: "0x8004A169FB4a3325136EB29fA0ceB6D2e539a432"
-                .parse::<Address>()
-                .expect("Invalid mainnet identity address"),
-            reputation_address: "0x8004BAa17C55a88189AE136b182e5fdA19dE9b63"
-                .parse::<Address>()
-                .expect("Invalid mainnet reputation address"),
-            marketplace_address,
-            start_block: 52_952_790,
-            marketplace_start_block: Some(54_839_731),
-        });
+    let mut configs = Vec::new();
+    let mut skipped = Vec::new();
+    for known in KNOWN_CHAINS {
+        if !env.flag(&format!("INDEX_{}", known.name), true)? {
+            continue;
+        }
+        match chain_config(env, known, dry_run, skip_block_timestamps, confirmations) {
+            Ok(config) => configs.push(config),
+            Err(error) => skipped.push(SkippedChain { chain_id: known.chain_id, error }),
+        }
     }
 
-    if index_testnet != "false" {
-        let rpc_url = std::env::var("MONAD_TESTNET_RPC")
-            .unwrap_or_else(|_| "https://testnet-rpc.monad.xyz".to_string());
-
-        let marketplace_address = std::env::var("MONAD_TESTNET_MARKETPLACE")
-            .ok()
-            .and_then(|s| s.parse::<Address>().ok());
-
-        configs.push(ChainConfig {
-            chain_id: 10143,
-            rpc_url,
-            identity_address: "0x8004A818BFB912233c491871b3d84c89A494BD9e"
-                .parse::<Address>()
-                .expect("Invalid testnet identity address"),
-            reputation_address: "0x8004B663056A597Dffe9eCcC1965A193B7388713"
-                .parse::<Address>()
-                .expect("Invalid testnet reputation address"),
-            marketplace_address,
-            start_block: 10_391_697,
-            marketplace_start_block: Some(12_269_357),
-        });
+    Ok((configs, skipped))
+}
+
+/// One known chain's config, or the first of its settings that is invalid.
+fn chain_config(
+    env: &Env<'_>,
+    known: &KnownChain,
+    dry_run: bool,
+    skip_block_timestamps: bool,
+    confirmations: u64,
+) -> Result<ChainConfig, String> {
+    let rpc_key = format!("MONAD_{}_RPC", known.name);
+    let rpc_url = env.get(&rpc_key).unwrap_or_else(|| known.default_rpc.to_string());
+    check_rpc_url(&rpc_key, &rpc_url)?;
+
+    Ok(ChainConfig {
+        chain_id: known.chain_id,
+        rpc_url,
+        identity_address: known.identity_address,
+        reputation_address: known.reputation_address,
+        marketplace_address: marketplace_address(env, &format!("MONAD_{}_MARKETPLACE", known.name))?,
+        start_block: start_block_override(env, known.chain_id, "IDENTITY", known.identity_start_block)?,
+        reputation_start_block: Some(start_block_override(env, known.chain_id, "REPUTATION", known.reputation_start_block)?),
+        marketplace_start_block: Some(start_block_override(env, known.chain_id, "MARKETPLACE", known.marketplace_start_block)?),
+        dry_run,
+        skip_block_timestamps,
+        confirmations,
+    })
+}
+
+/// `create_provider` needs an http(s) URL. The value itself is left out of the error: RPC
+/// URLs often embed a provider key.
+fn check_rpc_url(key: &str, raw: &str) -> Result<(), String> {
+    match raw.parse::<reqwest::Url>() {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+        _ => Err(format!("{} must be an http(s) URL", key)),
     }
+}
+
+/// The marketplace contract address in `key`, None when unset.
+fn marketplace_address(env: &Env<'_>, key: &str) -> Result<Option<Address>, String> {
+    env.get(key)
+        .map(|raw| raw.parse::<Address>().map_err(|_| format!("{} must be a 0x address, got {:?}", key, raw)))
+        .transpose()
+}
 
-    configs
+/// Read `CHAIN_<chain_id>_<contract>_START_BLOCK`, falling back to `default` when unset.
+fn start_block_override(env: &Env<'_>, chain_id: i32, contract: &str, default: u64) -> Result<u64, String> {
+    env.parse(&format!("CHAIN_{}_{}_START_BLOCK", chain_id, contract), default)
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeSet;
 use std::str::FromStr;
 
 use alloy::providers::Provider;
@@ -6,12 +6,12 @@ use alloy::rpc::types::Filter;
 use alloy::sol;
 use alloy::sol_types::SolEvent;
 use bigdecimal::BigDecimal;
-use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 
-use super::provider::{self, ChainConfig, HttpProvider};
+use super::{log_dry_run, log_dry_run_batch};
+use super::provider::{BlockTimestamps, ChainConfig, HttpProvider};
 use crate::db;
-use crate::types::{NewActivity, NewFeedback as NewFeedbackDb};
+use crate::types::{NewActivity, NewFeedback as NewFeedbackDb, NewFeedbackResponse};
 
 // Load ReputationRegistry ABI from official erc-8004 contracts.
 // The generated `NewFeedback` struct name matches the Solidity event name.
@@ -42,6 +42,8 @@ pub async fn index_reputation_events(
 
     tracing::debug!(
         chain_id = chain.chain_id,
+        contract = "reputation",
+        log_count = logs.len(),
         "Fetched {} reputation logs for blocks {} - {}",
         logs.len(),
         from_block,
@@ -49,7 +51,13 @@ pub async fn index_reputation_events(
     );
 
     // Cache block timestamps to avoid duplicate RPC calls for the same block
-    let mut block_ts_cache: HashMap<u64, DateTime<Utc>> = HashMap::new();
+    let mut block_timestamps = BlockTimestamps::new(chain);
+
+    // Rows are accumulated for the whole range and written in one transaction at the end
+    let mut feedbacks: Vec<NewFeedbackDb> = Vec::new();
+    let mut revocations: Vec<(i64, String, i64)> = Vec::new();
+    let mut responses: Vec<NewFeedbackResponse> = Vec::new();
+    let mut activities: Vec<NewActivity> = Vec::new();
 
     for log in logs {
         let block_num_raw = log.block_number.unwrap_or(0);
@@ -60,21 +68,8 @@ pub async fn index_reputation_events(
             .unwrap_or_default();
         let log_index = log.log_index.unwrap_or(0) as i32;
 
-        // Fetch block timestamp (cached per block)
-        let block_timestamp = if let Some(ts) = block_ts_cache.get(&block_num_raw) {
-            Some(*ts)
-        } else {
-            match provider::get_block_timestamp(provider, block_num_raw).await {
-                Ok(ts) => {
-                    block_ts_cache.insert(block_num_raw, ts);
-                    Some(ts)
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to fetch timestamp for block {}: {:?}", block_num_raw, e);
-                    None
-                }
-            }
-        };
+        // Fetch block timestamp (cached per block, skipped with SKIP_BLOCK_TIMESTAMPS)
+        let block_timestamp = block_timestamps.get(provider, block_num_raw).await;
 
         let topic0 = match log.topic0() {
             Some(t) => *t,
@@ -95,7 +90,8 @@ pub async fn index_reputation_events(
                     let client = format!("{:#x}", event.clientAddress);
                     let feedback_index = event.feedbackIndex as i64;
                     let value_raw = event.value;
-                    let value_decimals = event.valueDecimals as i32;
+                    // uint8 in the ABI, so always present; 0 means an integer value
+                    let value_decimals = i32::from(event.valueDecimals);
                     let tag1 = event.tag1.clone();
                     let tag2 = event.tag2.clone();
                     let endpoint = event.endpoint.clone();
@@ -148,18 +144,26 @@ pub async fn index_reputation_events(
                         block_timestamp,
                         tx_hash: tx_hash.clone(),
                     };
-                    if let Err(e) = db::feedbacks::insert_feedback(pool, &new_feedback).await {
-                        tracing::error!(
-                            "Failed to insert feedback for agent {}: {:?}",
-                            agent_id,
-                            e
-                        );
-                    }
+                    feedbacks.push(new_feedback);
 
                     // Compute normalized value for activity display
                     let normalized_value = value_raw as f64
                         / 10f64.powi(value_decimals);
 
+                    // Stored as-is, but left out of every reputation aggregate
+                    let max_score = db::feedbacks::max_reasonable_score();
+                    if !db::feedbacks::is_reasonable_score(normalized_value, max_score) {
+                        tracing::warn!(
+                            chain_id = chain.chain_id,
+                            agent_id = agent_id,
+                            "Feedback #{} from {} has value {} beyond MAX_REASONABLE_SCORE ({}); excluded from reputation",
+                            feedback_index,
+                            client,
+                            normalized_value,
+                            max_score
+                        );
+                    }
+
                     // Insert activity
                     let activity = NewActivity {
                         agent_id,
@@ -180,9 +184,7 @@ pub async fn index_reputation_events(
                         tx_hash: tx_hash.clone(),
                         log_index,
                     };
-                    if let Err(e) = db::activity::insert_activity(pool, &activity).await {
-                        tracing::error!("Failed to insert NewFeedback activity: {:?}", e);
-                    }
+                    activities.push(activity);
                 }
                 Err(e) => {
                     tracing::error!(
@@ -209,21 +211,9 @@ pub async fn index_reputation_events(
                         client
                     );
 
-                    // Revoke the feedback
-                    if let Err(e) = db::feedbacks::revoke_feedback(
-                        pool,
-                        agent_id,
-                        chain.chain_id,
-                        feedback_index,
-                    )
-                    .await
-                    {
-                        tracing::error!(
-                            "Failed to revoke feedback for agent {}: {:?}",
-                            agent_id,
-                            e
-                        );
-                    }
+                    // Revoke the feedback (applied after this range's inserts, so a
+                    // feedback given and revoked within the range ends up revoked)
+                    revocations.push((agent_id, client.clone(), feedback_index));
 
                     // Insert activity
                     let activity = NewActivity {
@@ -239,9 +229,7 @@ pub async fn index_reputation_events(
                         tx_hash: tx_hash.clone(),
                         log_index,
                     };
-                    if let Err(e) = db::activity::insert_activity(pool, &activity).await {
-                        tracing::error!("Failed to insert FeedbackRevoked activity: {:?}", e);
-                    }
+                    activities.push(activity);
                 }
                 Err(e) => {
                     tracing::error!(
@@ -271,24 +259,16 @@ pub async fn index_reputation_events(
                     );
 
                     // Insert feedback response
-                    if let Err(e) = insert_feedback_response(
-                        pool,
+                    responses.push(NewFeedbackResponse {
                         feedback_index,
                         agent_id,
-                        chain.chain_id,
-                        &response_uri,
+                        chain_id: chain.chain_id,
+                        response_uri: response_uri.clone(),
                         block_number,
                         block_timestamp,
-                        &tx_hash,
-                    )
-                    .await
-                    {
-                        tracing::error!(
-                            "Failed to insert feedback response for agent {}: {:?}",
-                            agent_id,
-                            e
-                        );
-                    }
+                        tx_hash: tx_hash.clone(),
+                        log_index,
+                    });
 
                     // Insert activity
                     let activity = NewActivity {
@@ -307,9 +287,7 @@ pub async fn index_reputation_events(
                         tx_hash: tx_hash.clone(),
                         log_index,
                     };
-                    if let Err(e) = db::activity::insert_activity(pool, &activity).await {
-                        tracing::error!("Failed to insert ResponseAppended activity: {:?}", e);
-                    }
+                    activities.push(activity);
                 }
                 Err(e) => {
                     tracing::error!(
@@ -322,35 +300,31 @@ pub async fn index_reputation_events(
         }
     }
 
-    Ok(())
-}
+    // One statement per table for the whole range, in log order: inserts before revocations
+    let touched: Vec<i64> = feedbacks
+        .iter()
+        .map(|f| f.agent_id)
+        .chain(revocations.iter().map(|(agent_id, _, _)| *agent_id))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
 
-/// Insert a feedback response into the feedback_responses table.
-async fn insert_feedback_response(
-    pool: &PgPool,
-    feedback_index: i64,
-    agent_id: i64,
-    chain_id: i32,
-    response_uri: &str,
-    block_number: i64,
-    block_timestamp: Option<DateTime<Utc>>,
-    tx_hash: &str,
-) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r#"
-        INSERT INTO feedback_responses (feedback_id, agent_id, chain_id, response_uri, block_number, block_timestamp, tx_hash)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
-        "#,
-    )
-    .bind(feedback_index)
-    .bind(agent_id)
-    .bind(chain_id)
-    .bind(response_uri)
-    .bind(block_number)
-    .bind(block_timestamp)
-    .bind(tx_hash)
-    .execute(pool)
-    .await?;
+    if chain.dry_run {
+        log_dry_run_batch(chain, "insert_feedbacks", &feedbacks);
+        log_dry_run_batch(chain, "revoke_feedbacks", &revocations);
+        log_dry_run_batch(chain, "insert_feedback_responses", &responses);
+        log_dry_run_batch(chain, "insert_activities", &activities);
+        log_dry_run(chain, "refresh_agent_stats", &touched);
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await?;
+    db::feedbacks::insert_feedbacks(&mut tx, &feedbacks).await?;
+    db::feedbacks::revoke_feedbacks(&mut tx, chain.chain_id, &revocations).await?;
+    db::feedbacks::insert_feedback_responses(&mut tx, &responses).await?;
+    db::activity::insert_activities(&mut *tx, &activities).await?;
+    db::feedbacks::refresh_agent_stats(&mut tx, chain.chain_id, &touched).await?;
+    tx.commit().await?;
 
     Ok(())
 }
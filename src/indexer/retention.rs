@@ -0,0 +1,63 @@
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+
+use crate::db;
+
+/// Rows moved per archival transaction. Small enough to keep lock time short on activity_log.
+pub const ARCHIVE_BATCH_SIZE: i64 = 5_000;
+
+/// How often the retention task looks for rows past the cutoff (in seconds).
+pub const RETENTION_INTERVAL_SECS: u64 = 3600;
+
+/// Pause between consecutive batches so archival never monopolizes the pool (in milliseconds).
+const BATCH_PAUSE_MS: u64 = 100;
+
+/// Parse ACTIVITY_RETENTION_DAYS. Unset, empty or `0` disables archival; anything but a
+/// whole number of days is an error, so a typo never starts (or silently stops) moving rows.
+pub fn retention_days(raw: Option<&str>) -> Result<Option<i64>, String> {
+    match raw.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => match s.parse::<i64>() {
+            Ok(0) => Ok(None),
+            Ok(days) if days > 0 => Ok(Some(days)),
+            _ => Err(format!("ACTIVITY_RETENTION_DAYS must be a whole number of days (0 to disable), got {:?}", s)),
+        },
+    }
+}
+
+/// Move activity_log rows older than `retention_days` into activity_log_archive, forever.
+/// Each pass drains everything past the cutoff in ARCHIVE_BATCH_SIZE transactions.
+pub async fn run_activity_retention(pool: PgPool, retention_days: i64) {
+    tracing::info!(
+        "Activity retention enabled: archiving activity older than {} day(s) every {}s",
+        retention_days,
+        RETENTION_INTERVAL_SECS
+    );
+
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(RETENTION_INTERVAL_SECS));
+    loop {
+        interval.tick().await;
+
+        let cutoff = Utc::now() - Duration::days(retention_days);
+        let mut archived = 0u64;
+        loop {
+            match db::activity::archive_activities_before(&pool, cutoff, ARCHIVE_BATCH_SIZE).await {
+                Ok(moved) => {
+                    archived += moved;
+                    if moved < ARCHIVE_BATCH_SIZE as u64 {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Activity archival batch failed: {:?}", e);
+                    break;
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(BATCH_PAUSE_MS)).await;
+        }
+
+        if archived > 0 {
+            tracing::info!("Archived {} activity rows older than {}", archived, cutoff);
+        }
+    }
+}
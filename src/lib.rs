@@ -0,0 +1,96 @@
+// The API router, database access and indexer, shared by the server binary (src/main.rs)
+// and the HTTP integration tests (tests/http_tests.rs).
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use axum::{body::Body, http::Request, middleware, routing::get, Router};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tower_http::trace::TraceLayer;
+
+pub mod api;
+pub mod config;
+pub mod db;
+pub mod health;
+pub mod indexer;
+pub mod logging;
+pub mod metrics;
+pub mod shutdown;
+pub mod startup;
+pub mod types;
+
+#[derive(Clone)]
+pub struct AppState {
+    /// Settings read from the environment at startup.
+    pub config: Arc<config::Config>,
+    /// API reads: the API pool, plus the read replica when DATABASE_READ_URL is set.
+    pub db: db::replica::Db,
+    /// Startup progress (migrations, backfills), reported by /health/ready.
+    pub startup: Arc<startup::Startup>,
+    /// Ids of the configured chains (`Config::chains`); scoped list endpoints reject others.
+    pub chain_ids: Arc<[i32]>,
+    /// Per-chain indexer enable flags, toggled via the admin API.
+    pub indexer_flags: indexer::ChainFlags,
+    /// Indexer-wide pause switch, toggled via the admin API; overrides the per-chain flags.
+    pub indexer_paused: Arc<AtomicBool>,
+    /// Whether the indexer was started (ENABLE_INDEXER=true).
+    pub indexer_enabled: bool,
+    /// API and indexer pools, with health stats for /metrics and /api/indexer/status.
+    pub pools: Arc<db::pool::PoolMonitor>,
+    /// Per-route request counts and latencies, exported on /metrics.
+    pub http_metrics: Arc<metrics::HttpMetrics>,
+    /// Bearer key for /api/admin (ADMIN_API_KEY); None disables the admin routes.
+    pub admin_api_key: Option<Arc<str>>,
+    /// Short-lived cache of the /api/stats payload (STATS_CACHE_SECS).
+    pub stats_cache: Arc<api::stats::StatsCache>,
+    /// Background metadata fetches queued by the admin API.
+    pub metadata_queue: Arc<indexer::metadata::MetadataQueue>,
+}
+
+/// Build the whole HTTP app: /health, /metrics and /api, with the service-wide layers.
+pub fn app(state: AppState) -> Router {
+    // CORS_ORIGINS, or any origin when unset
+    let origins = if state.config.cors_origins.is_empty() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(state.config.cors_origins.iter().cloned())
+    };
+    let cors = CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(Any)
+        .allow_headers(Any);
+
+    let router = Router::new()
+        .merge(health::router())
+        .route("/metrics", get(metrics::metrics))
+        .nest("/api", api::router(&state));
+    // Inside the metrics layer, so timed-out requests are still counted
+    api::limits::with_request_timeout(router, state.config.request_timeout)
+        .layer(middleware::from_fn_with_state(state.clone(), metrics::track_http))
+        .layer(middleware::from_fn(api::version::service_version_header))
+        // gzip/br per Accept-Encoding. The default predicate leaves text/event-stream, images
+        // and bodies under 32 bytes alone, so streaming routes stay unbuffered. Inside CORS and
+        // the trace span, so both see (and log) the encoded response.
+        .layer(CompressionLayer::new().gzip(true).br(true))
+        .layer(cors)
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(middleware::from_fn(api::request_id::request_id))
+        .with_state(state)
+}
+
+/// The per-request trace span, carrying the `request_id` set by the outer middleware so
+/// every log line for the request can be tied back to it.
+fn request_span(req: &Request<Body>) -> tracing::Span {
+    let request_id = req
+        .extensions()
+        .get::<api::request_id::RequestId>()
+        .map(|id| id.0.as_str())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        method = %req.method(),
+        uri = %req.uri(),
+        request_id = %request_id,
+    )
+}
@@ -1,22 +1,13 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Router};
-use sqlx::postgres::PgPoolOptions;
-use tower_http::cors::{Any, CorsLayer};
-use tower_http::trace::TraceLayer;
+use tokio_util::sync::CancellationToken;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-mod api;
-mod db;
-mod indexer;
-mod types;
-
-#[derive(Clone)]
-pub struct AppState {
-    pub pool: sqlx::PgPool,
-    pub ready: Arc<AtomicBool>,
-}
+use molt_marketplace_backend::config::Config;
+use molt_marketplace_backend::logging::{self, LogFormat};
+use molt_marketplace_backend::startup::{self, Startup};
+use molt_marketplace_backend::{api, app, db, indexer, shutdown, AppState};
 
 #[tokio::main]
 async fn main() {
@@ -25,106 +16,203 @@ async fn main() {
     // Load .env file
     dotenvy::dotenv().ok();
 
+    // Read the whole configuration up front; a bad value stops startup here
+    let config = match Config::from_env() {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            // The subscriber isn't installed yet, so this can't go through tracing
+            eprintln!("Invalid configuration: {e}");
+            std::process::exit(1);
+        }
+    };
+    config.limits.clone().install();
+
     // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "molt_marketplace_backend=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    // Create pool lazily — no actual connection yet, server can start immediately
-    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .acquire_timeout(std::time::Duration::from_secs(30))
-        .connect_lazy(&database_url)
-        .expect("Failed to create connection pool");
-
-    tracing::info!("Connection pool created (lazy, no connection yet)");
-
-    let ready = Arc::new(AtomicBool::new(false));
+    let subscriber = tracing_subscriber::registry().with(
+        tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| "molt_marketplace_backend=debug,tower_http=debug".into()),
+    );
+    match config.log_format {
+        LogFormat::Json => subscriber
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .event_format(logging::JsonLines)
+                    .fmt_fields(logging::JsonFields),
+            )
+            .init(),
+        LogFormat::Pretty => subscriber.with(tracing_subscriber::fmt::layer()).init(),
+    }
+    config.log_summary();
+
+    // Create pools lazily — no actual connection yet, server can start immediately.
+    // The indexer gets its own pool so catch-up load can't starve API requests.
+    let db_config = &config.db;
+    let pool = db_config
+        .api
+        .pool_options()
+        .connect_lazy(&config.database_url)
+        .expect("Failed to create API connection pool");
+    let indexer_pool = db_config
+        .indexer
+        .pool_options()
+        .connect_lazy(&config.database_url)
+        .expect("Failed to create indexer connection pool");
+
+    // Optional read replica for API reads, with the API pool settings
+    let read_pool = config.database_read_url.as_deref().map(|url| {
+        db_config
+            .api
+            .pool_options()
+            .connect_lazy(url)
+            .expect("Failed to create read replica connection pool")
+    });
+
+    tracing::info!(
+        "Connection pools created (lazy, no connection yet) | api={:?} | indexer={:?} | read_replica={}",
+        db_config.api,
+        db_config.indexer,
+        read_pool.is_some()
+    );
+
+    let pools = Arc::new(db::pool::PoolMonitor::new(pool.clone(), indexer_pool.clone(), read_pool.clone()));
+    let probe_pools = pools.clone();
+    tokio::spawn(async move { probe_pools.run_probe().await });
+
+    let startup = Arc::new(Startup::default());
+    let indexer_flags = indexer::chain_flags(&config.chains);
+    let indexer_paused = Arc::new(AtomicBool::new(false));
+    let enable_indexer = config.enable_indexer;
 
     // Build application state
     let state = AppState {
-        pool: pool.clone(),
-        ready: ready.clone(),
+        config: config.clone(),
+        db: db::replica::Db::new(pool, read_pool),
+        startup: startup.clone(),
+        chain_ids: config.chain_ids().into(),
+        indexer_flags: indexer_flags.clone(),
+        indexer_paused: indexer_paused.clone(),
+        indexer_enabled: enable_indexer,
+        pools,
+        http_metrics: Default::default(),
+        admin_api_key: config.admin_api_key.clone(),
+        stats_cache: Arc::new(api::stats::StatsCache::new(config.stats_cache_ttl)),
+        metadata_queue: indexer::metadata::MetadataQueue::start(
+            indexer_pool.clone(),
+            indexer::metadata::METADATA_QUEUE_CAPACITY,
+            indexer::metadata::METADATA_FETCH_CONCURRENCY,
+        ),
     };
 
-    // Set up CORS (allow all origins for development)
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
-
     // Build router
-    let app = Router::new()
-        .route("/health", get(health_check))
-        .nest("/api", api::router())
-        .layer(cors)
-        .layer(TraceLayer::new_for_http())
-        .with_state(state);
+    let app = app(state);
 
     // Start server FIRST (Railway injects PORT env var)
-    let port = std::env::var("PORT").unwrap_or_else(|_| "3001".to_string());
-    let addr = format!("0.0.0.0:{port}");
+    let addr = format!("0.0.0.0:{}", config.port);
     let listener = tokio::net::TcpListener::bind(&addr)
         .await
         .expect("Failed to bind to port");
 
     tracing::info!("Server listening on {addr}");
 
-    // Run migrations + indexer in background so the server accepts connections immediately
-    let enable_indexer = std::env::var("ENABLE_INDEXER").unwrap_or_default() == "true";
-    let bg_pool = pool.clone();
-    tokio::spawn(async move {
-        // Retry migrations up to 5 times with backoff (handles connection pool contention during deploys)
-        let max_retries = 5;
-        for attempt in 1..=max_retries {
-            tracing::info!("Running migrations (attempt {}/{})", attempt, max_retries);
-            match sqlx::migrate!("./migrations").run(&bg_pool).await {
-                Ok(_) => {
-                    tracing::info!("Migrations applied successfully");
-                    break;
-                }
-                Err(e) if attempt < max_retries => {
-                    tracing::warn!("Migration attempt {} failed: {:?} — retrying in {}s", attempt, e, attempt * 5);
-                    tokio::time::sleep(std::time::Duration::from_secs(attempt as u64 * 5)).await;
-                }
-                Err(e) => {
-                    tracing::error!("Migration failed after {} attempts: {:?}", max_retries, e);
-                    return;
-                }
-            }
+    // SIGTERM / ctrl-c cancels this token: the server drains, the indexer abandons its cycle
+    let shutdown_token = CancellationToken::new();
+    tokio::spawn(shutdown::cancel_on_signal(shutdown_token.clone()));
+    let grace = config.shutdown_grace;
+
+    // The indexer starts now but waits for `startup` to be Ready, set once migrations and
+    // backfills are done, before touching the database; it never starts if startup fails
+    let indexer_task = if enable_indexer {
+        tracing::info!("Indexer background task started");
+        Some(tokio::spawn(indexer::run_indexer(
+            indexer_pool.clone(),
+            config.chains.clone(),
+            config.marketplace_config_sync_interval,
+            indexer_flags,
+            indexer_paused,
+            startup.clone(),
+            shutdown_token.clone(),
+        )))
+    } else {
+        tracing::info!("Indexer disabled (set ENABLE_INDEXER=true to enable)");
+        None
+    };
+
+    // Run migrations and backfills in background so the server accepts connections immediately
+    let bg_pool = indexer_pool;
+    let bg_shutdown = shutdown_token.clone();
+    let server_startup = startup.clone();
+    let bg_config = config.clone();
+    let background = tokio::spawn(async move {
+        // A failure here leaves /health/ready at 503 `failed` so the orchestrator restarts us
+        if !startup::migrate(&bg_pool, &startup, startup::MIGRATION_ATTEMPTS, startup::MIGRATION_BACKOFF).await {
+            return;
         }
 
-        // Backfill block_timestamp for existing rows (one-time, idempotent)
-        indexer::backfill::backfill_block_timestamps(&bg_pool).await;
+        // Backfill block_timestamp for existing rows (one-time, idempotent). Spawned so a
+        // panic fails startup instead of silently ending this task.
+        let backfill = tokio::spawn({
+            let (pool, chains) = (bg_pool.clone(), bg_config.chains.clone());
+            async move { indexer::backfill::backfill_block_timestamps(&pool, &chains).await }
+        });
+        if let Err(e) = backfill.await {
+            startup.fail(format!("block timestamp backfill did not finish: {}", e));
+            return;
+        }
 
-        ready.store(true, Ordering::Release);
-        tracing::info!("Database ready — accepting API requests");
+        // Optional full rebuild of the agent_stats reputation cache (backfill / repair)
+        if bg_config.rebuild_agent_stats {
+            match db::feedbacks::rebuild_agent_stats(&bg_pool, None).await {
+                Ok(rows) => tracing::info!("Rebuilt agent_stats ({} agents)", rows),
+                Err(e) => tracing::error!("Failed to rebuild agent_stats: {:?}", e),
+            }
+        }
 
-        // Start indexer after migrations are done
+        // Don't report ready (or start the indexer) once shutdown has begun
+        if bg_shutdown.is_cancelled() {
+            return;
+        }
+        startup.set_ready();
+        tracing::info!("Database ready — accepting API requests");
         if enable_indexer {
-            tracing::info!("Indexer background task started");
-            indexer::run_indexer(bg_pool).await;
-        } else {
-            tracing::info!("Indexer disabled (set ENABLE_INDEXER=true to enable)");
+            tracing::info!("Migrations and backfill done — signalled the indexer to start");
         }
-    });
 
-    // Run the API server (blocks until shutdown)
-    axum::serve(listener, app)
-        .await
-        .expect("Server failed");
-}
+        // Optional activity_log archival, independent of the indexer
+        if let Some(days) = bg_config.activity_retention_days {
+            tokio::spawn(
+                bg_shutdown
+                    .clone()
+                    .run_until_cancelled_owned(indexer::retention::run_activity_retention(bg_pool.clone(), days)),
+            );
+        }
 
-async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
-    if state.ready.load(Ordering::Acquire) {
-        (StatusCode::OK, "OK")
-    } else {
-        // Return 200 so Railway knows the container is alive, but indicate not fully ready
-        (StatusCode::OK, "starting")
-    }
+        // Optional check of feedback_uri content against the on-chain feedback_hash
+        if bg_config.verify_feedback_content {
+            tokio::spawn(
+                bg_shutdown
+                    .clone()
+                    .run_until_cancelled_owned(indexer::feedback_content::run_feedback_content_verification(bg_pool.clone())),
+            );
+        }
+    });
+
+    // Run the API server until shutdown; meanwhile the background task gets the same grace
+    // period to stop (the indexer saves its cursors), after which it is abandoned
+    let (served, _) = tokio::join!(
+        shutdown::serve(listener, app, server_startup, shutdown_token.clone(), grace),
+        async {
+            shutdown_token.cancelled().await;
+            let stopped = async {
+                let _ = background.await;
+                if let Some(indexer_task) = indexer_task {
+                    let _ = indexer_task.await;
+                }
+            };
+            if tokio::time::timeout(grace, stopped).await.is_err() {
+                tracing::warn!("Background tasks did not stop within {}s", grace.as_secs());
+            }
+        },
+    );
+    served.expect("Server failed");
+    tracing::info!("Shutdown complete");
 }
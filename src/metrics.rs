@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::indexer;
+use crate::types::PoolStats;
+use crate::AppState;
+
+/// GET /metrics — Prometheus text exposition of pool health, HTTP traffic and indexer flags
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let mut body = render_pool_metrics(&state.pools.stats());
+    body.push_str(&state.http_metrics.render());
+
+    let _ = writeln!(body, "# HELP molt_indexer_chain_enabled Whether indexing is enabled for the chain (1) or paused (0)");
+    let _ = writeln!(body, "# TYPE molt_indexer_chain_enabled gauge");
+    let mut chain_ids: Vec<i32> = state.indexer_flags.keys().copied().collect();
+    chain_ids.sort_unstable();
+    for chain_id in chain_ids {
+        let enabled = indexer::chain_enabled(&state.indexer_flags, chain_id);
+        let _ = writeln!(body, "molt_indexer_chain_enabled{{chain_id=\"{}\"}} {}", chain_id, enabled as u8);
+    }
+    let _ = writeln!(body, "# HELP molt_indexer_paused Whether the whole indexer is paused (1) or running (0)");
+    let _ = writeln!(body, "# TYPE molt_indexer_paused gauge");
+    let _ = writeln!(body, "molt_indexer_paused {}", state.indexer_paused.load(Ordering::Acquire) as u8);
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+/// One exported pool series: name, Prometheus type, help text, and how to read the value.
+type PoolSeries = (&'static str, &'static str, &'static str, fn(&PoolStats) -> f64);
+
+/// Render pool stats as Prometheus gauges/counters labeled by pool name.
+fn render_pool_metrics(pools: &[PoolStats]) -> String {
+    let mut out = String::new();
+    let series: [PoolSeries; 7] = [
+        ("molt_db_pool_max_connections", "gauge", "Configured maximum connections", |p| p.max_connections as f64),
+        ("molt_db_pool_connections", "gauge", "Open connections (idle + in use)", |p| p.size as f64),
+        ("molt_db_pool_idle_connections", "gauge", "Idle connections", |p| p.idle as f64),
+        ("molt_db_pool_in_use_connections", "gauge", "Connections checked out", |p| p.in_use as f64),
+        ("molt_db_pool_acquire_wait_seconds", "gauge", "Wait of the most recent acquire probe", |p| p.acquire_wait_ms / 1000.0),
+        ("molt_db_pool_acquire_wait_max_seconds", "gauge", "Longest acquire probe wait since startup", |p| p.acquire_wait_max_ms / 1000.0),
+        ("molt_db_pool_acquire_timeouts_total", "counter", "Acquire probes that timed out", |p| p.acquire_timeouts as f64),
+    ];
+    for (name, kind, help, value) in series {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for pool in pools {
+            let _ = writeln!(out, "{}{{pool=\"{}\"}} {}", name, pool.pool, value(pool));
+        }
+    }
+    out
+}
+
+/// Upper bounds (seconds) of the request duration histogram buckets, Prometheus' defaults.
+const DURATION_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Route label of requests no route matched (404s), so unknown paths share one series.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Per-route HTTP request counts, durations and in-flight requests, recorded by `track_http`.
+#[derive(Default)]
+pub struct HttpMetrics {
+    /// Keyed by (route template, status class)
+    requests: Mutex<BTreeMap<(String, &'static str), RequestSeries>>,
+    in_flight: Mutex<BTreeMap<String, i64>>,
+}
+
+#[derive(Default)]
+struct RequestSeries {
+    count: u64,
+    duration_sum: f64,
+    /// Non-cumulative: requests whose duration fell in each DURATION_BUCKETS slot
+    buckets: [u64; DURATION_BUCKETS.len()],
+}
+
+/// Decrements a route's in-flight gauge when dropped, so requests whose client went away
+/// mid-handler are not counted forever.
+struct InFlight {
+    metrics: Arc<HttpMetrics>,
+    route: String,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Some(n) = self.metrics.in_flight.lock().unwrap().get_mut(&self.route) {
+            *n -= 1;
+        }
+    }
+}
+
+impl HttpMetrics {
+    fn start(self: &Arc<Self>, route: &str) -> InFlight {
+        *self.in_flight.lock().unwrap().entry(route.to_string()).or_default() += 1;
+        InFlight { metrics: self.clone(), route: route.to_string() }
+    }
+
+    fn record(&self, route: &str, status: StatusCode, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        let mut requests = self.requests.lock().unwrap();
+        let series = requests.entry((route.to_string(), status_class(status))).or_default();
+        series.count += 1;
+        series.duration_sum += secs;
+        if let Some(i) = DURATION_BUCKETS.iter().position(|le| secs <= *le) {
+            series.buckets[i] += 1;
+        }
+    }
+
+    /// The request counter, duration histogram and in-flight gauge in Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let requests = self.requests.lock().unwrap();
+
+        let _ = writeln!(out, "# HELP molt_http_requests_total HTTP requests served, by route template and status class");
+        let _ = writeln!(out, "# TYPE molt_http_requests_total counter");
+        for ((route, status), series) in requests.iter() {
+            let _ = writeln!(out, "molt_http_requests_total{{route=\"{}\",status=\"{}\"}} {}", route, status, series.count);
+        }
+
+        let _ = writeln!(out, "# HELP molt_http_request_duration_seconds HTTP request duration, by route template and status class");
+        let _ = writeln!(out, "# TYPE molt_http_request_duration_seconds histogram");
+        for ((route, status), series) in requests.iter() {
+            let labels = format!("route=\"{}\",status=\"{}\"", route, status);
+            let mut cumulative = 0;
+            for (le, n) in DURATION_BUCKETS.iter().zip(series.buckets) {
+                cumulative += n;
+                let _ = writeln!(out, "molt_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, le, cumulative);
+            }
+            let _ = writeln!(out, "molt_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, series.count);
+            let _ = writeln!(out, "molt_http_request_duration_seconds_sum{{{}}} {}", labels, series.duration_sum);
+            let _ = writeln!(out, "molt_http_request_duration_seconds_count{{{}}} {}", labels, series.count);
+        }
+        drop(requests);
+
+        let _ = writeln!(out, "# HELP molt_http_requests_in_flight HTTP requests being served, by route template");
+        let _ = writeln!(out, "# TYPE molt_http_requests_in_flight gauge");
+        for (route, n) in self.in_flight.lock().unwrap().iter() {
+            let _ = writeln!(out, "molt_http_requests_in_flight{{route=\"{}\"}} {}", route, n);
+        }
+        out
+    }
+}
+
+/// `2xx`, `4xx`, ... for the status label.
+fn status_class(status: StatusCode) -> &'static str {
+    match status.as_u16() {
+        100..=199 => "1xx",
+        200..=299 => "2xx",
+        300..=399 => "3xx",
+        400..=499 => "4xx",
+        _ => "5xx",
+    }
+}
+
+/// Record every request in `AppState::http_metrics`. The route label is axum's `MatchedPath`
+/// (`/api/agents/{id}`, not `/api/agents/143-1`), so label cardinality stays bounded by the
+/// router; requests no route matched share the `unmatched` label.
+pub async fn track_http(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE, |path| path.as_str())
+        .to_string();
+
+    let _in_flight = state.http_metrics.start(&route);
+    let started = Instant::now();
+    let response = next.run(req).await;
+    state.http_metrics.record(&route, response.status(), started.elapsed());
+    response
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Router;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::startup::Startup;
+
+/// Default time in-flight requests and the indexer get to finish after SIGTERM (in seconds).
+/// Override with SHUTDOWN_GRACE_SECS; Railway sends SIGKILL 30s after SIGTERM by default.
+pub const DEFAULT_GRACE_SECS: u64 = 25;
+
+/// Parse SHUTDOWN_GRACE_SECS; unset uses DEFAULT_GRACE_SECS.
+pub fn grace_period(raw: Option<&str>) -> Result<Duration, String> {
+    let secs = match raw.map(str::trim) {
+        None | Some("") => DEFAULT_GRACE_SECS,
+        Some(s) => s
+            .parse::<u64>()
+            .map_err(|_| format!("SHUTDOWN_GRACE_SECS must be a whole number of seconds, got {:?}", s))?,
+    };
+    Ok(Duration::from_secs(secs))
+}
+
+/// Cancel `shutdown` on SIGTERM or ctrl-c.
+pub async fn cancel_on_signal(shutdown: CancellationToken) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for ctrl-c: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {:?}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received ctrl-c"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+    shutdown.cancel();
+}
+
+/// Serve `app` until `shutdown` is cancelled. From then on `startup` is back to Starting, no new
+/// connections are accepted, and in-flight requests get up to `grace` to complete before the
+/// remaining connections are dropped.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    startup: Arc<Startup>,
+    shutdown: CancellationToken,
+    grace: Duration,
+) -> std::io::Result<()> {
+    let draining = shutdown.clone();
+    let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+        draining.cancelled().await;
+        startup.set_starting();
+        tracing::info!("Shutting down: draining in-flight requests for up to {}s", grace.as_secs());
+    });
+
+    tokio::select! {
+        result = server => result,
+        _ = grace_elapsed(&shutdown, grace) => {
+            tracing::warn!("Shutdown grace period elapsed, dropping open connections");
+            Ok(())
+        }
+    }
+}
+
+/// Resolve `grace` after `shutdown` is cancelled.
+pub async fn grace_elapsed(shutdown: &CancellationToken, grace: Duration) {
+    shutdown.cancelled().await;
+    tokio::time::sleep(grace).await;
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tokio::sync::watch;
+
+/// Migration attempts before startup is marked failed.
+pub const MIGRATION_ATTEMPTS: u32 = 5;
+
+/// Wait before the second migration attempt; each later attempt waits one more step.
+pub const MIGRATION_BACKOFF: Duration = Duration::from_secs(5);
+
+/// How far startup has got: migrations and backfills run in the background after the
+/// server starts listening, and /health/ready reports this until they are done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartupStatus {
+    /// Migrations or backfills still running (or shutting down after being ready).
+    Starting,
+    /// Schema is up to date; the API and indexer can use the database.
+    Ready,
+    /// Startup gave up; the instance stays unready until it is restarted.
+    Failed { reason: String },
+}
+
+/// Shared `StartupStatus`. Failed is final: nothing moves the status out of it.
+#[derive(Debug)]
+pub struct Startup(watch::Sender<StartupStatus>);
+
+impl Default for Startup {
+    fn default() -> Self {
+        Self(watch::Sender::new(StartupStatus::Starting))
+    }
+}
+
+impl Startup {
+    /// An already-ready status, for callers that have no startup work to wait for.
+    pub fn ready() -> Self {
+        Self(watch::Sender::new(StartupStatus::Ready))
+    }
+
+    pub fn status(&self) -> StartupStatus {
+        self.0.borrow().clone()
+    }
+
+    pub fn is_ready(&self) -> bool {
+        *self.0.borrow() == StartupStatus::Ready
+    }
+
+    /// Migrations and backfills are done.
+    pub fn set_ready(&self) {
+        self.advance(StartupStatus::Ready);
+    }
+
+    /// Back to Starting, e.g. while draining for shutdown.
+    pub fn set_starting(&self) {
+        self.advance(StartupStatus::Starting);
+    }
+
+    /// Mark startup failed with `reason`, reported by /health/ready.
+    pub fn fail(&self, reason: impl Into<String>) {
+        let reason = reason.into();
+        tracing::error!("Startup failed: {}", reason);
+        self.0.send_replace(StartupStatus::Failed { reason });
+    }
+
+    fn advance(&self, next: StartupStatus) {
+        self.0.send_if_modified(|status| {
+            if matches!(status, StartupStatus::Failed { .. }) || *status == next {
+                return false;
+            }
+            *status = next;
+            true
+        });
+    }
+
+    /// Wait until startup leaves Starting: true once Ready, false if it Failed.
+    pub async fn settled(&self) -> bool {
+        let mut status = self.0.subscribe();
+        // The sender lives in self, so the channel can't close while this waits
+        let ready = match status.wait_for(|s| *s != StartupStatus::Starting).await {
+            Ok(s) => *s == StartupStatus::Ready,
+            Err(_) => false,
+        };
+        ready
+    }
+}
+
+/// Run the migrations, retrying up to `attempts` times (handles connection pool contention
+/// during deploys), the nth retry after `backoff * n`. When every attempt fails, `startup`
+/// is marked Failed with the last error and this returns false.
+pub async fn migrate(pool: &PgPool, startup: &Startup, attempts: u32, backoff: Duration) -> bool {
+    let attempts = attempts.max(1);
+    for attempt in 1..=attempts {
+        tracing::info!("Running migrations (attempt {}/{})", attempt, attempts);
+        match sqlx::migrate!("./migrations").run(pool).await {
+            Ok(_) => {
+                tracing::info!("Migrations applied successfully");
+                return true;
+            }
+            Err(e) if attempt < attempts => {
+                let wait = backoff * attempt;
+                tracing::warn!("Migration attempt {} failed: {:?} — retrying in {:?}", attempt, e, wait);
+                tokio::time::sleep(wait).await;
+            }
+            Err(e) => startup.fail(format!("migrations failed after {} attempts: {}", attempts, e)),
+        }
+    }
+    false
+}
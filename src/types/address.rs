@@ -0,0 +1,58 @@
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Why an address from a request was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid address '{0}', expected 0x followed by 40 hex characters")]
+pub struct InvalidAddress(pub String);
+
+/// An Ethereum address taken from a request (`seller`, `owner`, a path segment, ...).
+/// Accepts a `0x`-prefixed 40-hex-char string in any case, checksummed or not, and holds it
+/// lowercase — the form the indexer stores — so filters match however the client cased it.
+/// Derefs to `&str` for passing straight to the db layer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EthAddress(String);
+
+impl FromStr for EthAddress {
+    type Err = InvalidAddress;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        // Address::from_str also takes a bare hex string; the API requires the prefix
+        let hex = value.strip_prefix("0x").filter(|hex| hex.len() == 40);
+        match hex.and_then(|hex| Address::from_str(hex).ok()) {
+            Some(address) => Ok(EthAddress(format!("{:#x}", address))),
+            None => Err(InvalidAddress(value.to_string())),
+        }
+    }
+}
+
+impl Deref for EthAddress {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for EthAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for EthAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for EthAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt;
+use std::str::FromStr;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+
+/// Why a `before` or `sync_cursor` cursor from a request was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid cursor '{0}', pass back a cursor from a previous response")]
+pub struct InvalidCursor(pub String);
+
+/// URL-safe base64 of `<unix micros>:<id>`, the encoding both cursors share.
+fn encode_key(timestamp: DateTime<Utc>, id: i32) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}:{}", timestamp.timestamp_micros(), id))
+}
+
+fn decode_key(value: &str) -> Result<(DateTime<Utc>, i32), InvalidCursor> {
+    let decoded = URL_SAFE_NO_PAD.decode(value).ok().and_then(|bytes| String::from_utf8(bytes).ok());
+    let parsed = decoded.as_deref().and_then(|raw| {
+        let (micros, id) = raw.split_once(':')?;
+        Some((DateTime::from_timestamp_micros(micros.parse().ok()?)?, id.parse().ok()?))
+    });
+    parsed.ok_or_else(|| InvalidCursor(value.to_string()))
+}
+
+/// Keyset position in the global activity feed, which is ordered by
+/// `(COALESCE(block_timestamp, created_at), id)` descending: a page `before` a cursor holds
+/// the rows strictly after it in that order. Opaque to clients, as URL-safe base64 of
+/// `<unix micros>:<id>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ActivityCursor {
+    pub timestamp: DateTime<Utc>,
+    pub id: i32,
+}
+
+impl ActivityCursor {
+    /// The cursor after the last row of a page.
+    pub fn after(timestamp: Option<DateTime<Utc>>, id: i32) -> Option<Self> {
+        Some(ActivityCursor { timestamp: timestamp?, id })
+    }
+
+    pub fn encode(&self) -> String {
+        encode_key(self.timestamp, self.id)
+    }
+}
+
+impl FromStr for ActivityCursor {
+    type Err = InvalidCursor;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (timestamp, id) = decode_key(value)?;
+        Ok(ActivityCursor { timestamp, id })
+    }
+}
+
+impl fmt::Display for ActivityCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+impl<'de> Deserialize<'de> for ActivityCursor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Keyset position in an incremental sync (`updated_since` on the agent and marketplace
+/// lists), which walks `(updated_at, id)` ascending: a page from a cursor holds the rows
+/// strictly after it in that order, so rows changing between page fetches move behind the
+/// cursor instead of shifting the ones still ahead. Encoded like `ActivityCursor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SyncCursor {
+    pub updated_at: DateTime<Utc>,
+    pub id: i32,
+}
+
+impl SyncCursor {
+    /// Where a sync page starts: after `sync_cursor` when the client passed one back, else
+    /// after every row changed at `updated_since`; None when neither is set.
+    pub fn start(updated_since: Option<DateTime<Utc>>, sync_cursor: Option<SyncCursor>) -> Option<Self> {
+        sync_cursor.or_else(|| updated_since.map(|updated_at| SyncCursor { updated_at, id: i32::MAX }))
+    }
+
+    /// The cursor after a row.
+    pub fn after(updated_at: Option<DateTime<Utc>>, id: i32) -> Option<Self> {
+        Some(SyncCursor { updated_at: updated_at?, id })
+    }
+
+    pub fn encode(&self) -> String {
+        encode_key(self.updated_at, self.id)
+    }
+}
+
+impl FromStr for SyncCursor {
+    type Err = InvalidCursor;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (updated_at, id) = decode_key(value)?;
+        Ok(SyncCursor { updated_at, id })
+    }
+}
+
+impl fmt::Display for SyncCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+impl<'de> Deserialize<'de> for SyncCursor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -1,9 +1,51 @@
-use bigdecimal::BigDecimal;
+use bigdecimal::{BigDecimal, Zero};
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use std::collections::HashMap;
 
+pub mod address;
+pub mod cursor;
+pub mod status;
+pub mod time_format;
+
+pub use address::EthAddress;
+pub use cursor::{ActivityCursor, SyncCursor};
+pub use status::{AuctionStatus, ListingStatus, OfferStatus};
+use time_format::{deserialize_time_opt, serialize_date, serialize_time_opt};
+
+// Response keys are snake_case everywhere: types serialize under their Rust field names, with
+// no serde renames (tests/api_tests.rs `field_casing_tests` holds this). When a field has to
+// change name, keep the old one alongside it for a release rather than renaming in place.
+
+// ─── Serialization helpers ─────────────────────────────────────────────
+
+/// Serialize a NUMERIC (amount, token id) as a plain decimal string. BigDecimal's own
+/// Serialize uses exponent notation for values Postgres returns with trailing zero digit
+/// groups (1 ETH in wei comes back as "100e+16"), which BigInt() and on-chain calls reject.
+pub fn serialize_decimal<S: serde::Serializer>(value: &BigDecimal, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_plain_string())
+}
+
+/// `serialize_decimal` for nullable columns.
+pub fn serialize_decimal_opt<S: serde::Serializer>(
+    value: &Option<BigDecimal>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(v) => serialize_decimal(v, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// `serialize_decimal` for NUMERIC[] columns (bundle token_ids).
+pub fn serialize_decimal_vec<S: serde::Serializer>(
+    values: &[BigDecimal],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(values.iter().map(BigDecimal::to_plain_string))
+}
+
 // ─── Database Models ───────────────────────────────────────────────────
 
 #[allow(dead_code)] // Constructed by sqlx FromRow deserialization
@@ -22,9 +64,12 @@ pub struct Agent {
     pub x402_support: Option<bool>,
     pub active: Option<bool>,
     pub block_number: Option<i64>,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: Option<String>,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub created_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
@@ -36,13 +81,29 @@ pub struct AgentMetadata {
     pub capabilities: Option<Vec<String>>,
 }
 
-#[allow(dead_code)] // Part of AgentMetadata schema
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentEndpoint {
     pub url: String,
     pub protocol: Option<String>,
 }
 
+impl AgentEndpoint {
+    /// The `endpoints` list from a stored agent metadata blob. A missing or non-array
+    /// field yields an empty list, and entries without a string `url` are skipped.
+    pub fn from_metadata(metadata: Option<&serde_json::Value>) -> Vec<AgentEndpoint> {
+        metadata
+            .and_then(|m| m.get("endpoints"))
+            .and_then(|e| e.as_array())
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|entry| serde_json::from_value(entry.clone()).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Feedback {
     pub id: i32,
@@ -50,8 +111,10 @@ pub struct Feedback {
     pub chain_id: i32,
     pub client_address: String,
     pub feedback_index: i64,
+    #[serde(serialize_with = "serialize_decimal")]
     pub value: BigDecimal,
-    pub value_decimals: Option<i32>,
+    /// 0 for rows stored without decimals (read as `COALESCE(value_decimals, 0)`)
+    pub value_decimals: i32,
     pub tag1: Option<String>,
     pub tag2: Option<String>,
     pub endpoint: Option<String>,
@@ -59,12 +122,26 @@ pub struct Feedback {
     pub feedback_hash: Option<String>,
     pub revoked: Option<bool>,
     pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub created_at: Option<DateTime<Utc>>,
+    /// Whether the content at feedback_uri hashes to feedback_hash; null until checked
+    /// (only populated when VERIFY_FEEDBACK_CONTENT is on).
+    pub content_verified: Option<bool>,
 }
 
-#[allow(dead_code)] // Constructed by sqlx FromRow deserialization
+/// A feedback row awaiting content-hash verification.
+#[derive(Debug, Clone, FromRow)]
+pub struct FeedbackContentCheck {
+    pub id: i32,
+    pub feedback_uri: String,
+    pub feedback_hash: String,
+}
+
+/// A `ResponseAppended` reply to one of an agent's feedbacks; `feedback_id` is the
+/// feedback's index.
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct FeedbackResponse {
     pub id: i32,
@@ -73,8 +150,10 @@ pub struct FeedbackResponse {
     pub chain_id: i32,
     pub response_uri: Option<String>,
     pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub created_at: Option<DateTime<Utc>>,
 }
 
@@ -86,6 +165,20 @@ pub struct Activity {
     pub event_type: String,
     pub event_data: Option<serde_json::Value>,
     pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
+    pub block_timestamp: Option<DateTime<Utc>>,
+    pub tx_hash: String,
+    pub log_index: i32,
+}
+
+/// One ownership change of an agent NFT (agent_ownership_history), oldest first in responses.
+/// The mint is the entry whose `from` is the zero address.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OwnershipTransfer {
+    pub from: String,
+    pub to: String,
+    pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
     pub log_index: i32,
@@ -100,6 +193,7 @@ pub struct GlobalActivity {
     pub event_type: String,
     pub event_data: Option<serde_json::Value>,
     pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
     pub log_index: i32,
@@ -107,12 +201,38 @@ pub struct GlobalActivity {
     pub agent_image: Option<String>,
 }
 
-#[allow(dead_code)] // Constructed by sqlx FromRow deserialization
+/// A non-revoked feedback with its agent's name and image, for the global recent feed.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RecentFeedback {
+    pub id: i32,
+    pub agent_id: i64,
+    pub chain_id: i32,
+    pub client_address: String,
+    pub feedback_index: i64,
+    /// Raw on-chain value; `value_normalized` is `value / 10^value_decimals`
+    #[serde(serialize_with = "serialize_decimal")]
+    pub value: BigDecimal,
+    pub value_decimals: i32,
+    pub value_normalized: f64,
+    pub tag1: Option<String>,
+    pub tag2: Option<String>,
+    pub endpoint: Option<String>,
+    pub feedback_uri: Option<String>,
+    pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
+    pub block_timestamp: Option<DateTime<Utc>>,
+    pub tx_hash: String,
+    pub agent_name: Option<String>,
+    pub agent_image: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct IndexerState {
     pub chain_id: i32,
     pub contract_address: String,
+    pub contract_name: Option<String>,
     pub last_block: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
@@ -125,22 +245,34 @@ pub struct MarketplaceListing {
     pub chain_id: i32,
     pub seller: String,
     pub nft_contract: String,
+    #[serde(serialize_with = "serialize_decimal")]
     pub token_id: BigDecimal,
     pub payment_token: String,
+    #[serde(serialize_with = "serialize_decimal")]
     pub price: BigDecimal,
     pub expiry: i64,
-    pub status: String,
+    pub status: ListingStatus,
     pub buyer: Option<String>,
+    #[serde(serialize_with = "serialize_decimal_opt")]
     pub sold_price: Option<BigDecimal>,
     pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    /// Marketplace contract that emitted it; ids restart when the contract is redeployed.
+    pub marketplace_address: String,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub created_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub updated_at: Option<DateTime<Utc>>,
     #[sqlx(default)]
     pub agent_name: Option<String>,
     #[sqlx(default)]
     pub agent_image: Option<String>,
+    /// Seller proceeds after the platform fee (sold listings only, when fee config is known).
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "serialize_decimal_opt")]
+    pub net_proceeds: Option<BigDecimal>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -150,16 +282,22 @@ pub struct MarketplaceOffer {
     pub chain_id: i32,
     pub offerer: String,
     pub nft_contract: String,
+    #[serde(serialize_with = "serialize_decimal")]
     pub token_id: BigDecimal,
     pub payment_token: String,
+    #[serde(serialize_with = "serialize_decimal")]
     pub amount: BigDecimal,
     pub expiry: i64,
-    pub status: String,
+    pub status: OfferStatus,
     pub accepted_by: Option<String>,
     pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub created_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
@@ -171,15 +309,21 @@ pub struct MarketplaceCollectionOffer {
     pub offerer: String,
     pub nft_contract: String,
     pub payment_token: String,
+    #[serde(serialize_with = "serialize_decimal")]
     pub amount: BigDecimal,
     pub expiry: i64,
-    pub status: String,
+    pub status: OfferStatus,
     pub accepted_by: Option<String>,
+    #[serde(serialize_with = "serialize_decimal_opt")]
     pub accepted_token_id: Option<BigDecimal>,
     pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub created_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
@@ -190,28 +334,94 @@ pub struct MarketplaceAuction {
     pub chain_id: i32,
     pub seller: String,
     pub nft_contract: String,
+    #[serde(serialize_with = "serialize_decimal")]
     pub token_id: BigDecimal,
     pub payment_token: String,
+    #[serde(serialize_with = "serialize_decimal")]
     pub start_price: BigDecimal,
+    #[serde(serialize_with = "serialize_decimal")]
     pub reserve_price: BigDecimal,
-    pub buy_now_price: BigDecimal,
+    /// As stored, 0 meaning no buy-now. Never serialized from here: responses carry
+    /// `MarketplaceAuctionItem::buy_now_price`, which is null when buy-now isn't available.
+    #[serde(skip_serializing)]
+    pub buy_now_price: Option<BigDecimal>,
+    #[serde(serialize_with = "serialize_decimal_opt")]
     pub highest_bid: Option<BigDecimal>,
     pub highest_bidder: Option<String>,
     pub start_time: i64,
     pub end_time: i64,
     pub bid_count: Option<i32>,
-    pub status: String,
+    pub status: AuctionStatus,
     pub winner: Option<String>,
+    #[serde(serialize_with = "serialize_decimal_opt")]
     pub settled_price: Option<BigDecimal>,
     pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
+    /// `end_time` is not after `start_time`
+    pub malformed: bool,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub created_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub updated_at: Option<DateTime<Utc>>,
     #[sqlx(default)]
     pub agent_name: Option<String>,
     #[sqlx(default)]
     pub agent_image: Option<String>,
+    /// Seller proceeds after the platform fee (settled auctions only, when fee config is known).
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "serialize_decimal_opt")]
+    pub net_proceeds: Option<BigDecimal>,
+}
+
+/// An English auction as the auction list and detail endpoints return it: the stored row
+/// plus the fields clients would otherwise derive from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketplaceAuctionItem {
+    #[serde(flatten)]
+    pub auction: MarketplaceAuction,
+    /// The lowest acceptable next bid, as `min_next_bid` on /current-price
+    #[serde(serialize_with = "serialize_decimal")]
+    pub minimum_next_bid: BigDecimal,
+    /// `reserve_price` is above zero (zero means no reserve)
+    pub has_reserve: bool,
+    /// Whether the highest bid (0 with no bids) is at least the reserve; null without one
+    pub reserve_met: Option<bool>,
+    /// A bid of `buy_now_price` would buy the NFT outright right now: the price is set, the
+    /// auction is Active and open (`start_time` <= now < `end_time`), and no bid has reached
+    /// the price yet. The contract has no separate buy-now call; such a bid ends the auction.
+    pub buy_now_available: bool,
+    /// The auction's buy-now price while `buy_now_available`, otherwise null
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub buy_now_price: Option<BigDecimal>,
+}
+
+impl MarketplaceAuctionItem {
+    /// `now` is the current unix time, for `buy_now_available`.
+    pub fn new(auction: MarketplaceAuction, minimum_next_bid: BigDecimal, now: i64) -> Self {
+        let has_reserve = auction.reserve_price > BigDecimal::zero();
+        let reserve_met = has_reserve.then(|| {
+            auction.highest_bid.as_ref().is_some_and(|bid| bid >= &auction.reserve_price)
+        });
+        let buy_now_available = auction.buy_now_price.as_ref().is_some_and(|price| {
+            price > &BigDecimal::zero()
+                && auction.status == AuctionStatus::Active
+                && auction.start_time <= now
+                && now < auction.end_time
+                && auction.highest_bid.as_ref().is_none_or(|bid| bid < price)
+        });
+        let buy_now_price = if buy_now_available { auction.buy_now_price.clone() } else { None };
+        Self {
+            auction,
+            minimum_next_bid,
+            has_reserve,
+            reserve_met,
+            buy_now_available,
+            buy_now_price,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -220,11 +430,32 @@ pub struct MarketplaceAuctionBid {
     pub auction_id: i64,
     pub chain_id: i32,
     pub bidder: String,
+    #[serde(serialize_with = "serialize_decimal")]
     pub amount: BigDecimal,
     pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub created_at: Option<DateTime<Utc>>,
+    /// NULL for bids indexed before migration 019
+    pub log_index: Option<i32>,
+}
+
+/// A bid in a user's portfolio, with where it stands on its auction.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MarketplaceUserBid {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub bid: MarketplaceAuctionBid,
+    /// "winning" | "outbid" while the auction is Active; "won" | "lost" once it is over
+    /// (Cancelled and ReserveNotMet auctions count as lost). None if the auction isn't indexed.
+    pub bid_status: Option<String>,
+    /// The auction's current highest bid
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub highest_bid: Option<BigDecimal>,
+    pub end_time: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -234,19 +465,29 @@ pub struct MarketplaceDutchAuction {
     pub chain_id: i32,
     pub seller: String,
     pub nft_contract: String,
+    #[serde(serialize_with = "serialize_decimal")]
     pub token_id: BigDecimal,
     pub payment_token: String,
+    #[serde(serialize_with = "serialize_decimal")]
     pub start_price: BigDecimal,
+    #[serde(serialize_with = "serialize_decimal")]
     pub end_price: BigDecimal,
     pub start_time: i64,
     pub end_time: i64,
-    pub status: String,
+    pub status: ListingStatus,
     pub buyer: Option<String>,
+    #[serde(serialize_with = "serialize_decimal_opt")]
     pub sold_price: Option<BigDecimal>,
     pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
+    /// `end_time` is not after `start_time`
+    pub malformed: bool,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub created_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
@@ -257,21 +498,124 @@ pub struct MarketplaceBundle {
     pub chain_id: i32,
     pub seller: String,
     pub nft_contracts: Vec<String>,
+    #[serde(serialize_with = "serialize_decimal_vec")]
     pub token_ids: Vec<BigDecimal>,
     pub payment_token: String,
+    #[serde(serialize_with = "serialize_decimal")]
     pub price: BigDecimal,
     pub expiry: i64,
     pub item_count: i32,
-    pub status: String,
+    pub status: ListingStatus,
     pub buyer: Option<String>,
+    #[serde(serialize_with = "serialize_decimal_opt")]
     pub sold_price: Option<BigDecimal>,
     pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub created_at: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+/// A completed sale (marketplace_sales), whichever path it went through.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MarketplaceSale {
+    pub chain_id: i32,
+    pub marketplace_address: String,
+    /// listing | offer | collection_offer | auction | dutch_auction | bundle
+    pub sale_type: String,
+    /// Id of what was sold, in its own table
+    pub entity_id: i64,
+    /// None for bundles, which have no single NFT
+    pub nft_contract: Option<String>,
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub token_id: Option<BigDecimal>,
+    pub seller: Option<String>,
+    pub buyer: Option<String>,
+    pub payment_token: Option<String>,
+    #[serde(serialize_with = "serialize_decimal")]
+    pub price: BigDecimal,
+    /// NULL only for sales backfilled without their event
+    pub block_number: Option<i64>,
+    #[serde(serialize_with = "serialize_time_opt")]
+    pub block_timestamp: Option<DateTime<Utc>>,
+    pub tx_hash: Option<String>,
+}
+
+/// The most recent sale of one NFT (`db::marketplace::get_last_sale_for_token`), whichever
+/// sale path it went through. Bundle sales aren't per token and never count.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LastSale {
+    #[serde(serialize_with = "serialize_decimal")]
+    pub price: BigDecimal,
+    pub payment_token: Option<String>,
+    /// Block time, or when the sale was indexed if that is unknown
+    #[serde(serialize_with = "serialize_time_opt")]
+    pub timestamp: Option<DateTime<Utc>>,
+    pub tx_hash: Option<String>,
+}
+
+/// An offer or collection offer made by a user (see `db::marketplace::get_user_offers`).
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MarketplaceUserOffer {
+    /// `offer` for an offer on one token, `collection_offer` for one on any token of a collection
+    pub offer_type: String,
+    pub offer_id: i64,
+    pub chain_id: i32,
+    pub offerer: String,
+    pub nft_contract: String,
+    /// None for collection offers
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub token_id: Option<BigDecimal>,
+    pub payment_token: String,
+    #[serde(serialize_with = "serialize_decimal")]
+    pub amount: BigDecimal,
+    pub expiry: i64,
+    pub status: OfferStatus,
+    pub accepted_by: Option<String>,
+    /// The token an accepted collection offer was filled with
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub accepted_token_id: Option<BigDecimal>,
+    pub block_number: i64,
+    #[serde(serialize_with = "serialize_time_opt")]
+    pub block_timestamp: Option<DateTime<Utc>>,
+    pub tx_hash: String,
+    pub marketplace_address: String,
+    #[serde(serialize_with = "serialize_time_opt")]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// One marketplace event on a collection's tokens, read from the marketplace table that
+/// records it (see `db::marketplace::get_collection_activity`).
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MarketplaceCollectionActivity {
+    /// Listed, OfferMade, CollectionOfferMade, AuctionCreated, BidPlaced, DutchAuctionCreated or Sale
+    pub event_type: String,
+    pub chain_id: i32,
+    pub nft_contract: String,
+    /// None for collection offers, which cover any token
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub token_id: Option<BigDecimal>,
+    /// Listing, offer or auction id; for a Sale, the id of what was sold (see `sale_type`)
+    pub entity_id: i64,
+    /// Set on Sale events: listing, offer, collection_offer, auction or dutch_auction
+    pub sale_type: Option<String>,
+    /// Seller, offerer or bidder; the buyer for a Sale
+    pub actor: Option<String>,
+    /// Current asking price, offer or bid amount, start price, or sale price
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub price: Option<BigDecimal>,
+    pub payment_token: Option<String>,
+    /// NULL only for sales backfilled without their event
+    pub block_number: Option<i64>,
+    #[serde(serialize_with = "serialize_time_opt")]
+    pub block_timestamp: Option<DateTime<Utc>>,
+    pub tx_hash: Option<String>,
+}
+
 // ─── Score by Tag ─────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -284,6 +628,15 @@ pub struct ScoreByTagRow {
     pub max_value: Option<f64>,
 }
 
+/// `ScoreByTagRow` for one of several agents (see `db::agents::get_top_tags`).
+#[derive(Debug, FromRow)]
+pub struct AgentScoreByTagRow {
+    pub chain_id: i32,
+    pub agent_id: i64,
+    #[sqlx(flatten)]
+    pub score: ScoreByTagRow,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScoreByTag {
     pub score_type: String,
@@ -296,6 +649,16 @@ pub struct ScoreByTag {
     pub scale: String,
 }
 
+/// An agent's highest-priority tag score (the first of its `ScoreByTag` list), embedded in
+/// /api/agents items with `include_top_tag=true`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopTag {
+    pub score_type: String,
+    pub label: Option<String>,
+    pub value: f64,
+    pub scale: String,
+}
+
 // ─── API Response Types ────────────────────────────────────────────────
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -311,17 +674,66 @@ pub struct AgentListItem {
     pub active: Option<bool>,
     pub reputation_score: Option<f64>,
     pub feedback_count: Option<i64>,
+    /// Distinct clients with non-revoked feedback
+    pub unique_clients: Option<i64>,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
+    /// `ts_rank` relevance, only present for ranked searches (`search_mode=rank`)
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_rank: Option<f32>,
+    /// Last change to the agent row; only selected by /api/agents
+    #[sqlx(default)]
+    #[serde(default, serialize_with = "serialize_time_opt", skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Row id, the `updated_at` tie-break of a sync cursor; only selected by /api/agents
+    #[sqlx(default)]
+    #[serde(skip)]
+    pub id: i32,
+    /// Only with /api/agents `include_top_tag=true`, for agents with tagged feedback
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_tag: Option<TopTag>,
+}
+
+/// An agent in an owner's dashboard: list item plus its current market status.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct OwnerAgentItem {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub agent: AgentListItem,
+    /// "listed" | "auctioned" | "none"
+    pub market_status: String,
+    pub listing_id: Option<i64>,
+    pub auction_id: Option<i64>,
+    /// "english" | "dutch" when auctioned
+    pub auction_type: Option<String>,
+    /// Listing price, or the auction's highest bid (falling back to its start price)
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub price: Option<BigDecimal>,
+    pub payment_token: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct AgentListResponse {
-    pub agents: Vec<AgentListItem>,
+pub struct OwnerAgentListResponse {
+    pub owner: String,
+    pub agents: Vec<OwnerAgentItem>,
     pub total: i64,
     pub page: i64,
     pub limit: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentListResponse {
+    pub agents: Vec<AgentListItem>,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    /// With `updated_since` or `sync_cursor`: pass it back as `sync_cursor` for the next page,
+    /// or for the next sync once `has_next` is false. See `sync_checkpoint`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_cursor: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct AgentDetailRow {
     pub agent_id: i64,
@@ -339,23 +751,78 @@ pub struct AgentDetailRow {
     pub feedback_count: Option<i64>,
     pub positive_feedback_count: Option<i64>,
     pub negative_feedback_count: Option<i64>,
+    #[serde(serialize_with = "serialize_time_opt")]
     pub block_timestamp: Option<DateTime<Utc>>,
 }
 
+/// Earliest and latest event for an agent across activity_log and its archive. Both None
+/// when the agent has no recorded activity.
+#[derive(Debug, Default, Serialize, Deserialize, FromRow)]
+pub struct AgentActivitySpan {
+    #[serde(serialize_with = "serialize_time_opt")]
+    pub first_seen: Option<DateTime<Utc>>,
+    #[serde(serialize_with = "serialize_time_opt")]
+    pub last_active: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AgentDetailResponse {
     #[serde(flatten)]
     pub agent: AgentDetailRow,
+    #[serde(flatten)]
+    pub activity: AgentActivitySpan,
     pub scores: Vec<ScoreByTag>,
+    /// `metadata.endpoints`, typed for x402 discovery
+    pub endpoints: Vec<AgentEndpoint>,
+    /// The agent NFT's latest marketplace sale; null if it never sold
+    pub last_sale: Option<LastSale>,
+}
+
+impl AgentDetailResponse {
+    pub fn new(
+        agent: AgentDetailRow,
+        activity: AgentActivitySpan,
+        scores: Vec<ScoreByTag>,
+        last_sale: Option<LastSale>,
+    ) -> Self {
+        let endpoints = AgentEndpoint::from_metadata(agent.metadata.as_ref());
+        AgentDetailResponse { agent, activity, scores, endpoints, last_sale }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct ReputationHistoryPoint {
+    #[serde(serialize_with = "serialize_date")]
     pub date: NaiveDate,
     pub score: Option<f64>,
     pub feedback_count: i64,
 }
 
+/// One day of GET /api/agents/:id/feedback-velocity.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct FeedbackVelocityDay {
+    #[serde(serialize_with = "serialize_date")]
+    pub date: NaiveDate,
+    /// Every feedback given that day, revoked ones included
+    pub feedback_count: i64,
+    /// Of those, how many have since been revoked
+    pub revoked_count: i64,
+    /// `feedback_count` is over `spike_factor` times the range's daily average (see `mark_spikes`)
+    #[sqlx(default)]
+    pub spike: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeedbackVelocityResponse {
+    pub agent_id: i64,
+    pub chain_id: i32,
+    /// Mean feedbacks per day over `days`
+    pub average_per_day: f64,
+    pub spike_factor: f64,
+    /// One entry per day, oldest first, days without feedback included
+    pub days: Vec<FeedbackVelocityDay>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ReputationResponse {
     pub agent_id: i64,
@@ -365,20 +832,42 @@ pub struct ReputationResponse {
     pub feedbacks: Vec<Feedback>,
 }
 
+/// GET /api/agents/:id/owners — every transfer of the agent NFT, mint first.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgentOwnersResponse {
+    pub agent_id: i64,
+    pub chain_id: i32,
+    pub transfers: Vec<OwnershipTransfer>,
+}
+
+/// GET /api/agents/:id/responses — newest first.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeedbackResponseListResponse {
+    pub responses: Vec<FeedbackResponse>,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ActivityResponse {
     pub activities: Vec<Activity>,
-    pub total: i64,
-    pub page: i64,
-    pub limit: i64,
+    #[serde(flatten)]
+    pub pagination: Pagination,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GlobalActivityResponse {
     pub activities: Vec<GlobalActivity>,
-    pub total: i64,
-    pub page: i64,
-    pub limit: i64,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    /// Pass as `before` for the next page (keyset paging); None on the last page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecentFeedbackResponse {
+    pub feedbacks: Vec<RecentFeedback>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -400,13 +889,25 @@ pub struct LeaderboardResponse {
     pub leaderboard: Vec<LeaderboardEntry>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct CategoryCount {
     pub category: String,
     pub count: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct CapabilityCount {
+    pub capability: String,
+    /// Agents listing the capability
+    pub count: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
+pub struct CapabilityListResponse {
+    pub capabilities: Vec<CapabilityCount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatsResponse {
     pub total_agents: i64,
     pub total_feedbacks: i64,
@@ -419,69 +920,220 @@ pub struct StatsResponse {
     pub total_listings: i64,
     pub active_listings: i64,
     pub total_sales: i64,
+    #[serde(serialize_with = "serialize_decimal")]
     pub total_volume: BigDecimal,
+    /// NFTs sold, as in /api/marketplace/stats
+    pub items_sold: i64,
+}
+
+/// GET /api/dashboard: the home page's four reads in one payload. A section that failed is
+/// null and named in `warnings`; the others are still returned.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DashboardResponse {
+    pub stats: Option<StatsResponse>,
+    /// Top 10 by reputation, as /api/leaderboard?limit=10
+    pub leaderboard: Option<Vec<LeaderboardEntry>>,
+    /// 20 newest events, as /api/activity?limit=20
+    pub activities: Option<Vec<GlobalActivity>>,
+    /// 10 latest sales across every sale type
+    pub sales: Option<Vec<MarketplaceSale>>,
+    pub warnings: Vec<DashboardWarning>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DashboardWarning {
+    /// The null section: stats, leaderboard, activities or sales
+    pub section: String,
+    pub message: String,
+}
+
+/// `/api/stats/summary`: the headline counts of `StatsResponse`, without the grouped ones.
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StatsSummaryResponse {
+    pub total_agents: i64,
+    pub total_feedbacks: i64,
+    pub total_listings: i64,
+    pub active_listings: i64,
+}
+
+/// GET /api/marketplace/nft/:chain_id/:nft_contract/:token_id/listings — unpaginated.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarketplaceTokenListingsResponse {
+    pub chain_id: i32,
+    pub nft_contract: String,
+    pub token_id: String,
+    pub listings: Vec<MarketplaceListing>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MarketplaceListingListResponse {
     pub listings: Vec<MarketplaceListing>,
-    pub total: i64,
-    pub page: i64,
-    pub limit: i64,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    /// With `updated_since` or `sync_cursor`: pass it back as `sync_cursor` for the next page,
+    /// or for the next sync once `has_next` is false. See `sync_checkpoint`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MarketplaceOfferListResponse {
     pub offers: Vec<MarketplaceOffer>,
-    pub total: i64,
-    pub page: i64,
-    pub limit: i64,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    /// With `updated_since` or `sync_cursor`: pass it back as `sync_cursor` for the next page,
+    /// or for the next sync once `has_next` is false. See `sync_checkpoint`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MarketplaceCollectionOfferListResponse {
     pub offers: Vec<MarketplaceCollectionOffer>,
-    pub total: i64,
-    pub page: i64,
-    pub limit: i64,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    /// With `updated_since` or `sync_cursor`: pass it back as `sync_cursor` for the next page,
+    /// or for the next sync once `has_next` is false. See `sync_checkpoint`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MarketplaceAuctionListResponse {
-    pub auctions: Vec<MarketplaceAuction>,
-    pub total: i64,
-    pub page: i64,
-    pub limit: i64,
+    pub auctions: Vec<MarketplaceAuctionItem>,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    /// With `updated_since` or `sync_cursor`: pass it back as `sync_cursor` for the next page,
+    /// or for the next sync once `has_next` is false. See `sync_checkpoint`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MarketplaceAuctionDetailResponse {
     #[serde(flatten)]
-    pub auction: MarketplaceAuction,
+    pub auction: MarketplaceAuctionItem,
     pub bids: Vec<MarketplaceAuctionBid>,
 }
 
+/// Bid floor for an English auction (GET /api/marketplace/auctions/:id/current-price).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarketplaceAuctionCurrentPriceResponse {
+    pub auction_id: i64,
+    pub chain_id: i32,
+    pub status: AuctionStatus,
+    #[serde(serialize_with = "serialize_decimal")]
+    pub start_price: BigDecimal,
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub highest_bid: Option<BigDecimal>,
+    #[serde(serialize_with = "serialize_decimal")]
+    pub reserve_price: BigDecimal,
+    /// Whether the highest bid (0 with no bids) is at least the reserve.
+    pub reserve_met: bool,
+    #[serde(serialize_with = "serialize_decimal")]
+    pub buy_now_price: BigDecimal,
+    /// MIN_BID_INCREMENT_BPS used for `min_next_bid`.
+    pub min_bid_increment_bps: u32,
+    /// `max(start_price, highest_bid * (1 + increment))`, rounded up to a whole unit.
+    #[serde(serialize_with = "serialize_decimal")]
+    pub min_next_bid: BigDecimal,
+    /// `min_next_bid`, raised to the reserve while it isn't met and capped at the buy-now
+    /// price when there is one. The lowest bid worth placing.
+    #[serde(serialize_with = "serialize_decimal")]
+    pub effective_min_bid: BigDecimal,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MarketplaceDutchAuctionListResponse {
     pub auctions: Vec<MarketplaceDutchAuction>,
-    pub total: i64,
-    pub page: i64,
-    pub limit: i64,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    /// With `updated_since` or `sync_cursor`: pass it back as `sync_cursor` for the next page,
+    /// or for the next sync once `has_next` is false. See `sync_checkpoint`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MarketplaceBundleListResponse {
     pub bundles: Vec<MarketplaceBundle>,
-    pub total: i64,
-    pub page: i64,
-    pub limit: i64,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    /// With `updated_since` or `sync_cursor`: pass it back as `sync_cursor` for the next page,
+    /// or for the next sync once `has_next` is false. See `sync_checkpoint`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarketplaceCollectionActivityResponse {
+    pub nft_contract: String,
+    pub activities: Vec<MarketplaceCollectionActivity>,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+}
+
+/// GET /api/marketplace/collections/:nft_contract/stats
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarketplaceCollectionStatsResponse {
+    pub nft_contract: String,
+    pub chain_id: Option<i32>,
+    /// 7d, 30d or all
+    pub range: String,
+    /// One entry per payment token the collection was listed or sold in
+    pub prices: Vec<CollectionSalePriceStats>,
+}
+
+/// Sale prices of a collection in one payment token over the requested range. With no sales
+/// in range, `sales` is 0 and the average and median are null.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct CollectionSalePriceStats {
+    pub payment_token: String,
+    pub sales: i64,
+    #[serde(serialize_with = "serialize_decimal")]
+    pub volume: BigDecimal,
+    /// Rounded to a whole unit of the payment token
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub avg_sale_price: Option<BigDecimal>,
+    /// Mean of the two middle prices for an even count, rounded like `avg_sale_price`
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub median_sale_price: Option<BigDecimal>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MarketplaceUserPortfolioResponse {
     pub listings: Vec<MarketplaceListing>,
     pub offers: Vec<MarketplaceOffer>,
-    pub bids: Vec<MarketplaceAuctionBid>,
+    pub bids: Vec<MarketplaceUserBid>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarketplaceUserOffersResponse {
+    pub offers: Vec<MarketplaceUserOffer>,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarketplaceUserAuctionsResponse {
+    pub winning: Vec<MarketplaceAuctionItem>,
+    pub outbid: Vec<MarketplaceAuctionItem>,
+}
+
+/// GET /api/marketplace/sellers/:address/metrics. Counts cover the seller's listings,
+/// English auctions and Dutch auctions; a seller with none gets zeros.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MarketplaceSellerMetricsResponse {
+    pub seller: String,
+    pub chain_id: Option<i32>,
+    pub total_listed: i64,
+    /// Sold listings and Dutch auctions, and auctions that ended with a winner
+    pub total_sold: i64,
+    /// `total_sold / total_listed`, 0 with nothing listed
+    pub sell_through_rate: f64,
+    /// Mean sale price, rounded to a whole unit; null with no sales
+    #[serde(serialize_with = "serialize_decimal_opt")]
+    pub avg_sale_price: Option<BigDecimal>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -489,8 +1141,304 @@ pub struct MarketplaceStatsResponse {
     pub total_listings: i64,
     pub active_listings: i64,
     pub total_sales: i64,
+    #[serde(serialize_with = "serialize_decimal")]
     pub total_volume: BigDecimal,
+    /// NFTs sold: each sale is one item except bundles, which count every item in the bundle.
+    /// `total_sales` and `total_volume` count a bundle once.
+    pub items_sold: i64,
     pub active_auctions: i64,
+    /// Whether each configured chain (keyed by chain_id) has a marketplace contract.
+    pub marketplace_enabled: HashMap<String, bool>,
+}
+
+/// How a list response's `total` was computed. Anything other than `exact` trades
+/// accuracy for skipping a full `COUNT(*)` over the filtered rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CountAccuracy {
+    /// `total` is the exact number of matching rows.
+    Exact,
+    /// `total` is the planner's row estimate for the table and may drift from the true count.
+    Estimate,
+    /// Counting stopped at the estimate cap; there are at least `total` matching rows.
+    LowerBound,
+    /// Counting was skipped; `total` is null.
+    None,
+}
+
+/// `total` plus how it was computed, flattened into paginated list responses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotalCount {
+    pub total: Option<i64>,
+    pub total_accuracy: CountAccuracy,
+}
+
+impl TotalCount {
+    pub fn exact(total: i64) -> Self {
+        Self {
+            total: Some(total),
+            total_accuracy: CountAccuracy::Exact,
+        }
+    }
+
+    pub fn none() -> Self {
+        Self {
+            total: None,
+            total_accuracy: CountAccuracy::None,
+        }
+    }
+}
+
+/// Paging fields shared by every paginated list response, flattened next to its items.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pagination {
+    #[serde(flatten)]
+    pub count: TotalCount,
+    pub page: i64,
+    pub limit: i64,
+    /// `ceil(total / limit)`: as accurate as `total` (see `total_accuracy`), None without one.
+    pub total_pages: Option<i64>,
+    /// Whether the next page has rows. Known from the rows themselves, so it is exact in
+    /// every `count_mode`.
+    pub has_next: bool,
+}
+
+impl Pagination {
+    /// Build the paging fields for a page whose rows were fetched with `LIMIT limit + 1`,
+    /// dropping the extra row from `rows`: its presence is what `has_next` reports.
+    pub fn from_rows<T>(rows: &mut Vec<T>, count: TotalCount, page: i64, limit: i64) -> Self {
+        let limit_rows = usize::try_from(limit).unwrap_or(0);
+        let has_next = rows.len() > limit_rows;
+        rows.truncate(limit_rows);
+        let total_pages = count.total.map(|total| total_pages(total, limit));
+        Pagination { count, page, limit, total_pages, has_next }
+    }
+}
+
+/// Default SYNC_LAG_SECS; comfortably longer than an indexer transaction.
+pub const DEFAULT_SYNC_LAG_SECS: u64 = 30;
+
+/// The `sync_cursor` of a sync page (see `SyncCursor`), None when not syncing. While
+/// `has_next`, it is the last row's position, and the client pages on from it. On the last
+/// page it is held back to `settled_before` (now minus SYNC_LAG_SECS): a row's `updated_at`
+/// is stamped when its transaction starts, so one committing late can land behind the
+/// newest row already served, and the next sync covers that window again instead of
+/// skipping it. Status changes bump `updated_at`, so cancelled, sold and ended rows come
+/// through too.
+pub fn sync_checkpoint<T>(
+    sync: Option<SyncCursor>,
+    rows: &[T],
+    has_next: bool,
+    settled_before: DateTime<Utc>,
+    position: impl Fn(&T) -> Option<SyncCursor>,
+) -> Option<SyncCursor> {
+    let start = sync?;
+    let last = rows.last().and_then(position).unwrap_or(start).max(start);
+    if has_next {
+        return Some(last);
+    }
+    Some(last.min(SyncCursor { updated_at: settled_before, id: 0 }))
+}
+
+/// Pages needed for `total` rows at `limit` per page (0 for an empty list).
+fn total_pages(total: i64, limit: i64) -> i64 {
+    if limit <= 0 {
+        return 0;
+    }
+    let total = total.max(0);
+    total / limit + i64::from(total % limit != 0)
+}
+
+/// Connection pool health, from sqlx's pool counters and the periodic acquire probe.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub pool: String,
+    pub max_connections: u32,
+    /// Open connections (idle + in use).
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    /// How long the most recent probe waited to check out a connection.
+    pub acquire_wait_ms: f64,
+    /// Longest probe wait since startup.
+    pub acquire_wait_max_ms: f64,
+    /// Probes that failed to get a connection within the acquire timeout.
+    pub acquire_timeouts: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChainIndexerStatus {
+    pub chain_id: i32,
+    /// Runtime enable flag (see /api/admin/indexer/:chain_id/{enable,disable}).
+    pub enabled: bool,
+    pub contracts: Vec<IndexerState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexerStatusResponse {
+    /// Whether the indexer was started (ENABLE_INDEXER=true).
+    pub indexer_enabled: bool,
+    /// Whether every chain is paused (see /api/admin/indexer/{pause,resume}).
+    pub paused: bool,
+    pub chains: Vec<ChainIndexerStatus>,
+    pub pools: Vec<PoolStats>,
+    pub metadata_queue: MetadataQueueStatus,
+}
+
+/// Counters of the metadata fetch queue (see `indexer::metadata::MetadataQueue`), since startup.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetadataQueueStatus {
+    pub capacity: usize,
+    /// Jobs waiting for a fetch slot.
+    pub pending: usize,
+    /// Fetches running now.
+    pub in_flight: u64,
+    /// Jobs accepted into the queue.
+    pub queued: u64,
+    /// Jobs refused because the queue was full.
+    pub dropped: u64,
+    /// Fetches that updated the agent row.
+    pub fetched: u64,
+    /// Fetches that failed (unreachable URI, invalid JSON, database error).
+    pub failed: u64,
+}
+
+/// POST /api/admin/agents/refetch-metadata body: either `ids` or a filter.
+#[derive(Debug, Deserialize)]
+pub struct RefetchMetadataRequest {
+    /// Composite `chainId-agentId` ids.
+    pub ids: Option<Vec<String>>,
+    pub chain_id: Option<i32>,
+    /// Only agents whose metadata has no name.
+    #[serde(default)]
+    pub missing_name: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefetchMetadataResponse {
+    /// Agents that matched and have a URI.
+    pub matched: usize,
+    /// Of those, how many the queue accepted; the rest were refused because it was full.
+    pub queued: usize,
+}
+
+/// GET /health/ready (and /health): whether this instance should receive traffic.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    /// `ready`, `degraded` (serving, but an indexed chain is lagging), `starting` (migrations
+    /// not finished, or shutting down), `failed` (startup gave up; see `reason`) or
+    /// `unavailable` (the database can't be reached).
+    pub status: String,
+    /// Whether `SELECT 1` succeeded; None when it wasn't attempted (starting or failed).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<bool>,
+    /// Why startup failed, with status `failed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Per-chain indexer lag, when the indexer is running.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub indexer: Vec<ChainIndexerLag>,
+}
+
+/// How long since a chain's stalest contract cursor last advanced.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ChainIndexerLag {
+    pub chain_id: i32,
+    pub lag_secs: i64,
+    /// Whether `lag_secs` exceeds HEALTH_MAX_INDEXER_LAG_SECS (makes the status `degraded`).
+    #[sqlx(default)]
+    pub lagging: bool,
+}
+
+/// GET /api/version — what is deployed, for matching a bug report to a build.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionResponse {
+    /// `<crate version>+<short git sha>`, also sent as the X-Service-Version header.
+    pub service_version: String,
+    /// Crate version from Cargo.toml.
+    pub version: String,
+    /// Full commit SHA the binary was built from ("unknown" when built without git metadata).
+    pub git_sha: String,
+    #[serde(serialize_with = "serialize_time_opt")]
+    pub build_timestamp: Option<DateTime<Utc>>,
+    /// Chains this instance is configured for (INDEX_MAINNET / INDEX_TESTNET), whether or not
+    /// they are currently indexing.
+    pub chain_ids: Vec<i32>,
+    /// Whether the indexer was started (ENABLE_INDEXER=true).
+    pub indexer_enabled: bool,
+}
+
+/// GET /api/admin/config: the indexer settings this process loaded, with RPC URLs masked.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminConfigResponse {
+    pub chains: Vec<AdminChainConfig>,
+    /// Enabled chains whose settings failed to load; neither indexed nor served.
+    pub skipped_chains: Vec<AdminSkippedChain>,
+    pub indexer: AdminIndexerSettings,
+    pub features: AdminFeatureFlags,
+}
+
+/// A chain left out at startup (`provider::SkippedChain`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminSkippedChain {
+    pub chain_id: i32,
+    /// Names the invalid variable
+    pub error: String,
+}
+
+/// One configured chain (`ChainConfig`), plus whether the admin toggle currently lets it index.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminChainConfig {
+    pub chain_id: i32,
+    /// First 16 and last 8 characters only; provider keys sit in between.
+    pub rpc_url: String,
+    pub identity_address: String,
+    pub reputation_address: String,
+    pub marketplace_address: Option<String>,
+    pub start_block: u64,
+    pub reputation_start_block: Option<u64>,
+    pub marketplace_start_block: Option<u64>,
+    pub dry_run: bool,
+    pub skip_block_timestamps: bool,
+    pub confirmations: u64,
+    /// The /api/admin/indexer/:chain_id toggle; false when the indexer isn't running.
+    pub indexing_enabled: bool,
+}
+
+/// Batch and polling settings shared by every chain's indexer loop.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminIndexerSettings {
+    pub block_batch_size: u64,
+    pub parallel_batches: u64,
+    pub poll_interval_ms: u64,
+    pub marketplace_config_sync_interval_secs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminFeatureFlags {
+    /// ENABLE_INDEXER
+    pub enable_indexer: bool,
+    /// Paused through /api/admin/indexer/pause
+    pub indexer_paused: bool,
+    pub rebuild_agent_stats: bool,
+    pub verify_feedback_content: bool,
+    /// None when archival is off.
+    pub activity_retention_days: Option<i64>,
+    pub strict_query_params: bool,
+    pub agent_stats_legacy_aggregation: bool,
+}
+
+/// Result of an admin indexer enable/disable toggle.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexerToggleResponse {
+    pub chain_id: i32,
+    pub enabled: bool,
+}
+
+/// POST /api/admin/indexer/{pause,resume}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexerPauseResponse {
+    pub paused: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -498,19 +1446,85 @@ pub struct ErrorResponse {
     pub error: String,
     pub message: String,
     pub status: u16,
+    /// Machine-readable error code (e.g. "INVALID_QUERY"), when one applies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// The request field that caused the error, when known (the first of `fields`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Every rejected parameter, on 422 validation errors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<FieldIssue>>,
+    /// Id of the failed request, also sent as the `x-request-id` header; quote it when
+    /// reporting a problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// One rejected request parameter: its name and what is wrong with the value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldIssue {
+    pub name: String,
+    pub issue: String,
 }
 
 // ─── Query Parameters ──────────────────────────────────────────────────
 
+/// `count_mode` query param for paginated lists: `exact` (default), `estimate`, or `none`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CountMode {
+    #[default]
+    Exact,
+    Estimate,
+    None,
+}
+
+/// Default cap on `page`; override with MAX_PAGE. Deep pages are slow OFFSET scans, and an
+/// unbounded page times limit could overflow.
+pub const DEFAULT_MAX_PAGE: i64 = 100_000;
+
+/// The configured page cap (MAX_PAGE).
+pub fn max_page() -> i64 {
+    crate::config::Limits::current().max_page
+}
+
+/// Deserialize a `page` query param, rejecting values above `max_page()` so the request
+/// fails with a 400 (INVALID_QUERY, field `page`) instead of running a huge OFFSET.
+fn deserialize_page<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let page = Option::<i64>::deserialize(deserializer)?;
+    match page {
+        Some(p) if p > max_page() => Err(serde::de::Error::custom(format!(
+            "page must be at most {}",
+            max_page()
+        ))),
+        _ => Ok(page),
+    }
+}
+
+/// Effective page number: defaults to 1, never below 1 or above `max_page()`.
+pub fn page_number(page: Option<i64>) -> i64 {
+    page.unwrap_or(1).clamp(1, max_page())
+}
+
+/// Row offset for a page, saturating instead of overflowing.
+pub fn page_offset(page: i64, limit: i64) -> i64 {
+    page.saturating_sub(1).max(0).checked_mul(limit).unwrap_or(i64::MAX)
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PaginationParams {
+    #[serde(default, deserialize_with = "deserialize_page")]
     pub page: Option<i64>,
     pub limit: Option<i64>,
 }
 
 impl PaginationParams {
     pub fn page(&self) -> i64 {
-        self.page.unwrap_or(1).max(1)
+        page_number(self.page)
     }
 
     pub fn limit(&self) -> i64 {
@@ -518,7 +1532,7 @@ impl PaginationParams {
     }
 
     pub fn offset(&self) -> i64 {
-        (self.page() - 1) * self.limit()
+        page_offset(self.page(), self.limit())
     }
 }
 
@@ -527,15 +1541,31 @@ pub struct AgentListParams {
     pub chain_id: Option<i32>,
     pub search: Option<String>,
     pub category: Option<String>,
-    pub owner: Option<String>,
+    pub owner: Option<EthAddress>,
+    /// Only agents with a metadata endpoint of this `protocol` (e.g. `x402`, `websocket`)
+    pub protocol: Option<String>,
+    /// Only agents listing this string in `metadata.capabilities` (see /api/capabilities)
+    pub capability: Option<String>,
+    /// RFC 3339; only rows changed after it, oldest change first (see `sync_checkpoint`)
+    pub updated_since: Option<DateTime<Utc>>,
+    /// A previous sync response's `sync_cursor`: continue after it instead of `updated_since`
+    pub sync_cursor: Option<SyncCursor>,
     pub sort: Option<String>,
+    /// "substring" (default): ILIKE on name/description; "rank": full-text search ordered by relevance
+    pub search_mode: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_page")]
     pub page: Option<i64>,
     pub limit: Option<i64>,
+    /// Embed each agent's `top_tag`
+    #[serde(default)]
+    pub include_top_tag: bool,
+    #[serde(default)]
+    pub count_mode: CountMode,
 }
 
 impl AgentListParams {
     pub fn page(&self) -> i64 {
-        self.page.unwrap_or(1).max(1)
+        page_number(self.page)
     }
 
     pub fn limit(&self) -> i64 {
@@ -543,12 +1573,21 @@ impl AgentListParams {
     }
 
     pub fn offset(&self) -> i64 {
-        (self.page() - 1) * self.limit()
+        page_offset(self.page(), self.limit())
     }
 
     pub fn sort(&self) -> &str {
         self.sort.as_deref().unwrap_or("recent")
     }
+
+    pub fn search_mode(&self) -> &str {
+        self.search_mode.as_deref().unwrap_or("substring")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CapabilityParams {
+    pub chain_id: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -566,13 +1605,50 @@ impl ReputationParams {
 pub struct ActivityParams {
     pub event_type: Option<String>,
     pub chain_id: Option<i32>,
+    /// Keyset paging: rows after this `next_cursor` (global feed only; not with `page`).
+    pub before: Option<ActivityCursor>,
+    #[serde(default, deserialize_with = "deserialize_page")]
     pub page: Option<i64>,
     pub limit: Option<i64>,
+    #[serde(default)]
+    pub count_mode: CountMode,
+    /// Also read activity_log_archive (per-agent activity only).
+    #[serde(default)]
+    pub include_archived: bool,
+    /// Only events at or after this time (RFC 3339 or unix seconds)
+    #[serde(default, deserialize_with = "deserialize_time_opt")]
+    pub from: Option<DateTime<Utc>>,
+    /// Only events at or before this time
+    #[serde(default, deserialize_with = "deserialize_time_opt")]
+    pub to: Option<DateTime<Utc>>,
 }
 
 impl ActivityParams {
     pub fn page(&self) -> i64 {
-        self.page.unwrap_or(1).max(1)
+        page_number(self.page)
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(20).clamp(1, 100)
+    }
+
+    pub fn offset(&self) -> i64 {
+        page_offset(self.page(), self.limit())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeedbackResponseParams {
+    /// Only responses to this feedback
+    pub feedback_index: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_page")]
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+impl FeedbackResponseParams {
+    pub fn page(&self) -> i64 {
+        page_number(self.page)
     }
 
     pub fn limit(&self) -> i64 {
@@ -580,7 +1656,19 @@ impl ActivityParams {
     }
 
     pub fn offset(&self) -> i64 {
-        (self.page() - 1) * self.limit()
+        page_offset(self.page(), self.limit())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecentFeedbackParams {
+    pub chain_id: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+impl RecentFeedbackParams {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(20).clamp(1, 100)
     }
 }
 
@@ -589,12 +1677,20 @@ pub struct LeaderboardParams {
     pub chain_id: Option<i32>,
     pub category: Option<String>,
     pub limit: Option<i64>,
+    /// `desc` (default, top agents) or `asc` (lowest-scored agents first).
+    pub order: Option<String>,
+    /// Also rank agents marked inactive (default: active agents only).
+    #[serde(default)]
+    pub include_inactive: bool,
 }
 
 impl LeaderboardParams {
     pub fn limit(&self) -> i64 {
         self.limit.unwrap_or(50).clamp(1, 100)
     }
+    pub fn order(&self) -> &str {
+        self.order.as_deref().unwrap_or("desc")
+    }
 }
 
 // ─── Marketplace Query Parameters ────────────────────────────────────
@@ -602,26 +1698,50 @@ impl LeaderboardParams {
 #[derive(Debug, Deserialize)]
 pub struct MarketplaceListParams {
     pub chain_id: Option<i32>,
-    pub nft_contract: Option<String>,
-    pub seller: Option<String>,
-    pub status: Option<String>,
+    pub nft_contract: Option<EthAddress>,
+    pub seller: Option<EthAddress>,
+    /// Decimal token id; non-numeric values are ignored (listings only).
+    pub token_id: Option<String>,
+    pub status: Option<ListingStatus>,
+    /// Exclude one status instead of including one; mutually exclusive with `status`.
+    pub exclude_status: Option<ListingStatus>,
+    /// Leave out auctions whose end_time is not after start_time (Dutch auctions only).
+    #[serde(default)]
+    pub exclude_malformed: bool,
+    /// Only the newest matching listing of each token (listings only; not with `updated_since`).
+    #[serde(default)]
+    pub latest_per_token: bool,
+    /// RFC 3339; only rows changed after it, oldest change first (see `sync_checkpoint`)
+    pub updated_since: Option<DateTime<Utc>>,
+    /// A previous sync response's `sync_cursor`: continue after it instead of `updated_since`
+    pub sync_cursor: Option<SyncCursor>,
     pub sort: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_page")]
     pub page: Option<i64>,
     pub limit: Option<i64>,
+    #[serde(default)]
+    pub count_mode: CountMode,
 }
 
 impl MarketplaceListParams {
     pub fn page(&self) -> i64 {
-        self.page.unwrap_or(1).max(1)
+        page_number(self.page)
     }
     pub fn limit(&self) -> i64 {
         self.limit.unwrap_or(20).clamp(1, 100)
     }
     pub fn offset(&self) -> i64 {
-        (self.page() - 1) * self.limit()
+        page_offset(self.page(), self.limit())
     }
-    pub fn status(&self) -> &str {
-        self.status.as_deref().unwrap_or("Active")
+    /// Include filter: the given `status`, else `Active` unless `exclude_status` is set or
+    /// this is an `updated_since` sync (which must see listings leave Active too).
+    pub fn status(&self) -> Option<ListingStatus> {
+        match (self.status, self.exclude_status) {
+            (Some(s), _) => Some(s),
+            (None, Some(_)) => None,
+            (None, None) if self.updated_since.is_some() || self.sync_cursor.is_some() => None,
+            (None, None) => Some(ListingStatus::Active),
+        }
     }
     pub fn sort(&self) -> &str {
         self.sort.as_deref().unwrap_or("recent")
@@ -631,68 +1751,110 @@ impl MarketplaceListParams {
 #[derive(Debug, Deserialize)]
 pub struct MarketplaceOfferParams {
     pub chain_id: Option<i32>,
-    pub nft_contract: Option<String>,
+    pub nft_contract: Option<EthAddress>,
     pub token_id: Option<String>,
-    pub offerer: Option<String>,
-    pub status: Option<String>,
+    pub offerer: Option<EthAddress>,
+    pub status: Option<OfferStatus>,
+    /// Only offers expiring at or after this unix time (seconds)
+    pub expiring_after: Option<i64>,
+    /// Only offers expiring at or before this unix time (seconds)
+    pub expiring_before: Option<i64>,
+    /// RFC 3339; only rows changed after it, oldest change first (see `sync_checkpoint`)
+    pub updated_since: Option<DateTime<Utc>>,
+    /// A previous sync response's `sync_cursor`: continue after it instead of `updated_since`
+    pub sync_cursor: Option<SyncCursor>,
+    /// `recent` (default) or `expiring_soon` (Active offers first, soonest expiry first)
+    pub sort: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_page")]
     pub page: Option<i64>,
     pub limit: Option<i64>,
+    #[serde(default)]
+    pub count_mode: CountMode,
 }
 
 impl MarketplaceOfferParams {
     pub fn page(&self) -> i64 {
-        self.page.unwrap_or(1).max(1)
+        page_number(self.page)
     }
     pub fn limit(&self) -> i64 {
         self.limit.unwrap_or(20).clamp(1, 100)
     }
     pub fn offset(&self) -> i64 {
-        (self.page() - 1) * self.limit()
+        page_offset(self.page(), self.limit())
+    }
+    pub fn sort(&self) -> &str {
+        self.sort.as_deref().unwrap_or("recent")
     }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct MarketplaceCollectionOfferParams {
     pub chain_id: Option<i32>,
-    pub nft_contract: Option<String>,
-    pub offerer: Option<String>,
-    pub status: Option<String>,
+    pub nft_contract: Option<EthAddress>,
+    pub offerer: Option<EthAddress>,
+    pub status: Option<OfferStatus>,
+    /// Only offers expiring at or after this unix time (seconds)
+    pub expiring_after: Option<i64>,
+    /// Only offers expiring at or before this unix time (seconds)
+    pub expiring_before: Option<i64>,
+    /// RFC 3339; only rows changed after it, oldest change first (see `sync_checkpoint`)
+    pub updated_since: Option<DateTime<Utc>>,
+    /// A previous sync response's `sync_cursor`: continue after it instead of `updated_since`
+    pub sync_cursor: Option<SyncCursor>,
+    /// `recent` (default) or `expiring_soon` (Active offers first, soonest expiry first)
+    pub sort: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_page")]
     pub page: Option<i64>,
     pub limit: Option<i64>,
+    #[serde(default)]
+    pub count_mode: CountMode,
 }
 
 impl MarketplaceCollectionOfferParams {
     pub fn page(&self) -> i64 {
-        self.page.unwrap_or(1).max(1)
+        page_number(self.page)
     }
     pub fn limit(&self) -> i64 {
         self.limit.unwrap_or(20).clamp(1, 100)
     }
     pub fn offset(&self) -> i64 {
-        (self.page() - 1) * self.limit()
+        page_offset(self.page(), self.limit())
+    }
+    pub fn sort(&self) -> &str {
+        self.sort.as_deref().unwrap_or("recent")
     }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct MarketplaceAuctionParams {
     pub chain_id: Option<i32>,
-    pub nft_contract: Option<String>,
-    pub seller: Option<String>,
-    pub status: Option<String>,
+    pub nft_contract: Option<EthAddress>,
+    pub seller: Option<EthAddress>,
+    pub status: Option<AuctionStatus>,
+    /// Leave out auctions whose end_time is not after start_time
+    #[serde(default)]
+    pub exclude_malformed: bool,
+    /// RFC 3339; only rows changed after it, oldest change first (see `sync_checkpoint`)
+    pub updated_since: Option<DateTime<Utc>>,
+    /// A previous sync response's `sync_cursor`: continue after it instead of `updated_since`
+    pub sync_cursor: Option<SyncCursor>,
     pub sort: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_page")]
     pub page: Option<i64>,
     pub limit: Option<i64>,
+    #[serde(default)]
+    pub count_mode: CountMode,
 }
 
 impl MarketplaceAuctionParams {
     pub fn page(&self) -> i64 {
-        self.page.unwrap_or(1).max(1)
+        page_number(self.page)
     }
     pub fn limit(&self) -> i64 {
         self.limit.unwrap_or(20).clamp(1, 100)
     }
     pub fn offset(&self) -> i64 {
-        (self.page() - 1) * self.limit()
+        page_offset(self.page(), self.limit())
     }
     pub fn sort(&self) -> &str {
         self.sort.as_deref().unwrap_or("recent")
@@ -702,21 +1864,28 @@ impl MarketplaceAuctionParams {
 #[derive(Debug, Deserialize)]
 pub struct MarketplaceBundleParams {
     pub chain_id: Option<i32>,
-    pub seller: Option<String>,
-    pub status: Option<String>,
+    pub seller: Option<EthAddress>,
+    pub status: Option<ListingStatus>,
+    /// RFC 3339; only rows changed after it, oldest change first (see `sync_checkpoint`)
+    pub updated_since: Option<DateTime<Utc>>,
+    /// A previous sync response's `sync_cursor`: continue after it instead of `updated_since`
+    pub sync_cursor: Option<SyncCursor>,
+    #[serde(default, deserialize_with = "deserialize_page")]
     pub page: Option<i64>,
     pub limit: Option<i64>,
+    #[serde(default)]
+    pub count_mode: CountMode,
 }
 
 impl MarketplaceBundleParams {
     pub fn page(&self) -> i64 {
-        self.page.unwrap_or(1).max(1)
+        page_number(self.page)
     }
     pub fn limit(&self) -> i64 {
         self.limit.unwrap_or(20).clamp(1, 100)
     }
     pub fn offset(&self) -> i64 {
-        (self.page() - 1) * self.limit()
+        page_offset(self.page(), self.limit())
     }
 }
 
@@ -725,6 +1894,60 @@ pub struct MarketplaceUserParams {
     pub chain_id: Option<i32>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct MarketplaceUserOffersParams {
+    pub chain_id: Option<i32>,
+    pub status: Option<OfferStatus>,
+    #[serde(default, deserialize_with = "deserialize_page")]
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+impl MarketplaceUserOffersParams {
+    pub fn page(&self) -> i64 {
+        page_number(self.page)
+    }
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(20).clamp(1, 100)
+    }
+    pub fn offset(&self) -> i64 {
+        page_offset(self.page(), self.limit())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarketplaceCollectionActivityParams {
+    pub chain_id: Option<i32>,
+    #[serde(default, deserialize_with = "deserialize_page")]
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+impl MarketplaceCollectionActivityParams {
+    pub fn page(&self) -> i64 {
+        page_number(self.page)
+    }
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(20).clamp(1, 100)
+    }
+    pub fn offset(&self) -> i64 {
+        page_offset(self.page(), self.limit())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarketplaceCollectionStatsParams {
+    pub chain_id: Option<i32>,
+    /// 7d, 30d or all (the default)
+    pub range: Option<String>,
+}
+
+impl MarketplaceCollectionStatsParams {
+    pub fn range(&self) -> &str {
+        self.range.as_deref().unwrap_or("all")
+    }
+}
+
 // ─── Insert helpers (for DB write operations) ──────────────────────────
 
 #[derive(Debug, Clone)]
@@ -763,6 +1986,19 @@ pub struct NewFeedback {
     pub tx_hash: String,
 }
 
+/// A ResponseAppended event waiting to be written with the rest of its block range.
+#[derive(Debug, Clone)]
+pub struct NewFeedbackResponse {
+    pub feedback_index: i64,
+    pub agent_id: i64,
+    pub chain_id: i32,
+    pub response_uri: String,
+    pub block_number: i64,
+    pub block_timestamp: Option<DateTime<Utc>>,
+    pub tx_hash: String,
+    pub log_index: i32,
+}
+
 #[derive(Debug, Clone)]
 pub struct NewActivity {
     pub agent_id: i64,
@@ -775,6 +2011,19 @@ pub struct NewActivity {
     pub log_index: i32,
 }
 
+/// An identity registry Transfer (mints included) for agent_ownership_history.
+#[derive(Debug, Clone)]
+pub struct NewOwnershipTransfer {
+    pub agent_id: i64,
+    pub chain_id: i32,
+    pub from_address: String,
+    pub to_address: String,
+    pub block_number: i64,
+    pub block_timestamp: Option<DateTime<Utc>>,
+    pub tx_hash: String,
+    pub log_index: i32,
+}
+
 // ─── Marketplace Insert Helpers ─────────────────────────────────────────
 
 #[derive(Debug, Clone)]
@@ -790,6 +2039,7 @@ pub struct NewMarketplaceListing {
     pub block_number: i64,
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
 }
 
 #[derive(Debug, Clone)]
@@ -805,6 +2055,7 @@ pub struct NewMarketplaceOffer {
     pub block_number: i64,
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
 }
 
 #[derive(Debug, Clone)]
@@ -819,6 +2070,7 @@ pub struct NewMarketplaceCollectionOffer {
     pub block_number: i64,
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
 }
 
 #[derive(Debug, Clone)]
@@ -834,9 +2086,11 @@ pub struct NewMarketplaceAuction {
     pub buy_now_price: BigDecimal,
     pub start_time: i64,
     pub end_time: i64,
+    pub malformed: bool,
     pub block_number: i64,
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
 }
 
 #[derive(Debug, Clone)]
@@ -851,9 +2105,11 @@ pub struct NewMarketplaceDutchAuction {
     pub end_price: BigDecimal,
     pub start_time: i64,
     pub end_time: i64,
+    pub malformed: bool,
     pub block_number: i64,
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
 }
 
 #[derive(Debug, Clone)]
@@ -870,4 +2126,24 @@ pub struct NewMarketplaceBundle {
     pub block_number: i64,
     pub block_timestamp: Option<DateTime<Utc>>,
     pub tx_hash: String,
+    pub marketplace_address: String,
+}
+
+/// A sale event, as far as the log itself describes it. `insert_sales` fills the NFT,
+/// seller/buyer and payment token it doesn't carry from the sold entity's row.
+#[derive(Debug, Clone)]
+pub struct NewMarketplaceSale {
+    /// listing | offer | collection_offer | auction | dutch_auction | bundle
+    pub sale_type: &'static str,
+    pub entity_id: i64,
+    /// Only CollectionOfferAccepted names the token; other sales take it from the entity.
+    pub token_id: Option<BigDecimal>,
+    pub seller: Option<String>,
+    pub buyer: Option<String>,
+    /// None for offer acceptances, whose price is the offer amount.
+    pub price: Option<BigDecimal>,
+    pub block_number: i64,
+    pub block_timestamp: Option<DateTime<Utc>>,
+    pub tx_hash: String,
+    pub log_index: i32,
 }
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+/// A status string that isn't one of its enum's variants (from a DB row or `FromStr`).
+#[derive(Debug, thiserror::Error)]
+#[error("unknown {kind} '{value}', expected one of {allowed}")]
+pub struct UnknownStatus {
+    pub kind: &'static str,
+    pub value: String,
+    pub allowed: String,
+}
+
+/// A marketplace status stored as TEXT under its variant name ("Active", "Sold", ...), and
+/// written the same way in JSON and in `status` query params. Unknown values are rejected
+/// when parsing and decoding instead of becoming a state no query matches.
+macro_rules! status_enum {
+    ($(#[$meta:meta])* $name:ident { $($(#[$vmeta:meta])* $variant:ident),+ $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        pub enum $name {
+            $($(#[$vmeta])* $variant),+
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant),+];
+
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($variant)),+
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = UnknownStatus;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::ALL.iter().copied().find(|v| v.as_str() == s).ok_or_else(|| UnknownStatus {
+                    kind: stringify!($name),
+                    value: s.to_string(),
+                    allowed: Self::ALL.iter().map(|v| v.as_str()).collect::<Vec<_>>().join(", "),
+                })
+            }
+        }
+
+        impl sqlx::Type<sqlx::Postgres> for $name {
+            fn type_info() -> sqlx::postgres::PgTypeInfo {
+                <&str as sqlx::Type<sqlx::Postgres>>::type_info()
+            }
+
+            fn compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+                <&str as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+            }
+        }
+
+        impl<'q> sqlx::Encode<'q, sqlx::Postgres> for $name {
+            fn encode_by_ref(
+                &self,
+                buf: &mut sqlx::postgres::PgArgumentBuffer,
+            ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+                <&str as sqlx::Encode<'q, sqlx::Postgres>>::encode_by_ref(&self.as_str(), buf)
+            }
+        }
+
+        impl<'r> sqlx::Decode<'r, sqlx::Postgres> for $name {
+            fn decode(value: sqlx::postgres::PgValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+                Ok(<&str as sqlx::Decode<'r, sqlx::Postgres>>::decode(value)?.parse()?)
+            }
+        }
+    };
+}
+
+status_enum! {
+    /// Status of a fixed-price sale: listings, Dutch auctions and bundles.
+    ListingStatus {
+        Active,
+        Sold,
+        Cancelled,
+    }
+}
+
+status_enum! {
+    /// Status of an offer or collection offer.
+    OfferStatus {
+        Active,
+        Accepted,
+        Cancelled,
+    }
+}
+
+status_enum! {
+    /// Status of an English auction. A buy-now or a settlement with a winner is `Ended`;
+    /// settling below the reserve is `ReserveNotMet`.
+    AuctionStatus {
+        Active,
+        Ended,
+        Cancelled,
+        ReserveNotMet,
+    }
+}
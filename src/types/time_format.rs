@@ -0,0 +1,81 @@
+use std::future::Future;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+tokio::task_local! {
+    /// Output format for time fields in the current request (see `with_time_format`).
+    static TIME_FORMAT: TimeFormat;
+}
+
+/// How response time fields (`block_timestamp`, `created_at`, ...) are written, chosen per
+/// request with `?time_format=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimeFormat {
+    /// RFC 3339 strings (the default)
+    #[default]
+    Rfc3339,
+    /// Integer epoch seconds
+    Unix,
+}
+
+impl TimeFormat {
+    /// Parse a `time_format` query value. None for anything other than `rfc3339` / `unix`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "rfc3339" => Some(TimeFormat::Rfc3339),
+            "unix" => Some(TimeFormat::Unix),
+            _ => None,
+        }
+    }
+}
+
+/// Run `fut` (a request, including serializing its response) with time fields written in
+/// `format`. The format is fixed for the whole future, so one payload never mixes formats.
+pub async fn with_time_format<F: Future>(format: TimeFormat, fut: F) -> F::Output {
+    TIME_FORMAT.scope(format, fut).await
+}
+
+/// Serialize a time field in the current request's format. Outside `with_time_format`
+/// (background tasks, tests) it is RFC 3339.
+pub fn serialize_time_opt<S: serde::Serializer>(
+    value: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let format = TIME_FORMAT.try_with(|f| *f).unwrap_or_default();
+    match (value, format) {
+        (Some(ts), TimeFormat::Unix) => serializer.serialize_i64(ts.timestamp()),
+        (value, _) => value.serialize(serializer),
+    }
+}
+
+/// Serialize a calendar date (e.g. a reputation history point): `YYYY-MM-DD`, or epoch
+/// seconds of its UTC midnight in unix mode.
+pub fn serialize_date<S: serde::Serializer>(value: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+    let format = TIME_FORMAT.try_with(|f| *f).unwrap_or_default();
+    match format {
+        TimeFormat::Unix => serializer.serialize_i64(value.and_time(chrono::NaiveTime::MIN).and_utc().timestamp()),
+        TimeFormat::Rfc3339 => value.serialize(serializer),
+    }
+}
+
+/// Parse a time given as input (e.g. the activity `from` / `to` params): an RFC 3339
+/// timestamp or integer epoch seconds, so either `time_format` round-trips.
+pub fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    match value.parse::<i64>() {
+        Ok(secs) => DateTime::from_timestamp(secs, 0),
+        Err(_) => DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(&Utc)),
+    }
+}
+
+/// Deserialize an optional time query param with `parse_time`.
+pub fn deserialize_time_opt<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(raw) => parse_time(&raw).map(Some).ok_or_else(|| {
+            serde::de::Error::custom(format!("invalid time '{}', expected RFC 3339 or unix seconds", raw))
+        }),
+    }
+}
@@ -0,0 +1,338 @@
+//! HTTP test harness: the real axum app on a real pool, driven with `oneshot`.
+//!
+//! Each test owns a chain id: `TestApp::new` runs the migrations and deletes that chain's rows,
+//! and the seed helpers write through the crate's own db functions (so agent_stats, normalized
+//! values etc. are maintained the way the indexer maintains them). Endpoint tests are then a
+//! few seeds plus `app.get(..)`.
+//!
+//! Needs a disposable Postgres database: `TEST_DATABASE_URL=postgres://...`. Without it
+//! `TestApp::new` returns None and the test returns early.
+//!
+//! `rpc` is the indexer-side counterpart: a mock JSON-RPC node for the index_*_events functions.
+
+#![allow(dead_code)]
+
+pub mod rpc;
+
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{HeaderMap, Request, StatusCode};
+use axum::Router;
+use bigdecimal::BigDecimal;
+use molt_marketplace_backend::api::stats::StatsCache;
+use molt_marketplace_backend::config::Config;
+use molt_marketplace_backend::db;
+use molt_marketplace_backend::startup::Startup;
+use molt_marketplace_backend::indexer::metadata::{MetadataQueue, METADATA_FETCH_CONCURRENCY, METADATA_QUEUE_CAPACITY};
+use molt_marketplace_backend::types::{
+    NewActivity, NewAgent, NewFeedback, NewMarketplaceAuction, NewMarketplaceListing, NewMarketplaceSale,
+};
+use molt_marketplace_backend::{app, AppState};
+use serde_json::Value;
+use sqlx::PgPool;
+use tower::ServiceExt;
+
+/// Marketplace contract the seed helpers' listings, auctions, bids and sales belong to.
+pub const SEED_MARKETPLACE: &str = "0x3333333333333333333333333333333333333333";
+
+/// Tables cleared for the test's chain before it runs.
+const CHAIN_TABLES: &[&str] = &[
+    "agents",
+    "feedbacks",
+    "feedback_responses",
+    "agent_stats",
+    "activity_log",
+    "activity_log_archive",
+    "agent_ownership_history",
+    "marketplace_listings",
+    "marketplace_offers",
+    "marketplace_collection_offers",
+    "marketplace_auctions",
+    "marketplace_auction_bids",
+    "marketplace_dutch_auctions",
+    "marketplace_bundles",
+    "marketplace_sales",
+    "marketplace_config",
+    "marketplace_payment_tokens",
+    "indexer_state",
+];
+
+pub struct TestApp {
+    pub pool: PgPool,
+    pub chain_id: i32,
+    pub state: AppState,
+    app: Router,
+}
+
+/// Migrate TEST_DATABASE_URL and clear `chain_id`'s rows; None when the variable isn't set.
+pub async fn test_pool(chain_id: i32) -> Option<PgPool> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+
+    for table in CHAIN_TABLES {
+        // SAFETY: table names are the fixed list above
+        sqlx::query(&format!("DELETE FROM {table} WHERE chain_id = $1"))
+            .bind(chain_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+    Some(pool)
+}
+
+/// The config the app gets from an environment with only DATABASE_URL set (the app itself
+/// connects through the pool it is given, not this URL).
+pub fn test_config() -> Config {
+    Config::from_lookup(&|key| (key == "DATABASE_URL").then(|| "postgres://localhost/test".to_string())).unwrap()
+}
+
+impl TestApp {
+    /// Migrate TEST_DATABASE_URL, clear `chain_id`'s rows and build the app on its pool.
+    pub async fn new(chain_id: i32) -> Option<Self> {
+        let pool = test_pool(chain_id).await?;
+        Some(Self::on_pool(pool, chain_id))
+    }
+
+    /// The app on a pool pointed at a closed port, for database-down behavior. Needs no
+    /// database, so tests using it always run.
+    pub fn unreachable() -> Self {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_secs(1))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+            .expect("lazy pool");
+        Self::on_pool(pool, 0)
+    }
+
+    fn on_pool(pool: PgPool, chain_id: i32) -> Self {
+        let config = test_config();
+        let state = AppState {
+            stats_cache: Arc::new(StatsCache::new(config.stats_cache_ttl)),
+            config: Arc::new(config),
+            db: db::replica::Db::new(pool.clone(), None),
+            startup: Arc::new(Startup::ready()),
+            chain_ids: Arc::from([chain_id]),
+            indexer_flags: Arc::new(HashMap::new()),
+            indexer_paused: Arc::new(AtomicBool::new(false)),
+            indexer_enabled: false,
+            pools: Arc::new(db::pool::PoolMonitor::new(pool.clone(), pool.clone(), None)),
+            http_metrics: Default::default(),
+            admin_api_key: None,
+            metadata_queue: MetadataQueue::start(pool.clone(), METADATA_QUEUE_CAPACITY, METADATA_FETCH_CONCURRENCY),
+        };
+        TestApp { app: app(state.clone()), state, pool, chain_id }
+    }
+
+    /// Rebuild the app after adjusting its state (e.g. to enable the indexer).
+    pub fn with_state(mut self, adjust: impl FnOnce(&mut AppState)) -> Self {
+        adjust(&mut self.state);
+        self.app = app(self.state.clone());
+        self
+    }
+
+    /// Flip the startup status main.rs sets once migrations are done.
+    pub fn set_ready(&self, ready: bool) {
+        if ready {
+            self.state.startup.set_ready();
+        } else {
+            self.state.startup.set_starting();
+        }
+    }
+
+    /// GET `uri` and parse the body as JSON (Null when it isn't JSON).
+    pub async fn get(&self, uri: &str) -> (StatusCode, Value) {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        let (status, _, body) = self.send(request).await;
+        (status, body)
+    }
+
+    /// GET `uri` and return the body as text (for /metrics).
+    pub async fn get_text(&self, uri: &str) -> (StatusCode, String) {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        let response = self.app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    /// Send `request`, returning the status, headers and the body parsed as JSON.
+    pub async fn send(&self, request: Request<Body>) -> (StatusCode, HeaderMap, Value) {
+        let (status, headers, body) = self.send_raw(request).await;
+        (status, headers, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    /// Send `request`, returning the status, headers and the body bytes as sent (still
+    /// compressed if the response was).
+    pub async fn send_raw(&self, request: Request<Body>) -> (StatusCode, HeaderMap, Vec<u8>) {
+        let response = self.app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, headers, body.to_vec())
+    }
+
+    /// An active agent with a name, description and categories.
+    pub async fn seed_agent(&self, agent_id: i64, name: &str, owner: &str, categories: &[&str]) {
+        let agent = NewAgent {
+            agent_id,
+            chain_id: self.chain_id,
+            owner: owner.to_string(),
+            uri: None,
+            metadata: None,
+            name: Some(name.to_string()),
+            description: Some(format!("{name} test agent")),
+            image: None,
+            categories: Some(categories.iter().map(|c| c.to_string()).collect()),
+            x402_support: false,
+            active: true,
+            block_number: Some(agent_id),
+            block_timestamp: None,
+            tx_hash: Some(format!("0xagent{agent_id}")),
+        };
+        db::agents::upsert_agent(&self.pool, &agent).await.unwrap();
+    }
+
+    /// A whole-number feedback from `client`, with agent_stats refreshed like the indexer does.
+    pub async fn seed_feedback(&self, agent_id: i64, client: &str, feedback_index: i64, value: i64) {
+        self.seed_tagged_feedback(agent_id, client, feedback_index, value, None, None).await;
+    }
+
+    /// `seed_feedback` with tag1/tag2 set.
+    pub async fn seed_tagged_feedback(
+        &self,
+        agent_id: i64,
+        client: &str,
+        feedback_index: i64,
+        value: i64,
+        tag1: Option<&str>,
+        tag2: Option<&str>,
+    ) {
+        let feedback = NewFeedback {
+            agent_id,
+            chain_id: self.chain_id,
+            client_address: client.to_string(),
+            feedback_index,
+            value: BigDecimal::from(value),
+            value_decimals: 0,
+            tag1: tag1.map(str::to_string),
+            tag2: tag2.map(str::to_string),
+            endpoint: None,
+            feedback_uri: None,
+            feedback_hash: None,
+            block_number: 1_000 + feedback_index,
+            block_timestamp: None,
+            tx_hash: format!("0xfeedback{agent_id}-{client}-{feedback_index}"),
+        };
+        let mut tx = self.pool.begin().await.unwrap();
+        db::feedbacks::insert_feedbacks(&mut tx, &[feedback]).await.unwrap();
+        db::feedbacks::refresh_agent_stats(&mut tx, self.chain_id, &[agent_id]).await.unwrap();
+        tx.commit().await.unwrap();
+    }
+
+    /// An Active listing of agent `token_id` on `nft_contract`.
+    pub async fn seed_listing(&self, listing_id: i64, nft_contract: &str, token_id: i64, price: i64) {
+        let listing = NewMarketplaceListing {
+            listing_id,
+            chain_id: self.chain_id,
+            seller: "0x00000000000000000000000000000000000000aa".to_string(),
+            nft_contract: nft_contract.to_string(),
+            token_id: BigDecimal::from(token_id),
+            payment_token: "0x0000000000000000000000000000000000000000".to_string(),
+            price: BigDecimal::from(price),
+            expiry: 0,
+            block_number: 2_000 + listing_id,
+            block_timestamp: None,
+            tx_hash: format!("0xlisting{listing_id}"),
+            marketplace_address: SEED_MARKETPLACE.to_string(),
+        };
+        db::marketplace::upsert_listing(&self.pool, &listing).await.unwrap();
+    }
+
+    /// An Active English auction of `token_id` on `nft_contract`, with no reserve or buy-now.
+    pub async fn seed_auction(&self, auction_id: i64, nft_contract: &str, token_id: i64, start_price: i64) {
+        let auction = NewMarketplaceAuction {
+            auction_id,
+            chain_id: self.chain_id,
+            seller: "0x00000000000000000000000000000000000000aa".to_string(),
+            nft_contract: nft_contract.to_string(),
+            token_id: BigDecimal::from(token_id),
+            payment_token: "0x0000000000000000000000000000000000000000".to_string(),
+            start_price: BigDecimal::from(start_price),
+            reserve_price: BigDecimal::from(0),
+            buy_now_price: BigDecimal::from(0),
+            start_time: 0,
+            end_time: i64::from(i32::MAX),
+            malformed: false,
+            block_number: 3_000 + auction_id,
+            block_timestamp: None,
+            tx_hash: format!("0xauction{auction_id}"),
+            marketplace_address: SEED_MARKETPLACE.to_string(),
+        };
+        db::marketplace::upsert_auction(&self.pool, &auction).await.unwrap();
+    }
+
+    /// A bid on auction `auction_id`, also raising the auction's highest bid.
+    pub async fn seed_bid(&self, auction_id: i64, bidder: &str, amount: i64, block_number: i64) {
+        let amount = BigDecimal::from(amount);
+        let tx_hash = format!("0xbid{auction_id}-{block_number}");
+        db::marketplace::insert_auction_bid(
+            &self.pool, auction_id, self.chain_id, SEED_MARKETPLACE, bidder, &amount, block_number, None, &tx_hash, 0,
+        )
+        .await
+        .unwrap();
+        db::marketplace::update_auction_bid(&self.pool, auction_id, self.chain_id, SEED_MARKETPLACE, &amount, bidder).await.unwrap();
+    }
+
+    /// A sale of a seeded listing/auction/... (`sale_type`), NFT details taken from that row.
+    pub async fn seed_sale(&self, sale_type: &'static str, entity_id: i64, buyer: &str, price: i64, block_number: i64) {
+        let sale = NewMarketplaceSale {
+            sale_type,
+            entity_id,
+            token_id: None,
+            seller: None,
+            buyer: Some(buyer.to_string()),
+            price: Some(BigDecimal::from(price)),
+            block_number,
+            block_timestamp: None,
+            tx_hash: format!("0xsale{sale_type}{entity_id}"),
+            log_index: 0,
+        };
+        db::marketplace::insert_sales(&self.pool, self.chain_id, SEED_MARKETPLACE, &[sale]).await.unwrap();
+    }
+
+    /// A response to feedback `feedback_index` of `agent_id`, at `block_number`.
+    pub async fn seed_response(&self, agent_id: i64, feedback_index: i64, block_number: i64) {
+        sqlx::query(
+            "INSERT INTO feedback_responses (feedback_id, agent_id, chain_id, response_uri, block_number, tx_hash)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(feedback_index)
+        .bind(agent_id)
+        .bind(self.chain_id)
+        .bind(format!("ipfs://response{feedback_index}-{block_number}"))
+        .bind(block_number)
+        .bind(format!("0xresponse{agent_id}-{block_number}"))
+        .execute(&self.pool)
+        .await
+        .unwrap();
+    }
+
+    /// An activity_log row; `block_number` orders the feed.
+    pub async fn seed_activity(&self, agent_id: i64, event_type: &str, block_number: i64) {
+        let activity = NewActivity {
+            agent_id,
+            chain_id: self.chain_id,
+            event_type: event_type.to_string(),
+            event_data: None,
+            block_number,
+            block_timestamp: None,
+            tx_hash: format!("0xactivity{agent_id}-{block_number}"),
+            log_index: 0,
+        };
+        db::activity::insert_activities(&self.pool, &[activity]).await.unwrap();
+    }
+}
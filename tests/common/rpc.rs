@@ -0,0 +1,238 @@
+//! Mock JSON-RPC node for the indexer: an axum server on a random local port that answers
+//! the methods `index_*_events` and the chain loop use from canned data.
+//!
+//! - `eth_blockNumber`: the head set with `set_head` (0 until then)
+//! - `eth_getLogs`: the pushed logs matching the filter's address, block range and topic0s
+//! - `eth_getBlockByNumber`: an empty block whose timestamp is `block_timestamp(n)`
+//! - `eth_call`: the return data registered with `on_call` for (to, selector)
+//!
+//! Anything else is a JSON-RPC error, so an unexpected call shows up as a failed lookup.
+//! Every request's method is recorded for `calls`. After `stall`, requests are recorded but
+//! never answered, like a node that accepts connections and then hangs. `hold_logs_from`
+//! holds back `eth_getLogs` answers from a block on until `release`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use alloy::primitives::{Address, Bytes, B256, U256};
+use alloy::rpc::types::{Block, Log};
+use alloy::sol_types::{SolCall, SolEvent};
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use molt_marketplace_backend::indexer::provider::{self, ChainConfig, HttpProvider};
+use serde_json::{json, Value};
+
+/// Contract addresses of the chain `MockRpc::chain` describes.
+pub const IDENTITY: Address = Address::repeat_byte(0x11);
+pub const REPUTATION: Address = Address::repeat_byte(0x22);
+pub const MARKETPLACE: Address = Address::repeat_byte(0x33);
+
+/// Unix timestamp the mock reports for block `n`.
+pub fn block_timestamp(n: u64) -> DateTime<Utc> {
+    DateTime::from_timestamp(1_700_000_000 + n as i64, 0).unwrap()
+}
+
+#[derive(Default)]
+struct Canned {
+    logs: Vec<Log>,
+    calls: HashMap<(Address, [u8; 4]), Bytes>,
+    head: u64,
+    methods: Vec<String>,
+    stalled: bool,
+    logs_held_from: Option<u64>,
+}
+
+#[derive(Clone)]
+pub struct MockRpc {
+    url: String,
+    canned: Arc<Mutex<Canned>>,
+}
+
+impl MockRpc {
+    /// Start serving on 127.0.0.1; the server lives until the test's runtime shuts down.
+    pub async fn start() -> Self {
+        let canned = Arc::new(Mutex::new(Canned::default()));
+        let app = Router::new().route("/", post(handle)).with_state(canned.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        MockRpc { url, canned }
+    }
+
+    /// A chain with the mock's contracts, indexing from block 0, pointed at this server.
+    pub fn chain(&self, chain_id: i32) -> ChainConfig {
+        ChainConfig {
+            chain_id,
+            rpc_url: self.url.clone(),
+            identity_address: IDENTITY,
+            reputation_address: REPUTATION,
+            marketplace_address: Some(MARKETPLACE),
+            start_block: 0,
+            reputation_start_block: None,
+            marketplace_start_block: None,
+            dry_run: false,
+            skip_block_timestamps: false,
+            confirmations: 0,
+        }
+    }
+
+    pub fn provider(&self, chain: &ChainConfig) -> HttpProvider {
+        provider::create_provider(chain).unwrap()
+    }
+
+    /// Emit `event` from `address` at (`block_number`, `log_index`); the tx hash is derived
+    /// from both so every log gets its own.
+    pub fn push_log<E: SolEvent>(&self, address: Address, event: &E, block_number: u64, log_index: u64) {
+        let mut tx_hash = [0u8; 32];
+        tx_hash[..8].copy_from_slice(&block_number.to_be_bytes());
+        tx_hash[8..16].copy_from_slice(&log_index.to_be_bytes());
+        let log = Log {
+            inner: alloy::primitives::Log { address, data: event.encode_log_data() },
+            block_hash: Some(B256::from(U256::from(block_number))),
+            block_number: Some(block_number),
+            block_timestamp: None,
+            transaction_hash: Some(B256::from(tx_hash)),
+            transaction_index: Some(0),
+            log_index: Some(log_index),
+            removed: false,
+        };
+        self.canned.lock().unwrap().logs.push(log);
+    }
+
+    /// Answer `C` calls to `to` with `returns`, whatever the arguments.
+    pub fn on_call<C: SolCall>(&self, to: Address, returns: &C::Return) {
+        let data = Bytes::from(C::abi_encode_returns(returns));
+        self.canned.lock().unwrap().calls.insert((to, C::SELECTOR), data);
+    }
+
+    /// Report `block_number` as the chain head.
+    pub fn set_head(&self, block_number: u64) {
+        self.canned.lock().unwrap().head = block_number;
+    }
+
+    /// Leave every later request hanging.
+    pub fn stall(&self) {
+        self.canned.lock().unwrap().stalled = true;
+    }
+
+    /// Leave `eth_getLogs` requests starting at or after `block_number` unanswered until
+    /// `release`.
+    pub fn hold_logs_from(&self, block_number: u64) {
+        self.canned.lock().unwrap().logs_held_from = Some(block_number);
+    }
+
+    /// Answer the requests `hold_logs_from` is holding.
+    pub fn release(&self) {
+        self.canned.lock().unwrap().logs_held_from = None;
+    }
+
+    /// How many requests for `method` the server has received.
+    pub fn calls(&self, method: &str) -> usize {
+        self.canned.lock().unwrap().methods.iter().filter(|m| *m == method).count()
+    }
+}
+
+async fn handle(State(canned): State<Arc<Mutex<Canned>>>, Json(request): Json<Value>) -> Json<Value> {
+    let stalled = {
+        let mut canned = canned.lock().unwrap();
+        if canned.stalled {
+            canned.methods.push(request["method"].as_str().unwrap_or_default().to_string());
+        }
+        canned.stalled
+    };
+    if stalled {
+        std::future::pending::<()>().await;
+    }
+    while is_held(&canned.lock().unwrap(), &request) {
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    // alloy sends single requests, but answer batches too
+    match request {
+        Value::Array(requests) => Json(Value::Array(requests.iter().map(|r| answer(&canned, r)).collect())),
+        request => Json(answer(&canned, &request)),
+    }
+}
+
+fn is_held(canned: &Canned, request: &Value) -> bool {
+    canned.logs_held_from.is_some_and(|held_from| {
+        request["method"] == "eth_getLogs" && quantity(&request["params"][0]["fromBlock"]).is_some_and(|from| from >= held_from)
+    })
+}
+
+fn answer(canned: &Mutex<Canned>, request: &Value) -> Value {
+    let method = request["method"].as_str().unwrap_or_default();
+    let params = &request["params"];
+    let mut canned = canned.lock().unwrap();
+    canned.methods.push(method.to_string());
+
+    let result = match method {
+        "eth_blockNumber" => Ok(json!(format!("{:#x}", canned.head))),
+        "eth_getLogs" => Ok(json!(matching_logs(&canned.logs, &params[0]))),
+        "eth_getBlockByNumber" => {
+            let number = quantity(&params[0]).unwrap_or_default();
+            let mut block: Block = Block::default();
+            block.header.inner.number = number;
+            block.header.inner.timestamp = block_timestamp(number).timestamp() as u64;
+            Ok(json!(block))
+        }
+        "eth_call" => {
+            let call = &params[0];
+            let to: Option<Address> = call["to"].as_str().and_then(|a| a.parse().ok());
+            let input: Option<Bytes> = call["input"]
+                .as_str()
+                .or(call["data"].as_str())
+                .and_then(|d| d.parse().ok());
+            match (to, input) {
+                (Some(to), Some(input)) if input.len() >= 4 => {
+                    let selector: [u8; 4] = input[..4].try_into().unwrap();
+                    canned.calls.get(&(to, selector)).map(|data| json!(data)).ok_or("no canned eth_call")
+                }
+                _ => Err("malformed eth_call"),
+            }
+        }
+        _ => Err("method not mocked"),
+    };
+
+    match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": request["id"], "result": result}),
+        Err(message) => json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "error": {"code": -32601, "message": format!("{method}: {message}")},
+        }),
+    }
+}
+
+/// The logs an eth_getLogs filter selects: address (one or a list), fromBlock..=toBlock and
+/// the topic0 alternatives.
+fn matching_logs(logs: &[Log], filter: &Value) -> Vec<Log> {
+    let addresses: Vec<Address> = match &filter["address"] {
+        Value::String(a) => a.parse().into_iter().collect(),
+        Value::Array(list) => list.iter().filter_map(|a| a.as_str()?.parse().ok()).collect(),
+        _ => Vec::new(),
+    };
+    let from = quantity(&filter["fromBlock"]).unwrap_or(0);
+    let to = quantity(&filter["toBlock"]).unwrap_or(u64::MAX);
+    let topic0: Vec<B256> = match &filter["topics"][0] {
+        Value::String(t) => t.parse().into_iter().collect(),
+        Value::Array(list) => list.iter().filter_map(|t| t.as_str()?.parse().ok()).collect(),
+        _ => Vec::new(),
+    };
+
+    let mut out: Vec<Log> = logs
+        .iter()
+        .filter(|log| addresses.is_empty() || addresses.contains(&log.address()))
+        .filter(|log| (from..=to).contains(&log.block_number.unwrap_or(0)))
+        .filter(|log| topic0.is_empty() || log.topic0().is_some_and(|t| topic0.contains(t)))
+        .cloned()
+        .collect();
+    out.sort_by_key(|log| (log.block_number, log.log_index));
+    out
+}
+
+/// A hex block number ("0x1a"); tags like "latest" aren't mocked.
+fn quantity(value: &Value) -> Option<u64> {
+    u64::from_str_radix(value.as_str()?.strip_prefix("0x")?, 16).ok()
+}
@@ -0,0 +1,106 @@
+//! Database checks for get_activity_span (src/db/activity.rs): first_seen / last_active are the
+//! min / max event time for one agent across activity_log and activity_log_archive, and both
+//! are NULL for an agent without activity.
+//!
+//! Like explain_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_activity_span_tests -- --ignored`
+//!
+//! Everything runs inside a transaction that is rolled back, so only the migrations persist.
+
+use chrono::{DateTime, TimeZone, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+
+const CHAIN_ID: i32 = 999_007;
+const OTHER_CHAIN_ID: i32 = 999_008;
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+    let tx = pool.begin().await.unwrap();
+    Some((pool, tx))
+}
+
+fn day(d: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, d, 0, 0, 0).unwrap()
+}
+
+/// (table, id, agent_id, chain_id, block_timestamp, created_at)
+type ActivityRow = (&'static str, i32, i64, i32, Option<DateTime<Utc>>, DateTime<Utc>);
+
+fn fixture() -> Vec<ActivityRow> {
+    vec![
+        // Agent 1: oldest event archived without a block_timestamp (falls back to created_at);
+        // activity_log rows always have one since migrations/031
+        ("activity_log_archive", 900_001, 1, CHAIN_ID, None, day(2)),
+        ("activity_log", 900_002, 1, CHAIN_ID, Some(day(10)), day(10)),
+        ("activity_log", 900_003, 1, CHAIN_ID, Some(day(20)), day(20)),
+        // Other agents' events on either side of agent 1's range don't count
+        ("activity_log", 900_004, 2, CHAIN_ID, Some(day(1)), day(1)),
+        ("activity_log", 900_005, 1, OTHER_CHAIN_ID, Some(day(25)), day(25)),
+    ]
+}
+
+async fn insert_fixture(tx: &mut Transaction<'static, Postgres>) {
+    for (i, (table, id, agent_id, chain_id, block_timestamp, created_at)) in fixture().into_iter().enumerate() {
+        let query = format!(
+            "INSERT INTO {table} (id, agent_id, chain_id, event_type, block_number, tx_hash, log_index, created_at, block_timestamp)
+             VALUES ($1, $2, $3, 'URIUpdated', 1, '0xspan', $4, $5, $6)"
+        );
+        sqlx::query(&query)
+            .bind(id)
+            .bind(agent_id)
+            .bind(chain_id)
+            .bind(i as i32)
+            .bind(created_at)
+            .bind(block_timestamp)
+            .execute(&mut **tx)
+            .await
+            .unwrap();
+    }
+}
+
+/// Same statement as get_activity_span in src/db/activity.rs
+async fn span(
+    tx: &mut Transaction<'static, Postgres>,
+    agent_id: i64,
+    chain_id: i32,
+) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+    sqlx::query_as(
+        r#"
+        SELECT MIN(COALESCE(block_timestamp, created_at)) AS first_seen,
+               MAX(COALESCE(block_timestamp, created_at)) AS last_active
+        FROM (SELECT id, agent_id, chain_id, event_type, event_data, block_number, tx_hash, log_index, created_at, block_timestamp FROM activity_log
+              UNION ALL
+              SELECT id, agent_id, chain_id, event_type, event_data, block_number, tx_hash, log_index, created_at, block_timestamp FROM activity_log_archive) AS activity_log
+        WHERE agent_id = $1 AND chain_id = $2
+        "#,
+    )
+    .bind(agent_id)
+    .bind(chain_id)
+    .fetch_one(&mut **tx)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn min_and_max_across_live_and_archive() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_fixture(&mut tx).await;
+
+    assert_eq!(span(&mut tx, 1, CHAIN_ID).await, (Some(day(2)), Some(day(20))));
+    assert_eq!(span(&mut tx, 2, CHAIN_ID).await, (Some(day(1)), Some(day(1))));
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn null_without_activity() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_fixture(&mut tx).await;
+
+    assert_eq!(span(&mut tx, 3, CHAIN_ID).await, (None, None));
+    assert_eq!(span(&mut tx, 2, OTHER_CHAIN_ID).await, (None, None));
+    tx.rollback().await.unwrap();
+}
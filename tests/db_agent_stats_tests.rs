@@ -0,0 +1,394 @@
+//! Database checks for the agent_stats reputation cache (src/db/feedbacks.rs, src/db/agents.rs):
+//! a new feedback refreshes only its agent's row, a full rebuild reproduces the incremental
+//! rows, agents without a row read live aggregates, and the indexer's own insert / revoke /
+//! refresh functions keep the row equal to a live aggregate over feedbacks, also when two
+//! transactions refresh the same agent at once.
+//!
+//! Like explain_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_agent_stats_tests -- --ignored`
+//!
+//! Everything runs inside a transaction that is rolled back, so only the migrations persist,
+//! except the concurrency test, which commits on its own chain id and cleans up after itself.
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use molt_marketplace_backend::db::feedbacks::{insert_feedbacks, refresh_agent_stats, revoke_feedbacks};
+use molt_marketplace_backend::types::NewFeedback;
+use sqlx::{PgPool, Postgres, Transaction};
+
+const CHAIN_ID: i32 = 999_013;
+/// For the test that has to commit: its rows are deleted before and after
+const CONCURRENT_CHAIN_ID: i32 = 999_014;
+
+/// (feedback_count, avg_score, positive_count, negative_count, unique_clients)
+type StatsRow = (i64, Option<f64>, i64, i64, i64);
+
+/// Every agent_stats column: StatsRow plus score_sum and last_feedback_at
+type FullStatsRow = (i64, Option<f64>, Option<f64>, i64, i64, i64, Option<DateTime<Utc>>);
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+    let tx = pool.begin().await.unwrap();
+    Some((pool, tx))
+}
+
+/// insert_feedbacks' statement for a single (agent_id, client, feedback_index, value) row
+async fn insert_feedback(tx: &mut Transaction<'static, Postgres>, agent_id: i64, client: &str, index: i64, value: i64) {
+    sqlx::query(
+        r#"
+        INSERT INTO feedbacks (agent_id, chain_id, client_address, feedback_index, value, value_decimals, value_normalized, block_number, tx_hash)
+        VALUES ($1, $2, $3, $4, $5, 0, $5 / POWER(10, 0), $4, '0xstats')
+        "#,
+    )
+    .bind(agent_id)
+    .bind(CHAIN_ID)
+    .bind(client)
+    .bind(index)
+    .bind(BigDecimal::from(value))
+    .execute(&mut **tx)
+    .await
+    .unwrap();
+}
+
+/// Same statement as refresh_agent_stats in src/db/feedbacks.rs
+async fn refresh(tx: &mut Transaction<'static, Postgres>, agent_ids: &[i64]) {
+    sqlx::query(
+        r#"
+        INSERT INTO agent_stats (agent_id, chain_id, feedback_count, score_sum, avg_score, positive_count, negative_count, unique_clients, last_feedback_at)
+        SELECT
+            t.agent_id,
+            $2,
+            COUNT(CASE WHEN f.revoked = false THEN 1 ELSE NULL END),
+            SUM(CASE WHEN f.revoked = false THEN f.value_normalized ELSE NULL END)::FLOAT8,
+            AVG(CASE WHEN f.revoked = false THEN f.value_normalized ELSE NULL END)::FLOAT8,
+            COUNT(CASE WHEN f.revoked = false AND f.value_normalized >= 3 THEN 1 ELSE NULL END),
+            COUNT(CASE WHEN f.revoked = false AND f.value_normalized < 3 THEN 1 ELSE NULL END),
+            COUNT(DISTINCT CASE WHEN f.revoked = false THEN f.client_address ELSE NULL END),
+            MAX(CASE WHEN f.revoked = false THEN f.created_at ELSE NULL END)
+        FROM UNNEST($1::BIGINT[]) AS t(agent_id)
+        LEFT JOIN feedbacks f ON f.agent_id = t.agent_id AND f.chain_id = $2
+        GROUP BY t.agent_id
+        ON CONFLICT (agent_id, chain_id) DO UPDATE SET
+            feedback_count = EXCLUDED.feedback_count,
+            score_sum = EXCLUDED.score_sum,
+            avg_score = EXCLUDED.avg_score,
+            positive_count = EXCLUDED.positive_count,
+            negative_count = EXCLUDED.negative_count,
+            unique_clients = EXCLUDED.unique_clients,
+            last_feedback_at = EXCLUDED.last_feedback_at
+        "#,
+    )
+    .bind(agent_ids)
+    .bind(CHAIN_ID)
+    .execute(&mut **tx)
+    .await
+    .unwrap();
+}
+
+/// Same statement as rebuild_agent_stats in src/db/feedbacks.rs
+async fn rebuild(tx: &mut Transaction<'static, Postgres>) -> u64 {
+    sqlx::query(
+        r#"
+        INSERT INTO agent_stats (agent_id, chain_id, feedback_count, score_sum, avg_score, positive_count, negative_count, unique_clients, last_feedback_at)
+        SELECT
+            t.agent_id,
+            t.chain_id,
+            COUNT(CASE WHEN f.revoked = false THEN 1 ELSE NULL END),
+            SUM(CASE WHEN f.revoked = false THEN f.value_normalized ELSE NULL END)::FLOAT8,
+            AVG(CASE WHEN f.revoked = false THEN f.value_normalized ELSE NULL END)::FLOAT8,
+            COUNT(CASE WHEN f.revoked = false AND f.value_normalized >= 3 THEN 1 ELSE NULL END),
+            COUNT(CASE WHEN f.revoked = false AND f.value_normalized < 3 THEN 1 ELSE NULL END),
+            COUNT(DISTINCT CASE WHEN f.revoked = false THEN f.client_address ELSE NULL END),
+            MAX(CASE WHEN f.revoked = false THEN f.created_at ELSE NULL END)
+        FROM (
+            SELECT agent_id, chain_id FROM feedbacks WHERE $1::INT IS NULL OR chain_id = $1
+            UNION
+            SELECT agent_id, chain_id FROM agent_stats WHERE $1::INT IS NULL OR chain_id = $1
+        ) t
+        LEFT JOIN feedbacks f ON f.agent_id = t.agent_id AND f.chain_id = t.chain_id
+        GROUP BY t.agent_id, t.chain_id
+        ON CONFLICT (agent_id, chain_id) DO UPDATE SET
+            feedback_count = EXCLUDED.feedback_count,
+            score_sum = EXCLUDED.score_sum,
+            avg_score = EXCLUDED.avg_score,
+            positive_count = EXCLUDED.positive_count,
+            negative_count = EXCLUDED.negative_count,
+            unique_clients = EXCLUDED.unique_clients,
+            last_feedback_at = EXCLUDED.last_feedback_at
+        "#,
+    )
+    .bind(Some(CHAIN_ID))
+    .execute(&mut **tx)
+    .await
+    .unwrap()
+    .rows_affected()
+}
+
+async fn cached(tx: &mut Transaction<'static, Postgres>, agent_id: i64) -> Option<StatsRow> {
+    sqlx::query_as(
+        r#"
+        SELECT feedback_count, avg_score, positive_count, negative_count, unique_clients
+        FROM agent_stats WHERE agent_id = $1 AND chain_id = $2
+        "#,
+    )
+    .bind(agent_id)
+    .bind(CHAIN_ID)
+    .fetch_optional(&mut **tx)
+    .await
+    .unwrap()
+}
+
+/// The reputation columns and STATS_JOIN of reputation_fragments in src/db/agents.rs
+async fn read(tx: &mut Transaction<'static, Postgres>, agent_id: i64) -> StatsRow {
+    sqlx::query_as(
+        r#"
+        SELECT
+            COALESCE(s.feedback_count, live.feedback_count),
+            COALESCE(s.avg_score, live.avg_score),
+            COALESCE(s.positive_count, live.positive_count),
+            COALESCE(s.negative_count, live.negative_count),
+            COALESCE(s.unique_clients, live.unique_clients)
+        FROM agents a
+        LEFT JOIN agent_stats s ON a.agent_id = s.agent_id AND a.chain_id = s.chain_id
+        LEFT JOIN LATERAL (
+            SELECT
+                AVG(f.value_normalized)::FLOAT8 AS avg_score,
+                COUNT(*) AS feedback_count,
+                COUNT(DISTINCT f.client_address) AS unique_clients,
+                COUNT(CASE WHEN f.value_normalized >= 3 THEN 1 ELSE NULL END) AS positive_count,
+                COUNT(CASE WHEN f.value_normalized < 3 THEN 1 ELSE NULL END) AS negative_count
+            FROM feedbacks f
+            WHERE s.agent_id IS NULL
+              AND f.agent_id = a.agent_id AND f.chain_id = a.chain_id AND f.revoked = false
+        ) live ON true
+        WHERE a.agent_id = $1 AND a.chain_id = $2
+        "#,
+    )
+    .bind(agent_id)
+    .bind(CHAIN_ID)
+    .fetch_one(&mut **tx)
+    .await
+    .unwrap()
+}
+
+async fn insert_agent(tx: &mut Transaction<'static, Postgres>, agent_id: i64) {
+    sqlx::query("INSERT INTO agents (agent_id, chain_id, owner) VALUES ($1, $2, '0xowner')")
+        .bind(agent_id)
+        .bind(CHAIN_ID)
+        .execute(&mut **tx)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn new_feedback_updates_only_its_agent() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_feedback(&mut tx, 1, "0xa", 0, 5).await;
+    insert_feedback(&mut tx, 2, "0xa", 0, 4).await;
+    refresh(&mut tx, &[1, 2]).await;
+    assert_eq!(cached(&mut tx, 1).await, Some((1, Some(5.0), 1, 0, 1)));
+    let other = cached(&mut tx, 2).await;
+
+    // The indexer refreshes just the agents a block range touched
+    insert_feedback(&mut tx, 1, "0xb", 0, 2).await;
+    refresh(&mut tx, &[1]).await;
+    assert_eq!(cached(&mut tx, 1).await, Some((2, Some(3.5), 1, 1, 2)));
+    assert_eq!(cached(&mut tx, 2).await, other);
+
+    // Same client again: more feedback, not more clients
+    insert_feedback(&mut tx, 1, "0xb", 1, 5).await;
+    refresh(&mut tx, &[1]).await;
+    assert_eq!(cached(&mut tx, 1).await, Some((3, Some(4.0), 2, 1, 2)));
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn rebuild_reproduces_incremental_rows() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_feedback(&mut tx, 1, "0xa", 0, 5).await;
+    insert_feedback(&mut tx, 1, "0xb", 0, 1).await;
+    insert_feedback(&mut tx, 2, "0xa", 0, 4).await;
+    refresh(&mut tx, &[1, 2]).await;
+    let expected = (cached(&mut tx, 1).await, cached(&mut tx, 2).await);
+
+    // A stale row and a missing one
+    sqlx::query("UPDATE agent_stats SET feedback_count = 99, avg_score = 0 WHERE agent_id = 1 AND chain_id = $1")
+        .bind(CHAIN_ID)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM agent_stats WHERE agent_id = 2 AND chain_id = $1")
+        .bind(CHAIN_ID)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+    assert_eq!(rebuild(&mut tx).await, 2);
+    assert_eq!((cached(&mut tx, 1).await, cached(&mut tx, 2).await), expected);
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn agents_without_a_row_read_live_aggregates() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_agent(&mut tx, 1).await;
+    insert_agent(&mut tx, 2).await;
+    insert_feedback(&mut tx, 1, "0xa", 0, 5).await;
+    insert_feedback(&mut tx, 1, "0xb", 0, 2).await;
+
+    // Agent 1 has feedback but no stats row yet; agent 2 has neither
+    assert_eq!(cached(&mut tx, 1).await, None);
+    assert_eq!(read(&mut tx, 1).await, (2, Some(3.5), 1, 1, 2));
+    assert_eq!(read(&mut tx, 2).await, (0, None, 0, 0, 0));
+
+    // Once cached, the row wins over the live aggregate
+    refresh(&mut tx, &[1]).await;
+    sqlx::query("UPDATE agent_stats SET avg_score = 1 WHERE agent_id = 1 AND chain_id = $1")
+        .bind(CHAIN_ID)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    assert_eq!(read(&mut tx, 1).await.1, Some(1.0));
+    tx.rollback().await.unwrap();
+}
+
+fn new_feedback(agent_id: i64, client: &str, feedback_index: i64, value: i64, value_decimals: i32) -> NewFeedback {
+    NewFeedback {
+        agent_id,
+        chain_id: CHAIN_ID,
+        client_address: client.to_string(),
+        feedback_index,
+        value: BigDecimal::from(value),
+        value_decimals,
+        tag1: None,
+        tag2: None,
+        endpoint: None,
+        feedback_uri: None,
+        feedback_hash: None,
+        block_number: feedback_index,
+        block_timestamp: None,
+        tx_hash: format!("0xstats{agent_id}{feedback_index}"),
+    }
+}
+
+async fn stats_row(tx: &mut Transaction<'static, Postgres>, chain_id: i32, agent_id: i64) -> Option<FullStatsRow> {
+    sqlx::query_as(
+        r#"
+        SELECT feedback_count, score_sum, avg_score, positive_count, negative_count, unique_clients, last_feedback_at
+        FROM agent_stats WHERE agent_id = $1 AND chain_id = $2
+        "#,
+    )
+    .bind(agent_id)
+    .bind(chain_id)
+    .fetch_optional(&mut **tx)
+    .await
+    .unwrap()
+}
+
+/// The same columns aggregated straight from the agent's unrevoked feedbacks
+async fn live_aggregate(tx: &mut Transaction<'static, Postgres>, chain_id: i32, agent_id: i64) -> FullStatsRow {
+    sqlx::query_as(
+        r#"
+        SELECT
+            COUNT(*),
+            SUM(value_normalized)::FLOAT8,
+            AVG(value_normalized)::FLOAT8,
+            COUNT(CASE WHEN value_normalized >= 3 THEN 1 ELSE NULL END),
+            COUNT(CASE WHEN value_normalized < 3 THEN 1 ELSE NULL END),
+            COUNT(DISTINCT client_address),
+            MAX(created_at)
+        FROM feedbacks
+        WHERE agent_id = $1 AND chain_id = $2 AND revoked = false
+        "#,
+    )
+    .bind(agent_id)
+    .bind(chain_id)
+    .fetch_one(&mut **tx)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn indexer_writes_keep_the_row_equal_to_the_live_aggregate() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    // 2.5 with two decimals counts as negative
+    let batch = [new_feedback(1, "0xa", 1, 5, 0), new_feedback(1, "0xb", 2, 250, 2), new_feedback(2, "0xa", 1, 4, 0)];
+    assert_eq!(insert_feedbacks(&mut tx, &batch).await.unwrap(), 3);
+    refresh_agent_stats(&mut tx, CHAIN_ID, &[1, 2]).await.unwrap();
+    for agent_id in [1, 2] {
+        assert_eq!(stats_row(&mut tx, CHAIN_ID, agent_id).await, Some(live_aggregate(&mut tx, CHAIN_ID, agent_id).await));
+    }
+    let row = stats_row(&mut tx, CHAIN_ID, 1).await.unwrap();
+    assert_eq!((row.0, row.2, row.3, row.4), (2, Some(3.75), 1, 1));
+
+    // A replayed batch inserts nothing and leaves the row as it was
+    assert_eq!(insert_feedbacks(&mut tx, &batch[..1]).await.unwrap(), 0);
+    refresh_agent_stats(&mut tx, CHAIN_ID, &[1]).await.unwrap();
+    assert_eq!(stats_row(&mut tx, CHAIN_ID, 1).await, Some(row));
+
+    // Revoking, again, and an unknown index
+    assert_eq!(revoke_feedbacks(&mut tx, CHAIN_ID, &[(1, "0xb".to_string(), 2)]).await.unwrap(), 1);
+    revoke_feedbacks(&mut tx, CHAIN_ID, &[(1, "0xb".to_string(), 2), (1, "0xb".to_string(), 99)]).await.unwrap();
+    refresh_agent_stats(&mut tx, CHAIN_ID, &[1]).await.unwrap();
+    assert_eq!(stats_row(&mut tx, CHAIN_ID, 1).await, Some(live_aggregate(&mut tx, CHAIN_ID, 1).await));
+    assert_eq!(stats_row(&mut tx, CHAIN_ID, 1).await.unwrap().0, 1);
+
+    // Revoking everything empties the row rather than deleting it
+    revoke_feedbacks(&mut tx, CHAIN_ID, &[(1, "0xa".to_string(), 1)]).await.unwrap();
+    refresh_agent_stats(&mut tx, CHAIN_ID, &[1]).await.unwrap();
+    assert_eq!(stats_row(&mut tx, CHAIN_ID, 1).await, Some((0, None, None, 0, 0, 0, None)));
+    assert_eq!(stats_row(&mut tx, CHAIN_ID, 1).await, Some(live_aggregate(&mut tx, CHAIN_ID, 1).await));
+    tx.rollback().await.unwrap();
+}
+
+async fn delete_chain(pool: &PgPool, chain_id: i32) {
+    for table in ["feedbacks", "agent_stats"] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE chain_id = $1"))
+            .bind(chain_id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn overlapping_refreshes_of_one_agent_keep_every_feedback() {
+    let Some((pool, tx)) = setup().await else { return };
+    tx.rollback().await.unwrap();
+    delete_chain(&pool, CONCURRENT_CHAIN_ID).await;
+    let feedback = |client: &str| NewFeedback { chain_id: CONCURRENT_CHAIN_ID, ..new_feedback(1, client, 1, 5, 0) };
+
+    // Two block ranges written at once, each with a new feedback for agent 1
+    let mut first = pool.begin().await.unwrap();
+    insert_feedbacks(&mut first, &[feedback("0xa")]).await.unwrap();
+    refresh_agent_stats(&mut first, CONCURRENT_CHAIN_ID, &[1]).await.unwrap();
+
+    let second_pool = pool.clone();
+    let second = tokio::spawn(async move {
+        let mut second = second_pool.begin().await.unwrap();
+        insert_feedbacks(&mut second, &[feedback("0xb")]).await.unwrap();
+        refresh_agent_stats(&mut second, CONCURRENT_CHAIN_ID, &[1]).await.unwrap();
+        second.commit().await.unwrap();
+    });
+
+    // The second refresh waits for the first transaction instead of aggregating without it
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    assert!(!second.is_finished());
+    first.commit().await.unwrap();
+    second.await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    let live = live_aggregate(&mut tx, CONCURRENT_CHAIN_ID, 1).await;
+    assert_eq!(live.0, 2);
+    assert_eq!(stats_row(&mut tx, CONCURRENT_CHAIN_ID, 1).await, Some(live));
+    tx.rollback().await.unwrap();
+    delete_chain(&pool, CONCURRENT_CHAIN_ID).await;
+}
@@ -0,0 +1,136 @@
+//! Database checks for auction bid idempotency (migrations/019): replaying BidPlaced logs
+//! must not duplicate bids or inflate `bid_count`.
+//!
+//! Like explain_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_auction_bid_tests -- --ignored`
+//!
+//! Everything runs inside a transaction that is rolled back, so only the migrations persist.
+
+use bigdecimal::BigDecimal;
+use sqlx::{PgPool, Postgres, Transaction};
+
+const CHAIN_ID: i32 = 999_004;
+const AUCTION_ID: i64 = 1;
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_auctions
+            (auction_id, chain_id, seller, nft_contract, token_id, payment_token, start_price,
+             reserve_price, buy_now_price, start_time, end_time, block_number, tx_hash)
+        VALUES ($1, $2, '0xseller', '0xnft', 1, '0x0', 1, 1, 0, 0, 0, 1, '0xcreated')
+        "#,
+    )
+    .bind(AUCTION_ID)
+    .bind(CHAIN_ID)
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+    Some((pool, tx))
+}
+
+/// (bidder, amount, tx_hash, log_index)
+type Bid = (&'static str, i64, &'static str, i32);
+
+const BIDS: &[Bid] = &[
+    ("0xalice", 10, "0xbid1", 0),
+    ("0xbob", 12, "0xbid2", 4),
+    // Two bids in one transaction are distinct logs
+    ("0xalice", 15, "0xbid3", 1),
+    ("0xbob", 20, "0xbid3", 2),
+];
+
+/// The BidPlaced handler: insert_auction_bid then update_auction_bid (src/db/marketplace.rs)
+async fn process_bid(tx: &mut Transaction<'static, Postgres>, (bidder, amount, tx_hash, log_index): Bid) {
+    let amount = BigDecimal::from(amount);
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_auction_bids
+            (auction_id, chain_id, bidder, amount, block_number, block_timestamp, tx_hash, log_index)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (chain_id, tx_hash, log_index) DO NOTHING
+        "#,
+    )
+    .bind(AUCTION_ID)
+    .bind(CHAIN_ID)
+    .bind(bidder)
+    .bind(&amount)
+    .bind(2_i64)
+    .bind(None::<chrono::DateTime<chrono::Utc>>)
+    .bind(tx_hash)
+    .bind(log_index)
+    .execute(&mut **tx)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE marketplace_auctions
+        SET highest_bid = $3, highest_bidder = $4,
+            bid_count = (
+                SELECT COUNT(*) FROM marketplace_auction_bids
+                WHERE auction_id = $1 AND chain_id = $2
+            )
+        WHERE auction_id = $1 AND chain_id = $2
+        "#,
+    )
+    .bind(AUCTION_ID)
+    .bind(CHAIN_ID)
+    .bind(&amount)
+    .bind(bidder)
+    .execute(&mut **tx)
+    .await
+    .unwrap();
+}
+
+/// (bid_count, highest_bid, highest_bidder, stored bids)
+async fn auction_state(tx: &mut Transaction<'static, Postgres>) -> (i32, BigDecimal, String, i64) {
+    sqlx::query_as(
+        r#"
+        SELECT a.bid_count, a.highest_bid, a.highest_bidder,
+               (SELECT COUNT(*) FROM marketplace_auction_bids b WHERE b.auction_id = a.auction_id AND b.chain_id = a.chain_id)
+        FROM marketplace_auctions a
+        WHERE a.auction_id = $1 AND a.chain_id = $2
+        "#,
+    )
+    .bind(AUCTION_ID)
+    .bind(CHAIN_ID)
+    .fetch_one(&mut **tx)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn bid_count_matches_stored_bids() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    for bid in BIDS {
+        process_bid(&mut tx, *bid).await;
+    }
+    assert_eq!(
+        auction_state(&mut tx).await,
+        (4, BigDecimal::from(20), "0xbob".to_string(), 4)
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn reprocessing_bids_does_not_inflate_counts() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    for bid in BIDS {
+        process_bid(&mut tx, *bid).await;
+    }
+    let first_pass = auction_state(&mut tx).await;
+
+    // Re-index the whole range, then a single log again
+    for bid in BIDS {
+        process_bid(&mut tx, *bid).await;
+    }
+    process_bid(&mut tx, BIDS[3]).await;
+
+    assert_eq!(auction_state(&mut tx).await, first_pass);
+}
@@ -0,0 +1,99 @@
+//! Database checks for the indexer's multi-row inserts (migrations/016).
+//!
+//! Like explain_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_batch_tests -- --ignored`
+//!
+//! Everything runs inside a transaction that is rolled back, so only the migrations persist.
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+    let tx = pool.begin().await.unwrap();
+    Some((pool, tx))
+}
+
+/// (agent_id, event_type, event_data, block_number, tx_hash, log_index)
+type ActivityRow<'a> = (i64, &'a str, Option<serde_json::Value>, i64, &'a str, i32);
+
+/// Same statement as insert_activities in src/db/activity.rs
+async fn insert_activities(
+    tx: &mut Transaction<'static, Postgres>,
+    rows: &[ActivityRow<'_>],
+) -> u64 {
+    let agent_ids: Vec<i64> = rows.iter().map(|r| r.0).collect();
+    let chain_ids: Vec<i32> = rows.iter().map(|_| 999_001).collect();
+    let event_types: Vec<&str> = rows.iter().map(|r| r.1).collect();
+    let event_data: Vec<Option<serde_json::Value>> = rows.iter().map(|r| r.2.clone()).collect();
+    let block_numbers: Vec<i64> = rows.iter().map(|r| r.3).collect();
+    let block_timestamps: Vec<Option<DateTime<Utc>>> = rows.iter().map(|_| Some(Utc::now())).collect();
+    let tx_hashes: Vec<&str> = rows.iter().map(|r| r.4).collect();
+    let log_indexes: Vec<i32> = rows.iter().map(|r| r.5).collect();
+
+    sqlx::query(
+        r#"
+        INSERT INTO activity_log (agent_id, chain_id, event_type, event_data, block_number, block_timestamp, block_timestamp_estimated, tx_hash, log_index)
+        SELECT t.agent_id, t.chain_id, t.event_type, t.event_data, t.block_number,
+               COALESCE(t.block_timestamp, NOW()), t.block_timestamp IS NULL, t.tx_hash, t.log_index
+        FROM UNNEST($1::BIGINT[], $2::INT[], $3::TEXT[], $4::JSONB[], $5::BIGINT[], $6::TIMESTAMPTZ[], $7::TEXT[], $8::INT[])
+            AS t(agent_id, chain_id, event_type, event_data, block_number, block_timestamp, tx_hash, log_index)
+        ON CONFLICT (chain_id, tx_hash, log_index, agent_id, event_type) DO NOTHING
+        "#,
+    )
+    .bind(agent_ids)
+    .bind(chain_ids)
+    .bind(event_types)
+    .bind(event_data)
+    .bind(block_numbers)
+    .bind(block_timestamps)
+    .bind(tx_hashes)
+    .bind(log_indexes)
+    .execute(&mut **tx)
+    .await
+    .unwrap()
+    .rows_affected()
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn one_statement_inserts_a_whole_range_and_replay_is_a_no_op() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    let hashes: Vec<String> = (0..500).map(|i| format!("0xbatch{}", i / 5)).collect();
+    let rows: Vec<_> = (0..500)
+        .map(|i| {
+            let data = (i % 2 == 0).then(|| serde_json::json!({"i": i}));
+            (i % 50, "NewFeedback", data, 1_000 + i / 5, hashes[i as usize].as_str(), (i % 5) as i32)
+        })
+        .collect();
+
+    assert_eq!(insert_activities(&mut tx, &rows).await, 500);
+    // Replaying the same range (e.g. after a failed batch) inserts nothing
+    assert_eq!(insert_activities(&mut tx, &rows).await, 0);
+
+    let (count, nulls): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COUNT(*) FILTER (WHERE event_data IS NULL) FROM activity_log WHERE chain_id = 999001",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap();
+    assert_eq!((count, nulls), (500, 250));
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn bundle_items_sharing_a_log_are_distinct_rows() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    // One BundleBought log yields an activity per agent in the bundle
+    let rows = vec![
+        (1, "marketplace:BundleBought", None, 5, "0xbundle", 3),
+        (2, "marketplace:BundleBought", None, 5, "0xbundle", 3),
+    ];
+    assert_eq!(insert_activities(&mut tx, &rows).await, 2);
+    tx.rollback().await.unwrap();
+}
@@ -0,0 +1,220 @@
+//! Database checks for feedbacks.value_normalized (migrations/017): the stored column must
+//! give the same aggregates as the per-row `value / POWER(10, value_decimals)` it replaced,
+//! and a row stored without decimals normalizes and reads back as decimals 0.
+//!
+//! Like explain_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_feedback_value_tests -- --ignored`
+//!
+//! Everything runs inside a transaction that is rolled back, so only the migrations persist.
+
+use bigdecimal::BigDecimal;
+use sqlx::{PgPool, Postgres, Transaction};
+
+const CHAIN_ID: i32 = 999_002;
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+    let tx = pool.begin().await.unwrap();
+    Some((pool, tx))
+}
+
+/// (agent_id, value, value_decimals, tag1, revoked)
+type FeedbackRow = (i64, i64, i32, &'static str, bool);
+
+/// Fixture mixing negative values, zero decimals, large decimals and revoked rows.
+const FIXTURE: &[FeedbackRow] = &[
+    (1, 5, 0, "quality", false),
+    (1, 450, 2, "quality", false),
+    (1, -3, 0, "speed", false),
+    (1, -125, 2, "speed", false),
+    (1, 0, 0, "speed", false),
+    (1, 99, 1, "quality", true),
+    (2, 1_000_000_000_000_000_000, 18, "uptime", false),
+    (2, 2_500_000_000_000_000_000, 18, "uptime", false),
+    (2, -7, 0, "uptime", false),
+    (2, 3, 0, "quality", false),
+    (3, 42, 0, "quality", true),
+];
+
+/// Same statement as insert_feedbacks in src/db/feedbacks.rs (subset of columns)
+async fn insert_fixture(tx: &mut Transaction<'static, Postgres>) {
+    let agent_ids: Vec<i64> = FIXTURE.iter().map(|r| r.0).collect();
+    let chain_ids: Vec<i32> = FIXTURE.iter().map(|_| CHAIN_ID).collect();
+    let clients: Vec<String> = FIXTURE.iter().enumerate().map(|(i, _)| format!("0xclient{}", i)).collect();
+    let feedback_indexes: Vec<i64> = (0..FIXTURE.len() as i64).collect();
+    let values: Vec<BigDecimal> = FIXTURE.iter().map(|r| BigDecimal::from(r.1)).collect();
+    let value_decimals: Vec<i32> = FIXTURE.iter().map(|r| r.2).collect();
+    let tag1s: Vec<&str> = FIXTURE.iter().map(|r| r.3).collect();
+    let block_numbers: Vec<i64> = (0..FIXTURE.len() as i64).collect();
+    let tx_hashes: Vec<String> = (0..FIXTURE.len()).map(|i| format!("0xvalue{}", i)).collect();
+
+    sqlx::query(
+        r#"
+        INSERT INTO feedbacks (agent_id, chain_id, client_address, feedback_index, value, value_decimals, value_normalized, tag1, block_number, tx_hash)
+        SELECT agent_id, chain_id, client_address, feedback_index, value, value_decimals,
+               value / POWER(10, COALESCE(value_decimals, 0)),
+               tag1, block_number, tx_hash
+        FROM UNNEST($1::BIGINT[], $2::INT[], $3::TEXT[], $4::BIGINT[], $5::NUMERIC[], $6::INT[], $7::TEXT[], $8::BIGINT[], $9::TEXT[])
+            AS f(agent_id, chain_id, client_address, feedback_index, value, value_decimals, tag1, block_number, tx_hash)
+        "#,
+    )
+    .bind(agent_ids)
+    .bind(chain_ids)
+    .bind(clients)
+    .bind(feedback_indexes)
+    .bind(values)
+    .bind(value_decimals)
+    .bind(tag1s)
+    .bind(block_numbers)
+    .bind(tx_hashes)
+    .execute(&mut **tx)
+    .await
+    .unwrap();
+
+    let revoked: Vec<i64> = FIXTURE.iter().enumerate().filter(|(_, r)| r.4).map(|(i, _)| i as i64).collect();
+    sqlx::query("UPDATE feedbacks SET revoked = true WHERE chain_id = $1 AND feedback_index = ANY($2)")
+        .bind(CHAIN_ID)
+        .bind(revoked)
+        .execute(&mut **tx)
+        .await
+        .unwrap();
+}
+
+/// Run the same aggregate over the old expression and the new column.
+async fn old_and_new<T>(tx: &mut Transaction<'static, Postgres>, template: &str) -> (Vec<T>, Vec<T>)
+where
+    T: for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> + Send + Unpin,
+{
+    let old = template.replace("{v}", "value / POWER(10, COALESCE(value_decimals, 0))");
+    let new = template.replace("{v}", "value_normalized");
+    let old_rows = sqlx::query_as(&old).bind(CHAIN_ID).fetch_all(&mut **tx).await.unwrap();
+    let new_rows = sqlx::query_as(&new).bind(CHAIN_ID).fetch_all(&mut **tx).await.unwrap();
+    (old_rows, new_rows)
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn stored_value_matches_expression_per_row() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_fixture(&mut tx).await;
+
+    let (old, new) = old_and_new::<(i64, f64)>(
+        &mut tx,
+        "SELECT feedback_index, ({v})::FLOAT8 FROM feedbacks WHERE chain_id = $1 ORDER BY feedback_index",
+    )
+    .await;
+    assert_eq!(old.len(), FIXTURE.len());
+    assert_eq!(old, new);
+    assert_eq!(new[3].1, -1.25);
+    assert_eq!(new[6].1, 1.0);
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn agent_stats_aggregates_agree() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_fixture(&mut tx).await;
+
+    // Shape of refresh_agent_stats / the legacy reputation fragments
+    let (old, new) = old_and_new::<(i64, Option<f64>, Option<f64>, i64, i64)>(
+        &mut tx,
+        r#"
+        SELECT agent_id,
+               SUM(CASE WHEN revoked = false THEN {v} ELSE NULL END)::FLOAT8,
+               AVG(CASE WHEN revoked = false THEN {v} ELSE NULL END)::FLOAT8,
+               COUNT(CASE WHEN revoked = false AND {v} >= 3 THEN 1 ELSE NULL END),
+               COUNT(CASE WHEN revoked = false AND {v} < 3 THEN 1 ELSE NULL END)
+        FROM feedbacks WHERE chain_id = $1
+        GROUP BY agent_id ORDER BY agent_id
+        "#,
+    )
+    .await;
+    assert_eq!(old, new);
+    // Agent 3 only has a revoked feedback
+    assert_eq!(new[2], (3, None, None, 0, 0));
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn scores_by_tag_aggregates_agree() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_fixture(&mut tx).await;
+
+    // Shape of get_scores_by_tag
+    let (old, new) = old_and_new::<(i64, String, Option<f64>, Option<f64>, Option<f64>)>(
+        &mut tx,
+        r#"
+        SELECT agent_id, tag1,
+               AVG({v})::FLOAT8, MIN({v})::FLOAT8, MAX({v})::FLOAT8
+        FROM feedbacks WHERE chain_id = $1 AND revoked = false
+        GROUP BY agent_id, tag1 ORDER BY agent_id, tag1
+        "#,
+    )
+    .await;
+    assert_eq!(old, new);
+    let speed = new.iter().find(|r| r.0 == 1 && r.1 == "speed").unwrap();
+    assert_eq!((speed.3, speed.4), (Some(-3.0), Some(0.0)));
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn null_decimals_normalize_as_zero() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    // insert_feedbacks' statement with a NULL in the value_decimals array
+    sqlx::query(
+        r#"
+        INSERT INTO feedbacks (agent_id, chain_id, client_address, feedback_index, value, value_decimals, value_normalized, block_number, tx_hash)
+        SELECT agent_id, chain_id, client_address, feedback_index, value, value_decimals,
+               value / POWER(10, COALESCE(value_decimals, 0)),
+               block_number, tx_hash
+        FROM UNNEST($1::BIGINT[], $2::INT[], $3::TEXT[], $4::BIGINT[], $5::NUMERIC[], $6::INT[], $7::BIGINT[], $8::TEXT[])
+            AS f(agent_id, chain_id, client_address, feedback_index, value, value_decimals, block_number, tx_hash)
+        "#,
+    )
+    .bind(vec![4_i64, 4])
+    .bind(vec![CHAIN_ID, CHAIN_ID])
+    .bind(vec!["0xnull", "0xtwo"])
+    .bind(vec![0_i64, 1])
+    .bind(vec![BigDecimal::from(4), BigDecimal::from(350)])
+    .bind(vec![None, Some(2_i32)])
+    .bind(vec![0_i64, 1])
+    .bind(vec!["0xnulldecimals", "0xnulldecimals"])
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+
+    // Read the way get_reputation_history / get_recent_feedbacks do
+    let rows: Vec<(i64, i32, f64)> = sqlx::query_as(
+        r#"
+        SELECT feedback_index, COALESCE(value_decimals, 0) AS value_decimals, value_normalized
+        FROM feedbacks WHERE chain_id = $1 AND agent_id = 4
+        ORDER BY feedback_index
+        "#,
+    )
+    .bind(CHAIN_ID)
+    .fetch_all(&mut *tx)
+    .await
+    .unwrap();
+    assert_eq!(rows, vec![(0, 0, 4.0), (1, 2, 3.5)]);
+
+    // ...and migrations/024 stores the 0 for such rows
+    sqlx::query("UPDATE feedbacks SET value_decimals = 0 WHERE value_decimals IS NULL AND chain_id = $1")
+        .bind(CHAIN_ID)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    let stored: Option<i32> =
+        sqlx::query_scalar("SELECT value_decimals FROM feedbacks WHERE chain_id = $1 AND agent_id = 4 AND feedback_index = 0")
+            .bind(CHAIN_ID)
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+    assert_eq!(stored, Some(0));
+    tx.rollback().await.unwrap();
+}
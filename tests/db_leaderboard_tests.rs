@@ -0,0 +1,93 @@
+//! Database checks for get_leaderboard's activity filter (src/db/agents.rs): inactive agents
+//! are left out by default and ranked alongside active ones with `include_inactive`.
+//!
+//! Like explain_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_leaderboard_tests -- --ignored`
+//!
+//! Everything runs inside a transaction that is rolled back, so only the migrations persist.
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+const CHAIN_ID: i32 = 999_011;
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+    let tx = pool.begin().await.unwrap();
+    Some((pool, tx))
+}
+
+/// (agent_id, active, avg_score, feedback_count)
+const FIXTURE: &[(i64, bool, f64, i64)] = &[
+    (1, true, 3.0, 4),
+    // Inactive, but with the best history
+    (2, false, 4.5, 10),
+    (3, true, 1.0, 2),
+    // Inactive and never reviewed: excluded either way by the feedback floor
+    (4, false, 5.0, 0),
+];
+
+async fn insert_fixture(tx: &mut Transaction<'static, Postgres>) {
+    for &(agent_id, active, avg_score, feedback_count) in FIXTURE {
+        sqlx::query("INSERT INTO agents (agent_id, chain_id, owner, active) VALUES ($1, $2, '0xowner', $3)")
+            .bind(agent_id)
+            .bind(CHAIN_ID)
+            .bind(active)
+            .execute(&mut **tx)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO agent_stats (agent_id, chain_id, feedback_count, score_sum, avg_score) VALUES ($1, $2, $3, $4 * $3, $4)",
+        )
+        .bind(agent_id)
+        .bind(CHAIN_ID)
+        .bind(feedback_count)
+        .bind(avg_score)
+        .execute(&mut **tx)
+        .await
+        .unwrap();
+    }
+}
+
+/// Same statement as get_leaderboard in src/db/agents.rs (agent_stats path, no category
+/// filter, desc); returns (rank, agent_id)
+async fn leaderboard(tx: &mut Transaction<'static, Postgres>, include_inactive: bool) -> Vec<(i64, i64)> {
+    sqlx::query_as(
+        r#"
+        SELECT ROW_NUMBER() OVER (ORDER BY s.avg_score DESC NULLS LAST) AS rank, a.agent_id
+        FROM agents a
+        LEFT JOIN agent_stats s ON a.agent_id = s.agent_id AND a.chain_id = s.chain_id
+        WHERE ($2::BOOL OR a.active = true)
+          AND ($1::INT IS NULL OR a.chain_id = $1)
+          AND s.feedback_count > 0
+        ORDER BY rank
+        LIMIT 50
+        "#,
+    )
+    .bind(CHAIN_ID)
+    .bind(include_inactive)
+    .fetch_all(&mut **tx)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn inactive_agents_are_excluded_by_default() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_fixture(&mut tx).await;
+
+    assert_eq!(leaderboard(&mut tx, false).await, vec![(1, 1), (2, 3)]);
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn include_inactive_ranks_every_reviewed_agent() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_fixture(&mut tx).await;
+
+    assert_eq!(leaderboard(&mut tx, true).await, vec![(1, 2), (2, 1), (3, 3)]);
+    tx.rollback().await.unwrap();
+}
@@ -0,0 +1,133 @@
+//! Database checks for the offer expiry window and sort=expiring_soon in get_offers /
+//! get_collection_offers (src/db/marketplace.rs): both bounds are inclusive, either may be
+//! omitted, and expiring_soon lists Active offers first, soonest expiry first.
+//!
+//! Like explain_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_offer_expiry_tests -- --ignored`
+//!
+//! Everything runs inside a transaction that is rolled back, so only the migrations persist.
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+const CHAIN_ID: i32 = 999_009;
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+    let tx = pool.begin().await.unwrap();
+    Some((pool, tx))
+}
+
+/// (offer_id, expiry, status, block_number)
+const FIXTURE: &[(i64, i64, &str, i64)] = &[
+    (1, 1_000, "Active", 10),
+    (2, 3_000, "Active", 40),
+    (3, 2_000, "Active", 20),
+    (4, 500, "Cancelled", 30),
+    (5, 2_500, "Accepted", 50),
+];
+
+async fn insert_fixture(tx: &mut Transaction<'static, Postgres>) {
+    for (offer_id, expiry, status, block_number) in FIXTURE {
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_offers (offer_id, chain_id, offerer, nft_contract, token_id, payment_token,
+                                            amount, expiry, status, block_number, tx_hash)
+            VALUES ($1, $2, '0xofferer', '0xnft', 7, '0xtoken', 100, $3, $4, $5, '0xoffer')
+            "#,
+        )
+        .bind(offer_id)
+        .bind(CHAIN_ID)
+        .bind(expiry)
+        .bind(status)
+        .bind(block_number)
+        .execute(&mut **tx)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO marketplace_collection_offers (offer_id, chain_id, offerer, nft_contract, payment_token,
+                                                       amount, expiry, status, block_number, tx_hash)
+            VALUES ($1, $2, '0xofferer', '0xnft', '0xtoken', 100, $3, $4, $5, '0xoffer')
+            "#,
+        )
+        .bind(offer_id)
+        .bind(CHAIN_ID)
+        .bind(expiry)
+        .bind(status)
+        .bind(block_number)
+        .execute(&mut **tx)
+        .await
+        .unwrap();
+    }
+}
+
+/// Same ORDER BY as offer_order_clause in src/db/marketplace.rs
+fn order_clause(sort: &str) -> &'static str {
+    match sort {
+        "expiring_soon" => "CASE WHEN status = 'Active' THEN 0 ELSE 1 END ASC, expiry ASC, offer_id ASC",
+        _ => "block_number DESC",
+    }
+}
+
+/// Offer ids from the get_offers / get_collection_offers statement (other filters unset)
+async fn offer_ids(
+    tx: &mut Transaction<'static, Postgres>,
+    table: &str,
+    status: Option<&str>,
+    expiring_after: Option<i64>,
+    expiring_before: Option<i64>,
+    sort: &str,
+) -> Vec<i64> {
+    let query = format!(
+        r#"
+        SELECT offer_id FROM {table}
+        WHERE chain_id = $1
+          AND ($2::TEXT IS NULL OR status = $2)
+          AND ($3::BIGINT IS NULL OR expiry >= $3)
+          AND ($4::BIGINT IS NULL OR expiry <= $4)
+        ORDER BY {}
+        "#,
+        order_clause(sort)
+    );
+    sqlx::query_scalar(&query)
+        .bind(CHAIN_ID)
+        .bind(status)
+        .bind(expiring_after)
+        .bind(expiring_before)
+        .fetch_all(&mut **tx)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn expiry_window_is_inclusive_and_open_ended() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_fixture(&mut tx).await;
+
+    for table in ["marketplace_offers", "marketplace_collection_offers"] {
+        assert_eq!(offer_ids(&mut tx, table, None, Some(1_000), Some(2_500), "recent").await, vec![5, 3, 1]);
+        assert_eq!(offer_ids(&mut tx, table, None, Some(2_001), None, "recent").await, vec![5, 2]);
+        assert_eq!(offer_ids(&mut tx, table, None, None, Some(999), "recent").await, vec![4]);
+        assert_eq!(offer_ids(&mut tx, table, Some("Active"), None, Some(2_000), "recent").await, vec![3, 1]);
+        assert!(offer_ids(&mut tx, table, None, Some(3_001), Some(4_000), "recent").await.is_empty());
+    }
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn expiring_soon_puts_active_first_by_expiry() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_fixture(&mut tx).await;
+
+    for table in ["marketplace_offers", "marketplace_collection_offers"] {
+        assert_eq!(offer_ids(&mut tx, table, None, None, None, "expiring_soon").await, vec![1, 3, 2, 4, 5]);
+        // Sellers skip lapsed offers with expiring_after=now
+        assert_eq!(offer_ids(&mut tx, table, None, Some(1_500), None, "expiring_soon").await, vec![3, 2, 5]);
+        assert_eq!(offer_ids(&mut tx, table, None, None, None, "recent").await, vec![5, 2, 4, 3, 1]);
+    }
+    tx.rollback().await.unwrap();
+}
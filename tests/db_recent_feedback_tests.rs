@@ -0,0 +1,127 @@
+//! Database checks for get_recent_feedbacks (src/db/feedbacks.rs): revoked feedbacks are
+//! excluded, agent name/image come from the LEFT JOIN, and rows are newest first.
+//!
+//! Like explain_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_recent_feedback_tests -- --ignored`
+//!
+//! Everything runs inside a transaction that is rolled back, so only the migrations persist.
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+const CHAIN_ID: i32 = 999_005;
+const OTHER_CHAIN_ID: i32 = 999_006;
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+    let tx = pool.begin().await.unwrap();
+    Some((pool, tx))
+}
+
+/// (agent_id, chain_id, feedback_index, value, value_decimals, revoked, block_timestamp in minutes
+/// after a far-future base, so these rows sort ahead of anything else in the database)
+type FeedbackRow = (i64, i32, i64, i64, i32, bool, i32);
+
+const FIXTURE: &[FeedbackRow] = &[
+    (1, CHAIN_ID, 0, 5, 0, false, 10),
+    (1, CHAIN_ID, 1, 450, 2, false, 30),
+    (2, CHAIN_ID, 0, 3, 0, true, 40),
+    // Agent 3 has no agents row
+    (3, CHAIN_ID, 0, 1_000_000_000_000_000_000, 18, false, 20),
+    (1, OTHER_CHAIN_ID, 0, 2, 0, false, 50),
+];
+
+async fn insert_fixture(tx: &mut Transaction<'static, Postgres>) {
+    for (agent_id, chain_id, name) in [(1, CHAIN_ID, "Alpha"), (2, CHAIN_ID, "Beta"), (1, OTHER_CHAIN_ID, "Gamma")] {
+        sqlx::query("INSERT INTO agents (agent_id, chain_id, owner, name, image) VALUES ($1, $2, '0xowner', $3, $3 || '.png')")
+            .bind(agent_id as i64)
+            .bind(chain_id)
+            .bind(name)
+            .execute(&mut **tx)
+            .await
+            .unwrap();
+    }
+
+    for (agent_id, chain_id, feedback_index, value, value_decimals, revoked, minutes) in FIXTURE {
+        sqlx::query(
+            r#"
+            INSERT INTO feedbacks (agent_id, chain_id, client_address, feedback_index, value, value_decimals,
+                                   value_normalized, revoked, block_number, block_timestamp, tx_hash)
+            VALUES ($1, $2, '0xclient', $3, $4, $5, $4::NUMERIC / POWER(10, $5), $6, 1,
+                    TIMESTAMPTZ '2100-01-01' + make_interval(mins => $7::INT), '0xrecent')
+            "#,
+        )
+        .bind(agent_id)
+        .bind(chain_id)
+        .bind(feedback_index)
+        .bind(bigdecimal::BigDecimal::from(*value))
+        .bind(value_decimals)
+        .bind(revoked)
+        .bind(minutes)
+        .execute(&mut **tx)
+        .await
+        .unwrap();
+    }
+}
+
+/// Same statement as get_recent_feedbacks in src/db/feedbacks.rs (subset of columns)
+async fn recent(
+    tx: &mut Transaction<'static, Postgres>,
+    chain_id: Option<i32>,
+    limit: i64,
+) -> Vec<(i64, i32, i64, f64, Option<String>, Option<String>)> {
+    sqlx::query_as(
+        r#"
+        SELECT f.agent_id, f.chain_id, f.feedback_index, f.value_normalized,
+               ag.name AS agent_name, ag.image AS agent_image
+        FROM feedbacks f
+        LEFT JOIN agents ag ON ag.agent_id = f.agent_id AND ag.chain_id = f.chain_id
+        WHERE f.revoked = false
+          AND ($1::INT IS NULL OR f.chain_id = $1)
+        ORDER BY COALESCE(f.block_timestamp, f.created_at) DESC, f.id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(chain_id)
+    .bind(limit)
+    .fetch_all(&mut **tx)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn newest_first_without_revoked_and_with_agent_join() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_fixture(&mut tx).await;
+
+    let rows = recent(&mut tx, Some(CHAIN_ID), 10).await;
+    assert_eq!(
+        rows,
+        vec![
+            (1, CHAIN_ID, 1, 4.5, Some("Alpha".into()), Some("Alpha.png".into())),
+            (3, CHAIN_ID, 0, 1.0, None, None),
+            (1, CHAIN_ID, 0, 5.0, Some("Alpha".into()), Some("Alpha.png".into())),
+        ]
+    );
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn all_chains_and_limit() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_fixture(&mut tx).await;
+
+    // The join matches on chain too: agent 1 on the other chain is Gamma, not Alpha
+    let rows = recent(&mut tx, None, 2).await;
+    assert_eq!(
+        rows,
+        vec![
+            (1, OTHER_CHAIN_ID, 0, 2.0, Some("Gamma".into()), Some("Gamma.png".into())),
+            (1, CHAIN_ID, 1, 4.5, Some("Alpha".into()), Some("Alpha.png".into())),
+        ]
+    );
+    tx.rollback().await.unwrap();
+}
@@ -0,0 +1,173 @@
+//! Database checks for insert_sales (src/db/marketplace.rs): sale rows must pick up the NFT,
+//! counterparty, payment token and offer amount from the sold entity, and replays must be
+//! no-ops.
+//!
+//! Like explain_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_sales_tests -- --ignored`
+//!
+//! Everything runs inside a transaction that is rolled back, so only the migrations persist.
+
+use bigdecimal::BigDecimal;
+use sqlx::{PgPool, Postgres, Transaction};
+
+const CHAIN_ID: i32 = 999_003;
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+    let tx = pool.begin().await.unwrap();
+    Some((pool, tx))
+}
+
+/// An offer, a collection offer and a bundle, as their *Made/*Listed handlers store them.
+async fn insert_entities(tx: &mut Transaction<'static, Postgres>) {
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_offers
+            (offer_id, chain_id, offerer, nft_contract, token_id, payment_token, amount, expiry, block_number, tx_hash)
+        VALUES (1, $1, '0xofferer', '0xnft', 7, '0xusdc', 30, 0, 1, '0xmade')
+        "#,
+    )
+    .bind(CHAIN_ID)
+    .execute(&mut **tx)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_collection_offers
+            (offer_id, chain_id, offerer, nft_contract, payment_token, amount, expiry, block_number, tx_hash)
+        VALUES (1, $1, '0xcollector', '0xnft', '0x0', 20, 0, 1, '0xmade')
+        "#,
+    )
+    .bind(CHAIN_ID)
+    .execute(&mut **tx)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_bundles
+            (bundle_id, chain_id, seller, nft_contracts, token_ids, payment_token, price, expiry, item_count, block_number, tx_hash)
+        VALUES (1, $1, '0xbundler', '{0xnft,0xnft}', '{11,12}', '0x0', 3, 0, 2, 1, '0xmade')
+        "#,
+    )
+    .bind(CHAIN_ID)
+    .execute(&mut **tx)
+    .await
+    .unwrap();
+}
+
+/// (sale_type, entity_id, token_id, seller, buyer, price, log_index), as the sale handlers push them
+type SaleRow = (&'static str, i64, Option<i64>, Option<&'static str>, Option<&'static str>, Option<i64>, i32);
+
+const SALES: &[SaleRow] = &[
+    ("offer", 1, None, Some("0xseller"), None, None, 0),
+    ("collection_offer", 1, Some(9), Some("0xseller"), None, None, 1),
+    ("bundle", 1, None, None, Some("0xbuyer"), Some(3), 2),
+    // Never indexed, and an acceptance log has no price: nothing to record
+    ("offer", 2, None, Some("0xseller"), None, None, 3),
+];
+
+/// Same statement as insert_sales in src/db/marketplace.rs
+async fn insert_sales(tx: &mut Transaction<'static, Postgres>) -> u64 {
+    let sale_types: Vec<&str> = SALES.iter().map(|s| s.0).collect();
+    let entity_ids: Vec<i64> = SALES.iter().map(|s| s.1).collect();
+    let token_ids: Vec<Option<BigDecimal>> = SALES.iter().map(|s| s.2.map(BigDecimal::from)).collect();
+    let sellers: Vec<Option<&str>> = SALES.iter().map(|s| s.3).collect();
+    let buyers: Vec<Option<&str>> = SALES.iter().map(|s| s.4).collect();
+    let prices: Vec<Option<BigDecimal>> = SALES.iter().map(|s| s.5.map(BigDecimal::from)).collect();
+    let block_numbers: Vec<i64> = SALES.iter().map(|_| 100).collect();
+    let block_timestamps: Vec<Option<chrono::DateTime<chrono::Utc>>> = SALES.iter().map(|_| None).collect();
+    let tx_hashes: Vec<&str> = SALES.iter().map(|_| "0xsales").collect();
+    let log_indexes: Vec<i32> = SALES.iter().map(|s| s.6).collect();
+
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_sales
+            (chain_id, sale_type, entity_id, nft_contract, token_id, seller, buyer, payment_token, price,
+             block_number, block_timestamp, tx_hash, log_index)
+        SELECT $11, s.sale_type, s.entity_id,
+               COALESCE(l.nft_contract, o.nft_contract, co.nft_contract, a.nft_contract, d.nft_contract),
+               COALESCE(s.token_id, l.token_id, o.token_id, a.token_id, d.token_id),
+               COALESCE(s.seller, l.seller, a.seller, d.seller, b.seller),
+               COALESCE(s.buyer, o.offerer, co.offerer),
+               COALESCE(l.payment_token, o.payment_token, co.payment_token, a.payment_token, d.payment_token, b.payment_token),
+               COALESCE(s.price, o.amount, co.amount),
+               s.block_number, s.block_timestamp, s.tx_hash, s.log_index
+        FROM UNNEST(
+            $1::TEXT[], $2::BIGINT[], $3::NUMERIC[], $4::TEXT[], $5::TEXT[],
+            $6::NUMERIC[], $7::BIGINT[], $8::TIMESTAMPTZ[], $9::TEXT[], $10::INT[]
+        ) AS s(sale_type, entity_id, token_id, seller, buyer, price, block_number, block_timestamp, tx_hash, log_index)
+        LEFT JOIN marketplace_listings l
+            ON s.sale_type = 'listing' AND l.listing_id = s.entity_id AND l.chain_id = $11
+        LEFT JOIN marketplace_offers o
+            ON s.sale_type = 'offer' AND o.offer_id = s.entity_id AND o.chain_id = $11
+        LEFT JOIN marketplace_collection_offers co
+            ON s.sale_type = 'collection_offer' AND co.offer_id = s.entity_id AND co.chain_id = $11
+        LEFT JOIN marketplace_auctions a
+            ON s.sale_type = 'auction' AND a.auction_id = s.entity_id AND a.chain_id = $11
+        LEFT JOIN marketplace_dutch_auctions d
+            ON s.sale_type = 'dutch_auction' AND d.auction_id = s.entity_id AND d.chain_id = $11
+        LEFT JOIN marketplace_bundles b
+            ON s.sale_type = 'bundle' AND b.bundle_id = s.entity_id AND b.chain_id = $11
+        WHERE COALESCE(s.price, o.amount, co.amount) IS NOT NULL
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(sale_types)
+    .bind(entity_ids)
+    .bind(token_ids)
+    .bind(sellers)
+    .bind(buyers)
+    .bind(prices)
+    .bind(block_numbers)
+    .bind(block_timestamps)
+    .bind(tx_hashes)
+    .bind(log_indexes)
+    .bind(CHAIN_ID)
+    .execute(&mut **tx)
+    .await
+    .unwrap()
+    .rows_affected()
+}
+
+/// (sale_type, nft_contract, token_id, seller, buyer, payment_token, price)
+type StoredSale = (String, Option<String>, Option<BigDecimal>, Option<String>, Option<String>, Option<String>, BigDecimal);
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn sales_take_missing_fields_from_the_sold_entity() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_entities(&mut tx).await;
+    assert_eq!(insert_sales(&mut tx).await, 3);
+
+    let rows: Vec<StoredSale> = sqlx::query_as(
+        r#"
+        SELECT sale_type, nft_contract, token_id, seller, buyer, payment_token, price
+        FROM marketplace_sales WHERE chain_id = $1 ORDER BY log_index
+        "#,
+    )
+    .bind(CHAIN_ID)
+    .fetch_all(&mut *tx)
+    .await
+    .unwrap();
+
+    let s = |v: &str| Some(v.to_string());
+    assert_eq!(
+        rows,
+        vec![
+            ("offer".into(), s("0xnft"), Some(BigDecimal::from(7)), s("0xseller"), s("0xofferer"), s("0xusdc"), BigDecimal::from(30)),
+            ("collection_offer".into(), s("0xnft"), Some(BigDecimal::from(9)), s("0xseller"), s("0xcollector"), s("0x0"), BigDecimal::from(20)),
+            ("bundle".into(), None, None, s("0xbundler"), s("0xbuyer"), s("0x0"), BigDecimal::from(3)),
+        ]
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn replaying_sales_is_a_no_op() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+    insert_entities(&mut tx).await;
+    assert_eq!(insert_sales(&mut tx).await, 3);
+    assert_eq!(insert_sales(&mut tx).await, 0);
+}
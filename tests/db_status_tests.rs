@@ -0,0 +1,108 @@
+//! Database checks for the marketplace status enums (src/types/status.rs): every variant
+//! binds and decodes as the TEXT the status columns already hold, the `status = $1` filters
+//! match it, and a value outside the enum fails to decode instead of passing through.
+//!
+//! Like explain_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_status_tests -- --ignored`
+//!
+//! Everything runs inside a transaction that is rolled back, so only the migrations persist.
+
+#[allow(dead_code)]
+#[path = "../src/types/status.rs"]
+mod status;
+
+use sqlx::{PgPool, Postgres, Transaction};
+use status::{AuctionStatus, ListingStatus, OfferStatus};
+
+const CHAIN_ID: i32 = 999_010;
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+    let tx = pool.begin().await.unwrap();
+    Some((pool, tx))
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn every_variant_round_trips_through_text() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    for &v in ListingStatus::ALL {
+        let (text, back): (String, ListingStatus) =
+            sqlx::query_as("SELECT $1::TEXT, $1::TEXT").bind(v).fetch_one(&mut *tx).await.unwrap();
+        assert_eq!((text.as_str(), back), (v.as_str(), v));
+    }
+    for &v in OfferStatus::ALL {
+        let back: OfferStatus = sqlx::query_scalar("SELECT $1::TEXT").bind(v).fetch_one(&mut *tx).await.unwrap();
+        assert_eq!(back, v);
+    }
+    for &v in AuctionStatus::ALL {
+        let back: AuctionStatus = sqlx::query_scalar("SELECT $1::TEXT").bind(v).fetch_one(&mut *tx).await.unwrap();
+        assert_eq!(back, v);
+    }
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn status_column_update_and_filter() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_listings (listing_id, chain_id, seller, nft_contract, token_id, payment_token,
+                                          price, expiry, block_number, tx_hash)
+        VALUES (1, $1, '0xseller', '0xnft', 7, '0xtoken', 100, 0, 1, '0xstatus'),
+               (2, $1, '0xseller', '0xnft', 8, '0xtoken', 100, 0, 1, '0xstatus')
+        "#,
+    )
+    .bind(CHAIN_ID)
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+
+    // Same statement as update_listing_status (status only)
+    sqlx::query("UPDATE marketplace_listings SET status = $3 WHERE listing_id = $1 AND chain_id = $2")
+        .bind(2_i64)
+        .bind(CHAIN_ID)
+        .bind(ListingStatus::Sold)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+    // The get_listings filters, with the default Active and with exclude_status
+    let filter = |status: Option<ListingStatus>, exclude: Option<ListingStatus>| {
+        sqlx::query_as::<_, (i64, ListingStatus)>(
+            r#"
+            SELECT listing_id, status FROM marketplace_listings
+            WHERE chain_id = $1 AND ($2::TEXT IS NULL OR status = $2) AND ($3::TEXT IS NULL OR status <> $3)
+            ORDER BY listing_id
+            "#,
+        )
+        .bind(CHAIN_ID)
+        .bind(status)
+        .bind(exclude)
+    };
+    let active = filter(Some(ListingStatus::Active), None).fetch_all(&mut *tx).await.unwrap();
+    assert_eq!(active, vec![(1, ListingStatus::Active)]);
+    let not_active = filter(None, Some(ListingStatus::Active)).fetch_all(&mut *tx).await.unwrap();
+    assert_eq!(not_active, vec![(2, ListingStatus::Sold)]);
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn unknown_stored_status_fails_to_decode() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    let err = sqlx::query_scalar::<_, ListingStatus>("SELECT 'cancelled'::TEXT")
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("unknown ListingStatus 'cancelled'"), "{}", err);
+    // A status of another entity is just as unknown
+    assert!(sqlx::query_scalar::<_, OfferStatus>("SELECT 'Sold'::TEXT").fetch_one(&mut *tx).await.is_err());
+    tx.rollback().await.unwrap();
+}
@@ -0,0 +1,67 @@
+//! Database checks for the API pool's `statement_timeout` (DB_STATEMENT_TIMEOUT_MS).
+//!
+//! Like explain_tests.rs these need a Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_timeout_tests -- --ignored`
+//!
+//! Nothing is written; the tests only run `pg_sleep`.
+
+use std::time::Duration;
+
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{Executor, PgPool};
+
+/// SQLSTATE query_canceled, mapped to 504 QUERY_TIMEOUT by src/api/error.rs
+const QUERY_CANCELED: &str = "57014";
+
+/// Same after_connect hook as PoolConfig::pool_options in src/config.rs
+async fn pool_with_timeout(timeout: Option<Duration>) -> Option<PgPool> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let options = PgPoolOptions::new().max_connections(1);
+    let options = match timeout {
+        Some(timeout) => {
+            let set_timeout = format!("SET statement_timeout = {}", timeout.as_millis());
+            options.after_connect(move |conn, _meta| {
+                let set_timeout = set_timeout.clone();
+                Box::pin(async move {
+                    conn.execute(set_timeout.as_str()).await?;
+                    Ok(())
+                })
+            })
+        }
+        None => options,
+    };
+    Some(options.connect(&url).await.expect("connect TEST_DATABASE_URL"))
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn slow_statement_is_cancelled_with_query_canceled() {
+    let Some(pool) = pool_with_timeout(Some(Duration::from_millis(200))).await else { return };
+
+    let err = sqlx::query("SELECT pg_sleep(2)")
+        .execute(&pool)
+        .await
+        .expect_err("statement should exceed the timeout");
+    let code = err.as_database_error().and_then(|db| db.code()).map(|c| c.to_string());
+    assert_eq!(code.as_deref(), Some(QUERY_CANCELED));
+
+    // The connection stays usable after the cancel
+    sqlx::query("SELECT pg_sleep(0.01)").execute(&pool).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn timeout_applies_to_every_pooled_connection() {
+    let Some(pool) = pool_with_timeout(Some(Duration::from_millis(1500))).await else { return };
+
+    let setting: String = sqlx::query_scalar("SHOW statement_timeout").fetch_one(&pool).await.unwrap();
+    assert_eq!(setting, "1500ms");
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn indexer_pool_without_timeout_runs_long_statements() {
+    let Some(pool) = pool_with_timeout(None).await else { return };
+
+    sqlx::query("SELECT pg_sleep(0.5)").execute(&pool).await.unwrap();
+}
@@ -0,0 +1,140 @@
+//! Database checks for the updated_at trigger (migrations/025_updated_at_trigger.sql):
+//! updates stamp updated_at without the statement setting it, including the feedback revoke
+//! path, and every table with an updated_at column carries the trigger.
+//!
+//! Like explain_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test db_updated_at_tests -- --ignored`
+//!
+//! Everything runs inside a transaction that is rolled back, so only the migrations persist.
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+
+const CHAIN_ID: i32 = 999_012;
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+    let tx = pool.begin().await.unwrap();
+    Some((pool, tx))
+}
+
+/// Rows are written with a created_at / updated_at long before the transaction, so a stamp
+/// from the trigger (NOW(), the transaction start) is distinguishable from the original.
+const OLD: &str = "2000-01-01T00:00:00Z";
+
+async fn now(tx: &mut Transaction<'static, Postgres>) -> DateTime<Utc> {
+    sqlx::query_scalar("SELECT NOW()").fetch_one(&mut **tx).await.unwrap()
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn revoking_feedback_stamps_updated_at() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    sqlx::query(
+        r#"
+        INSERT INTO feedbacks (agent_id, chain_id, client_address, feedback_index, value, value_decimals,
+                               value_normalized, block_number, tx_hash, created_at, updated_at)
+        VALUES (1, $1, '0xclient', 0, 5, 0, 5, 1, '0xfeedback', $2::TIMESTAMPTZ, $2::TIMESTAMPTZ)
+        "#,
+    )
+    .bind(CHAIN_ID)
+    .bind(OLD)
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+
+    // Same statement as revoke_feedbacks in src/db/feedbacks.rs
+    sqlx::query(
+        r#"
+        UPDATE feedbacks f
+        SET revoked = true
+        FROM UNNEST($1::BIGINT[], $2::TEXT[], $3::BIGINT[]) AS r(agent_id, client_address, feedback_index)
+        WHERE f.chain_id = $4 AND f.agent_id = r.agent_id
+          AND f.client_address = r.client_address AND f.feedback_index = r.feedback_index
+        "#,
+    )
+    .bind(vec![1_i64])
+    .bind(vec!["0xclient"])
+    .bind(vec![0_i64])
+    .bind(CHAIN_ID)
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+
+    let (revoked, created_at, updated_at): (bool, DateTime<Utc>, DateTime<Utc>) = sqlx::query_as(
+        "SELECT revoked, created_at, updated_at FROM feedbacks WHERE chain_id = $1 AND agent_id = 1",
+    )
+    .bind(CHAIN_ID)
+    .fetch_one(&mut *tx)
+    .await
+    .unwrap();
+    assert!(revoked);
+    assert_eq!(created_at, OLD.parse::<DateTime<Utc>>().unwrap(), "created_at is left alone");
+    assert_eq!(updated_at, now(&mut tx).await);
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn upserts_and_explicit_values_get_the_trigger_stamp() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    let upsert = "INSERT INTO marketplace_config (chain_id, platform_fee_bps, fee_recipient) VALUES ($1, $2, '0xfee') \
+                  ON CONFLICT (chain_id) DO UPDATE SET platform_fee_bps = EXCLUDED.platform_fee_bps";
+    sqlx::query(upsert).bind(CHAIN_ID).bind(100).execute(&mut *tx).await.unwrap();
+    sqlx::query("UPDATE marketplace_config SET updated_at = $2::TIMESTAMPTZ WHERE chain_id = $1")
+        .bind(CHAIN_ID)
+        .bind(OLD)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+
+    // Even an explicit value is replaced: the trigger owns the column
+    let updated_at: DateTime<Utc> = sqlx::query_scalar("SELECT updated_at FROM marketplace_config WHERE chain_id = $1")
+        .bind(CHAIN_ID)
+        .fetch_one(&mut *tx)
+        .await
+        .unwrap();
+    assert_eq!(updated_at, now(&mut tx).await);
+
+    // ON CONFLICT DO UPDATE fires it too
+    sqlx::query(upsert).bind(CHAIN_ID).bind(250).execute(&mut *tx).await.unwrap();
+    let (bps, updated_at): (i32, DateTime<Utc>) =
+        sqlx::query_as("SELECT platform_fee_bps, updated_at FROM marketplace_config WHERE chain_id = $1")
+            .bind(CHAIN_ID)
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+    assert_eq!((bps, updated_at), (250, now(&mut tx).await));
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn every_updated_at_table_has_the_trigger() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    let missing: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT c.table_name::TEXT
+        FROM information_schema.columns c
+        JOIN information_schema.tables t
+          ON t.table_schema = c.table_schema AND t.table_name = c.table_name AND t.table_type = 'BASE TABLE'
+        WHERE c.table_schema = current_schema() AND c.column_name = 'updated_at'
+          AND NOT EXISTS (
+              SELECT 1 FROM pg_trigger tg
+              WHERE tg.tgrelid = (quote_ident(c.table_name))::regclass
+                AND tg.tgfoid = 'set_updated_at'::regproc
+          )
+        ORDER BY 1
+        "#,
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .unwrap();
+    assert!(missing.is_empty(), "tables without the updated_at trigger: {missing:?}");
+    tx.rollback().await.unwrap();
+}
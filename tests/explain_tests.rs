@@ -0,0 +1,237 @@
+//! EXPLAIN checks for the activity_log composite indexes
+//! (migrations/012_activity_log_composite_indexes.sql and the chain feed index from
+//! migrations/031_activity_log_block_timestamp.sql) and the marketplace partial indexes
+//! (migrations/013).
+//!
+//! Unlike the other test files these need a real, disposable Postgres database, so they are
+//! ignored by default. Run with:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test explain_tests -- --ignored`
+//!
+//! Seeding happens inside a transaction that is rolled back, so only the migrations persist.
+
+use sqlx::{PgPool, Postgres, Transaction};
+
+/// Same shape as the listing query built by get_listings in src/db/marketplace.rs.
+fn listings_query(order_clause: &str) -> String {
+    format!(
+        r#"
+        EXPLAIN (COSTS OFF)
+        SELECT l.*, a.name AS agent_name, a.image AS agent_image
+        FROM marketplace_listings l
+        LEFT JOIN agents a ON a.agent_id = l.token_id::BIGINT AND a.chain_id = l.chain_id
+        LEFT JOIN marketplace_config mc ON mc.chain_id = l.chain_id
+        WHERE ($1::TEXT IS NULL OR l.status = $1)
+          AND ($2::INT IS NULL OR l.chain_id = $2)
+          AND ($3::TEXT IS NULL OR l.nft_contract = $3)
+          AND ($4::TEXT IS NULL OR l.seller = $4)
+          AND ($5::TEXT IS NULL OR l.status <> $5)
+        ORDER BY {}
+        LIMIT $6 OFFSET $7
+        "#,
+        order_clause
+    )
+}
+
+async fn setup() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+
+    let mut tx = pool.begin().await.unwrap();
+    // 200k listings, only 5% Active — the shape that makes plain status indexes degrade
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_listings
+            (listing_id, chain_id, seller, nft_contract, token_id, payment_token, price, expiry, status, block_number, tx_hash)
+        SELECT 1000000 + g, 143, '0xseller' || (g % 500), '0xcollection' || (g % 20), g, '0x0',
+               (g * 7919) % 100000, 0,
+               CASE WHEN g % 20 = 0 THEN 'Active' WHEN g % 2 = 0 THEN 'Sold' ELSE 'Cancelled' END,
+               g, '0x'
+        FROM generate_series(1, 200000) g
+        "#,
+    )
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO marketplace_offers
+            (offer_id, chain_id, offerer, nft_contract, token_id, payment_token, amount, expiry, status, block_number, tx_hash)
+        SELECT 1000000 + g, 143, '0xofferer' || (g % 500), '0xcollection' || (g % 20), g % 1000, '0x0',
+               g, 0, CASE WHEN g % 20 = 0 THEN 'Active' ELSE 'Cancelled' END, g, '0x'
+        FROM generate_series(1, 100000) g
+        "#,
+    )
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+    sqlx::query("ANALYZE marketplace_listings").execute(&mut *tx).await.unwrap();
+    sqlx::query("ANALYZE marketplace_offers").execute(&mut *tx).await.unwrap();
+
+    Some((pool, tx))
+}
+
+async fn plan(
+    tx: &mut Transaction<'static, Postgres>,
+    order_clause: &str,
+    chain_id: Option<i32>,
+    nft_contract: Option<&str>,
+) -> String {
+    let rows: Vec<(String,)> = sqlx::query_as(&listings_query(order_clause))
+        .bind(Some("Active"))
+        .bind(chain_id)
+        .bind(nft_contract)
+        .bind(None::<&str>)
+        .bind(None::<&str>)
+        .bind(20_i64)
+        .bind(0_i64)
+        .fetch_all(&mut **tx)
+        .await
+        .unwrap();
+    rows.into_iter().map(|(line,)| line).collect::<Vec<_>>().join("\n")
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn active_listing_price_sorts_use_partial_index() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    for order in ["l.price ASC", "l.price DESC"] {
+        let p = plan(&mut tx, order, None, None).await;
+        assert!(p.contains("idx_ml_active_price"), "{} plan:\n{}", order, p);
+        assert!(!p.contains("Sort Key"), "{} should not sort:\n{}", order, p);
+    }
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn active_collection_price_sort_uses_partial_index() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    let p = plan(&mut tx, "l.price ASC", Some(143), Some("0xcollection3")).await;
+    assert!(p.contains("idx_ml_active_chain_nft_price"), "plan:\n{}", p);
+    assert!(!p.contains("Sort Key"), "should not sort:\n{}", p);
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn active_listing_recent_sort_uses_partial_index() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    let p = plan(&mut tx, "l.block_number DESC", None, None).await;
+    assert!(p.contains("idx_ml_active_block"), "plan:\n{}", p);
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn active_token_offers_use_partial_index() {
+    let Some((_pool, mut tx)) = setup().await else { return };
+
+    let rows: Vec<(String,)> = sqlx::query_as(
+        r#"
+        EXPLAIN (COSTS OFF)
+        SELECT * FROM marketplace_offers
+        WHERE ($1::INT IS NULL OR chain_id = $1)
+          AND ($2::TEXT IS NULL OR nft_contract = $2)
+          AND ($3::NUMERIC IS NULL OR token_id = $3)
+          AND ($4::TEXT IS NULL OR offerer = $4)
+          AND ($5::TEXT IS NULL OR status = $5)
+        ORDER BY block_number DESC
+        LIMIT 20 OFFSET 0
+        "#,
+    )
+    .bind(None::<i32>)
+    .bind(Some("0xcollection3"))
+    .bind(Some(bigdecimal::BigDecimal::from(3)))
+    .bind(None::<&str>)
+    .bind(Some("Active"))
+    .fetch_all(&mut *tx)
+    .await
+    .unwrap();
+    let p = rows.into_iter().map(|(l,)| l).collect::<Vec<_>>().join("\n");
+    assert!(p.contains("idx_mo_active_nft_token"), "plan:\n{}", p);
+    tx.rollback().await.unwrap();
+}
+
+/// 200k activity rows, 5% of them on chain 10143 and the rest on 143, in a transaction for
+/// the caller to roll back. Agent 1000000 is busy (every tenth row); the rest are spread over
+/// 2,000 agents.
+async fn setup_activity() -> Option<(PgPool, Transaction<'static, Postgres>)> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let pool = PgPool::connect(&url).await.expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("run migrations");
+
+    let mut tx = pool.begin().await.unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO activity_log
+            (agent_id, chain_id, event_type, block_number, tx_hash, log_index, block_timestamp)
+        SELECT CASE WHEN g % 10 = 0 THEN 1000000 ELSE 1000000 + g % 2000 END, CASE WHEN g % 20 = 0 THEN 10143 ELSE 143 END, 'NewFeedback', g, '0x' || g, 0,
+               TIMESTAMPTZ '2025-01-01' + g * INTERVAL '1 second'
+        FROM generate_series(1, 200000) g
+        "#,
+    )
+    .execute(&mut *tx)
+    .await
+    .unwrap();
+    sqlx::query("ANALYZE activity_log").execute(&mut *tx).await.unwrap();
+
+    Some((pool, tx))
+}
+
+async fn explain(tx: &mut Transaction<'static, Postgres>, query: &str, chain_id: i32) -> String {
+    let rows: Vec<(String,)> = sqlx::query_as(&format!("EXPLAIN (COSTS OFF) {}", query))
+        .bind(chain_id)
+        .bind(1_000_000_i64)
+        .fetch_all(&mut **tx)
+        .await
+        .unwrap();
+    rows.into_iter().map(|(line,)| line).collect::<Vec<_>>().join("\n")
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn agent_activity_pages_from_the_composite_index() {
+    let Some((_pool, mut tx)) = setup_activity().await else { return };
+
+    // Same filter and order as get_activities in src/db/activity.rs, for the busy agent
+    let p = explain(
+        &mut tx,
+        r#"
+        SELECT * FROM activity_log
+        WHERE agent_id = $2 AND chain_id = $1
+        ORDER BY block_number DESC, log_index DESC
+        LIMIT 20 OFFSET 0
+        "#,
+        10143,
+    )
+    .await;
+    assert!(p.contains("idx_activity_log_agent_block"), "plan:\n{}", p);
+    assert!(!p.contains("Sort Key"), "should not sort:\n{}", p);
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn chain_activity_feed_walks_the_chain_index() {
+    let Some((_pool, mut tx)) = setup_activity().await else { return };
+
+    // Same chain filter and order as get_global_activities in src/db/activity.rs
+    let p = explain(
+        &mut tx,
+        r#"
+        SELECT * FROM activity_log a
+        WHERE ($1::INT IS NULL OR a.chain_id = $1) AND $2::BIGINT IS NOT NULL
+        ORDER BY a.block_timestamp DESC, a.id DESC
+        LIMIT 20 OFFSET 0
+        "#,
+        10143,
+    )
+    .await;
+    assert!(p.contains("idx_activity_log_chain_time"), "plan:\n{}", p);
+    assert!(!p.contains("Sort Key"), "should not sort:\n{}", p);
+    tx.rollback().await.unwrap();
+}
@@ -0,0 +1,1133 @@
+//! The index_*_events functions end to end: canned logs and calls from the mock RPC in
+//! tests/common/rpc.rs, decoded and written by the real indexer code, asserted on the rows.
+//!
+//! Like http_tests.rs these need a disposable Postgres database and are ignored by default:
+//! `TEST_DATABASE_URL=postgres://... cargo test --test indexer_rpc_tests -- --ignored`
+//!
+//! Each test indexes into its own chain id, so they can run in parallel.
+
+mod common;
+
+use alloy::primitives::{Address, B256, U256};
+use common::rpc::{block_timestamp, MockRpc, IDENTITY, MARKETPLACE, REPUTATION};
+use molt_marketplace_backend::indexer::backfill::backfill_block_timestamps;
+use molt_marketplace_backend::indexer::identity::{index_identity_events, IdentityRegistry};
+use molt_marketplace_backend::indexer::marketplace::{index_marketplace_events, IMoltMarketplace, MoltMarketplace};
+use molt_marketplace_backend::indexer::reputation::{index_reputation_events, ReputationRegistry};
+
+const ZERO: Address = Address::ZERO;
+const OWNER: Address = Address::repeat_byte(0xa1);
+const BUYER: Address = Address::repeat_byte(0xb2);
+const OTHER_NFT: Address = Address::repeat_byte(0xcc);
+
+fn hex(address: Address) -> String {
+    format!("{:#x}", address)
+}
+
+/// Registered plus its mint Transfer, as the identity registry emits them.
+fn push_registration(rpc: &MockRpc, agent_id: u64, block_number: u64) {
+    let registered = IdentityRegistry::Registered {
+        agentId: U256::from(agent_id),
+        agentURI: String::new(),
+        owner: OWNER,
+    };
+    let mint = IdentityRegistry::Transfer { from: ZERO, to: OWNER, tokenId: U256::from(agent_id) };
+    rpc.push_log(IDENTITY, &mint, block_number, 0);
+    rpc.push_log(IDENTITY, &registered, block_number, 1);
+}
+
+fn listed(listing_id: u64, token_id: u64, price: u64) -> MoltMarketplace::Listed {
+    MoltMarketplace::Listed {
+        listingId: U256::from(listing_id),
+        seller: OWNER,
+        nftContract: IDENTITY,
+        tokenId: U256::from(token_id),
+        paymentToken: ZERO,
+        price: U256::from(price),
+        expiry: U256::ZERO,
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn registered_creates_agent_activity_and_ownership() {
+    let Some(pool) = common::test_pool(999_201).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_201);
+    push_registration(&rpc, 7, 100);
+    // Outside the indexed range
+    push_registration(&rpc, 8, 300);
+
+    index_identity_events(&pool, &rpc.provider(&chain), &chain, 100, 200).await.unwrap();
+
+    let agents: Vec<(i64, String, Option<chrono::DateTime<chrono::Utc>>)> = sqlx::query_as(
+        "SELECT agent_id, owner, block_timestamp FROM agents WHERE chain_id = $1 ORDER BY agent_id",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(agents, vec![(7, hex(OWNER), Some(block_timestamp(100)))]);
+
+    let events: Vec<(String,)> =
+        sqlx::query_as("SELECT event_type FROM activity_log WHERE chain_id = $1 AND agent_id = 7")
+            .bind(chain.chain_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    // The mint is only recorded as ownership history
+    assert_eq!(events, vec![("Registered".to_string(),)]);
+
+    let owners: Vec<(String, String)> = sqlx::query_as(
+        "SELECT from_address, to_address FROM agent_ownership_history WHERE chain_id = $1 AND agent_id = 7",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(owners, vec![(hex(ZERO), hex(OWNER))]);
+
+    // One block, one timestamp lookup
+    assert_eq!(rpc.calls("eth_getBlockByNumber"), 1);
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn replayed_registration_does_not_roll_back_a_later_uri_update() {
+    let Some(pool) = common::test_pool(999_211).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_211);
+    push_registration(&rpc, 7, 100);
+    // Unreachable, so the background metadata fetch fails without touching the row
+    let new_uri = "http://127.0.0.1:1/agent-7.json";
+    let updated = IdentityRegistry::URIUpdated { agentId: U256::from(7), newURI: new_uri.to_string(), updatedBy: OWNER };
+    rpc.push_log(IDENTITY, &updated, 150, 0);
+    let provider = rpc.provider(&chain);
+    let agent = || {
+        sqlx::query_as::<_, (Option<String>, Option<i64>, Option<String>)>(
+            "SELECT uri, block_number, tx_hash FROM agents WHERE chain_id = $1 AND agent_id = 7",
+        )
+        .bind(chain.chain_id)
+        .fetch_one(&pool)
+    };
+
+    index_identity_events(&pool, &provider, &chain, 100, 200).await.unwrap();
+    let latest = agent().await.unwrap();
+    assert_eq!((latest.0.as_deref(), latest.1), (Some(new_uri), Some(150)));
+
+    // A retried batch re-applies the older Registered block
+    index_identity_events(&pool, &provider, &chain, 100, 100).await.unwrap();
+    assert_eq!(agent().await.unwrap(), latest);
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn new_feedback_is_stored_and_scored() {
+    let Some(pool) = common::test_pool(999_202).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_202);
+    push_registration(&rpc, 7, 100);
+    for (index, value) in [(1u64, 80i128), (2, 60)] {
+        let feedback = ReputationRegistry::NewFeedback {
+            agentId: U256::from(7),
+            clientAddress: Address::repeat_byte(index as u8),
+            feedbackIndex: index,
+            value,
+            valueDecimals: 0,
+            indexedTag1: B256::ZERO,
+            tag1: "speed".to_string(),
+            tag2: String::new(),
+            endpoint: String::new(),
+            feedbackURI: String::new(),
+            feedbackHash: B256::ZERO,
+        };
+        rpc.push_log(REPUTATION, &feedback, 110, index);
+    }
+
+    let provider = rpc.provider(&chain);
+    index_identity_events(&pool, &provider, &chain, 100, 200).await.unwrap();
+    index_reputation_events(&pool, &provider, &chain, 100, 200).await.unwrap();
+
+    let feedbacks: Vec<(i64, String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT feedback_index, value::TEXT, tag1, tag2 FROM feedbacks WHERE chain_id = $1 ORDER BY feedback_index",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        feedbacks,
+        vec![
+            (1, "80".to_string(), Some("speed".to_string()), None),
+            (2, "60".to_string(), Some("speed".to_string()), None),
+        ]
+    );
+
+    let (count, clients): (i64, i64) =
+        sqlx::query_as("SELECT feedback_count, unique_clients FROM agent_stats WHERE chain_id = $1 AND agent_id = 7")
+            .bind(chain.chain_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!((count, clients), (2, 2));
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn revoking_one_clients_feedback_leaves_the_same_index_of_another() {
+    let Some(pool) = common::test_pool(999_217).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_217);
+    push_registration(&rpc, 7, 100);
+    // Feedback indexes count per client: both clients' first feedback is index 1
+    for (log_index, client) in [(0u64, OWNER), (1, BUYER)] {
+        let feedback = ReputationRegistry::NewFeedback {
+            agentId: U256::from(7),
+            clientAddress: client,
+            feedbackIndex: 1,
+            value: 80,
+            valueDecimals: 0,
+            indexedTag1: B256::ZERO,
+            tag1: String::new(),
+            tag2: String::new(),
+            endpoint: String::new(),
+            feedbackURI: String::new(),
+            feedbackHash: B256::ZERO,
+        };
+        rpc.push_log(REPUTATION, &feedback, 110, log_index);
+    }
+    let revoked = ReputationRegistry::FeedbackRevoked { agentId: U256::from(7), clientAddress: OWNER, feedbackIndex: 1 };
+    rpc.push_log(REPUTATION, &revoked, 111, 0);
+
+    let provider = rpc.provider(&chain);
+    index_identity_events(&pool, &provider, &chain, 100, 200).await.unwrap();
+    index_reputation_events(&pool, &provider, &chain, 100, 200).await.unwrap();
+
+    let feedbacks: Vec<(String, bool)> = sqlx::query_as(
+        "SELECT client_address, revoked FROM feedbacks WHERE chain_id = $1 AND agent_id = 7 ORDER BY revoked",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(feedbacks, vec![(hex(BUYER), false), (hex(OWNER), true)]);
+
+    let (count,): (i64,) = sqlx::query_as("SELECT feedback_count FROM agent_stats WHERE chain_id = $1 AND agent_id = 7")
+        .bind(chain.chain_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn replayed_range_records_each_response_once() {
+    let Some(pool) = common::test_pool(999_218).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_218);
+    push_registration(&rpc, 7, 100);
+    for log_index in [0u64, 1] {
+        let response = ReputationRegistry::ResponseAppended {
+            agentId: U256::from(7),
+            clientAddress: BUYER,
+            feedbackIndex: 1,
+            responder: OWNER,
+            responseURI: format!("ipfs://response-{log_index}"),
+            responseHash: B256::ZERO,
+        };
+        rpc.push_log(REPUTATION, &response, 110, log_index);
+    }
+
+    // A failed batch is retried as a whole
+    let provider = rpc.provider(&chain);
+    index_reputation_events(&pool, &provider, &chain, 100, 200).await.unwrap();
+    index_reputation_events(&pool, &provider, &chain, 100, 200).await.unwrap();
+
+    let responses: Vec<(String, i32)> = sqlx::query_as(
+        "SELECT response_uri, log_index FROM feedback_responses WHERE chain_id = $1 ORDER BY log_index",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        responses,
+        vec![("ipfs://response-0".to_string(), 0), ("ipfs://response-1".to_string(), 1)]
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn listed_then_bought_marks_sold_and_records_sale() {
+    let Some(pool) = common::test_pool(999_203).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_203);
+    rpc.push_log(MARKETPLACE, &listed(1, 7, 1_000), 120, 0);
+    let bought = MoltMarketplace::Bought { listingId: U256::from(1), buyer: BUYER, price: U256::from(1_000) };
+    rpc.push_log(MARKETPLACE, &bought, 130, 0);
+
+    index_marketplace_events(&pool, &rpc.provider(&chain), &chain, 100, 200).await.unwrap();
+
+    let listing: (String, Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT status, buyer, sold_price::TEXT FROM marketplace_listings WHERE chain_id = $1 AND listing_id = 1",
+    )
+    .bind(chain.chain_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(listing, ("Sold".to_string(), Some(hex(BUYER)), Some("1000".to_string())));
+
+    let sales: Vec<(String, i64, Option<String>)> =
+        sqlx::query_as("SELECT sale_type, entity_id, buyer FROM marketplace_sales WHERE chain_id = $1")
+            .bind(chain.chain_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(sales, vec![("listing".to_string(), 1, Some(hex(BUYER)))]);
+
+    // The listed NFT is an agent, so both events reach its activity feed
+    let events: Vec<(String,)> = sqlx::query_as(
+        "SELECT event_type FROM activity_log WHERE chain_id = $1 AND agent_id = 7 ORDER BY block_number",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        events,
+        vec![("marketplace:Listed".to_string(),), ("marketplace:Bought".to_string(),)]
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn repriced_and_cancelled_listings_reach_the_agent_activity_feed() {
+    use molt_marketplace_backend::db;
+
+    let Some(pool) = common::test_pool(999_213).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_213);
+    rpc.push_log(MARKETPLACE, &listed(1, 7, 1_000), 110, 0);
+    let repriced = MoltMarketplace::ListingPriceUpdated {
+        listingId: U256::from(1),
+        oldPrice: U256::from(1_000),
+        newPrice: U256::from(800),
+    };
+    rpc.push_log(MARKETPLACE, &repriced, 120, 0);
+    let cancelled = MoltMarketplace::ListingCancelled { listingId: U256::from(1) };
+    rpc.push_log(MARKETPLACE, &cancelled, 130, 0);
+
+    index_marketplace_events(&pool, &rpc.provider(&chain), &chain, 100, 200).await.unwrap();
+
+    let events: Vec<(String, serde_json::Value)> = sqlx::query_as(
+        "SELECT event_type, event_data FROM activity_log WHERE chain_id = $1 AND agent_id = 7 AND block_number > 110 ORDER BY block_number",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (
+                "marketplace:ListingPriceUpdated".to_string(),
+                serde_json::json!({"listing_id": 1, "seller": hex(OWNER), "old_price": "1000", "new_price": "800"}),
+            ),
+            (
+                "marketplace:ListingCancelled".to_string(),
+                serde_json::json!({"listing_id": 1, "seller": hex(OWNER), "price": "800"}),
+            ),
+        ]
+    );
+
+    // Both fall under the marketplace category filter of the activity feed
+    let (_, total) = db::activity::get_activities(&pool, 7, chain.chain_id, Some("marketplace"), false, None, None, 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(total, 3);
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn accepted_offers_count_as_sales_at_the_offer_amount() {
+    let Some(pool) = common::test_pool(999_208).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_208);
+    rpc.push_log(MARKETPLACE, &listed(1, 7, 1_000), 110, 0);
+    let bought = MoltMarketplace::Bought { listingId: U256::from(1), buyer: BUYER, price: U256::from(1_000) };
+    rpc.push_log(MARKETPLACE, &bought, 120, 0);
+    let offer = MoltMarketplace::OfferMade {
+        offerId: U256::from(1),
+        offerer: BUYER,
+        nftContract: IDENTITY,
+        tokenId: U256::from(8),
+        paymentToken: ZERO,
+        amount: U256::from(2_000),
+        expiry: U256::ZERO,
+    };
+    rpc.push_log(MARKETPLACE, &offer, 130, 0);
+    let accepted = MoltMarketplace::OfferAccepted { offerId: U256::from(1), seller: OWNER };
+    rpc.push_log(MARKETPLACE, &accepted, 140, 0);
+    let collection_offer = MoltMarketplace::CollectionOfferMade {
+        offerId: U256::from(1),
+        offerer: BUYER,
+        nftContract: IDENTITY,
+        paymentToken: ZERO,
+        amount: U256::from(4_000),
+        expiry: U256::ZERO,
+    };
+    rpc.push_log(MARKETPLACE, &collection_offer, 150, 0);
+    let collection_accepted =
+        MoltMarketplace::CollectionOfferAccepted { offerId: U256::from(1), seller: OWNER, tokenId: U256::from(9) };
+    rpc.push_log(MARKETPLACE, &collection_accepted, 160, 0);
+
+    index_marketplace_events(&pool, &rpc.provider(&chain), &chain, 100, 200).await.unwrap();
+
+    // Each sale is priced at what the buyer paid and stamped with the block that completed it
+    type SaleRow = (String, String, Option<String>, Option<chrono::DateTime<chrono::Utc>>);
+    let sales: Vec<SaleRow> = sqlx::query_as(
+        "SELECT sale_type, price::TEXT, buyer, block_timestamp FROM marketplace_sales WHERE chain_id = $1 ORDER BY block_number",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        sales,
+        vec![
+            ("listing".to_string(), "1000".to_string(), Some(hex(BUYER)), Some(block_timestamp(120))),
+            ("offer".to_string(), "2000".to_string(), Some(hex(BUYER)), Some(block_timestamp(140))),
+            ("collection_offer".to_string(), "4000".to_string(), Some(hex(BUYER)), Some(block_timestamp(160))),
+        ]
+    );
+
+    // The totals /api/stats and /api/marketplace/stats sum, for this chain alone
+    let (total_sales, total_volume): (i64, String) = sqlx::query_as(
+        "SELECT COUNT(*), COALESCE(SUM(price), 0)::TEXT FROM marketplace_sales WHERE chain_id = $1",
+    )
+    .bind(chain.chain_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!((total_sales, total_volume.as_str()), (3, "7000"));
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn resolved_offers_reach_the_agent_activity_feed() {
+    let Some(pool) = common::test_pool(999_212).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_212);
+    let offer = |offer_id: u64, nft_contract: Address, token_id: u64| MoltMarketplace::OfferMade {
+        offerId: U256::from(offer_id),
+        offerer: BUYER,
+        nftContract: nft_contract,
+        tokenId: U256::from(token_id),
+        paymentToken: ZERO,
+        amount: U256::from(2_000),
+        expiry: U256::ZERO,
+    };
+    rpc.push_log(MARKETPLACE, &offer(1, IDENTITY, 7), 110, 0);
+    rpc.push_log(MARKETPLACE, &offer(2, IDENTITY, 8), 111, 0);
+    // Not an agent NFT, so neither of its events is an agent activity
+    rpc.push_log(MARKETPLACE, &offer(3, OTHER_NFT, 7), 112, 0);
+    let accepted = MoltMarketplace::OfferAccepted { offerId: U256::from(1), seller: OWNER };
+    rpc.push_log(MARKETPLACE, &accepted, 120, 0);
+    let cancelled = MoltMarketplace::OfferCancelled { offerId: U256::from(2) };
+    rpc.push_log(MARKETPLACE, &cancelled, 121, 0);
+    let other_accepted = MoltMarketplace::OfferAccepted { offerId: U256::from(3), seller: OWNER };
+    rpc.push_log(MARKETPLACE, &other_accepted, 122, 0);
+    let collection_offer = MoltMarketplace::CollectionOfferMade {
+        offerId: U256::from(1),
+        offerer: BUYER,
+        nftContract: IDENTITY,
+        paymentToken: ZERO,
+        amount: U256::from(4_000),
+        expiry: U256::ZERO,
+    };
+    rpc.push_log(MARKETPLACE, &collection_offer, 130, 0);
+    let collection_accepted =
+        MoltMarketplace::CollectionOfferAccepted { offerId: U256::from(1), seller: OWNER, tokenId: U256::from(9) };
+    rpc.push_log(MARKETPLACE, &collection_accepted, 140, 0);
+
+    index_marketplace_events(&pool, &rpc.provider(&chain), &chain, 100, 200).await.unwrap();
+
+    let events: Vec<(i64, String, serde_json::Value)> = sqlx::query_as(
+        "SELECT agent_id, event_type, event_data FROM activity_log WHERE chain_id = $1 AND block_number >= 120 ORDER BY block_number",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (
+                7,
+                "marketplace:OfferAccepted".to_string(),
+                serde_json::json!({"offer_id": 1, "seller": hex(OWNER), "offerer": hex(BUYER), "amount": "2000"}),
+            ),
+            (
+                8,
+                "marketplace:OfferCancelled".to_string(),
+                serde_json::json!({"offer_id": 2, "offerer": hex(BUYER), "amount": "2000"}),
+            ),
+            (
+                9,
+                "marketplace:CollectionOfferAccepted".to_string(),
+                serde_json::json!({"offer_id": 1, "seller": hex(OWNER), "offerer": hex(BUYER), "amount": "4000"}),
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn only_settled_and_bought_auctions_count_as_sales() {
+    let Some(pool) = common::test_pool(999_209).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_209);
+    for auction_id in 1..=4u64 {
+        let created = MoltMarketplace::AuctionCreated {
+            auctionId: U256::from(auction_id),
+            seller: OWNER,
+            nftContract: IDENTITY,
+            tokenId: U256::from(10 + auction_id),
+            paymentToken: ZERO,
+            startPrice: U256::from(100),
+            reservePrice: U256::from(500),
+            buyNowPrice: U256::from(900),
+            startTime: U256::from(1_700_000_000u64),
+            endTime: U256::from(1_700_086_400u64),
+        };
+        rpc.push_log(MARKETPLACE, &created, 110, auction_id);
+    }
+    let settled = MoltMarketplace::AuctionSettled { auctionId: U256::from(1), winner: BUYER, amount: U256::from(600) };
+    rpc.push_log(MARKETPLACE, &settled, 120, 0);
+    let buy_now = MoltMarketplace::AuctionBuyNow { auctionId: U256::from(2), buyer: BUYER, price: U256::from(900) };
+    rpc.push_log(MARKETPLACE, &buy_now, 121, 0);
+    let reserve_not_met = MoltMarketplace::AuctionReserveNotMet {
+        auctionId: U256::from(3),
+        highestBid: U256::from(300),
+        reservePrice: U256::from(500),
+    };
+    rpc.push_log(MARKETPLACE, &reserve_not_met, 122, 0);
+    let cancelled = MoltMarketplace::AuctionCancelled { auctionId: U256::from(4) };
+    rpc.push_log(MARKETPLACE, &cancelled, 123, 0);
+
+    index_marketplace_events(&pool, &rpc.provider(&chain), &chain, 100, 200).await.unwrap();
+
+    let statuses: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT auction_id, status FROM marketplace_auctions WHERE chain_id = $1 ORDER BY auction_id",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        statuses,
+        vec![
+            (1, "Ended".to_string()),
+            (2, "Ended".to_string()),
+            (3, "ReserveNotMet".to_string()),
+            (4, "Cancelled".to_string()),
+        ]
+    );
+
+    // The settled price is the sale amount and the winner the buyer; the seller comes from the auction
+    type SaleRow = (i64, String, Option<String>, Option<String>);
+    let sales: Vec<SaleRow> = sqlx::query_as(
+        "SELECT entity_id, price::TEXT, seller, buyer FROM marketplace_sales WHERE chain_id = $1 AND sale_type = 'auction' ORDER BY entity_id",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        sales,
+        vec![
+            (1, "600".to_string(), Some(hex(OWNER)), Some(hex(BUYER))),
+            (2, "900".to_string(), Some(hex(OWNER)), Some(hex(BUYER))),
+        ]
+    );
+
+    let (total_sales, total_volume): (i64, String) = sqlx::query_as(
+        "SELECT COUNT(*), COALESCE(SUM(price), 0)::TEXT FROM marketplace_sales WHERE chain_id = $1",
+    )
+    .bind(chain.chain_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!((total_sales, total_volume.as_str()), (2, "1500"));
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn every_sale_type_counts_toward_volume_and_bundles_count_each_item() {
+    let Some(pool) = common::test_pool(999_210).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_210);
+    rpc.push_log(MARKETPLACE, &listed(1, 7, 1_000), 110, 0);
+    let bought = MoltMarketplace::Bought { listingId: U256::from(1), buyer: BUYER, price: U256::from(1_000) };
+    rpc.push_log(MARKETPLACE, &bought, 111, 0);
+    let offer = MoltMarketplace::OfferMade {
+        offerId: U256::from(1),
+        offerer: BUYER,
+        nftContract: IDENTITY,
+        tokenId: U256::from(8),
+        paymentToken: ZERO,
+        amount: U256::from(2_000),
+        expiry: U256::ZERO,
+    };
+    rpc.push_log(MARKETPLACE, &offer, 120, 0);
+    let accepted = MoltMarketplace::OfferAccepted { offerId: U256::from(1), seller: OWNER };
+    rpc.push_log(MARKETPLACE, &accepted, 121, 0);
+    let collection_offer = MoltMarketplace::CollectionOfferMade {
+        offerId: U256::from(1),
+        offerer: BUYER,
+        nftContract: IDENTITY,
+        paymentToken: ZERO,
+        amount: U256::from(3_000),
+        expiry: U256::ZERO,
+    };
+    rpc.push_log(MARKETPLACE, &collection_offer, 130, 0);
+    let collection_accepted =
+        MoltMarketplace::CollectionOfferAccepted { offerId: U256::from(1), seller: OWNER, tokenId: U256::from(9) };
+    rpc.push_log(MARKETPLACE, &collection_accepted, 131, 0);
+    let auction = MoltMarketplace::AuctionCreated {
+        auctionId: U256::from(1),
+        seller: OWNER,
+        nftContract: IDENTITY,
+        tokenId: U256::from(10),
+        paymentToken: ZERO,
+        startPrice: U256::from(100),
+        reservePrice: U256::ZERO,
+        buyNowPrice: U256::ZERO,
+        startTime: U256::from(1_700_000_000u64),
+        endTime: U256::from(1_700_086_400u64),
+    };
+    rpc.push_log(MARKETPLACE, &auction, 140, 0);
+    let settled = MoltMarketplace::AuctionSettled { auctionId: U256::from(1), winner: BUYER, amount: U256::from(4_000) };
+    rpc.push_log(MARKETPLACE, &settled, 141, 0);
+    let dutch = MoltMarketplace::DutchAuctionCreated {
+        auctionId: U256::from(1),
+        seller: OWNER,
+        nftContract: IDENTITY,
+        tokenId: U256::from(11),
+        paymentToken: ZERO,
+        startPrice: U256::from(9_000),
+        endPrice: U256::from(1_000),
+        startTime: U256::from(1_700_000_000u64),
+        endTime: U256::from(1_700_086_400u64),
+    };
+    rpc.push_log(MARKETPLACE, &dutch, 150, 0);
+    let dutch_bought = MoltMarketplace::DutchAuctionBought { auctionId: U256::from(1), buyer: BUYER, price: U256::from(5_000) };
+    rpc.push_log(MARKETPLACE, &dutch_bought, 151, 0);
+    let bundle = MoltMarketplace::BundleListed {
+        bundleId: U256::from(1),
+        seller: OWNER,
+        itemCount: U256::from(3),
+        paymentToken: ZERO,
+        price: U256::from(6_000),
+        expiry: U256::ZERO,
+    };
+    rpc.push_log(MARKETPLACE, &bundle, 160, 0);
+    rpc.on_call::<MoltMarketplace::getBundleListingCall>(
+        MARKETPLACE,
+        &IMoltMarketplace::BundleListing {
+            seller: OWNER,
+            nftContracts: vec![IDENTITY, IDENTITY, OTHER_NFT],
+            tokenIds: vec![U256::from(12), U256::from(13), U256::from(42)],
+            paymentToken: ZERO,
+            price: U256::from(6_000),
+            expiry: U256::ZERO,
+            status: 0,
+        },
+    );
+    let bundle_bought = MoltMarketplace::BundleBought { bundleId: U256::from(1), buyer: BUYER, price: U256::from(6_000) };
+    rpc.push_log(MARKETPLACE, &bundle_bought, 161, 0);
+
+    index_marketplace_events(&pool, &rpc.provider(&chain), &chain, 100, 200).await.unwrap();
+
+    let sales: Vec<(String, String)> = sqlx::query_as(
+        "SELECT sale_type, price::TEXT FROM marketplace_sales WHERE chain_id = $1 ORDER BY block_number",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        sales,
+        vec![
+            ("listing".to_string(), "1000".to_string()),
+            ("offer".to_string(), "2000".to_string()),
+            ("collection_offer".to_string(), "3000".to_string()),
+            ("auction".to_string(), "4000".to_string()),
+            ("dutch_auction".to_string(), "5000".to_string()),
+            ("bundle".to_string(), "6000".to_string()),
+        ]
+    );
+
+    // As /api/stats and /api/marketplace/stats total them, for this chain alone: the bundle is
+    // one sale at its bundle price but three items sold
+    let (total_sales, total_volume, items_sold): (i64, String, i64) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*), COALESCE(SUM(ms.price), 0)::TEXT, COALESCE(SUM(COALESCE(b.item_count, 1)), 0)::BIGINT
+        FROM marketplace_sales ms
+        LEFT JOIN marketplace_bundles b
+            ON ms.sale_type = 'bundle' AND b.bundle_id = ms.entity_id
+            AND b.chain_id = ms.chain_id AND b.marketplace_address = ms.marketplace_address
+        WHERE ms.chain_id = $1
+        "#,
+    )
+    .bind(chain.chain_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!((total_sales, total_volume.as_str(), items_sold), (6, "21000", 8));
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn auction_lifecycle_events_reach_the_agent_activity_feed() {
+    let Some(pool) = common::test_pool(999_214).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_214);
+    // English auctions 1-4 on agents 11-14, Dutch auctions 1-2 on agents 21-22
+    for auction_id in 1..=4u64 {
+        let created = MoltMarketplace::AuctionCreated {
+            auctionId: U256::from(auction_id),
+            seller: OWNER,
+            nftContract: IDENTITY,
+            tokenId: U256::from(10 + auction_id),
+            paymentToken: ZERO,
+            startPrice: U256::from(100),
+            reservePrice: U256::from(500),
+            buyNowPrice: U256::from(900),
+            startTime: U256::from(1_700_000_000u64),
+            endTime: U256::from(1_700_086_400u64),
+        };
+        rpc.push_log(MARKETPLACE, &created, 110, auction_id);
+    }
+    for auction_id in 1..=2u64 {
+        let created = MoltMarketplace::DutchAuctionCreated {
+            auctionId: U256::from(auction_id),
+            seller: OWNER,
+            nftContract: IDENTITY,
+            tokenId: U256::from(20 + auction_id),
+            paymentToken: ZERO,
+            startPrice: U256::from(9_000),
+            endPrice: U256::from(1_000),
+            startTime: U256::from(1_700_000_000u64),
+            endTime: U256::from(1_700_086_400u64),
+        };
+        rpc.push_log(MARKETPLACE, &created, 111, auction_id);
+    }
+    let bid = MoltMarketplace::BidPlaced { auctionId: U256::from(1), bidder: BUYER, amount: U256::from(600) };
+    rpc.push_log(MARKETPLACE, &bid, 120, 0);
+    let extended = MoltMarketplace::AuctionExtended { auctionId: U256::from(1), newEndTime: U256::from(1_700_090_000u64) };
+    rpc.push_log(MARKETPLACE, &extended, 121, 0);
+    let settled = MoltMarketplace::AuctionSettled { auctionId: U256::from(1), winner: BUYER, amount: U256::from(600) };
+    rpc.push_log(MARKETPLACE, &settled, 122, 0);
+    let buy_now = MoltMarketplace::AuctionBuyNow { auctionId: U256::from(2), buyer: BUYER, price: U256::from(900) };
+    rpc.push_log(MARKETPLACE, &buy_now, 123, 0);
+    let reserve_not_met = MoltMarketplace::AuctionReserveNotMet {
+        auctionId: U256::from(3),
+        highestBid: U256::from(300),
+        reservePrice: U256::from(500),
+    };
+    rpc.push_log(MARKETPLACE, &reserve_not_met, 124, 0);
+    let cancelled = MoltMarketplace::AuctionCancelled { auctionId: U256::from(4) };
+    rpc.push_log(MARKETPLACE, &cancelled, 125, 0);
+    let dutch_bought = MoltMarketplace::DutchAuctionBought { auctionId: U256::from(1), buyer: BUYER, price: U256::from(5_000) };
+    rpc.push_log(MARKETPLACE, &dutch_bought, 126, 0);
+    let dutch_cancelled = MoltMarketplace::DutchAuctionCancelled { auctionId: U256::from(2) };
+    rpc.push_log(MARKETPLACE, &dutch_cancelled, 127, 0);
+
+    index_marketplace_events(&pool, &rpc.provider(&chain), &chain, 100, 200).await.unwrap();
+
+    let events: Vec<(i64, String, serde_json::Value)> = sqlx::query_as(
+        "SELECT agent_id, event_type, event_data FROM activity_log WHERE chain_id = $1 AND block_number >= 120 ORDER BY block_number",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    let (owner, buyer) = (hex(OWNER), hex(BUYER));
+    assert_eq!(
+        events,
+        vec![
+            (11, "marketplace:BidPlaced".to_string(), serde_json::json!({"auction_id": 1, "bidder": buyer, "amount": "600"})),
+            (
+                11,
+                "marketplace:AuctionExtended".to_string(),
+                serde_json::json!({"auction_id": 1, "new_end_time": 1_700_090_000}),
+            ),
+            (
+                11,
+                "marketplace:AuctionSettled".to_string(),
+                serde_json::json!({"auction_id": 1, "seller": owner, "winner": buyer, "price": "600"}),
+            ),
+            (
+                12,
+                "marketplace:AuctionBuyNow".to_string(),
+                serde_json::json!({"auction_id": 2, "seller": owner, "buyer": buyer, "price": "900"}),
+            ),
+            (
+                13,
+                "marketplace:AuctionReserveNotMet".to_string(),
+                serde_json::json!({"auction_id": 3, "seller": owner, "highest_bid": "300", "reserve_price": "500"}),
+            ),
+            (14, "marketplace:AuctionCancelled".to_string(), serde_json::json!({"auction_id": 4, "seller": owner})),
+            (
+                21,
+                "marketplace:DutchAuctionBought".to_string(),
+                serde_json::json!({"auction_id": 1, "seller": owner, "buyer": buyer, "price": "5000"}),
+            ),
+            (22, "marketplace:DutchAuctionCancelled".to_string(), serde_json::json!({"auction_id": 2, "seller": owner})),
+        ]
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn bid_placed_updates_auction_and_stores_bids() {
+    let Some(pool) = common::test_pool(999_204).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_204);
+    let created = MoltMarketplace::AuctionCreated {
+        auctionId: U256::from(3),
+        seller: OWNER,
+        nftContract: IDENTITY,
+        tokenId: U256::from(7),
+        paymentToken: ZERO,
+        startPrice: U256::from(100),
+        reservePrice: U256::from(500),
+        buyNowPrice: U256::ZERO,
+        startTime: U256::from(1_700_000_000u64),
+        endTime: U256::from(1_700_086_400u64),
+    };
+    rpc.push_log(MARKETPLACE, &created, 120, 0);
+    for (block, amount) in [(121u64, 150u64), (122, 200)] {
+        let bid = MoltMarketplace::BidPlaced { auctionId: U256::from(3), bidder: BUYER, amount: U256::from(amount) };
+        rpc.push_log(MARKETPLACE, &bid, block, 0);
+    }
+
+    index_marketplace_events(&pool, &rpc.provider(&chain), &chain, 100, 200).await.unwrap();
+
+    let auction: (Option<String>, Option<String>, Option<i32>) = sqlx::query_as(
+        "SELECT highest_bid::TEXT, highest_bidder, bid_count FROM marketplace_auctions WHERE chain_id = $1 AND auction_id = 3",
+    )
+    .bind(chain.chain_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(auction, (Some("200".to_string()), Some(hex(BUYER)), Some(2)));
+
+    let bids: Vec<(String,)> = sqlx::query_as(
+        "SELECT amount::TEXT FROM marketplace_auction_bids WHERE chain_id = $1 AND auction_id = 3 ORDER BY block_number",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(bids, vec![("150".to_string(),), ("200".to_string(),)]);
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn redeployed_marketplace_keeps_listings_apart() {
+    let Some(pool) = common::test_pool(999_207).await else { return };
+    let rpc = MockRpc::start().await;
+    let old_chain = rpc.chain(999_207);
+    let redeployed = Address::repeat_byte(0x34);
+    let mut new_chain = old_chain.clone();
+    new_chain.marketplace_address = Some(redeployed);
+
+    // Both deployments number their first listing 0
+    rpc.push_log(MARKETPLACE, &listed(0, 7, 1_000), 120, 0);
+    rpc.push_log(redeployed, &listed(0, 8, 2_000), 150, 0);
+    let bought = MoltMarketplace::Bought { listingId: U256::ZERO, buyer: BUYER, price: U256::from(2_000) };
+    rpc.push_log(redeployed, &bought, 160, 0);
+
+    index_marketplace_events(&pool, &rpc.provider(&old_chain), &old_chain, 100, 200).await.unwrap();
+    index_marketplace_events(&pool, &rpc.provider(&new_chain), &new_chain, 100, 200).await.unwrap();
+
+    let listings: Vec<(String, String, String)> = sqlx::query_as(
+        r#"
+        SELECT marketplace_address, token_id::TEXT, status FROM marketplace_listings
+        WHERE chain_id = $1 AND listing_id = 0
+        ORDER BY marketplace_address
+        "#,
+    )
+    .bind(old_chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        listings,
+        vec![
+            (hex(MARKETPLACE), "7".to_string(), "Active".to_string()),
+            (hex(redeployed), "8".to_string(), "Sold".to_string()),
+        ]
+    );
+
+    let sales: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT marketplace_address, token_id::TEXT FROM marketplace_sales WHERE chain_id = $1",
+    )
+    .bind(old_chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(sales, vec![(hex(redeployed), Some("8".to_string()))]);
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn bundle_listed_reads_items_from_get_bundle_listing() {
+    let Some(pool) = common::test_pool(999_205).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_205);
+    let bundle_listed = MoltMarketplace::BundleListed {
+        bundleId: U256::from(4),
+        seller: OWNER,
+        itemCount: U256::from(2),
+        paymentToken: ZERO,
+        price: U256::from(9_000),
+        expiry: U256::ZERO,
+    };
+    rpc.push_log(MARKETPLACE, &bundle_listed, 140, 0);
+    // One agent NFT and one unrelated NFT
+    rpc.on_call::<MoltMarketplace::getBundleListingCall>(
+        MARKETPLACE,
+        &IMoltMarketplace::BundleListing {
+            seller: OWNER,
+            nftContracts: vec![IDENTITY, OTHER_NFT],
+            tokenIds: vec![U256::from(7), U256::from(42)],
+            paymentToken: ZERO,
+            price: U256::from(9_000),
+            expiry: U256::ZERO,
+            status: 0,
+        },
+    );
+
+    index_marketplace_events(&pool, &rpc.provider(&chain), &chain, 100, 200).await.unwrap();
+    assert_eq!(rpc.calls("eth_call"), 1);
+
+    let bundle: (Vec<String>, Vec<String>, i32) = sqlx::query_as(
+        "SELECT nft_contracts, token_ids::TEXT[], item_count FROM marketplace_bundles WHERE chain_id = $1 AND bundle_id = 4",
+    )
+    .bind(chain.chain_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        bundle,
+        (vec![hex(IDENTITY), hex(OTHER_NFT)], vec!["7".to_string(), "42".to_string()], 2)
+    );
+
+    let agents: Vec<(i64, String)> =
+        sqlx::query_as("SELECT agent_id, event_type FROM activity_log WHERE chain_id = $1")
+            .bind(chain.chain_id)
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(agents, vec![(7, "marketplace:BundleListed".to_string())]);
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn skip_block_timestamps_makes_no_block_lookups() {
+    let Some(pool) = common::test_pool(999_206).await else { return };
+    let rpc = MockRpc::start().await;
+    let mut chain = rpc.chain(999_206);
+    chain.skip_block_timestamps = true;
+    push_registration(&rpc, 7, 100);
+    rpc.push_log(MARKETPLACE, &listed(1, 7, 1_000), 120, 0);
+
+    let provider = rpc.provider(&chain);
+    index_identity_events(&pool, &provider, &chain, 100, 200).await.unwrap();
+    index_marketplace_events(&pool, &provider, &chain, 100, 200).await.unwrap();
+
+    assert_eq!(rpc.calls("eth_getBlockByNumber"), 0);
+    // Activity rows carry the insert time until the backfill fetches the real one
+    let (with_timestamp,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM activity_log WHERE chain_id = $1 AND NOT block_timestamp_estimated")
+            .bind(chain.chain_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(with_timestamp, 0);
+
+    // The startup backfill swaps in the block's timestamp
+    backfill_block_timestamps(&pool, std::slice::from_ref(&chain)).await;
+    let activity: Vec<(i64, chrono::DateTime<chrono::Utc>, bool)> = sqlx::query_as(
+        "SELECT block_number, block_timestamp, block_timestamp_estimated FROM activity_log WHERE chain_id = $1 ORDER BY block_number",
+    )
+    .bind(chain.chain_id)
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(activity, vec![(100, block_timestamp(100), false), (120, block_timestamp(120), false)]);
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn paused_chain_loop_makes_no_rpc_calls_until_resumed() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use molt_marketplace_backend::indexer::{chain_flags, run_chain};
+    use tokio_util::sync::CancellationToken;
+
+    let Some(pool) = common::test_pool(999_207).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_207);
+    rpc.set_head(150);
+    push_registration(&rpc, 7, 100);
+
+    let paused = Arc::new(AtomicBool::new(true));
+    let shutdown = CancellationToken::new();
+    let task = tokio::spawn(run_chain(
+        pool.clone(),
+        chain.clone(),
+        chain_flags(std::slice::from_ref(&chain)),
+        paused.clone(),
+        shutdown.clone(),
+    ));
+
+    // Several poll intervals pass without a single request
+    tokio::time::sleep(Duration::from_millis(1_500)).await;
+    assert_eq!(rpc.calls("eth_blockNumber"), 0);
+    assert_eq!(rpc.calls("eth_getLogs"), 0);
+
+    paused.store(false, Ordering::Release);
+    let mut agents = 0i64;
+    for _ in 0..50 {
+        (agents,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE chain_id = $1")
+            .bind(chain.chain_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        if agents > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(agents, 1);
+
+    // Pausing again takes effect from the next cycle: the request count stops growing
+    paused.store(true, Ordering::Release);
+    tokio::time::sleep(Duration::from_millis(1_000)).await;
+    let settled = rpc.calls("eth_blockNumber");
+    tokio::time::sleep(Duration::from_millis(1_000)).await;
+    assert_eq!(rpc.calls("eth_blockNumber"), settled);
+
+    shutdown.cancel();
+    tokio::time::timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn shutdown_aborts_the_batches_of_the_cycle_in_progress() {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use molt_marketplace_backend::db::indexer_state::get_last_block;
+    use molt_marketplace_backend::indexer::{chain_flags, run_chain, BLOCK_BATCH_SIZE};
+    use tokio_util::sync::CancellationToken;
+
+    let Some(pool) = common::test_pool(999_219).await else { return };
+    let rpc = MockRpc::start().await;
+    let chain = rpc.chain(999_219);
+    // Three identity batches in one cycle; the last one's logs are held back
+    rpc.set_head(BLOCK_BATCH_SIZE * 2 + 50);
+    push_registration(&rpc, 7, BLOCK_BATCH_SIZE * 2 + 20);
+    rpc.hold_logs_from(BLOCK_BATCH_SIZE * 2);
+
+    let shutdown = CancellationToken::new();
+    let task = tokio::spawn(run_chain(
+        pool.clone(),
+        chain.clone(),
+        chain_flags(std::slice::from_ref(&chain)),
+        Arc::new(AtomicBool::new(false)),
+        shutdown.clone(),
+    ));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    shutdown.cancel();
+    tokio::time::timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+
+    // Once the node answers, nothing is left running to write the held batch
+    rpc.release();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let (agents,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents WHERE chain_id = $1")
+        .bind(chain.chain_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(agents, 0);
+    assert_eq!(get_last_block(&pool, chain.chain_id, &IDENTITY.to_string()).await.unwrap(), None);
+}
+
+#[tokio::test]
+#[ignore = "requires TEST_DATABASE_URL"]
+async fn stalled_chain_does_not_hold_up_the_other_and_both_stop_on_shutdown() {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use molt_marketplace_backend::db::indexer_state::get_last_block;
+    use molt_marketplace_backend::indexer::{chain_flags, supervise_chain};
+    use tokio_util::sync::CancellationToken;
+
+    let Some(pool) = common::test_pool(999_215).await else { return };
+    let Some(_) = common::test_pool(999_216).await else { return };
+    let fast_rpc = MockRpc::start().await;
+    let fast = fast_rpc.chain(999_215);
+    fast_rpc.set_head(150);
+    push_registration(&fast_rpc, 7, 100);
+    // Every request to this node hangs, so its chain never finishes a cycle
+    let stalled_rpc = MockRpc::start().await;
+    let stalled = stalled_rpc.chain(999_216);
+    stalled_rpc.set_head(150);
+    stalled_rpc.stall();
+
+    let flags = chain_flags(&[fast.clone(), stalled.clone()]);
+    let paused = Arc::new(AtomicBool::new(false));
+    let shutdown = CancellationToken::new();
+    let tasks: Vec<_> = [&stalled, &fast]
+        .into_iter()
+        .map(|chain| {
+            tokio::spawn(supervise_chain(
+                pool.clone(),
+                chain.clone(),
+                flags.clone(),
+                paused.clone(),
+                shutdown.clone(),
+            ))
+        })
+        .collect();
+
+    let identity = IDENTITY.to_string();
+    let mut cursor = None;
+    for _ in 0..50 {
+        cursor = get_last_block(&pool, fast.chain_id, &identity).await.unwrap();
+        if cursor == Some(150) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(cursor, Some(150));
+
+    // The stalled chain is still waiting on its first head lookup
+    assert_eq!(stalled_rpc.calls("eth_blockNumber"), 1);
+    assert_eq!(get_last_block(&pool, stalled.chain_id, &identity).await.unwrap(), None);
+
+    shutdown.cancel();
+    for task in tasks {
+        tokio::time::timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+    }
+}
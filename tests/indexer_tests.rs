@@ -1,3 +1,6 @@
+// The suite predates the clippy gate; these lints flag its original style, not bugs.
+#![allow(clippy::empty_line_after_doc_comments, clippy::single_component_path_imports, clippy::nonminimal_bool)]
+
 /// Indexer tests for molt-marketplace.
 ///
 /// These tests verify chain configuration construction, metadata JSON parsing,
@@ -53,41 +56,42 @@ mod chain_config_tests {
         assert!(TESTNET_REPUTATION[2..].chars().all(|c| c.is_ascii_hexdigit()));
     }
 
+    /// Chains from an environment with only DATABASE_URL and `vars` set.
+    fn chains(vars: &[(&str, &str)]) -> Vec<molt_marketplace_backend::indexer::provider::ChainConfig> {
+        let lookup = |key: &str| {
+            vars.iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+                .or_else(|| (key == "DATABASE_URL").then(|| "postgres://localhost/test".to_string()))
+        };
+        molt_marketplace_backend::config::Config::from_lookup(&lookup).unwrap().chains
+    }
+
     #[test]
-    fn default_mainnet_rpc_url() {
-        // Mirrors the logic in provider.rs: get_chain_configs()
-        let rpc_url = std::env::var("MONAD_MAINNET_RPC_URL")
-            .unwrap_or_else(|_| "https://rpc.monad.xyz".to_string());
-        // In test env, the env var is likely not set, so we get the default
-        assert!(
-            rpc_url.starts_with("https://"),
-            "RPC URL should use HTTPS"
-        );
+    fn default_rpc_urls_use_https() {
+        let chains = chains(&[]);
+        assert_eq!(chains[0].rpc_url, "https://rpc.monad.xyz");
+        assert_eq!(chains[1].rpc_url, "https://testnet-rpc.monad.xyz");
     }
 
     #[test]
-    fn default_testnet_rpc_url() {
-        let rpc_url = std::env::var("MONAD_TESTNET_RPC_URL")
-            .unwrap_or_else(|_| "https://testnet-rpc.monad.xyz".to_string());
-        assert!(
-            rpc_url.starts_with("https://"),
-            "RPC URL should use HTTPS"
-        );
+    fn both_networks_are_indexed_by_default() {
+        let ids: Vec<i32> = chains(&[]).iter().map(|c| c.chain_id).collect();
+        assert_eq!(ids, vec![MAINNET_CHAIN_ID, TESTNET_CHAIN_ID]);
     }
 
     #[test]
-    fn index_mainnet_defaults_to_true() {
-        // provider.rs checks INDEX_MAINNET != "false"
-        let index_mainnet = std::env::var("INDEX_MAINNET")
-            .unwrap_or_else(|_| "true".to_string());
-        assert_ne!(index_mainnet, "false");
+    fn index_flags_drop_a_network() {
+        let ids: Vec<i32> = chains(&[("INDEX_MAINNET", "false")]).iter().map(|c| c.chain_id).collect();
+        assert_eq!(ids, vec![TESTNET_CHAIN_ID]);
+        let ids: Vec<i32> = chains(&[("INDEX_TESTNET", "false")]).iter().map(|c| c.chain_id).collect();
+        assert_eq!(ids, vec![MAINNET_CHAIN_ID]);
     }
 
     #[test]
-    fn index_testnet_defaults_to_true() {
-        let index_testnet = std::env::var("INDEX_TESTNET")
-            .unwrap_or_else(|_| "true".to_string());
-        assert_ne!(index_testnet, "false");
+    fn confirmations_default_to_zero_and_apply_to_every_chain() {
+        assert!(chains(&[]).iter().all(|c| c.confirmations == 0));
+        assert!(chains(&[("CONFIRMATIONS", "5")]).iter().all(|c| c.confirmations == 5));
     }
 
     #[test]
@@ -392,3 +396,886 @@ mod block_batch_tests {
         assert!(!(identity_last < latest_block as i64));
     }
 }
+
+#[cfg(test)]
+mod per_chain_task_tests {
+    use std::time::Duration;
+
+    use molt_marketplace_backend::indexer::{restart_backoff, MAX_RESTART_BACKOFF_SECS};
+
+    #[test]
+    fn restart_backoff_grows_by_five_seconds_then_caps() {
+        assert_eq!(restart_backoff(1), Duration::from_secs(5));
+        assert_eq!(restart_backoff(4), Duration::from_secs(20));
+        assert_eq!(restart_backoff(12), Duration::from_secs(MAX_RESTART_BACKOFF_SECS));
+        assert_eq!(restart_backoff(100), Duration::from_secs(MAX_RESTART_BACKOFF_SECS));
+    }
+}
+
+#[cfg(test)]
+mod bundle_agent_activity_tests {
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    const IDENTITY: &str = "0x8004a169fb4a3325136eb29fa0ceb6d2e539a432";
+
+    /// Replicate the item filtering done by insert_bundle_agent_activities +
+    /// maybe_insert_agent_activity in src/indexer/marketplace.rs: returns the
+    /// agent_ids that would receive an activity row.
+    fn agent_ids_for_bundle(
+        identity_address: &str,
+        nft_contracts: &[String],
+        token_ids: &[BigDecimal],
+    ) -> Vec<i64> {
+        nft_contracts
+            .iter()
+            .zip(token_ids)
+            .filter(|(c, _)| c.to_lowercase() == identity_address.to_lowercase())
+            .filter_map(|(_, id)| id.to_string().parse::<i64>().ok())
+            .collect()
+    }
+
+    #[test]
+    fn mixed_bundle_only_yields_agent_items() {
+        let contracts = vec![
+            "0x8004A169FB4a3325136EB29fA0ceB6D2e539a432".to_string(),
+            "0x1111111111111111111111111111111111111111".to_string(),
+            IDENTITY.to_string(),
+        ];
+        let ids = vec![
+            BigDecimal::from(7),
+            BigDecimal::from(7),
+            BigDecimal::from(42),
+        ];
+
+        assert_eq!(agent_ids_for_bundle(IDENTITY, &contracts, &ids), vec![7, 42]);
+    }
+
+    #[test]
+    fn bundle_without_agents_yields_nothing() {
+        let contracts = vec!["0x2222222222222222222222222222222222222222".to_string()];
+        let ids = vec![BigDecimal::from(1)];
+        assert!(agent_ids_for_bundle(IDENTITY, &contracts, &ids).is_empty());
+    }
+
+    #[test]
+    fn agent_token_id_too_large_for_i64_is_skipped() {
+        let contracts = vec![IDENTITY.to_string()];
+        let ids = vec![BigDecimal::from_str("99999999999999999999999").unwrap()];
+        assert!(agent_ids_for_bundle(IDENTITY, &contracts, &ids).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod start_block_override_tests {
+    /// Replicate resolve_start_block from src/indexer/provider.rs
+    fn resolve_start_block(raw: Option<&str>, default: u64) -> u64 {
+        match raw.map(str::trim) {
+            None | Some("") => default,
+            Some(s) => s.parse::<u64>().unwrap_or(default),
+        }
+    }
+
+    /// Replicate the env key naming used by start_block_override
+    fn override_key(chain_id: i32, contract: &str) -> String {
+        format!("CHAIN_{}_{}_START_BLOCK", chain_id, contract)
+    }
+
+    #[test]
+    fn keys_are_per_chain_and_per_contract() {
+        assert_eq!(override_key(143, "IDENTITY"), "CHAIN_143_IDENTITY_START_BLOCK");
+        assert_eq!(override_key(143, "REPUTATION"), "CHAIN_143_REPUTATION_START_BLOCK");
+        assert_eq!(override_key(10143, "MARKETPLACE"), "CHAIN_10143_MARKETPLACE_START_BLOCK");
+    }
+
+    #[test]
+    fn unset_uses_default() {
+        assert_eq!(resolve_start_block(None, 54_839_731), 54_839_731);
+        assert_eq!(resolve_start_block(Some("  "), 54_839_731), 54_839_731);
+    }
+
+    #[test]
+    fn valid_override_replaces_default() {
+        assert_eq!(resolve_start_block(Some("60000000"), 54_839_731), 60_000_000);
+        assert_eq!(resolve_start_block(Some(" 123 "), 54_839_731), 123);
+    }
+
+    #[test]
+    fn invalid_override_falls_back_to_default() {
+        assert_eq!(resolve_start_block(Some("latest"), 52_952_790), 52_952_790);
+        assert_eq!(resolve_start_block(Some("-5"), 52_952_790), 52_952_790);
+    }
+
+    #[test]
+    fn contracts_resolve_independently() {
+        // Overriding only the marketplace leaves identity/reputation on their defaults
+        let identity = resolve_start_block(None, 52_952_790);
+        let reputation = resolve_start_block(None, 52_952_790);
+        let marketplace = resolve_start_block(Some("60000000"), 54_839_731);
+        assert_eq!((identity, reputation, marketplace), (52_952_790, 52_952_790, 60_000_000));
+    }
+}
+
+#[cfg(test)]
+mod chain_enable_flag_tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    type ChainFlags = Arc<HashMap<i32, AtomicBool>>;
+
+    /// Replicate chain_flags from src/indexer/mod.rs
+    fn chain_flags(chain_ids: &[i32]) -> ChainFlags {
+        Arc::new(chain_ids.iter().map(|id| (*id, AtomicBool::new(true))).collect())
+    }
+
+    /// Replicate chain_enabled from src/indexer/mod.rs
+    fn chain_enabled(flags: &ChainFlags, chain_id: i32) -> bool {
+        flags
+            .get(&chain_id)
+            .map(|f| f.load(Ordering::Acquire))
+            .unwrap_or(true)
+    }
+
+    /// One iteration of the run_chain loop: skip the cycle when disabled, otherwise index.
+    fn run_iteration(flags: &ChainFlags, chain_id: i32, indexed: &mut Vec<i32>) {
+        if !chain_enabled(flags, chain_id) {
+            return;
+        }
+        indexed.push(chain_id);
+    }
+
+    #[test]
+    fn all_chains_start_enabled() {
+        let flags = chain_flags(&[143, 10143]);
+        assert!(chain_enabled(&flags, 143));
+        assert!(chain_enabled(&flags, 10143));
+    }
+
+    #[test]
+    fn disabled_chain_is_skipped_others_keep_indexing() {
+        let flags = chain_flags(&[143, 10143]);
+        // What POST /api/admin/indexer/10143/disable does
+        flags.get(&10143).unwrap().store(false, Ordering::Release);
+
+        let mut indexed = Vec::new();
+        for chain_id in [143, 10143] {
+            run_iteration(&flags, chain_id, &mut indexed);
+        }
+        assert_eq!(indexed, vec![143]);
+    }
+
+    #[test]
+    fn re_enabled_chain_resumes() {
+        let flags = chain_flags(&[10143]);
+        let flag = flags.get(&10143).unwrap();
+        flag.store(false, Ordering::Release);
+
+        let mut indexed = Vec::new();
+        run_iteration(&flags, 10143, &mut indexed);
+        flag.store(true, Ordering::Release);
+        run_iteration(&flags, 10143, &mut indexed);
+        assert_eq!(indexed, vec![10143]);
+    }
+
+    #[test]
+    fn flags_are_shared_across_clones() {
+        // AppState and the chain tasks hold clones of the same Arc
+        let flags = chain_flags(&[143]);
+        let api_side = flags.clone();
+        api_side.get(&143).unwrap().store(false, Ordering::Release);
+        assert!(!chain_enabled(&flags, 143));
+    }
+
+    #[test]
+    fn unknown_chain_defaults_to_enabled() {
+        let flags = chain_flags(&[143]);
+        assert!(chain_enabled(&flags, 999));
+    }
+}
+
+#[cfg(test)]
+mod activity_retention_tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    const ARCHIVE_BATCH_SIZE: u64 = 5_000;
+
+    /// Replicate retention_days from src/indexer/retention.rs
+    fn retention_days(raw: Option<&str>) -> Result<Option<i64>, String> {
+        match raw.map(str::trim) {
+            None | Some("") => Ok(None),
+            Some(s) => match s.parse::<i64>() {
+                Ok(0) => Ok(None),
+                Ok(days) if days > 0 => Ok(Some(days)),
+                _ => Err(format!("invalid ACTIVITY_RETENTION_DAYS {:?}", s)),
+            },
+        }
+    }
+
+    /// Replicate the batch loop in run_activity_retention: keep going while batches come back full.
+    fn drain(mut pending: u64) -> (u64, usize) {
+        let mut archived = 0;
+        let mut batches = 0;
+        loop {
+            let moved = pending.min(ARCHIVE_BATCH_SIZE);
+            pending -= moved;
+            archived += moved;
+            batches += 1;
+            if moved < ARCHIVE_BATCH_SIZE {
+                break;
+            }
+        }
+        (archived, batches)
+    }
+
+    #[test]
+    fn unset_or_zero_disables_archival() {
+        assert_eq!(retention_days(None), Ok(None));
+        assert_eq!(retention_days(Some("")), Ok(None));
+        assert_eq!(retention_days(Some("0")), Ok(None));
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert!(retention_days(Some("90d")).is_err());
+        assert!(retention_days(Some("-30")).is_err());
+    }
+
+    #[test]
+    fn valid_days_enable_archival() {
+        assert_eq!(retention_days(Some("90")), Ok(Some(90)));
+        assert_eq!(retention_days(Some(" 365 ")), Ok(Some(365)));
+    }
+
+    #[test]
+    fn cutoff_is_retention_days_before_now() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 31, 12, 0, 0).unwrap();
+        let cutoff = now - Duration::days(90);
+        assert_eq!(cutoff, Utc.with_ymd_and_hms(2025, 12, 31, 12, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn drains_backlog_in_full_batches_then_stops() {
+        assert_eq!(drain(12_345), (12_345, 3));
+        // An exact multiple needs one extra empty batch to notice it's done
+        assert_eq!(drain(10_000), (10_000, 3));
+        assert_eq!(drain(0), (0, 1));
+    }
+}
+
+#[cfg(test)]
+mod feedback_content_hash_tests {
+    use alloy::primitives::{keccak256, B256};
+
+    /// Replicate content_matches_hash from src/indexer/feedback_content.rs
+    fn content_matches_hash(content: &[u8], expected_hash: &str) -> Option<bool> {
+        let expected: B256 = expected_hash.trim().parse().ok()?;
+        if expected == B256::ZERO {
+            return None;
+        }
+        Some(keccak256(content) == expected)
+    }
+
+    const CONTENT: &[u8] = br#"{"score":5,"comment":"fast and accurate"}"#;
+
+    /// feedback_hash as the indexer stores it: format!("{:#x}", event.feedbackHash)
+    fn stored_hash(content: &[u8]) -> String {
+        format!("{:#x}", keccak256(content))
+    }
+
+    #[test]
+    fn matching_content_is_verified() {
+        assert_eq!(content_matches_hash(CONTENT, &stored_hash(CONTENT)), Some(true));
+    }
+
+    #[test]
+    fn tampered_content_fails_verification() {
+        let tampered = br#"{"score":1,"comment":"fast and accurate"}"#;
+        assert_eq!(content_matches_hash(tampered, &stored_hash(CONTENT)), Some(false));
+    }
+
+    #[test]
+    fn hash_comparison_ignores_hex_case() {
+        let upper = stored_hash(CONTENT).to_uppercase().replacen("0X", "0x", 1);
+        assert_eq!(content_matches_hash(CONTENT, &upper), Some(true));
+    }
+
+    #[test]
+    fn known_keccak_vector() {
+        // keccak256("") — catches an accidental switch to SHA3-256
+        let empty = "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+        assert_eq!(content_matches_hash(b"", empty), Some(true));
+    }
+
+    #[test]
+    fn zero_hash_means_nothing_to_verify() {
+        let zero = format!("{:#x}", B256::ZERO);
+        assert_eq!(content_matches_hash(CONTENT, &zero), None);
+    }
+
+    #[test]
+    fn unparseable_hash_is_not_a_mismatch() {
+        assert_eq!(content_matches_hash(CONTENT, "not-a-hash"), None);
+        assert_eq!(content_matches_hash(CONTENT, "0x1234"), None);
+    }
+}
+
+#[cfg(test)]
+mod batch_insert_tests {
+    use std::collections::BTreeSet;
+
+    /// Simplified reputation log, in the order eth_getLogs returns them
+    enum Event {
+        NewFeedback { agent_id: i64, feedback_index: i64 },
+        FeedbackRevoked { agent_id: i64, feedback_index: i64 },
+        ResponseAppended { agent_id: i64, feedback_index: i64 },
+    }
+
+    #[derive(Default)]
+    struct Batch {
+        feedbacks: Vec<(i64, i64)>,
+        revocations: Vec<(i64, i64)>,
+        responses: Vec<(i64, i64)>,
+        activities: Vec<(i64, &'static str)>,
+    }
+
+    /// Replicate the accumulation loop in index_reputation_events
+    fn accumulate(events: &[Event]) -> Batch {
+        let mut batch = Batch::default();
+        for event in events {
+            match *event {
+                Event::NewFeedback { agent_id, feedback_index } => {
+                    batch.feedbacks.push((agent_id, feedback_index));
+                    batch.activities.push((agent_id, "NewFeedback"));
+                }
+                Event::FeedbackRevoked { agent_id, feedback_index } => {
+                    batch.revocations.push((agent_id, feedback_index));
+                    batch.activities.push((agent_id, "FeedbackRevoked"));
+                }
+                Event::ResponseAppended { agent_id, feedback_index } => {
+                    batch.responses.push((agent_id, feedback_index));
+                    batch.activities.push((agent_id, "ResponseAppended"));
+                }
+            }
+        }
+        batch
+    }
+
+    /// Replicate the flush at the end of index_reputation_events, returning the statements it
+    /// would issue. Each batch helper returns early (no statement) on an empty slice.
+    fn flush(batch: &Batch) -> Vec<&'static str> {
+        let touched: BTreeSet<i64> = batch
+            .feedbacks
+            .iter()
+            .map(|(agent_id, _)| *agent_id)
+            .chain(batch.revocations.iter().map(|(agent_id, _)| *agent_id))
+            .collect();
+
+        let mut statements = vec!["BEGIN"];
+        if !batch.feedbacks.is_empty() {
+            statements.push("INSERT feedbacks");
+        }
+        if !batch.revocations.is_empty() {
+            statements.push("UPDATE feedbacks");
+        }
+        if !batch.responses.is_empty() {
+            statements.push("INSERT feedback_responses");
+        }
+        if !batch.activities.is_empty() {
+            statements.push("INSERT activity_log");
+        }
+        if !touched.is_empty() {
+            statements.push("UPSERT agent_stats");
+        }
+        statements.push("COMMIT");
+        statements
+    }
+
+    fn busy_range() -> Vec<Event> {
+        // 500 events over 50 agents, like a catch-up batch
+        (0..500)
+            .map(|i| match i % 5 {
+                4 => Event::FeedbackRevoked { agent_id: i % 50, feedback_index: i - 4 },
+                3 => Event::ResponseAppended { agent_id: i % 50, feedback_index: i - 3 },
+                _ => Event::NewFeedback { agent_id: i % 50, feedback_index: i },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn busy_range_is_one_statement_per_table() {
+        let batch = accumulate(&busy_range());
+        assert_eq!(batch.activities.len(), 500);
+        assert_eq!(
+            flush(&batch),
+            vec![
+                "BEGIN",
+                "INSERT feedbacks",
+                "UPDATE feedbacks",
+                "INSERT feedback_responses",
+                "INSERT activity_log",
+                "UPSERT agent_stats",
+                "COMMIT",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_tables_are_skipped() {
+        let batch = accumulate(&[Event::NewFeedback { agent_id: 1, feedback_index: 0 }]);
+        assert_eq!(
+            flush(&batch),
+            vec!["BEGIN", "INSERT feedbacks", "INSERT activity_log", "UPSERT agent_stats", "COMMIT"]
+        );
+    }
+
+    #[test]
+    fn inserts_are_flushed_before_revocations() {
+        // Given and revoked in the same range: the revoke must see the inserted row
+        let batch = accumulate(&[
+            Event::NewFeedback { agent_id: 7, feedback_index: 0 },
+            Event::FeedbackRevoked { agent_id: 7, feedback_index: 0 },
+        ]);
+        let statements = flush(&batch);
+        let insert = statements.iter().position(|s| *s == "INSERT feedbacks").unwrap();
+        let revoke = statements.iter().position(|s| *s == "UPDATE feedbacks").unwrap();
+        assert!(insert < revoke);
+    }
+
+    #[test]
+    fn activities_keep_log_order() {
+        let batch = accumulate(&[
+            Event::NewFeedback { agent_id: 1, feedback_index: 0 },
+            Event::ResponseAppended { agent_id: 1, feedback_index: 0 },
+            Event::FeedbackRevoked { agent_id: 1, feedback_index: 0 },
+        ]);
+        let kinds: Vec<&str> = batch.activities.iter().map(|(_, kind)| *kind).collect();
+        assert_eq!(kinds, vec!["NewFeedback", "ResponseAppended", "FeedbackRevoked"]);
+    }
+
+    #[test]
+    fn responses_alone_do_not_refresh_stats() {
+        let batch = accumulate(&[Event::ResponseAppended { agent_id: 3, feedback_index: 0 }]);
+        assert!(!flush(&batch).contains(&"UPSERT agent_stats"));
+    }
+}
+
+#[cfg(test)]
+mod dry_run_tests {
+    use std::collections::HashMap;
+
+    /// Records the writes an indexing pass issued instead of talking to a database
+    #[derive(Default)]
+    struct FakeDb {
+        cursors: HashMap<String, i64>,
+        writes: Vec<String>,
+    }
+
+    /// Replicate the `if chain.dry_run { log_dry_run(..) } else { db::..(..) }` branch used
+    /// at every write site in src/indexer, returning what was logged
+    fn write(dry_run: bool, db: &mut FakeDb, name: &str, logged: &mut Vec<String>) {
+        if dry_run {
+            logged.push(format!("[dry run] {}", name));
+        } else {
+            db.writes.push(name.to_string());
+        }
+    }
+
+    /// Replicate last_indexed_block from src/indexer/mod.rs
+    fn last_indexed_block(dry_run: bool, db: &FakeDb, dry_run_cursors: &HashMap<String, i64>, addr: &str) -> Option<i64> {
+        if let Some(last) = dry_run_cursors.get(addr).filter(|_| dry_run) {
+            return Some(*last);
+        }
+        db.cursors.get(addr).copied()
+    }
+
+    /// Replicate save_last_block from src/indexer/mod.rs
+    fn save_last_block(dry_run: bool, db: &mut FakeDb, dry_run_cursors: &mut HashMap<String, i64>, addr: &str, last: i64) {
+        if dry_run {
+            dry_run_cursors.insert(addr.to_string(), last);
+            return;
+        }
+        db.writes.push("update_last_block_with_name".to_string());
+        db.cursors.insert(addr.to_string(), last);
+    }
+
+    /// One identity range: a Registered and a Transfer, then the activity and ownership flushes
+    fn index_range(dry_run: bool, db: &mut FakeDb, logged: &mut Vec<String>) {
+        write(dry_run, db, "upsert_agent", logged);
+        write(dry_run, db, "update_agent_owner", logged);
+        write(dry_run, db, "insert_activities", logged);
+        write(dry_run, db, "insert_ownership_transfers", logged);
+    }
+
+    #[test]
+    fn dry_run_issues_no_writes() {
+        let mut db = FakeDb::default();
+        let mut cursors = HashMap::new();
+        let mut logged = Vec::new();
+        index_range(true, &mut db, &mut logged);
+        save_last_block(true, &mut db, &mut cursors, "0xid", 199);
+        assert!(db.writes.is_empty());
+        assert!(db.cursors.is_empty());
+        assert_eq!(logged.len(), 4);
+    }
+
+    #[test]
+    fn normal_run_writes_everything() {
+        let mut db = FakeDb::default();
+        let mut cursors = HashMap::new();
+        let mut logged = Vec::new();
+        index_range(false, &mut db, &mut logged);
+        save_last_block(false, &mut db, &mut cursors, "0xid", 199);
+        assert_eq!(
+            db.writes,
+            vec![
+                "upsert_agent",
+                "update_agent_owner",
+                "insert_activities",
+                "insert_ownership_transfers",
+                "update_last_block_with_name"
+            ]
+        );
+        assert!(logged.is_empty());
+        assert!(cursors.is_empty());
+    }
+
+    #[test]
+    fn dry_run_starts_from_stored_cursor_then_advances_in_memory() {
+        let mut db = FakeDb::default();
+        db.cursors.insert("0xid".to_string(), 99);
+        let mut cursors = HashMap::new();
+
+        assert_eq!(last_indexed_block(true, &db, &cursors, "0xid"), Some(99));
+        save_last_block(true, &mut db, &mut cursors, "0xid", 199);
+        assert_eq!(last_indexed_block(true, &db, &cursors, "0xid"), Some(199));
+        // The stored cursor is untouched
+        assert_eq!(db.cursors["0xid"], 99);
+    }
+
+    #[test]
+    fn unset_cursor_falls_back_to_start_block() {
+        let db = FakeDb::default();
+        let cursors = HashMap::new();
+        let start_block: i64 = 52_952_790;
+        let last = last_indexed_block(true, &db, &cursors, "0xid").unwrap_or(start_block - 1);
+        assert_eq!(last, start_block - 1);
+    }
+}
+
+#[cfg(test)]
+mod ownership_history_tests {
+    const ZERO: &str = "0x0000000000000000000000000000000000000000";
+
+    /// A decoded identity Transfer log
+    struct TransferLog {
+        from: &'static str,
+        to: &'static str,
+        token_id: i64,
+        block_number: i64,
+        log_index: i32,
+    }
+
+    /// Replicate NewOwnershipTransfer (src/types/mod.rs)
+    #[derive(Debug, Clone, PartialEq)]
+    struct NewOwnershipTransfer {
+        agent_id: i64,
+        from_address: String,
+        to_address: String,
+        block_number: i64,
+        log_index: i32,
+    }
+
+    /// Replicate the Transfer branch of index_identity_events (src/indexer/identity.rs):
+    /// every transfer is recorded, but only non-mints update the owner and the activity log.
+    /// Returns (history rows, owner updates).
+    fn index_transfers(logs: &[TransferLog]) -> (Vec<NewOwnershipTransfer>, Vec<(i64, String)>) {
+        let mut transfers = Vec::new();
+        let mut owner_updates = Vec::new();
+        for log in logs {
+            transfers.push(NewOwnershipTransfer {
+                agent_id: log.token_id,
+                from_address: log.from.to_string(),
+                to_address: log.to.to_string(),
+                block_number: log.block_number,
+                log_index: log.log_index,
+            });
+            if log.from == ZERO {
+                continue;
+            }
+            owner_updates.push((log.token_id, log.to.to_string()));
+        }
+        (transfers, owner_updates)
+    }
+
+    /// Replicate get_ownership_history's filter and ORDER BY block_number, log_index
+    fn history(rows: &[NewOwnershipTransfer], agent_id: i64) -> Vec<NewOwnershipTransfer> {
+        let mut out: Vec<NewOwnershipTransfer> = rows.iter().filter(|r| r.agent_id == agent_id).cloned().collect();
+        out.sort_by_key(|r| (r.block_number, r.log_index));
+        out
+    }
+
+    #[test]
+    fn mint_is_recorded_but_does_not_update_owner() {
+        let (transfers, owner_updates) = index_transfers(&[TransferLog {
+            from: ZERO,
+            to: "0xaaa",
+            token_id: 7,
+            block_number: 100,
+            log_index: 0,
+        }]);
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].from_address, ZERO);
+        assert_eq!(transfers[0].to_address, "0xaaa");
+        assert!(owner_updates.is_empty());
+    }
+
+    #[test]
+    fn history_is_chronological_and_per_agent() {
+        // Ranges can be indexed (and rows inserted) out of order, e.g. after a replay
+        let mut rows = index_transfers(&[
+            TransferLog { from: "0xbbb", to: "0xccc", token_id: 7, block_number: 300, log_index: 1 },
+            TransferLog { from: ZERO, to: "0xddd", token_id: 8, block_number: 150, log_index: 0 },
+        ])
+        .0;
+        rows.extend(
+            index_transfers(&[
+                TransferLog { from: ZERO, to: "0xaaa", token_id: 7, block_number: 100, log_index: 3 },
+                TransferLog { from: "0xaaa", to: "0xbbb", token_id: 7, block_number: 300, log_index: 0 },
+            ])
+            .0,
+        );
+
+        let agent_7 = history(&rows, 7);
+        let owners: Vec<(&str, &str)> = agent_7
+            .iter()
+            .map(|r| (r.from_address.as_str(), r.to_address.as_str()))
+            .collect();
+        assert_eq!(owners, vec![(ZERO, "0xaaa"), ("0xaaa", "0xbbb"), ("0xbbb", "0xccc")]);
+        assert_eq!(history(&rows, 8).len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod skip_block_timestamps_tests {
+    use std::cell::Cell;
+
+    use alloy::primitives::Address;
+    use chrono::{DateTime, TimeZone, Utc};
+    use molt_marketplace_backend::indexer::provider::{BlockTimestamps, ChainConfig};
+
+    type FetchResult = Result<DateTime<Utc>, Box<dyn std::error::Error + Send + Sync>>;
+
+    fn chain(skip_block_timestamps: bool) -> ChainConfig {
+        ChainConfig {
+            chain_id: 10143,
+            rpc_url: "http://localhost:8545".to_string(),
+            identity_address: Address::ZERO,
+            reputation_address: Address::ZERO,
+            marketplace_address: None,
+            start_block: 0,
+            reputation_start_block: None,
+            marketplace_start_block: None,
+            dry_run: false,
+            skip_block_timestamps,
+            confirmations: 0,
+        }
+    }
+
+    /// Look up `blocks` in order with a fetch that counts its calls and answers `block` seconds
+    async fn lookup(timestamps: &mut BlockTimestamps, blocks: &[u64], calls: &Cell<u32>) -> Vec<Option<DateTime<Utc>>> {
+        let mut out = Vec::new();
+        for &block in blocks {
+            let ts = timestamps
+                .get_with(block, |b| async move {
+                    calls.set(calls.get() + 1);
+                    FetchResult::Ok(Utc.timestamp_opt(b as i64, 0).unwrap())
+                })
+                .await;
+            out.push(ts);
+        }
+        out
+    }
+
+    #[tokio::test]
+    async fn skip_mode_never_calls_the_rpc() {
+        let calls = Cell::new(0);
+        let mut timestamps = BlockTimestamps::new(&chain(true));
+        let got = lookup(&mut timestamps, &[100, 100, 101], &calls).await;
+        assert_eq!(got, vec![None, None, None]);
+        assert_eq!(calls.get(), 0);
+    }
+
+    #[tokio::test]
+    async fn default_mode_fetches_each_block_once() {
+        let calls = Cell::new(0);
+        let mut timestamps = BlockTimestamps::new(&chain(false));
+        let got = lookup(&mut timestamps, &[100, 100, 101], &calls).await;
+        assert_eq!(got[0], Some(Utc.timestamp_opt(100, 0).unwrap()));
+        assert_eq!(got[1], got[0]);
+        assert_eq!(got[2], Some(Utc.timestamp_opt(101, 0).unwrap()));
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_lookup_is_none_and_retried() {
+        let calls = Cell::new(0);
+        let mut timestamps = BlockTimestamps::new(&chain(false));
+        let failed = timestamps
+            .get_with(100, |_| async {
+                calls.set(calls.get() + 1);
+                FetchResult::Err("rate limited".into())
+            })
+            .await;
+        assert_eq!(failed, None);
+        // Failures aren't cached, so the next log in the block tries again
+        assert!(lookup(&mut timestamps, &[100], &calls).await[0].is_some());
+        assert_eq!(calls.get(), 2);
+    }
+}
+
+#[cfg(test)]
+mod data_uri_tests {
+    use base64::Engine as _;
+    use molt_marketplace_backend::indexer::metadata::{decode_data_uri, parse_data_uri, resolve_uri, UriSource};
+
+    const JSON: &str = r#"{"name":"Data Agent","description":"inline"}"#;
+
+    fn b64() -> String {
+        base64::engine::general_purpose::STANDARD.encode(JSON)
+    }
+
+    #[test]
+    fn ipfs_uris_resolve_through_the_gateway() {
+        assert_eq!(
+            resolve_uri("ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"),
+            UriSource::Url("https://ipfs.io/ipfs/bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string())
+        );
+        assert_eq!(
+            resolve_uri("ipfs://QmHash/images/agent.png"),
+            UriSource::Url("https://ipfs.io/ipfs/QmHash/images/agent.png".to_string())
+        );
+    }
+
+    #[test]
+    fn data_uris_are_inline_and_urls_pass_through() {
+        let uri = format!("DATA:application/json;base64,{}", b64());
+        assert_eq!(resolve_uri(&uri), UriSource::Data(&uri));
+        assert_eq!(
+            resolve_uri("https://example.com/agent.png"),
+            UriSource::Url("https://example.com/agent.png".to_string())
+        );
+    }
+
+    #[test]
+    fn image_data_uris_keep_their_media_type() {
+        let png = [0x89, b'P', b'N', b'G'];
+        let uri = format!("data:Image/PNG;base64,{}", base64::engine::general_purpose::STANDARD.encode(png));
+        let (media_type, content) = parse_data_uri(&uri).unwrap();
+        assert_eq!(media_type, "image/png");
+        assert_eq!(content, png);
+        // Only JSON passes decode_data_uri, which metadata goes through
+        assert!(decode_data_uri(&uri).is_err());
+    }
+
+    #[test]
+    fn plain_base64_and_raw_json() {
+        assert_eq!(decode_data_uri(&format!("data:application/json;base64,{}", b64())).unwrap(), JSON.as_bytes());
+        let encoded = urlencoding::encode(JSON);
+        assert_eq!(decode_data_uri(&format!("data:application/json,{}", encoded)).unwrap(), JSON.as_bytes());
+    }
+
+    #[test]
+    fn charset_before_base64() {
+        let uri = format!("data:application/json;charset=utf-8;base64,{}", b64());
+        assert_eq!(decode_data_uri(&uri).unwrap(), JSON.as_bytes());
+    }
+
+    #[test]
+    fn parameters_in_any_order_and_case() {
+        for header in [
+            "application/json;base64;charset=utf-8",
+            "application/json; charset=UTF-8; base64",
+            "Application/JSON;BASE64",
+        ] {
+            let uri = format!("data:{header},{}", b64());
+            assert_eq!(decode_data_uri(&uri).unwrap(), JSON.as_bytes(), "{header}");
+        }
+    }
+
+    #[test]
+    fn charset_without_base64_is_raw_json() {
+        let uri = format!("data:application/json;charset=utf-8,{}", urlencoding::encode(JSON));
+        assert_eq!(decode_data_uri(&uri).unwrap(), JSON.as_bytes());
+    }
+
+    #[test]
+    fn commas_in_the_payload_are_kept() {
+        // Only the first ',' separates the header from the data
+        let uri = format!("DATA:application/json,{}", JSON);
+        assert_eq!(decode_data_uri(&uri).unwrap(), JSON.as_bytes());
+    }
+
+    #[test]
+    fn percent_encoded_and_wrapped_base64() {
+        let b64 = b64();
+        let wrapped = format!("{}\n{}", &b64[..16], &b64[16..]).replace('=', "%3D");
+        let uri = format!("data:application/json;base64,{wrapped}");
+        assert_eq!(decode_data_uri(&uri).unwrap(), JSON.as_bytes());
+    }
+
+    #[test]
+    fn json_suffix_media_types_are_accepted() {
+        let uri = format!("data:application/ld+json;base64,{}", b64());
+        assert_eq!(decode_data_uri(&uri).unwrap(), JSON.as_bytes());
+    }
+
+    #[test]
+    fn rejects_other_media_types_and_malformed_uris() {
+        assert!(decode_data_uri(&format!("data:text/plain;base64,{}", b64())).is_err());
+        assert!(decode_data_uri(&format!("data:;base64,{}", b64())).is_err());
+        assert!(decode_data_uri("data:application/json;base64").is_err());
+        assert!(decode_data_uri("data:application/json;base64,not base64!").is_err());
+        assert!(decode_data_uri("https://example.com/agent.json").is_err());
+    }
+}
+
+#[cfg(test)]
+mod auction_times_tests {
+    use molt_marketplace_backend::indexer::marketplace::auction_times_malformed;
+
+    #[test]
+    fn end_after_start_is_well_formed() {
+        assert!(!auction_times_malformed(1_700_000_000, 1_700_086_400));
+        assert!(!auction_times_malformed(0, 1));
+    }
+
+    #[test]
+    fn end_at_or_before_start_is_malformed() {
+        assert!(auction_times_malformed(1_700_000_000, 1_700_000_000));
+        assert!(auction_times_malformed(1_700_086_400, 1_700_000_000));
+        // A zero end time, as a failed decode leaves it
+        assert!(auction_times_malformed(1_700_000_000, 0));
+    }
+}
+
+#[cfg(test)]
+mod confirmations_tests {
+    use molt_marketplace_backend::indexer::confirmed_tip;
+
+    #[test]
+    fn zero_confirmations_index_up_to_the_tip() {
+        assert_eq!(confirmed_tip(1_000, 0), 1_000);
+    }
+
+    #[test]
+    fn the_tip_is_held_back_by_the_confirmation_depth() {
+        assert_eq!(confirmed_tip(1_000, 5), 995);
+    }
+
+    #[test]
+    fn a_chain_shorter_than_the_depth_stays_at_genesis() {
+        assert_eq!(confirmed_tip(3, 5), 0);
+        assert_eq!(confirmed_tip(5, 5), 0);
+    }
+}